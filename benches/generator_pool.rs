@@ -0,0 +1,35 @@
+//! Codes per second through [`GeneratorPool`], against building a generator for every code
+//! as a request handler without the pool would: `cargo bench --bench generator_pool`
+
+use clipboard_qr::generator_pool::{GenerationJob, GeneratorPool};
+use clipboard_qr::qr_generator::QRGenerator;
+use clipboard_qr::qr_style::QrStyle;
+use std::time::{Duration, Instant};
+
+const CODES: usize = 2000;
+
+fn text(index: usize) -> String {
+    format!("https://example.com/item/{}", index)
+}
+
+fn report(what: &str, elapsed: Duration) {
+    println!("{:<28} {:>5} codes in {:>9.2?}, {:>6.0} codes/s", what, CODES, elapsed, CODES as f64 / elapsed.as_secs_f64());
+}
+
+fn main() {
+    let pool = GeneratorPool::with_default_size(QrStyle::default());
+    println!("{} workers", pool.workers());
+    for format in ["svg", "png"] {
+        let started = Instant::now();
+        let results = pool.generate_all((0..CODES).map(|index| GenerationJob::new(text(index), format)));
+        let elapsed = started.elapsed();
+        assert!(results.iter().all(|result| result.is_ok()));
+        report(&format!("{} through the pool", format), elapsed);
+
+        let started = Instant::now();
+        for index in 0..CODES {
+            QRGenerator::with_style(QrStyle::default()).render(&text(index), format).unwrap();
+        }
+        report(&format!("{} one generator per code", format), started.elapsed());
+    }
+}
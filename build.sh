@@ -75,6 +75,15 @@ run_tests() {
     print_status "Running tests..."
     cargo test
     print_success "Tests completed"
+    check_core
+}
+
+# Function to check the library alone, as used without the desktop app (e.g. for WASM)
+check_core() {
+    print_status "Checking the library without default features..."
+    cargo check --lib --no-default-features
+    cargo test --lib --no-default-features --features core
+    print_success "Core library check completed"
 }
 
 # Function to run linter
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use eframe::egui;
+use std::process::Child;
+use tracing::{error, info};
+
+use crate::clipboard_handler::ClipboardHandler;
+use crate::config::AppConfig;
+
+/// Optional features this build was made with
+fn features() -> Vec<&'static str> {
+    [
+        (cfg!(feature = "camera"), "camera"),
+        (cfg!(feature = "server"), "server"),
+        (cfg!(feature = "wayland-data-control"), "wayland-data-control"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, name)| enabled.then_some(name))
+    .collect()
+}
+
+/// Version, build and where the config file is, for bug reports
+fn version_info() -> String {
+    let features = features();
+    format!(
+        "clipboard-qr {} ({} {}){}",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        if features.is_empty() { String::new() } else { format!(", features: {}", features.join(", ")) }
+    )
+}
+
+/// Show the version, licence and config file location in a small window; Esc closes it
+pub fn run() -> Result<()> {
+    info!("Opening About window");
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("About Clipboard QR")
+            .with_inner_size([380.0, 220.0])
+            .with_resizable(false),
+        ..Default::default()
+    };
+    let app = AboutApp { status: None, clipboard: ClipboardHandler::builder().spawn_listener(false).build() };
+    eframe::run_native("About Clipboard QR", options, Box::new(|_cc| Ok(Box::new(app))))
+        .map_err(|e| anyhow::anyhow!("Failed to open the About window: {}", e))
+}
+
+/// Open the About window in its own process, since the tray's event loop can't host egui
+pub fn open_window() -> Result<Child> {
+    let exe = std::env::current_exe()?;
+    std::process::Command::new(exe)
+        .arg("about")
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to open the About window: {}", e))
+}
+
+struct AboutApp {
+    /// Result of copying the version
+    status: Option<String>,
+    /// Kept while the window is open, as X11 and Wayland clipboard content only lasts as
+    /// long as the handler that set it
+    clipboard: ClipboardHandler,
+}
+
+impl eframe::App for AboutApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(format!("Clipboard QR {}", env!("CARGO_PKG_VERSION")));
+                ui.label(env!("CARGO_PKG_DESCRIPTION"));
+                ui.label(format!("{} licence, by {}", env!("CARGO_PKG_LICENSE"), env!("CARGO_PKG_AUTHORS")));
+            });
+            ui.separator();
+            match AppConfig::config_path() {
+                Some(path) => ui.label(format!("Config file: {}", path.display())),
+                None => ui.label("No config directory; defaults apply"),
+            };
+            let features = features();
+            if !features.is_empty() {
+                ui.label(format!("Built with: {}", features.join(", ")));
+            }
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                if ui.button("Copy Version Info").clicked() {
+                    let copied = self.clipboard.set_text(&version_info());
+                    self.status = Some(match copied {
+                        Ok(()) => "📋 Copied".to_string(),
+                        Err(e) => {
+                            error!("Failed to copy the version info: {}", e);
+                            format!("❌ {}", e)
+                        }
+                    });
+                }
+                if ui.button("Close").clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+                if let Some(status) = &self.status {
+                    ui.label(status);
+                }
+            });
+        });
+    }
+}
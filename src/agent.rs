@@ -0,0 +1,146 @@
+use anyhow::Result;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+use tracing::{info, warn};
+use url::Url;
+
+use crate::config::AgentConfig;
+use crate::diagnostics::{Fatal, FailureKind};
+use crate::monitor::{ClipboardMonitor, MonitorEvent, ScanOutcome};
+use crate::qr_scanner::ScanSettings;
+use crate::redaction::Redactor;
+use crate::remote::{Outbox, PushClient, PushPayload, PushSource};
+
+pub struct AgentOptions {
+    pub host: Url,
+    pub token: String,
+    pub name: String,
+    pub forward_text: bool,
+    pub retry: Duration,
+    pub queue_size: usize,
+    pub scan: ScanSettings,
+    /// See [`ClipboardMonitor::give_up_after`]
+    pub give_up: Option<Duration>,
+}
+
+impl AgentOptions {
+    /// Combine the config with command line overrides; agents always authenticate
+    pub fn new(config: &AgentConfig, host: Option<Url>, token: Option<String>, name: Option<String>) -> Result<Self> {
+        let host = host
+            .or_else(|| config.host.clone())
+            .ok_or_else(|| anyhow::anyhow!("No host to forward to; pass --to or set [agent] host in the config"))?;
+        let token = token
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Agent mode needs a token; pass --token or set [remote] token in the config"))?;
+        Ok(Self {
+            host,
+            token,
+            name: name.or_else(|| config.name.clone()).unwrap_or_else(machine_name),
+            forward_text: config.forward_text,
+            retry: Duration::from_secs(config.retry_secs.max(1)),
+            queue_size: config.queue_size,
+            scan: ScanSettings::default(),
+            give_up: None,
+        })
+    }
+}
+
+/// Forward clipboard changes inside a VM or remote session to the host instance until Ctrl+C
+///
+/// Images are scanned here, in the guest, so QR codes that only ever reach the guest's
+/// clipboard still arrive on the host as text. Changes made while the host is unreachable
+/// are queued and sent in order once it is back.
+pub fn run(options: AgentOptions, redactor: Redactor) -> Result<()> {
+    let client = PushClient::new(&options.host, Some(options.token))?;
+    info!("Agent '{}' forwarding to {}", options.name, client.endpoint());
+    println!("Agent '{}' forwarding clipboard changes to {} (Ctrl+C to stop)", options.name, client.endpoint());
+
+    let (tx, rx) = mpsc::channel::<AgentEvent>();
+    let stop = tx.clone();
+    ctrlc::set_handler(move || {
+        let _ = stop.send(AgentEvent::Stop);
+    })?;
+    let lost = tx.clone();
+
+    let name = options.name.clone();
+    let forward_text = options.forward_text;
+    let scan = options.scan;
+    let monitor = ClipboardMonitor::new()
+        .with_scanner(move || scan.scanner())
+        .give_up_after(options.give_up, move |reason| {
+            let _ = lost.send(AgentEvent::ClipboardLost(reason));
+        })
+        .start(move |event, _context| {
+            let (text, source) = match event {
+                MonitorEvent::Text(text) if forward_text => (text.clone(), PushSource::Clipboard),
+                MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } => (content.clone(), PushSource::QrScan),
+                _ => return,
+            };
+            let _ = tx.send(AgentEvent::Change(PushPayload { text, source, origin: Some(name.clone()) }));
+        });
+
+    let mut outbox = Outbox::new(options.queue_size);
+    let mut reachable = true;
+    let mut failure = None;
+    loop {
+        match rx.recv_timeout(options.retry) {
+            Ok(AgentEvent::Change(payload)) => outbox.push(payload),
+            Ok(AgentEvent::ClipboardLost(reason)) => {
+                failure = Some(reason);
+                break;
+            }
+            Ok(AgentEvent::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+            // Nothing new; retry whatever is queued
+            Err(RecvTimeoutError::Timeout) if outbox.is_empty() => continue,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        let result = outbox.flush(|payload| {
+            client.push(payload)?;
+            println!("➡️  {}", redactor.text(&payload.text));
+            Ok(())
+        });
+        match result {
+            Ok(_) if !reachable => {
+                info!("Host {} reachable again", client.endpoint());
+                reachable = true;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                // Log the outage once rather than on every retry
+                if reachable {
+                    warn!("Host unreachable, queueing clipboard changes: {}", e);
+                    println!("⏸️  {} ({} change(s) queued)", e, outbox.len());
+                    reachable = false;
+                }
+            }
+        }
+    }
+
+    monitor.stop();
+    if !outbox.is_empty() || outbox.dropped() > 0 {
+        warn!("Agent stopped with {} unsent and {} dropped change(s)", outbox.len(), outbox.dropped());
+    }
+    match failure {
+        Some(reason) => Err(Fatal::new(FailureKind::ClipboardUnavailable, format!("Clipboard unavailable: {}", reason)).into()),
+        None => Ok(()),
+    }
+}
+
+enum AgentEvent {
+    Change(PushPayload),
+    /// The monitor gave up on the clipboard
+    ClipboardLost(String),
+    Stop,
+}
+
+/// The computer name, as other machines on the network see it
+fn machine_name() -> String {
+    let from_env = if cfg!(windows) { "COMPUTERNAME" } else { "HOSTNAME" };
+    std::env::var(from_env)
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "agent".to_string())
+}
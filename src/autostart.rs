@@ -0,0 +1,185 @@
+use anyhow::Result;
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::storage;
+
+/// Label of the macOS LaunchAgent, also its file name
+const LAUNCH_AGENT_LABEL: &str = "moe.undefined.clipboard-qr";
+
+/// Key of the Windows programs started at login, and this app's value in it
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+#[cfg(windows)]
+const RUN_VALUE: &str = "ClipboardQR";
+
+/// Whether the app is set to start at login
+pub fn is_enabled() -> Result<bool> {
+    platform::is_installed()
+}
+
+/// Start the app at login from where its executable is now; an existing entry is updated,
+/// so a moved executable keeps starting
+pub fn enable() -> Result<()> {
+    let exe = std::env::current_exe()?;
+    platform::install(&exe)?;
+    info!("Starting at login: {:?}", exe);
+    Ok(())
+}
+
+/// Stop starting the app at login; nothing happens if it isn't set to
+pub fn disable() -> Result<()> {
+    if !is_enabled()? {
+        return Ok(());
+    }
+    platform::remove()?;
+    info!("No longer starting at login");
+    Ok(())
+}
+
+/// [`enable`] or [`disable`] starting at login
+pub fn set(enabled: bool) -> Result<()> {
+    if enabled {
+        enable()
+    } else {
+        disable()
+    }
+}
+
+/// An XDG autostart entry running `exe`
+///
+/// The path is quoted as the Exec key requires, then escaped as a string value.
+pub fn desktop_entry(exe: &Path) -> String {
+    let mut quoted = String::from('"');
+    for c in exe.display().to_string().chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    let exec = quoted.replace('\\', "\\\\").replace('%', "%%");
+    format!(
+        "[Desktop Entry]\nType=Application\nName=Clipboard QR\nComment={}\nExec={}\nTerminal=false\nX-GNOME-Autostart-enabled=true\n",
+        env!("CARGO_PKG_DESCRIPTION"),
+        exec
+    )
+}
+
+/// A macOS LaunchAgent running `exe` at login
+pub fn launch_agent_plist(exe: &Path) -> String {
+    let escaped = exe.display().to_string().replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        LAUNCH_AGENT_LABEL, escaped
+    )
+}
+
+/// Where the autostart entry is written
+#[cfg(target_os = "macos")]
+fn entry_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join("Library").join("LaunchAgents").join(format!("{}.plist", LAUNCH_AGENT_LABEL)))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn entry_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("autostart").join("clipboard-qr.desktop"))
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::fs;
+
+    use super::*;
+
+    fn path() -> Result<PathBuf> {
+        entry_path().ok_or_else(|| anyhow::anyhow!("No directory for autostart entries"))
+    }
+
+    pub fn is_installed() -> Result<bool> {
+        Ok(path()?.exists())
+    }
+
+    pub fn install(exe: &Path) -> Result<()> {
+        let path = path()?;
+        storage::check_write("the autostart entry", &path)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let entry = if cfg!(target_os = "macos") { launch_agent_plist(exe) } else { desktop_entry(exe) };
+        fs::write(&path, entry).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn remove() -> Result<()> {
+        let path = path()?;
+        fs::remove_file(&path).map_err(|e| anyhow::anyhow!("Failed to remove {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::process::{Command, Stdio};
+
+    use super::*;
+
+    fn reg(args: &[&str]) -> Result<()> {
+        let output = Command::new("reg").args(args).stdin(Stdio::null()).output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("reg {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        Ok(())
+    }
+
+    pub fn is_installed() -> Result<bool> {
+        let status = Command::new("reg")
+            .args(["query", RUN_KEY, "/v", RUN_VALUE])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        Ok(status.success())
+    }
+
+    pub fn install(exe: &Path) -> Result<()> {
+        storage::check_write("the autostart entry", Path::new(RUN_KEY))?;
+        let command = format!("\"{}\"", exe.display());
+        reg(&["add", RUN_KEY, "/v", RUN_VALUE, "/t", "REG_SZ", "/d", &command, "/f"])
+    }
+
+    pub fn remove() -> Result<()> {
+        reg(&["delete", RUN_KEY, "/v", RUN_VALUE, "/f"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries() {
+        let entry = desktop_entry(Path::new("/opt/Clipboard QR/100% \"real\"/clipboard-qr"));
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry.contains("\nExec=\"/opt/Clipboard QR/100%% \\\\\"real\\\\\"/clipboard-qr\"\n"));
+
+        let plist = launch_agent_plist(Path::new("/Applications/Q&A <1>.app/Contents/MacOS/clipboard-qr"));
+        assert!(plist.contains("<string>/Applications/Q&amp;A &lt;1&gt;.app/Contents/MacOS/clipboard-qr</string>"));
+        assert!(plist.contains("<key>RunAtLoad</key>\n    <true/>"));
+        assert!(plist.contains(LAUNCH_AGENT_LABEL));
+    }
+}
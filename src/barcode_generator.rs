@@ -0,0 +1,368 @@
+use anyhow::Result;
+use image::{ImageOutputFormat, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+use std::io::Cursor;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::qr_style::QrStyle;
+
+/// Light modules either side of the bars; Code 128 and Code 39 ask for ten, EAN-13 for
+/// eleven on the left
+const QUIET_ZONE: usize = 11;
+
+/// Height of the bars in modules
+const BAR_HEIGHT: usize = 50;
+
+/// Height of the band with the text under the bars in SVGs, in modules
+const TEXT_BAND: usize = 10;
+
+/// Pixels per module in PNGs and SVG sizes
+pub const SCALE: u32 = 3;
+
+/// Modules of a wide Code 39 element; narrow ones take one
+const CODE39_WIDE: usize = 3;
+
+/// Kind of 1D barcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BarcodeSymbology {
+    /// Any printable ASCII; runs of digits are packed two to a symbol
+    #[default]
+    Code128,
+    /// 12 digits and a check digit, as on retail products
+    Ean13,
+    /// Upper case letters, digits and - . $ / + % and space, as on older inventory labels
+    Code39,
+}
+
+impl BarcodeSymbology {
+    pub const ALL: [BarcodeSymbology; 3] = [BarcodeSymbology::Code128, BarcodeSymbology::Ean13, BarcodeSymbology::Code39];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BarcodeSymbology::Code128 => "code128",
+            BarcodeSymbology::Ean13 => "ean13",
+            BarcodeSymbology::Code39 => "code39",
+        }
+    }
+
+    /// Label for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            BarcodeSymbology::Code128 => "Code 128",
+            BarcodeSymbology::Ean13 => "EAN-13",
+            BarcodeSymbology::Code39 => "Code 39",
+        }
+    }
+}
+
+impl FromStr for BarcodeSymbology {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|symbology| symbology.name() == s)
+            .ok_or_else(|| format!("unknown barcode type '{}', expected code128, ean13 or code39", s))
+    }
+}
+
+impl fmt::Display for BarcodeSymbology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An encoded 1D barcode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Barcode {
+    pub symbology: BarcodeSymbology,
+    /// Dark (`true`) and light modules from left to right, without the quiet zone
+    pub modules: Vec<bool>,
+    /// The text printed under the bars; for EAN-13 with its check digit
+    pub text: String,
+}
+
+impl Barcode {
+    /// Encode `text` as `symbology`; fails for text the symbology can't hold
+    pub fn encode(symbology: BarcodeSymbology, text: &str) -> Result<Self> {
+        if text.is_empty() {
+            return Err(anyhow::anyhow!("No text to generate a barcode"));
+        }
+        let (modules, text) = match symbology {
+            BarcodeSymbology::Code128 => (modules(&code128_widths(&code128_values(text)?)), text.to_string()),
+            BarcodeSymbology::Ean13 => ean13(text)?,
+            BarcodeSymbology::Code39 => (code39(text)?, text.to_string()),
+        };
+        Ok(Self { symbology, modules, text })
+    }
+
+    /// Modules across, quiet zones included
+    pub fn width(&self) -> usize {
+        self.modules.len() + 2 * QUIET_ZONE
+    }
+
+    /// The bars in the style's colours, `scale` pixels per module
+    pub fn rasterize(&self, style: &QrStyle, scale: u32) -> RgbaImage {
+        let (dark, light) = (style.foreground.to_rgba(), style.background.to_rgba());
+        RgbaImage::from_fn(self.width() as u32 * scale, BAR_HEIGHT as u32 * scale, |x, _| {
+            let module = (x / scale) as usize;
+            match module.checked_sub(QUIET_ZONE).and_then(|index| self.modules.get(index)) {
+                Some(true) => dark,
+                _ => light,
+            }
+        })
+    }
+
+    pub fn to_png(&self, style: &QrStyle) -> Result<Vec<u8>> {
+        let mut bytes = Cursor::new(Vec::new());
+        self.rasterize(style, SCALE).write_to(&mut bytes, ImageOutputFormat::Png)?;
+        Ok(bytes.into_inner())
+    }
+
+    /// The bars as rectangles in module units, with the text in a band underneath
+    pub fn to_svg(&self, style: &QrStyle) -> Result<String> {
+        let (width, height) = (self.width(), BAR_HEIGHT + TEXT_BAND);
+        let mut svg = String::from("<?xml version=\"1.0\" standalone=\"yes\"?>");
+        write!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {width} {height}\" shape-rendering=\"crispEdges\">",
+            width as u32 * SCALE,
+            height as u32 * SCALE
+        )?;
+        write!(svg, "<path d=\"M0 0h{width}v{height}H0z\" fill=\"{}\"/>", style.background.to_hex())?;
+        write!(svg, "<path fill=\"{}\" d=\"", style.foreground.to_hex())?;
+        let mut x = 0;
+        while x < self.modules.len() {
+            let run = self.modules[x..].iter().take_while(|&&dark| dark == self.modules[x]).count();
+            if self.modules[x] {
+                write!(svg, "M{} 0h{run}v{BAR_HEIGHT}h-{run}z", x + QUIET_ZONE)?;
+            }
+            x += run;
+        }
+        svg.push_str("\"/>");
+        let escaped = self.text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        write!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"8\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>",
+            width as f32 / 2.0,
+            BAR_HEIGHT as f32 + TEXT_BAND as f32 / 2.0,
+            style.foreground.to_hex(),
+            escaped
+        )?;
+        svg.push_str("</svg>");
+        Ok(svg)
+    }
+
+    /// PNG or SVG, by the extension of `path`; PNG without one
+    pub fn render_for_path(&self, path: &Path, style: &QrStyle) -> Result<Vec<u8>> {
+        match path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            None | Some("png") => self.to_png(style),
+            Some("svg") => Ok(self.to_svg(style)?.into_bytes()),
+            Some(ext) => Err(anyhow::anyhow!("Barcodes are written as PNG or SVG, not .{}", ext)),
+        }
+    }
+}
+
+/// Bar and space widths of Code 128 symbols 0 to 105, bar first; each adds up to 11 modules
+const CODE128_PATTERNS: [&str; 106] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213",
+    "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132",
+    "221231", "213212", "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211",
+    "212123", "212321", "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+    "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121", "313121", "211331",
+    "231131", "213113", "213311", "213131", "311123", "311321", "331121", "312113", "312311", "332111",
+    "314111", "221411", "431111", "111224", "111422", "121124", "121421", "141122", "141221", "112214",
+    "112412", "122114", "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+    "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311", "113141",
+    "114131", "311141", "411131", "211412", "211214", "211232",
+];
+
+const CODE128_STOP: &str = "2331112";
+const CODE128_START_B: u8 = 104;
+const CODE128_START_C: u8 = 105;
+const CODE128_TO_B: u8 = 100;
+const CODE128_TO_C: u8 = 99;
+
+/// Symbol values for `text`, start and check symbols included: code set B, switching to
+/// C for runs of digits long enough to save space
+fn code128_values(text: &str) -> Result<Vec<u8>> {
+    if let Some(c) = text.chars().find(|c| !(' '..='~').contains(c)) {
+        return Err(anyhow::anyhow!("Code 128 encodes printable ASCII only, not {:?}", c));
+    }
+    let bytes = text.as_bytes();
+    let digit_run = |from: usize| bytes[from..].iter().take_while(|b| b.is_ascii_digit()).count();
+
+    let mut in_c = digit_run(0) >= 4;
+    let mut values = vec![if in_c { CODE128_START_C } else { CODE128_START_B }];
+    let mut i = 0;
+    while i < bytes.len() {
+        let run = digit_run(i);
+        if in_c {
+            if run >= 2 {
+                values.push((bytes[i] - b'0') * 10 + bytes[i + 1] - b'0');
+                i += 2;
+            } else {
+                values.push(CODE128_TO_B);
+                in_c = false;
+            }
+            continue;
+        }
+        // Switching costs a symbol, so it only pays for six digits, or four at the end
+        if run >= 6 || (run >= 4 && i + run == bytes.len()) {
+            if run % 2 == 1 {
+                values.push(bytes[i] - b' ');
+                i += 1;
+            }
+            values.push(CODE128_TO_C);
+            in_c = true;
+            continue;
+        }
+        values.push(bytes[i] - b' ');
+        i += 1;
+    }
+
+    let check = values.iter().enumerate().map(|(position, &value)| position.max(1) * value as usize).sum::<usize>() % 103;
+    values.push(check as u8);
+    Ok(values)
+}
+
+/// Element widths of `values` followed by the stop pattern
+fn code128_widths(values: &[u8]) -> Vec<usize> {
+    values
+        .iter()
+        .map(|&value| CODE128_PATTERNS[value as usize])
+        .chain([CODE128_STOP])
+        .flat_map(|pattern| pattern.bytes().map(|width| (width - b'0') as usize))
+        .collect()
+}
+
+/// Alternating bars and spaces of the given widths, starting with a bar
+fn modules(widths: &[usize]) -> Vec<bool> {
+    widths.iter().enumerate().flat_map(|(index, &width)| std::iter::repeat_n(index % 2 == 0, width)).collect()
+}
+
+/// Left-hand odd parity patterns of the digits 0 to 9; right-hand ones are their inverse
+/// and even parity ones the inverse reversed
+const EAN_L: [&str; 10] = ["0001101", "0011001", "0010011", "0111101", "0100011", "0110001", "0101111", "0111011", "0110111", "0001011"];
+
+/// Parity of the six left-hand digits, by the first digit, which isn't drawn otherwise
+const EAN_PARITY: [&str; 10] = ["LLLLLL", "LLGLGG", "LLGGLG", "LLGGGL", "LGLLGG", "LGGLLG", "LGGGLL", "LGLGLG", "LGLGGL", "LGGLGL"];
+
+/// Check digit of the first twelve digits of an EAN-13
+fn ean13_check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits.iter().enumerate().map(|(index, &digit)| digit as u32 * if index % 2 == 0 { 1 } else { 3 }).sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+/// Modules of 12 digits, or 13 with a correct check digit, and the 13 digits printed
+fn ean13(text: &str) -> Result<(Vec<bool>, String)> {
+    if !text.bytes().all(|b| b.is_ascii_digit()) || !(12..=13).contains(&text.len()) {
+        return Err(anyhow::anyhow!("EAN-13 encodes 12 digits, or 13 with the check digit"));
+    }
+    let mut digits: Vec<u8> = text.bytes().map(|b| b - b'0').collect();
+    let check = ean13_check_digit(&digits[..12]);
+    match digits.get(12) {
+        Some(&given) if given != check => return Err(anyhow::anyhow!("The EAN-13 check digit of {} should be {}", &text[..12], check)),
+        Some(_) => {}
+        None => digits.push(check),
+    }
+
+    let bits = |pattern: &str| pattern.bytes().map(|b| b == b'1').collect::<Vec<bool>>();
+    let mut modules = bits("101");
+    for (index, &digit) in digits[1..7].iter().enumerate() {
+        let mut pattern = bits(EAN_L[digit as usize]);
+        if EAN_PARITY[digits[0] as usize].as_bytes()[index] == b'G' {
+            pattern = pattern.into_iter().rev().map(|dark| !dark).collect();
+        }
+        modules.extend(pattern);
+    }
+    modules.extend(bits("01010"));
+    for &digit in &digits[7..] {
+        modules.extend(bits(EAN_L[digit as usize]).into_iter().map(|dark| !dark));
+    }
+    modules.extend(bits("101"));
+    let text = digits.iter().map(|digit| char::from(b'0' + digit)).collect();
+    Ok((modules, text))
+}
+
+/// Code 39 characters with their nine elements, bar first; `1` marks the three wide ones
+const CODE39_PATTERNS: [(char, &str); 44] = [
+    ('0', "000110100"), ('1', "100100001"), ('2', "001100001"), ('3', "101100000"), ('4', "000110001"),
+    ('5', "100110000"), ('6', "001110000"), ('7', "000100101"), ('8', "100100100"), ('9', "001100100"),
+    ('A', "100001001"), ('B', "001001001"), ('C', "101001000"), ('D', "000011001"), ('E', "100011000"),
+    ('F', "001011000"), ('G', "000001101"), ('H', "100001100"), ('I', "001001100"), ('J', "000011100"),
+    ('K', "100000011"), ('L', "001000011"), ('M', "101000010"), ('N', "000010011"), ('O', "100010010"),
+    ('P', "001010010"), ('Q', "000000111"), ('R', "100000110"), ('S', "001000110"), ('T', "000010110"),
+    ('U', "110000001"), ('V', "011000001"), ('W', "111000000"), ('X', "010010001"), ('Y', "110010000"),
+    ('Z', "011010000"), ('-', "010000101"), ('.', "110000100"), (' ', "011000100"), ('$', "010101000"),
+    ('/', "010100010"), ('+', "010001010"), ('%', "000101010"), ('*', "010010100"),
+];
+
+/// Modules of `text` between the `*` start and stop characters, a narrow space apart
+fn code39(text: &str) -> Result<Vec<bool>> {
+    let pattern = |c: char| CODE39_PATTERNS.iter().find(|(symbol, _)| *symbol == c).map(|(_, pattern)| *pattern);
+    if let Some(c) = text.chars().find(|&c| c == '*' || pattern(c).is_none()) {
+        return Err(anyhow::anyhow!("Code 39 encodes upper case letters, digits, space and - . $ / + % only, not {:?}", c));
+    }
+    let mut modules = Vec::new();
+    for (index, c) in std::iter::once('*').chain(text.chars()).chain(['*']).enumerate() {
+        if index > 0 {
+            modules.push(false);
+        }
+        let widths: Vec<usize> = pattern(c).unwrap_or_default().bytes().map(|b| if b == b'1' { CODE39_WIDE } else { 1 }).collect();
+        modules.extend(self::modules(&widths));
+    }
+    Ok(modules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_barcodes() {
+        // Every Code 128 symbol is 11 modules with an even number of bar modules
+        for pattern in CODE128_PATTERNS {
+            let widths: Vec<u32> = pattern.bytes().map(|b| (b - b'0') as u32).collect();
+            assert_eq!(widths.iter().sum::<u32>(), 11, "{}", pattern);
+            assert_eq!(widths.iter().step_by(2).sum::<u32>() % 2, 0, "{}", pattern);
+        }
+        // Letters in code set B, then code set C for the six digits
+        assert_eq!(code128_values("HI345678").unwrap(), [104, 40, 41, 99, 34, 56, 78, 68]);
+        assert_eq!(code128_values("2024").unwrap(), [105, 20, 24, 70]);
+        assert!(code128_values("tab\there").is_err());
+        let code = Barcode::encode(BarcodeSymbology::Code128, "HI345678").unwrap();
+        assert_eq!(code.modules.len(), 8 * 11 + 13);
+
+        let ean = Barcode::encode(BarcodeSymbology::Ean13, "400638133393").unwrap();
+        assert_eq!(ean.text, "4006381333931");
+        assert_eq!(ean.modules.len(), 95);
+        assert_eq!(Barcode::encode(BarcodeSymbology::Ean13, "4006381333931").unwrap(), ean);
+        assert!(Barcode::encode(BarcodeSymbology::Ean13, "4006381333932").unwrap_err().to_string().contains("should be 1"));
+        assert!(Barcode::encode(BarcodeSymbology::Ean13, "12345").is_err());
+
+        // Code 39 characters have three wide elements: two bars and a space, or three spaces
+        for (_, pattern) in CODE39_PATTERNS {
+            let wide: Vec<usize> = pattern.bytes().enumerate().filter(|(_, b)| *b == b'1').map(|(index, _)| index).collect();
+            assert_eq!(wide.len(), 3, "{}", pattern);
+            assert!(matches!(wide.iter().filter(|index| *index % 2 == 0).count(), 0 | 2), "{}", pattern);
+        }
+        let code39 = Barcode::encode(BarcodeSymbology::Code39, "BOX-12").unwrap();
+        assert_eq!(code39.modules.len(), 8 * (6 + 3 * CODE39_WIDE) + 7);
+        assert!(Barcode::encode(BarcodeSymbology::Code39, "box-12").is_err());
+
+        let style = QrStyle::default();
+        let image = code.rasterize(&style, 2);
+        assert_eq!((image.width(), image.height()), ((code.modules.len() + 22) as u32 * 2, 100));
+        assert_eq!(*image.get_pixel(0, 0), style.background.to_rgba());
+        assert_eq!(*image.get_pixel(22, 0), style.foreground.to_rgba());
+        let svg = ean.to_svg(&style).unwrap();
+        assert!(svg.contains("viewBox=\"0 0 117 60\"") && svg.contains(">4006381333931</text>"));
+        assert!(ean.render_for_path(Path::new("label.jpg"), &style).is_err());
+        assert_eq!("ean13".parse::<BarcodeSymbology>(), Ok(BarcodeSymbology::Ean13));
+    }
+}
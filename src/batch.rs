@@ -0,0 +1,181 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::generator_pool::{GenerationJob, GeneratorPool};
+
+/// One code of a batch and where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchItem {
+    /// Line of the input the code was read from, for error messages
+    pub line: usize,
+    pub text: String,
+    /// File name from the CSV's name column; numbered after `line` without one
+    pub name: Option<String>,
+}
+
+/// One code per non-empty line
+pub fn parse_lines(input: &str) -> Vec<BatchItem> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(index, text)| BatchItem {
+            line: index + 1,
+            text: text.to_string(),
+            name: None,
+        })
+        .collect()
+}
+
+/// One code per row of a CSV with a header row, encoding `text_column` and named after
+/// `name_column` when the file has one
+pub fn parse_csv(input: &str, text_column: &str, name_column: &str) -> Result<Vec<BatchItem>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(input.as_bytes());
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|header| header.trim().eq_ignore_ascii_case(name));
+    let text_index = column(text_column).ok_or_else(|| {
+        anyhow!(
+            "CSV has no '{}' column; found: {}",
+            text_column,
+            headers.iter().collect::<Vec<_>>().join(", ")
+        )
+    })?;
+    let name_index = column(name_column);
+
+    let mut items = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |position| position.line() as usize);
+        let text = record.get(text_index).unwrap_or_default();
+        if text.trim().is_empty() {
+            warn!("Skipping CSV line {} without text", line);
+            continue;
+        }
+        let name = name_index
+            .and_then(|index| record.get(index))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string);
+        items.push(BatchItem {
+            line,
+            text: text.to_string(),
+            name,
+        });
+    }
+    Ok(items)
+}
+
+/// File names for `items` with the `extension` of the output format: the CSV name made safe
+/// for the file system, or qr_<line> without one; repeated names get a _2, _3... suffix
+pub fn file_names(items: &[BatchItem], extension: &str) -> Vec<String> {
+    let width = items.last().map_or(1, |item| item.line.to_string().len());
+    let suffix = format!(".{}", extension);
+    let mut taken = HashSet::new();
+    items
+        .iter()
+        .map(|item| {
+            let stem = match &item.name {
+                Some(name) => {
+                    let name = name.strip_suffix(&suffix).unwrap_or(name);
+                    let safe: String = name
+                        .chars()
+                        .map(|c| if c.is_alphanumeric() || "-_. ".contains(c) { c } else { '_' })
+                        .collect();
+                    // No hidden files or names made only of dots
+                    safe.trim_matches(|c: char| c == '.' || c == ' ').to_string()
+                }
+                None => String::new(),
+            };
+            let stem = if stem.is_empty() { format!("qr_{:0width$}", item.line, width = width) } else { stem };
+            let mut name = format!("{}{}", stem, suffix);
+            let mut copy = 1;
+            while !taken.insert(name.to_lowercase()) {
+                copy += 1;
+                name = format!("{}_{}{}", stem, copy, suffix);
+            }
+            name
+        })
+        .collect()
+}
+
+/// What a batch run wrote and what it couldn't
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub written: Vec<PathBuf>,
+    /// Input line and error of each code that failed
+    pub failed: Vec<(usize, String)>,
+}
+
+/// Generate every item with the renderer called `format` on the pool's workers and write them
+/// to `out_dir`, calling `progress` with the number done and the total after each one
+pub fn run(
+    pool: &GeneratorPool,
+    items: &[BatchItem],
+    out_dir: &Path,
+    format: &str,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<BatchReport> {
+    fs::create_dir_all(out_dir)?;
+    info!("Generating {} QR code(s) into {:?} with {} worker(s)", items.len(), out_dir, pool.workers());
+
+    // Queue everything first so all workers stay busy, then collect in input order
+    let pending: Vec<_> = items.iter().map(|item| pool.submit(GenerationJob::new(item.text.clone(), format))).collect();
+    let mut report = BatchReport::default();
+    for (done, ((item, name), result)) in items.iter().zip(file_names(items, format)).zip(pending).enumerate() {
+        let written = result
+            .recv()
+            .map_err(|_| anyhow!("Generator worker stopped unexpectedly"))
+            .and_then(|bytes| bytes)
+            .and_then(|bytes| {
+                let path = out_dir.join(name);
+                fs::write(&path, bytes)?;
+                Ok(path)
+            });
+        match written {
+            Ok(path) => report.written.push(path),
+            Err(e) => {
+                warn!("Batch line {} failed: {}", item.line, e);
+                report.failed.push((item.line, e.to_string()));
+            }
+        }
+        progress(done + 1, items.len());
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qr_style::QrStyle;
+
+    #[test]
+    fn test_batch_from_csv() {
+        let input = "filename,text\nmenu,https://example.com/menu\n,https://example.com/a\n../secret,https://example.com/b\nmenu.svg,https://example.com/c\nempty,\n";
+        let items = parse_csv(input, "text", "filename").unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[0].name.as_deref(), Some("menu"));
+        assert_eq!(items[1].line, 3);
+        assert_eq!(file_names(&items, "svg"), ["menu.svg", "qr_3.svg", "_secret.svg", "menu_2.svg"]);
+        assert!(parse_csv(input, "url", "filename").is_err());
+
+        let lines = parse_lines("one\n\ntwo\n");
+        assert_eq!(lines.iter().map(|item| item.line).collect::<Vec<_>>(), [1, 3]);
+
+        let dir = std::env::temp_dir().join(format!("clipboard-qr-batch-{}", std::process::id()));
+        let pool = GeneratorPool::new(2, QrStyle::default());
+        let mut calls = 0;
+        let report = run(&pool, &items, &dir, "svg", |_, total| {
+            calls += 1;
+            assert_eq!(total, 4);
+        })
+        .unwrap();
+        assert_eq!(calls, 4);
+        assert_eq!(report.written.len(), 4);
+        assert!(report.failed.is_empty());
+        assert!(fs::read_to_string(dir.join("menu_2.svg")).unwrap().starts_with("<?xml"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
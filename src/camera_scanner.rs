@@ -0,0 +1,283 @@
+use anyhow::Result;
+use eframe::egui;
+use image::RgbaImage;
+use nokhwa::pixel_format::RgbFormat;
+use nokhwa::utils::{ApiBackend, CameraIndex, RequestedFormat, RequestedFormatType};
+use nokhwa::Camera;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tracing::{debug, error, info, warn};
+
+use crate::clipboard_handler::ClipboardHandler;
+use crate::content_classifier::ContentClass;
+use crate::qr_scanner::{DecodedCode, ScanSettings};
+use crate::redaction::Redactor;
+
+const CAPTION_CHARS: usize = 80;
+
+/// The cameras the system offers, as (index, name)
+pub fn list() -> Result<Vec<(u32, String)>> {
+    let cameras = nokhwa::query(ApiBackend::Auto).map_err(|e| anyhow::anyhow!("Failed to list cameras: {}", e))?;
+    Ok(cameras
+        .iter()
+        .map(|camera| {
+            let index = match camera.index() {
+                CameraIndex::Index(index) => *index,
+                CameraIndex::String(_) => 0,
+            };
+            (index, camera.human_name())
+        })
+        .collect())
+}
+
+/// What the capture thread last saw
+#[derive(Debug, Default)]
+struct CameraState {
+    /// The newest frame and whether the preview has shown it yet
+    frame: Option<(RgbaImage, bool)>,
+    /// The newest decoded content and whether it has been copied yet
+    decoded: Option<(String, bool)>,
+    /// Every code in the newest frame
+    codes: Vec<DecodedCode>,
+    /// Why capturing stopped, if it failed
+    error: Option<String>,
+}
+
+/// Frames from a camera, each scanned for a QR code on a background thread
+///
+/// Frames are scanned one after the other, so the preview runs at the speed of the scanner
+/// rather than the camera; that is still several frames a second for a laptop camera.
+struct CameraScanner {
+    state: Arc<Mutex<CameraState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CameraScanner {
+    fn start(index: u32, scan: ScanSettings, ctx: egui::Context) -> Self {
+        let state = Arc::new(Mutex::new(CameraState::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_state = state.clone();
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || {
+            if let Err(e) = capture(index, &scan, &thread_state, &thread_stop, &ctx) {
+                error!("Camera capture stopped: {}", e);
+                if let Ok(mut state) = thread_state.lock() {
+                    state.error = Some(e.to_string());
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self { state, stop, thread: Some(thread) }
+    }
+}
+
+impl Drop for CameraScanner {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn capture(index: u32, scan: &ScanSettings, state: &Mutex<CameraState>, stop: &AtomicBool, ctx: &egui::Context) -> Result<()> {
+    let requested = RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestFrameRate);
+    let mut camera = Camera::new(CameraIndex::Index(index), requested).map_err(|e| anyhow::anyhow!("Failed to open camera {}: {}", index, e))?;
+    camera.open_stream().map_err(|e| anyhow::anyhow!("Failed to start camera {}: {}", index, e))?;
+    info!("Scanning with camera {} ({})", index, camera.info().human_name());
+
+    let scanner = scan.scanner();
+    while !stop.load(Ordering::Relaxed) {
+        let frame = camera.frame().map_err(|e| anyhow::anyhow!("Failed to read a camera frame: {}", e))?;
+        let rgb = frame.decode_image::<RgbFormat>().map_err(|e| anyhow::anyhow!("Failed to decode a camera frame: {}", e))?;
+        // nokhwa may use another version of the image crate, so go through the raw pixels
+        let (width, height) = (rgb.width(), rgb.height());
+        let rgba: Vec<u8> = rgb.into_raw().chunks_exact(3).flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255]).collect();
+        let Some(image) = RgbaImage::from_raw(width, height, rgba) else {
+            warn!("Skipping a {}x{} camera frame of the wrong size", width, height);
+            continue;
+        };
+
+        let codes = match scanner.scan_multiple_qr_codes(&image) {
+            Ok(codes) => codes,
+            Err(e) => {
+                debug!("Camera frame scan failed: {}", e);
+                Vec::new()
+            }
+        };
+        if let Ok(mut state) = state.lock() {
+            if let Some(content) = codes.first().map(|code| code.content.clone()) {
+                // Holding a code in front of the camera decodes it every frame; copy it once
+                if state.decoded.as_ref().is_none_or(|(last, _)| *last != content) {
+                    state.decoded = Some((content, false));
+                }
+            }
+            state.codes = codes;
+            state.frame = Some((image, false));
+        }
+        ctx.request_repaint();
+    }
+
+    let _ = camera.stop_stream();
+    Ok(())
+}
+
+/// Show a live preview of camera `index` and copy every new QR code it sees to the clipboard;
+/// Esc closes the window
+pub fn run(index: u32, scan: ScanSettings, redactor: Redactor) -> Result<()> {
+    info!("Starting camera scanning");
+
+    // macOS asks the user for camera access first
+    #[cfg(target_os = "macos")]
+    nokhwa::nokhwa_initialize(|granted| {
+        if !granted {
+            warn!("Camera access was not granted");
+        }
+    });
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_title("Clipboard QR Camera").with_inner_size([800.0, 680.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Clipboard QR Camera",
+        options,
+        Box::new(move |cc| Ok(Box::new(CameraApp::new(cc, index, scan, redactor)))),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to start camera window: {}", e))
+}
+
+struct CameraApp {
+    camera: CameraScanner,
+    clipboard: ClipboardHandler,
+    redactor: Redactor,
+    texture: Option<egui::TextureHandle>,
+    /// The last content copied, or why copying failed
+    status: Option<String>,
+    /// Codes in the frame on screen
+    codes: Vec<DecodedCode>,
+}
+
+impl CameraApp {
+    fn new(cc: &eframe::CreationContext<'_>, index: u32, scan: ScanSettings, redactor: Redactor) -> Self {
+        Self {
+            camera: CameraScanner::start(index, scan, cc.egui_ctx.clone()),
+            clipboard: ClipboardHandler::builder().spawn_listener(false).build(),
+            redactor,
+            texture: None,
+            status: None,
+            codes: Vec::new(),
+        }
+    }
+
+    fn copy(&mut self, content: &str) {
+        let preview = self.redactor.text_truncated(content, CAPTION_CHARS).replace(['\r', '\n', '\t'], " ");
+        self.status = Some(match self.clipboard.set_text(content) {
+            Ok(()) => {
+                info!("Copied QR code from camera: {}", self.redactor.text(content));
+                format!("✅ Copied: {}", preview)
+            }
+            Err(e) => format!("❌ Decoded {}, but copying failed: {}", preview, e),
+        });
+    }
+
+    /// A row per code in the frame, each with its own Copy button and smart actions
+    fn code_list(&mut self, ui: &mut egui::Ui) {
+        let mut copy = None;
+        let mut chosen = None;
+        for (index, code) in self.codes.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button("Copy").clicked() {
+                    copy = Some(index);
+                }
+                for action in ContentClass::classify(&code.content).actions() {
+                    if ui.button(action.label()).clicked() {
+                        chosen = Some(action);
+                    }
+                }
+                let preview = self.redactor.text_truncated(&code.content, CAPTION_CHARS).replace(['\r', '\n', '\t'], " ");
+                ui.label(format!("{} ({}x{} at {}, {})", preview, code.bounds.width, code.bounds.height, code.bounds.x, code.bounds.y));
+            });
+        }
+        if let Some(content) = copy.and_then(|index| self.codes.get(index)).map(|code| code.content.clone()) {
+            self.copy(&content);
+        }
+        if let Some(action) = chosen {
+            match action.copied_text() {
+                Some(text) => self.copy(&text),
+                None => {
+                    if let Err(e) = action.open() {
+                        self.status = Some(format!("❌ {} failed: {}", action.label(), e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Take the newest frame for the preview and copy newly decoded content
+    fn refresh(&mut self, ctx: &egui::Context) -> Option<String> {
+        let Ok(mut state) = self.camera.state.lock() else {
+            return None;
+        };
+        if let Some((image, shown)) = &mut state.frame {
+            if !*shown {
+                let size = [image.width() as usize, image.height() as usize];
+                let preview = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+                match &mut self.texture {
+                    Some(texture) => texture.set(preview, egui::TextureOptions::LINEAR),
+                    None => self.texture = Some(ctx.load_texture("camera-preview", preview, egui::TextureOptions::LINEAR)),
+                }
+                *shown = true;
+            }
+        }
+        self.codes.clone_from(&state.codes);
+        let mut copy = None;
+        if let Some((content, copied)) = &mut state.decoded {
+            if !*copied {
+                *copied = true;
+                copy = Some(content.clone());
+            }
+        }
+        let error = state.error.clone();
+        drop(state);
+        if let Some(content) = copy {
+            self.copy(&content);
+        }
+        error
+    }
+}
+
+impl eframe::App for CameraApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let error = self.refresh(ctx);
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
+        egui::TopBottomPanel::bottom("camera-status").show(ctx, |ui| {
+            let status = match (&error, &self.status) {
+                (Some(error), _) => format!("❌ {}", error),
+                (None, Some(status)) => status.clone(),
+                (None, None) => "Hold a QR code up to the camera".to_string(),
+            };
+            ui.label(status);
+            if !self.codes.is_empty() {
+                ui.separator();
+                self.code_list(ui);
+            }
+        });
+        egui::CentralPanel::default().show(ctx, |ui| match &self.texture {
+            Some(texture) => {
+                let available = ui.available_size();
+                let size = texture.size_vec2();
+                let scale = (available.x / size.x).min(available.y / size.y).min(1.0);
+                ui.centered_and_justified(|ui| ui.image((texture.id(), size * scale)));
+            }
+            None => {
+                ui.centered_and_justified(|ui| ui.label("Waiting for the camera…"));
+            }
+        });
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow::Result;
+use qrcode::{EcLevel, QrCode, Version};
+use std::fmt;
+
+use crate::report::{describe_ec_level, describe_version};
+
+/// Modules on each side of the code that must stay blank
+const QUIET_ZONE: usize = 4;
+
+/// Smallest module most printers and phone cameras handle reliably, in millimetres
+pub const MIN_MODULE_MM: f32 = 0.4;
+
+/// A phone camera resolves a module from about this many times its size away;
+/// the usual "scan from ten times the code width" rule for a typical code
+pub const DISTANCE_PER_MODULE: f32 = 300.0;
+
+/// Codes above this version get dense enough that phones struggle with them
+const COMFORTABLE_VERSION: i16 = 10;
+
+pub const EC_LEVELS: [EcLevel; 4] = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H];
+
+/// What will be encoded and how it will be scanned
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanInput {
+    pub payload: Payload,
+    /// Printed width of the code including the quiet zone, in millimetres
+    pub print_size_mm: Option<f32>,
+    /// Expected distance between phone and code, in millimetres
+    pub distance_mm: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    /// The actual content
+    Text(String),
+    /// Only the expected size; planned as arbitrary bytes, the least compact mode
+    Bytes(usize),
+}
+
+impl Payload {
+    fn data(&self) -> Vec<u8> {
+        match self {
+            Payload::Text(text) => text.as_bytes().to_vec(),
+            // Lowercase letters aren't alphanumeric in QR terms, so this forces byte mode
+            Payload::Bytes(len) => vec![b'a'; *len],
+        }
+    }
+}
+
+/// The code one error correction level would produce
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanOption {
+    pub error_correction: EcLevel,
+    /// `None` if the payload doesn't fit at this level
+    pub version: Option<Version>,
+    /// Modules per side, without the quiet zone
+    pub modules: usize,
+    /// Module size at the requested print size
+    pub module_mm: Option<f32>,
+    /// How far away the code can be scanned at the requested print size
+    pub max_distance_mm: Option<f32>,
+    /// Smallest print size that still scans from the requested distance
+    pub min_print_mm: Option<f32>,
+    /// Scannable under the requested conditions
+    pub suitable: bool,
+}
+
+/// One option per error correction level, lowest first, and the one to use
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub input: PlanInput,
+    pub options: Vec<PlanOption>,
+    /// Index into `options`; `None` if nothing is suitable
+    pub recommended: Option<usize>,
+}
+
+impl Plan {
+    pub fn recommended(&self) -> Option<&PlanOption> {
+        self.recommended.map(|index| &self.options[index])
+    }
+}
+
+/// Work out the code size at each error correction level and recommend the most
+/// robust one that still scans under the given conditions
+pub fn plan(input: PlanInput) -> Result<Plan> {
+    let data = input.payload.data();
+    let required_module_mm = input
+        .distance_mm
+        .map_or(MIN_MODULE_MM, |distance| (distance / DISTANCE_PER_MODULE).max(MIN_MODULE_MM));
+
+    let options: Vec<PlanOption> = EC_LEVELS
+        .into_iter()
+        .map(|level| {
+            let Ok(code) = QrCode::with_error_correction_level(&data, level) else {
+                return PlanOption {
+                    error_correction: level,
+                    version: None,
+                    modules: 0,
+                    module_mm: None,
+                    max_distance_mm: None,
+                    min_print_mm: None,
+                    suitable: false,
+                };
+            };
+            let modules = code.width();
+            let total = (modules + 2 * QUIET_ZONE) as f32;
+            let module_mm = input.print_size_mm.map(|size| size / total);
+            let suitable = match module_mm {
+                Some(module_mm) => module_mm >= required_module_mm,
+                // Without a print size any code can be printed large enough;
+                // just keep away from very dense versions
+                None => version_number(code.version()) <= COMFORTABLE_VERSION,
+            };
+            PlanOption {
+                error_correction: level,
+                version: Some(code.version()),
+                modules,
+                module_mm,
+                max_distance_mm: module_mm.map(|module_mm| module_mm * DISTANCE_PER_MODULE),
+                min_print_mm: Some(total * required_module_mm),
+                suitable,
+            }
+        })
+        .collect();
+
+    if options.iter().all(|option| option.version.is_none()) {
+        return Err(anyhow::anyhow!("The payload is too large for any QR code"));
+    }
+
+    // Options are ordered by error correction, so the last suitable one is the most robust
+    let recommended = options.iter().rposition(|option| option.suitable);
+    Ok(Plan { input, options, recommended })
+}
+
+fn version_number(version: Version) -> i16 {
+    match version {
+        Version::Normal(v) | Version::Micro(v) => v,
+    }
+}
+
+/// Parse a length such as `5cm`, `40mm`, `2in` or `1.5m` into millimetres; a bare number is millimetres
+pub fn parse_length(value: &str) -> Result<f32, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f32 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid length '{}', expected e.g. 5cm, 40mm, 2in or 1.5m", value))?;
+    let factor = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "mm" => 1.0,
+        "cm" => 10.0,
+        "m" => 1000.0,
+        "in" => 25.4,
+        other => return Err(format!("unknown length unit '{}', expected mm, cm, m or in", other)),
+    };
+    if number <= 0.0 {
+        return Err(format!("length must be positive, got '{}'", value));
+    }
+    Ok(number * factor)
+}
+
+/// `12 mm`, `3.5 cm` or `1.20 m`, whichever reads best
+pub fn format_length(mm: f32) -> String {
+    if mm < 10.0 {
+        format!("{:.1} mm", mm)
+    } else if mm < 1000.0 {
+        format!("{:.1} cm", mm / 10.0)
+    } else {
+        format!("{:.2} m", mm / 1000.0)
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.input.payload {
+            Payload::Text(text) => writeln!(f, "Payload: {} bytes of text", text.len())?,
+            Payload::Bytes(len) => writeln!(f, "Payload: {} bytes", len)?,
+        }
+        if let Some(size) = self.input.print_size_mm {
+            writeln!(f, "Print size: {} (including the quiet zone)", format_length(size))?;
+        }
+        if let Some(distance) = self.input.distance_mm {
+            writeln!(f, "Scanning distance: {}", format_length(distance))?;
+        }
+        writeln!(f)?;
+
+        let optional = |value: Option<f32>| value.map_or_else(|| "-".to_string(), format_length);
+        writeln!(f, "  {:<10} {:>7} {:>9} {:>11} {:>13} {:>13}", "ECC", "Version", "Modules", "Module", "Max distance", "Min print")?;
+        for (index, option) in self.options.iter().enumerate() {
+            let marker = if Some(index) == self.recommended { "→" } else { " " };
+            let Some(version) = option.version else {
+                writeln!(f, "{} {:<10} {:>7}", marker, describe_ec_level(option.error_correction), "too long")?;
+                continue;
+            };
+            writeln!(
+                f,
+                "{} {:<10} {:>7} {:>9} {:>11} {:>13} {:>13}",
+                marker,
+                describe_ec_level(option.error_correction),
+                describe_version(version),
+                format!("{0}x{0}", option.modules),
+                optional(option.module_mm),
+                optional(option.max_distance_mm),
+                optional(option.min_print_mm),
+            )?;
+        }
+        writeln!(f)?;
+
+        match self.recommended() {
+            Some(option) => write!(
+                f,
+                "Recommended: error correction {}, version {} ({} modules per side)",
+                describe_ec_level(option.error_correction),
+                option.version.map(describe_version).unwrap_or_default(),
+                option.modules
+            ),
+            None => {
+                let smallest = self.options.iter().filter_map(|option| option.min_print_mm).reduce(f32::min);
+                match smallest {
+                    Some(min) if self.input.print_size_mm.is_some() => {
+                        write!(f, "No level scans reliably at this size; print it at least {} wide", format_length(min))
+                    }
+                    _ => write!(f, "The payload needs a very dense code; consider shortening it, e.g. with a link"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_length() {
+        assert_eq!(parse_length("5cm"), Ok(50.0));
+        assert_eq!(parse_length("1.5 m"), Ok(1500.0));
+        assert_eq!(parse_length("2in"), Ok(50.8));
+        assert_eq!(parse_length("40"), Ok(40.0));
+        assert!(parse_length("5 ft").is_err());
+        assert!(parse_length("-3cm").is_err());
+    }
+
+    #[test]
+    fn test_plan() {
+        let input = PlanInput {
+            payload: Payload::Text("https://example.com/poster".to_string()),
+            print_size_mm: Some(80.0),
+            distance_mm: Some(500.0),
+        };
+        let poster = plan(input.clone()).unwrap();
+        assert_eq!(poster.options.len(), 4);
+        // Higher error correction never makes the code smaller
+        assert!(poster.options.windows(2).all(|pair| pair[0].modules <= pair[1].modules));
+        let recommended = poster.recommended().unwrap();
+        assert!(recommended.module_mm.unwrap() >= 500.0 / DISTANCE_PER_MODULE);
+
+        // A stamp-sized code can't be read from across a room
+        let far = plan(PlanInput { distance_mm: Some(5000.0), ..input }).unwrap();
+        assert_eq!(far.recommended, None);
+        assert!(far.to_string().contains("print it at least"));
+
+        assert!(plan(PlanInput { payload: Payload::Bytes(5000), print_size_mm: None, distance_mm: None }).is_err());
+    }
+}
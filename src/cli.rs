@@ -0,0 +1,544 @@
+use clap::{Parser, Subcommand};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use url::Url;
+
+use crate::barcode_generator::BarcodeSymbology;
+use crate::capacity::parse_length;
+use crate::qr_generator::QrSymbology;
+use crate::qr_style::{Color, ModuleShape, StylePreset};
+use crate::qr_templates::WifiSecurity;
+use crate::region::Region;
+use crate::viewing::ViewingDistance;
+
+/// A clipboard QR code application
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Don't print clipboard events to the console in tray mode
+    #[arg(long)]
+    pub silent_console: bool,
+
+    /// Start the tray even if the startup self-check finds no tray support
+    #[arg(long)]
+    pub force_tray: bool,
+
+    /// Show the clipboard as a fullscreen QR code on this display (Esc to exit)
+    #[arg(long)]
+    pub kiosk: bool,
+
+    /// Size the kiosk code for: desk, room or projector (default: last used on the display)
+    #[arg(long, requires = "kiosk", value_name = "PRESET")]
+    pub viewing: Option<ViewingDistance>,
+
+    /// Read-only guest mode: no saved history, scheduled tasks or network features
+    #[arg(long, global = true)]
+    pub guest: bool,
+
+    /// Write nothing to disk: no history file, presets, reports or default output files
+    #[arg(long, global = true)]
+    pub in_memory: bool,
+
+    /// QR colour preset: classic, high-contrast-print, okabe-ito-blue, navy-amber, espresso
+    #[arg(long, global = true)]
+    pub style: Option<StylePreset>,
+
+    /// Shape of QR modules: square, rounded or dots
+    #[arg(long, global = true)]
+    pub shape: Option<ModuleShape>,
+
+    /// Symbol for generated codes: qr, or micro-qr for text short enough (falls back to qr)
+    #[arg(long, global = true)]
+    pub symbology: Option<QrSymbology>,
+
+    /// Light modules around generated QR codes (default 4)
+    #[arg(long, global = true, value_name = "MODULES")]
+    pub quiet_zone: Option<usize>,
+
+    /// Dark frame around saved PNG and SVG codes, in modules (default 0, none)
+    #[arg(long, global = true, value_name = "MODULES")]
+    pub frame: Option<usize>,
+
+    /// Text under saved PNG and SVG codes, e.g. "Scan me"; an empty text removes it
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub caption: Option<String>,
+
+    /// Pixels per module of saved PNGs, copied images and windows (default 8)
+    #[arg(long, global = true, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub module_size: Option<u32>,
+
+    /// Make those images about this many pixels square instead, with whole pixels per module
+    #[arg(long, global = true, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub target_size: Option<u32>,
+
+    /// Where long-running modes write a JSON report when they exit with an error
+    /// (default: last-exit.json in the local data directory)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub exit_report: Option<PathBuf>,
+
+    /// Accept synthetic clipboard events on the control socket, see `debug inject-text`
+    #[arg(long, global = true)]
+    pub test_mode: bool,
+
+    /// Control socket of the running instance (default: clipboard-qr.sock in the runtime directory)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub control_socket: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Watch the clipboard without the system tray
+    Watch {
+        /// Print a one-line summary for every clipboard change
+        #[arg(long)]
+        term: bool,
+
+        /// Also render the QR code in the terminal for each change
+        #[arg(long, requires = "term")]
+        show_qr: bool,
+    },
+
+    /// Export a Markdown report with the QR image, content and encoding details
+    Report {
+        /// Text to encode; defaults to the current clipboard text
+        text: Option<String>,
+
+        /// Where to write the report (default: output/qr_report_<hash>.md)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Export an SVG with a chosen size, colours and captions; printed without --output.
+    /// The quiet zone follows --quiet-zone
+    Svg {
+        /// Text to encode; defaults to the current clipboard text
+        text: Option<String>,
+
+        /// Where to write the SVG
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Minimum width and height of the code in pixels
+        #[arg(long, default_value_t = 300, value_name = "PIXELS")]
+        size: u32,
+
+        /// Module colour as #RRGGBB (default: the style's)
+        #[arg(long, value_parser = parse_color, value_name = "COLOUR")]
+        foreground: Option<Color>,
+
+        /// Background colour as #RRGGBB (default: the style's)
+        #[arg(long, value_parser = parse_color, value_name = "COLOUR")]
+        background: Option<Color>,
+
+        /// Text above the code
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Text below the code
+        #[arg(long)]
+        footer: Option<String>,
+
+        /// Pick the options in a window and save from there
+        #[arg(long, conflicts_with = "output")]
+        dialog: bool,
+    },
+
+    /// Generate a QR code file for every line of a text file, or every row of a CSV file
+    Batch {
+        /// Text or CSV file; - reads standard input
+        input: PathBuf,
+
+        /// Folder to write the codes to; created if missing
+        #[arg(long, value_name = "DIR")]
+        out_dir: PathBuf,
+
+        /// Output format, e.g. png or svg
+        #[arg(short, long, default_value = "png")]
+        format: String,
+
+        /// Read the input as CSV with a header row; implied by a .csv extension
+        #[arg(long)]
+        csv: bool,
+
+        /// CSV column with the text to encode
+        #[arg(long, default_value = "text", value_name = "NAME")]
+        text_column: String,
+
+        /// CSV column with the file name for each code; rows without one are numbered by line
+        #[arg(long, default_value = "filename", value_name = "NAME")]
+        name_column: String,
+
+        /// Number of generator threads (default: the number of CPUs, up to 4)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+
+    /// Send clipboard text or decoded QR content to another instance's HTTP API
+    Push {
+        /// Base URL of the receiving instance, e.g. http://desktop.local:8787
+        #[arg(long)]
+        to: Url,
+
+        /// Access token for the receiving instance (default: [remote] token in the config)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Keep running and forward every clipboard change
+        #[arg(long, conflicts_with = "text")]
+        watch: bool,
+
+        /// Text to send; defaults to the clipboard text or the QR code in a clipboard image
+        text: Option<String>,
+    },
+
+    /// Developer tools that talk to a running instance over its control socket
+    Debug {
+        #[command(subcommand)]
+        action: DebugAction,
+    },
+
+    /// Import a scanned otpauth:// URI into the configured password manager, after confirming
+    ImportOtp {
+        /// The otpauth:// URI; defaults to the clipboard text or the QR code in a clipboard image
+        uri: Option<String>,
+    },
+
+    /// Send text, or the clipboard, to a phone paired with KDE Connect
+    Phone {
+        /// List reachable paired devices and exit
+        #[arg(long)]
+        list: bool,
+
+        /// Device id or name (default: [kdeconnect] device in the config, or the first reachable device)
+        #[arg(short, long)]
+        device: Option<String>,
+
+        /// Text to send; defaults to the clipboard text or the QR code in a clipboard image
+        #[arg(conflicts_with = "list")]
+        text: Option<String>,
+    },
+
+    /// Run inside a VM or remote desktop session and forward its clipboard to the host instance
+    Agent {
+        /// Base URL of the host instance (default: [agent] host in the config)
+        #[arg(long)]
+        to: Option<Url>,
+
+        /// Access token for the host instance (default: [remote] token in the config)
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Name this guest reports to the host (default: the machine name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Scan image files for QR codes; multi-page TIFFs are scanned page by page. Without
+    /// files a window opens to pick them
+    Scan {
+        files: Vec<PathBuf>,
+        /// Report every QR code in each image with its position, not just the first
+        #[arg(long, requires = "files")]
+        all: bool,
+    },
+
+    /// Edit common settings of the config file in a window
+    Settings,
+
+    /// Show in a window whether the running instance is monitoring, when the clipboard last
+    /// changed, its counters and the last decoded QR code; the tray's Show Status item opens it
+    Status {
+        /// Start from the status the tray writes to standard input
+        #[arg(long, hide = true)]
+        initial_from_stdin: bool,
+    },
+
+    /// Show the version, licence and config file location in a window
+    About,
+
+    /// Show the QR code of the clipboard text in a small always-on-top window (Esc to close);
+    /// the tray's Show QR Code item and left click open it
+    Popup {
+        /// Text to show instead of the clipboard's
+        text: Option<String>,
+        /// Show the text the tray writes to standard input
+        #[arg(long, hide = true, conflicts_with = "text")]
+        text_from_stdin: bool,
+    },
+
+    /// Scan QR codes held up to a webcam and copy each new one to the clipboard
+    /// (builds with the camera feature only)
+    Camera {
+        /// Camera to use, see --list
+        #[arg(long, default_value_t = 0)]
+        index: u32,
+
+        /// List the cameras and exit
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Serve a local HTTP API: POST /generate, POST /scan, GET /clipboard/latest and POST /clipboard
+    /// (builds with the server feature only)
+    Serve {
+        /// Address to listen on; use 0.0.0.0 to accept other machines, ideally with --token
+        #[arg(long, default_value = "127.0.0.1:8787")]
+        bind: SocketAddr,
+
+        /// Bearer token requests must carry (default: [remote] token in the config)
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// Capture a region of the screen and scan it for a QR code; the region is remembered,
+    /// so a code that keeps changing in the same place can be scanned again with --last
+    Capture {
+        /// WIDTHxHEIGHT+X+Y in screen pixels, e.g. 640x480+100+200 (default: select it with
+        /// the mouse, using slop on X11 or slurp on Wayland)
+        #[arg(conflicts_with_all = ["last", "list", "screen"])]
+        region: Option<Region>,
+
+        /// Capture the most recently captured region again, e.g. from a keyboard shortcut
+        #[arg(long, conflicts_with_all = ["list", "screen"])]
+        last: bool,
+
+        /// Capture the whole screen instead of a region
+        #[arg(long, conflicts_with = "list")]
+        screen: bool,
+
+        /// Put the decoded content on the clipboard
+        #[arg(long, conflicts_with = "list")]
+        copy: bool,
+
+        /// List the remembered regions and exit
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Tune preprocessing for an image that won't decode and save the settings as a preset
+    Tune {
+        /// Image file to tune on; defaults to the image on the clipboard
+        file: Option<PathBuf>,
+    },
+
+    /// Show where the decoder looked in an image and which candidate regions failed,
+    /// as an annotated image to attach to bug reports
+    Heatmap {
+        /// Image file to trace; defaults to the image on the clipboard
+        file: Option<PathBuf>,
+
+        /// Where to save the annotated image (default: scan-heatmap.png)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Render a QR label for a label printer, e.g. from a SKU on the clipboard
+    Label {
+        /// Text to encode; defaults to the current clipboard text
+        text: Option<String>,
+
+        /// Label template (default: [label] template in the config)
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// List the available templates and exit
+        #[arg(long)]
+        list: bool,
+
+        /// Leave out the text caption under the code
+        #[arg(long)]
+        no_caption: bool,
+
+        /// Override the template's rotation: 0, 90, 180 or 270
+        #[arg(long)]
+        rotate: Option<u16>,
+
+        /// Where to write the label PNG (default: output/label_<hash>.png)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Send the label to the printer queue
+        #[arg(long)]
+        print: bool,
+
+        /// Printer queue name (default: [label] printer, then the system default)
+        #[arg(long, requires = "print")]
+        printer: Option<String>,
+
+        #[arg(long, default_value_t = 1, requires = "print")]
+        copies: u32,
+    },
+
+    /// Generate a 1D barcode, e.g. for retail or inventory labels; the format follows the
+    /// extension, png or svg
+    Barcode {
+        /// Text to encode; defaults to the current clipboard text
+        text: Option<String>,
+
+        /// code128, ean13 (12 digits, or 13 with the check digit) or code39
+        #[arg(short = 't', long = "type", default_value_t = BarcodeSymbology::Code128, value_name = "TYPE")]
+        kind: BarcodeSymbology,
+
+        /// Where to write the barcode (default: output/barcode_<hash>.png)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// List clipboard and scan history, or export QR codes for past entries
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+
+    /// Recommend error correction and code size before printing, e.g. for a poster
+    Plan {
+        /// Text to encode; defaults to the current clipboard text unless --bytes is given
+        #[arg(conflicts_with = "bytes")]
+        text: Option<String>,
+
+        /// Expected payload size in bytes, if the content isn't known yet
+        #[arg(long)]
+        bytes: Option<usize>,
+
+        /// Printed width including the margin, e.g. 5cm, 40mm or 2in
+        #[arg(long, value_parser = parse_length)]
+        size: Option<f32>,
+
+        /// Distance the code will be scanned from, e.g. 50cm or 2m
+        #[arg(long, value_parser = parse_length)]
+        distance: Option<f32>,
+    },
+
+    /// Generate a QR code from a snippet in the config, filling in its placeholders
+    Snippet {
+        /// Snippet name
+        #[arg(required_unless_present = "list")]
+        name: Option<String>,
+
+        /// List the snippets and their placeholders and exit
+        #[arg(long)]
+        list: bool,
+
+        /// Placeholder value, e.g. --set ssid=Office; missing values are asked for
+        #[arg(long = "set", value_name = "NAME=VALUE", value_parser = parse_assignment)]
+        values: Vec<(String, String)>,
+
+        /// Save the QR code instead of printing it; the format follows the extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Delete the saved file after this long, e.g. 10m; overrides the snippet's `expire`.
+        /// Carried out by the tray app
+        #[arg(long, value_name = "DURATION", requires = "output")]
+        expire: Option<String>,
+
+        /// Copy the QR code image to the clipboard
+        #[arg(long)]
+        copy: bool,
+    },
+
+    /// Generate a QR code from a built-in template, e.g. Wi-Fi credentials
+    Template {
+        #[command(subcommand)]
+        template: Template,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Template {
+    /// Share a Wi-Fi network; without --ssid a form opens in a window
+    Wifi {
+        /// Network name
+        #[arg(long)]
+        ssid: Option<String>,
+
+        /// Network password; asked for when the network has one and it's missing
+        #[arg(long)]
+        password: Option<String>,
+
+        /// wpa, wep or none
+        #[arg(long, default_value_t = WifiSecurity::Wpa)]
+        security: WifiSecurity,
+
+        /// The network doesn't broadcast its name
+        #[arg(long)]
+        hidden: bool,
+
+        /// Save the QR code instead of printing it; the format follows the extension
+        #[arg(short, long, requires = "ssid")]
+        output: Option<PathBuf>,
+
+        /// Copy the QR code image to the clipboard
+        #[arg(long, requires = "ssid")]
+        copy: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DebugAction {
+    /// Check that an instance is listening on the control socket
+    Ping,
+
+    /// Make a running instance started with --test-mode handle text as if it had been copied
+    InjectText {
+        text: String,
+    },
+
+    /// Make a running instance started with --test-mode handle an image as if it had been copied
+    InjectImage {
+        file: PathBuf,
+    },
+
+    /// Write a zip for bug reports: redacted config, recent log, self-check, statistics and backends
+    Snapshot {
+        /// Where to write it (default: clipboard-qr-snapshot-<time>.zip in the current directory)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Also include the clipboard text and leave URLs in the log
+        #[arg(long)]
+        include_content: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryAction {
+    /// Show recent entries with their ids
+    List {
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Regenerate QR codes for entries: a folder of PNGs, or one PDF with a page per code
+    Export {
+        /// Entry ids from `history list`
+        #[arg(required_unless_present = "since")]
+        ids: Vec<u64>,
+
+        /// Export every entry from this period instead, e.g. 7d or 12h
+        #[arg(long, conflicts_with = "ids")]
+        since: Option<String>,
+
+        /// Output folder, or a .pdf file for one combined document
+        #[arg(short, long, required_unless_present = "copy")]
+        output: Option<PathBuf>,
+
+        /// Copy the QR code of a single entry to the clipboard as an image, ready to paste
+        #[arg(long, conflicts_with = "since")]
+        copy: bool,
+    },
+}
+
+fn parse_assignment(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected NAME=VALUE, got '{}'", value))
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    Color::from_hex(value).map_err(|e| e.to_string())
+}
@@ -1,13 +1,21 @@
 use arboard::Clipboard;
 use anyhow::Result;
 use tracing::{debug, warn, info};
-use std::time::SystemTime;
-use std::collections::hash_map::DefaultHasher;
+use std::time::{Duration, Instant};
 use std::hash::{Hash, Hasher};
+use rustc_hash::FxHasher;
 use image::{ImageBuffer, Rgba};
+use std::path::PathBuf;
 use std::sync::mpsc;
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::sync::Arc;
 use std::thread;
 
+use crate::normalize::NormalizationProfile;
+use crate::rich_text::RichContent;
+
 #[cfg(windows)]
 use winapi::shared::windef::HWND;
 #[cfg(windows)]
@@ -17,21 +25,295 @@ use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
 pub enum ClipboardData {
     Text(String),
     Image(ImageBuffer<Rgba<u8>, Vec<u8>>),
+    /// Files copied in a file manager (CF_HDROP on Windows, text/uri-list on Linux)
+    Files(Vec<PathBuf>),
     Empty,
+    /// The source application marked the content as not to be monitored,
+    /// e.g. a password manager; it must not be shown, stored or encoded
+    Excluded,
+}
+
+/// The X11 selection stamp before the listener has seen the selection change
+#[cfg(all(unix, not(target_os = "macos")))]
+const UNKNOWN_STAMP: u64 = u64::MAX;
+
+/// The sequence number comes from the listener itself, so it can't be used to check on it
+const SEQUENCE_FROM_LISTENER: bool = cfg!(all(unix, not(target_os = "macos")));
+
+/// Longest clipboard property read from an X11 owner, in 32-bit words (16 MiB)
+#[cfg(all(unix, not(target_os = "macos")))]
+const X11_MAX_PROPERTY_WORDS: u32 = 4 * 1024 * 1024;
+
+/// Windows clipboard formats whose presence marks content as private
+#[cfg(windows)]
+const WINDOWS_EXCLUSION_FORMATS: [&str; 2] = ["ExcludeClipboardContentFromMonitorProcessing", "Clipboard Viewer Ignore"];
+
+/// MIME type set by KeePassXC and other password managers for KDE Klipper
+#[cfg(all(unix, not(target_os = "macos")))]
+const KDE_PASSWORD_HINT: &str = "x-kde-passwordManagerHint";
+
+/// Pasteboard types password managers add to keep content out of clipboard tools, see
+/// nspasteboard.org
+#[cfg(target_os = "macos")]
+const MACOS_EXCLUSION_TYPES: [&str; 2] = ["org.nspasteboard.ConcealedType", "org.nspasteboard.TransientType"];
+
+/// Which clipboard formats a handler reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClipboardFormats {
+    pub text: bool,
+    pub image: bool,
+    /// HTML and RTF, for the text and images in them as far as `text` and `image` allow
+    pub rich_text: bool,
+    /// Files copied in a file manager
+    pub files: bool,
+}
+
+impl ClipboardFormats {
+    pub const ALL: Self = Self { text: true, image: true, rich_text: true, files: true };
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub const TEXT_ONLY: Self = Self { text: true, image: false, rich_text: false, files: false };
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub const IMAGE_ONLY: Self = Self { text: false, image: true, rich_text: false, files: false };
+
+    /// Text and/or images wherever the clipboard holds them: also in HTML and RTF, and
+    /// for images among copied files
+    pub const fn content(text: bool, image: bool) -> Self {
+        Self { text, image, rich_text: true, files: image }
+    }
+
+    /// Whether an update carrying `self` has anything a handler reading `other` reads
+    pub fn overlaps(self, other: Self) -> bool {
+        (self.text && other.text) || (self.image && other.image) || (self.rich_text && other.rich_text) || (self.files && other.files)
+    }
+}
+
+impl Default for ClipboardFormats {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Events delivered by the platform clipboard listener thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardEvent {
+    /// The clipboard content changed; `formats` says what is on it now. The X11 listener
+    /// can't tell the formats without reading the selection, so it never sends this
+    #[cfg_attr(all(unix, not(target_os = "macos"), not(feature = "wayland-data-control")), allow(dead_code))]
+    Updated { formats: ClipboardFormats },
+    /// Another client took ownership of the clipboard selection
+    OwnerChanged,
+    /// The event-driven listener failed and the thread fell back to polling
+    ListenerDegraded(String),
+    /// Keep-alive tick from a polling listener; the clipboard may not have changed
+    Poll,
+}
+
+/// How long the platform listener may lag behind a change polling already saw before it
+/// counts as stuck
+const LISTENER_GRACE: Duration = Duration::from_secs(2);
+/// Restarts after which a listener that keeps getting stuck is left alone; polling still
+/// picks up every change, only later
+const MAX_LISTENER_RESTARTS: u32 = 3;
+/// How often the clipboard is still read while a listener reports changes itself, so one
+/// that silently stops is noticed
+const VERIFY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Notices an event-driven platform listener that stopped delivering events while polling
+/// still sees the clipboard change
+#[derive(Debug, Default)]
+pub struct ListenerWatchdog {
+    /// When polling saw a change the listener hasn't reported yet
+    missed_since: Option<Instant>,
+    /// The listener only sends poll ticks, so it never reports changes itself
+    polling: bool,
+    /// The listener has reported changes itself, so reading the clipboard on every poll
+    /// would only repeat what it says
+    trusted: bool,
+    restarts: u32,
+}
+
+impl ListenerWatchdog {
+    /// The listener reported a clipboard change
+    pub fn heartbeat(&mut self) {
+        self.missed_since = None;
+        self.trusted = true;
+    }
+
+    /// The listener turned out to be a polling one, which the watchdog leaves alone
+    pub fn polling(&mut self) {
+        self.polling = true;
+        self.trusted = false;
+    }
+
+    /// Whether a poll without a listener event should still read the clipboard, given when
+    /// it was last read
+    pub fn should_verify(&self, last_verified: Instant, now: Instant) -> bool {
+        !self.trusted || self.missed_since.is_some() || now.duration_since(last_verified) >= VERIFY_INTERVAL
+    }
+
+    /// Check after each poll, with whether polling found a change the listener didn't report
+    /// and whether the listener thread has exited; returns why the listener should be restarted
+    pub fn check(&mut self, missed_change: bool, thread_stopped: bool, now: Instant) -> Option<&'static str> {
+        if self.polling || self.restarts >= MAX_LISTENER_RESTARTS {
+            return None;
+        }
+        if missed_change {
+            self.missed_since.get_or_insert(now);
+        }
+        let reason = if thread_stopped {
+            "listener thread exited"
+        } else if self.missed_since.is_some_and(|since| now.duration_since(since) >= LISTENER_GRACE) {
+            "listener stopped reporting clipboard changes"
+        } else {
+            return None;
+        };
+        self.restarts += 1;
+        self.missed_since = None;
+        self.trusted = false;
+        Some(reason)
+    }
+
+    pub fn restarts(&self) -> u32 {
+        self.restarts
+    }
+}
+
+/// Fingerprint of clipboard content for change detection; text is compared after
+/// `normalization`, images by every pixel, so two images of the same size still differ
+pub fn content_hash(data: &ClipboardData, normalization: NormalizationProfile) -> u64 {
+    let mut hasher = FxHasher::default();
+    match data {
+        ClipboardData::Text(text) => normalization.apply(text).hash(&mut hasher),
+        ClipboardData::Image(image) => return image_hash(image),
+        ClipboardData::Files(files) => files.hash(&mut hasher),
+        ClipboardData::Empty => "empty".hash(&mut hasher),
+        ClipboardData::Excluded => "excluded".hash(&mut hasher),
+    }
+    hasher.finish()
+}
+
+/// [`content_hash`] of an image, without copying it into [`ClipboardData`] first
+pub fn image_hash(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> u64 {
+    let mut hasher = FxHasher::default();
+    (image.width(), image.height()).hash(&mut hasher);
+    hasher.write(image.as_raw());
+    hasher.finish()
+}
+
+/// Configures a [`ClipboardHandler`]; obtained from [`ClipboardHandler::builder`]
+#[derive(Debug, Clone)]
+pub struct ClipboardHandlerBuilder {
+    poll_interval: Duration,
+    formats: ClipboardFormats,
+    spawn_listener: bool,
+    debounce: Duration,
+    normalization: NormalizationProfile,
+    keep_until_replaced: bool,
+}
+
+impl Default for ClipboardHandlerBuilder {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(100),
+            formats: ClipboardFormats::ALL,
+            spawn_listener: true,
+            debounce: Duration::ZERO,
+            normalization: NormalizationProfile::default(),
+            keep_until_replaced: false,
+        }
+    }
+}
+
+impl ClipboardHandlerBuilder {
+    /// How often fallback listeners and callers should poll the clipboard
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Which formats to read; others are ignored as if absent
+    pub fn formats(mut self, formats: ClipboardFormats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Whether to start the platform clipboard listener thread
+    pub fn spawn_listener(mut self, spawn: bool) -> Self {
+        self.spawn_listener = spawn;
+        self
+    }
+
+    /// Only report a change once the clipboard has been stable for this long
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// How text is canonicalized before deciding whether the clipboard changed
+    pub fn normalization(mut self, profile: NormalizationProfile) -> Self {
+        self.normalization = profile;
+        self
+    }
+
+    /// Make `set_text` and `set_image` return only once another app has taken the clipboard
+    /// over, for commands that exit right after copying; on X11 and Wayland the content
+    /// would otherwise go with the process. No effect elsewhere
+    pub fn keep_until_replaced(mut self, keep: bool) -> Self {
+        self.keep_until_replaced = keep;
+        self
+    }
+
+    pub fn build(self) -> ClipboardHandler {
+        ClipboardHandler::with_options(self)
+    }
 }
 
 pub struct ClipboardHandler {
     clipboard: Option<Clipboard>,
     last_hash: u64,
-    last_check_time: SystemTime,
+    /// The platform's change counter when the clipboard was last read, see
+    /// [`ClipboardHandler::sequence_number`]
+    last_sequence: Option<u64>,
+    poll_interval: Duration,
+    formats: ClipboardFormats,
+    debounce: Duration,
+    normalization: NormalizationProfile,
+    /// See [`ClipboardHandlerBuilder::keep_until_replaced`]
+    keep_until_replaced: bool,
+    pending_since: Option<Instant>,
+    degraded_reason: Option<String>,
+    watchdog: ListenerWatchdog,
+    /// When the clipboard was last read to look for a change
+    last_verified: Instant,
+    /// Why the listener was last restarted, until the owner takes it
+    restarted: Option<String>,
+    /// The X11 CLIPBOARD owner and the server time it took the selection at, as the listener
+    /// last saw them change, see [`ClipboardHandler::sequence_number`]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    selection_stamp: Arc<AtomicU64>,
     #[cfg(any(windows, unix))]
-    clipboard_channel: Option<mpsc::Receiver<()>>,
+    clipboard_channel: Option<mpsc::Receiver<ClipboardEvent>>,
     #[cfg(any(windows, unix))]
     clipboard_thread: Option<thread::JoinHandle<()>>,
 }
 
+impl Default for ClipboardHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ClipboardHandler {
     pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> ClipboardHandlerBuilder {
+        ClipboardHandlerBuilder::default()
+    }
+
+    fn with_options(options: ClipboardHandlerBuilder) -> Self {
         let clipboard = match Clipboard::new() {
             Ok(clipboard) => {
                 debug!("Clipboard initialized successfully");
@@ -43,32 +325,80 @@ impl ClipboardHandler {
             },
         };
 
-        #[cfg(windows)]
-        let (clipboard_channel, clipboard_thread) = Self::start_windows_clipboard_listener();
-
-        #[cfg(unix)]
-        let (clipboard_channel, clipboard_thread) = Self::start_linux_clipboard_listener();
-
-        #[cfg(not(any(windows, unix)))]
-        let (clipboard_channel, clipboard_thread): (Option<mpsc::Receiver<()>>, Option<thread::JoinHandle<()>>) = (None, None);
-
-        Self { 
+        #[allow(unused_mut)]
+        let mut handler = Self {
             clipboard,
             last_hash: 0,
-            last_check_time: SystemTime::now(),
+            last_sequence: None,
+            poll_interval: options.poll_interval,
+            formats: options.formats,
+            debounce: options.debounce,
+            normalization: options.normalization,
+            keep_until_replaced: options.keep_until_replaced,
+            pending_since: None,
+            degraded_reason: None,
+            watchdog: ListenerWatchdog::default(),
+            last_verified: Instant::now(),
+            restarted: None,
+            #[cfg(all(unix, not(target_os = "macos")))]
+            selection_stamp: Arc::new(AtomicU64::new(UNKNOWN_STAMP)),
             #[cfg(any(windows, unix))]
-            clipboard_channel,
+            clipboard_channel: None,
             #[cfg(any(windows, unix))]
-            clipboard_thread,
+            clipboard_thread: None,
+        };
+
+        #[cfg(any(windows, unix))]
+        if options.spawn_listener {
+            (handler.clipboard_channel, handler.clipboard_thread) = handler.start_listener();
         }
+        handler
+    }
+
+    #[cfg(any(windows, unix))]
+    fn start_listener(&self) -> (Option<mpsc::Receiver<ClipboardEvent>>, Option<thread::JoinHandle<()>>) {
+        #[cfg(windows)]
+        {
+            Self::start_windows_clipboard_listener()
+        }
+        #[cfg(target_os = "macos")]
+        {
+            Self::start_macos_clipboard_listener(self.poll_interval)
+        }
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            Self::start_linux_clipboard_listener(self.poll_interval, self.selection_stamp.clone())
+        }
+    }
+
+    /// Replace a stuck listener with a new one
+    ///
+    /// The old thread exits on its next send now that nobody receives; one blocked in the
+    /// OS for good lives on until the process exits, which the restart limit keeps rare.
+    #[cfg(any(windows, unix))]
+    fn restart_listener(&mut self, reason: &str) {
+        warn!(
+            "Clipboard {}; restarting it ({}/{})",
+            reason,
+            self.watchdog.restarts(),
+            MAX_LISTENER_RESTARTS
+        );
+        if self.watchdog.restarts() >= MAX_LISTENER_RESTARTS {
+            warn!("Clipboard listener keeps getting stuck; this is its last restart, after that only polling picks up changes");
+        }
+        let (channel, thread) = self.start_listener();
+        self.clipboard_channel = channel;
+        self.clipboard_thread = thread;
+        self.restarted = Some(reason.to_string());
     }
 
     #[cfg(windows)]
-    fn start_windows_clipboard_listener() -> (Option<mpsc::Receiver<()>>, Option<thread::JoinHandle<()>>) {
+    fn start_windows_clipboard_listener() -> (Option<mpsc::Receiver<ClipboardEvent>>, Option<thread::JoinHandle<()>>) {
         use winapi::um::winuser::{AddClipboardFormatListener, RemoveClipboardFormatListener, WM_CLIPBOARDUPDATE};
         use winapi::um::winuser::{GetMessageW, TranslateMessage, DispatchMessageW, MSG};
         use winapi::um::winuser::{CreateWindowExW, RegisterClassExW, WNDCLASSEXW};
         use winapi::um::winuser::{WS_OVERLAPPED, CW_USEDEFAULT};
+        use winapi::um::winuser::{IsClipboardFormatAvailable, RegisterClipboardFormatW, CF_DIB, CF_HDROP, CF_UNICODETEXT};
         use winapi::um::libloaderapi::GetModuleHandleW;
         use winapi::um::errhandlingapi::GetLastError;
         use std::ptr::null_mut;
@@ -139,6 +469,10 @@ impl ClipboardHandler {
                     let error = GetLastError();
                     warn!("Failed to add clipboard format listener, error code: {}. Falling back to polling.", error);
                     // Fall back to polling approach
+                    let _ = tx.send(ClipboardEvent::ListenerDegraded(format!(
+                        "AddClipboardFormatListener failed with error code {}",
+                        error
+                    )));
                     return;
                 } else {
                     info!("Windows clipboard listener started successfully");
@@ -154,7 +488,17 @@ impl ClipboardHandler {
 
                     if msg.message == WM_CLIPBOARDUPDATE {
                         // Send notification to main thread
-                        if let Err(e) = tx.send(()) {
+                        let registered = |name: &str| {
+                            let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+                            IsClipboardFormatAvailable(RegisterClipboardFormatW(wide.as_ptr())) != 0
+                        };
+                        let formats = ClipboardFormats {
+                            text: IsClipboardFormatAvailable(CF_UNICODETEXT) != 0,
+                            image: IsClipboardFormatAvailable(CF_DIB) != 0,
+                            rich_text: registered("HTML Format") || registered("Rich Text Format"),
+                            files: IsClipboardFormatAvailable(CF_HDROP) != 0,
+                        };
+                        if let Err(e) = tx.send(ClipboardEvent::Updated { formats }) {
                             warn!("Failed to send clipboard notification: {}", e);
                             break;
                         }
@@ -183,27 +527,109 @@ impl ClipboardHandler {
         unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
     }
 
-    #[cfg(unix)]
-    fn start_linux_clipboard_listener() -> (Option<mpsc::Receiver<()>>, Option<thread::JoinHandle<()>>) {
-        use std::time::Duration;
+    /// Send a poll tick every `interval` until the receiver is dropped.
+    /// `degraded` is reported first when polling replaces a failed event listener.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn spawn_polling_thread(
+        tx: mpsc::Sender<ClipboardEvent>,
+        interval: Duration,
+        degraded: Option<String>,
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            if let Some(reason) = degraded {
+                let _ = tx.send(ClipboardEvent::ListenerDegraded(reason));
+            }
+            loop {
+                thread::sleep(interval);
+                if tx.send(ClipboardEvent::Poll).is_err() {
+                    debug!("Clipboard polling thread stopped");
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Watch the pasteboard's change count, which macOS bumps on every copy; reading it is
+    /// cheap, unlike reading the content, so it's checked every `poll_interval`
+    #[cfg(target_os = "macos")]
+    fn start_macos_clipboard_listener(poll_interval: Duration) -> (Option<mpsc::Receiver<ClipboardEvent>>, Option<thread::JoinHandle<()>>) {
+        use objc2_app_kit::NSPasteboard;
+
+        let (tx, rx) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            // Pasteboard handles can't move between threads, so this one is fetched here
+            let pasteboard = unsafe { NSPasteboard::generalPasteboard() };
+            let mut last_count = unsafe { pasteboard.changeCount() };
+            loop {
+                thread::sleep(poll_interval);
+                let count = unsafe { pasteboard.changeCount() };
+                if count == last_count {
+                    continue;
+                }
+                last_count = count;
+
+                let types = Self::macos_pasteboard_types();
+                let event = if types.is_empty() {
+                    // Cleared; there's no format to filter on
+                    ClipboardEvent::OwnerChanged
+                } else {
+                    let has = |names: &[&str]| types.iter().any(|name| names.contains(&name.as_str()));
+                    ClipboardEvent::Updated {
+                        formats: ClipboardFormats {
+                            text: has(&["public.utf8-plain-text", "public.utf16-plain-text"]),
+                            image: has(&["public.png", "public.tiff"]),
+                            rich_text: has(&["public.html", "public.rtf"]),
+                            files: has(&["public.file-url"]),
+                        },
+                    }
+                };
+                if tx.send(event).is_err() {
+                    debug!("macOS pasteboard listener stopped");
+                    break;
+                }
+            }
+        });
+
+        info!("macOS pasteboard listener started successfully");
+        (Some(rx), Some(handle))
+    }
+
+    /// Type identifiers of what is on the general pasteboard, e.g. "public.png"
+    #[cfg(target_os = "macos")]
+    fn macos_pasteboard_types() -> Vec<String> {
+        use objc2_app_kit::NSPasteboard;
+
+        let types = unsafe { NSPasteboard::generalPasteboard().types() };
+        types.map(|types| types.iter().map(|name| name.to_string()).collect()).unwrap_or_default()
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn start_linux_clipboard_listener(
+        poll_interval: Duration,
+        stamp: Arc<AtomicU64>,
+    ) -> (Option<mpsc::Receiver<ClipboardEvent>>, Option<thread::JoinHandle<()>>) {
         use std::env;
         let (tx, rx) = mpsc::channel();
+        // Unknown until this listener sees a change, which a stuck one before it may have missed
+        stamp.store(UNKNOWN_STAMP, Ordering::Relaxed);
 
-        // Wayland: 直接轮询
+        // Wayland: 有 data-control 协议时监听事件, 否则轮询
         if env::var_os("WAYLAND_DISPLAY").is_some() {
-            let handle = thread::spawn(move || {
-                loop {
-                    thread::sleep(Duration::from_millis(100));
-                    if let Err(e) = tx.send(()) {
-                        warn!("Failed to send clipboard check signal: {}", e);
-                        break;
-                    }
+            #[cfg(feature = "wayland-data-control")]
+            match crate::wayland_clipboard::start(tx.clone(), poll_interval) {
+                Ok(handle) => return (Some(rx), Some(handle)),
+                Err(e) => {
+                    warn!("Failed to start Wayland clipboard listener: {}. Falling back to polling.", e);
+                    let reason = format!("Failed to start Wayland clipboard listener: {}", e);
+                    return (Some(rx), Some(Self::spawn_polling_thread(tx, poll_interval, Some(reason))));
                 }
-            });
-            return (Some(rx), Some(handle));
+            }
+            #[cfg(not(feature = "wayland-data-control"))]
+            return (Some(rx), Some(Self::spawn_polling_thread(tx, poll_interval, None)));
         }
 
         use x11rb::connection::Connection;
+        use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
         use x11rb::protocol::xproto::{ConnectionExt, WindowClass};
         use x11rb::protocol::Event;
         
@@ -211,15 +637,8 @@ impl ClipboardHandler {
             Ok((conn, screen_num)) => (conn, screen_num),
             Err(e) => {
                 warn!("Failed to connect to X11 server: {}. Falling back to polling.", e);
-                // Fall back to polling approach
-                loop {
-                    thread::sleep(Duration::from_millis(100));
-                    if let Err(e) = tx.send(()) {
-                        warn!("Failed to send clipboard check signal: {}", e);
-                        break;
-                    }
-                }
-                return (Some(rx), None);
+                let reason = format!("Failed to connect to X11 server: {}", e);
+                return (Some(rx), Some(Self::spawn_polling_thread(tx, poll_interval, Some(reason))));
             },
         };
 
@@ -231,29 +650,8 @@ impl ClipboardHandler {
             Ok(reply) => reply.reply().unwrap().atom,
             Err(e) => {
                 warn!("Failed to get CLIPBOARD atom: {}. Falling back to polling.", e);
-                loop {
-                    thread::sleep(Duration::from_millis(100));
-                    if let Err(e) = tx.send(()) {
-                        warn!("Failed to send clipboard check signal: {}", e);
-                        break;
-                    }
-                }
-                return (Some(rx), None);
-            },
-        };
-        
-        let targets_atom = match conn.intern_atom(false, b"TARGETS") {
-            Ok(reply) => reply.reply().unwrap().atom,
-            Err(e) => {
-                warn!("Failed to get TARGETS atom: {}. Falling back to polling.", e);
-                loop {
-                    thread::sleep(Duration::from_millis(100));
-                    if let Err(e) = tx.send(()) {
-                        warn!("Failed to send clipboard check signal: {}", e);
-                        break;
-                    }
-                }
-                return (Some(rx), None);
+                let reason = format!("Failed to get CLIPBOARD atom: {}", e);
+                return (Some(rx), Some(Self::spawn_polling_thread(tx, poll_interval, Some(reason))));
             },
         };
 
@@ -262,14 +660,8 @@ impl ClipboardHandler {
             Ok(id) => id,
             Err(e) => {
                 warn!("Failed to generate window ID: {}. Falling back to polling.", e);
-                loop {
-                    thread::sleep(Duration::from_millis(100));
-                    if let Err(e) = tx.send(()) {
-                        warn!("Failed to send clipboard check signal: {}", e);
-                        break;
-                    }
-                }
-                return (Some(rx), None);
+                let reason = format!("Failed to generate window ID: {}", e);
+                return (Some(rx), Some(Self::spawn_polling_thread(tx, poll_interval, Some(reason))));
             },
         };
 
@@ -286,31 +678,24 @@ impl ClipboardHandler {
             &x11rb::protocol::xproto::CreateWindowAux::new(), // value_list
         ) {
             warn!("Failed to create window: {}. Falling back to polling.", e);
-            loop {
-                thread::sleep(Duration::from_millis(100));
-                if let Err(e) = tx.send(()) {
-                    warn!("Failed to send clipboard check signal: {}", e);
-                    break;
-                }
-            }
-            return (Some(rx), None);
+            let reason = format!("Failed to create window: {}", e);
+            return (Some(rx), Some(Self::spawn_polling_thread(tx, poll_interval, Some(reason))));
         }
 
-        // Select for selection change events
-        if let Err(e) = conn.change_window_attributes(
-            window,
-            &x11rb::protocol::xproto::ChangeWindowAttributesAux::new()
-                .event_mask(x11rb::protocol::xproto::EventMask::NO_EVENT),
-        ) {
-            warn!("Failed to set window attributes: {}. Falling back to polling.", e);
-            loop {
-                thread::sleep(Duration::from_millis(100));
-                if let Err(e) = tx.send(()) {
-                    warn!("Failed to send clipboard check signal: {}", e);
-                    break;
-                }
-            }
-            return (Some(rx), None);
+        // XFixes reports every change of the CLIPBOARD owner, including owners that exit;
+        // core X11 has no event for it
+        let select_selection_input = || -> Result<()> {
+            conn.xfixes_query_version(5, 0)?.reply()?;
+            let events = SelectionEventMask::SET_SELECTION_OWNER
+                | SelectionEventMask::SELECTION_WINDOW_DESTROY
+                | SelectionEventMask::SELECTION_CLIENT_CLOSE;
+            conn.xfixes_select_selection_input(window, clipboard_atom, events)?.check()?;
+            Ok(())
+        };
+        if let Err(e) = select_selection_input() {
+            warn!("Failed to watch the clipboard with XFixes: {}. Falling back to polling.", e);
+            let reason = format!("Failed to watch the clipboard with XFixes: {}", e);
+            return (Some(rx), Some(Self::spawn_polling_thread(tx, poll_interval, Some(reason))));
         }
 
         info!("Linux X11 clipboard listener started successfully");
@@ -321,9 +706,14 @@ impl ClipboardHandler {
                 match conn.wait_for_event() {
                     Ok(event) => {
                         match event {
-                            Event::SelectionNotify(_) => {
-                                // Selection changed, notify main thread
-                                if let Err(e) = tx.send(()) {
+                            Event::XfixesSelectionNotify(notify) if notify.selection == clipboard_atom => {
+                                // Stored before telling the main thread, which reads it next
+                                let owner = match notify.owner {
+                                    x11rb::NONE => 0,
+                                    owner => u64::from(owner) << 32 | u64::from(notify.selection_timestamp),
+                                };
+                                stamp.store(owner, Ordering::Relaxed);
+                                if let Err(e) = tx.send(ClipboardEvent::OwnerChanged) {
                                     warn!("Failed to send clipboard notification: {}", e);
                                     break;
                                 }
@@ -335,11 +725,15 @@ impl ClipboardHandler {
                     },
                     Err(e) => {
                         warn!("Error waiting for X11 event: {}. Falling back to polling.", e);
+                        stamp.store(UNKNOWN_STAMP, Ordering::Relaxed);
+                        let _ = tx.send(ClipboardEvent::ListenerDegraded(format!(
+                            "Error waiting for X11 event: {}",
+                            e
+                        )));
                         // Fall back to polling
                         loop {
-                            thread::sleep(Duration::from_millis(100));
-                            if let Err(e) = tx.send(()) {
-                                warn!("Failed to send clipboard check signal: {}", e);
+                            thread::sleep(poll_interval);
+                            if tx.send(ClipboardEvent::Poll).is_err() {
                                 break;
                             }
                         }
@@ -352,11 +746,279 @@ impl ClipboardHandler {
         (Some(rx), Some(handle))
     }
 
+    /// Read the clipboard, reporting content flagged by its source as private as `Excluded`
     pub fn get_data(&mut self) -> Result<ClipboardData> {
+        let data = self.read_data()?;
+        Ok(Self::unless_excluded(data))
+    }
+
+    fn unless_excluded(data: ClipboardData) -> ClipboardData {
+        if matches!(data, ClipboardData::Empty) || !Self::is_excluded_from_monitoring() {
+            return data;
+        }
+        debug!("Clipboard content is excluded from monitoring by its source");
+        ClipboardData::Excluded
+    }
+
+    /// A number that changes whenever something is copied, where the platform has one that
+    /// is cheap to read: the clipboard sequence number on Windows, the pasteboard change
+    /// count on macOS, and on X11 the selection owner with the time it took the selection,
+    /// as the XFixes listener last reported them
+    fn sequence_number(&self) -> Option<u64> {
+        #[cfg(windows)]
+        {
+            use winapi::um::winuser::GetClipboardSequenceNumber;
+
+            // 0 when this process can't see the clipboard, e.g. on another desktop
+            match unsafe { GetClipboardSequenceNumber() } {
+                0 => None,
+                sequence => Some(u64::from(sequence)),
+            }
+        }
+        #[cfg(target_os = "macos")]
+        {
+            use objc2_app_kit::NSPasteboard;
+
+            Some(unsafe { NSPasteboard::generalPasteboard().changeCount() } as u64)
+        }
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            // Wayland only says what changed through the data-control listener's events
+            if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                return None;
+            }
+            match self.selection_stamp.load(Ordering::Relaxed) {
+                UNKNOWN_STAMP => None,
+                stamp => Some(stamp),
+            }
+        }
+        #[cfg(not(any(windows, unix)))]
+        {
+            None
+        }
+    }
+
+    /// Whether the current clipboard owner asked monitors to ignore its content
+    fn is_excluded_from_monitoring() -> bool {
+        #[cfg(windows)]
+        {
+            Self::windows_excluded()
+        }
+        #[cfg(target_os = "macos")]
+        {
+            Self::macos_pasteboard_types()
+                .iter()
+                .any(|name| MACOS_EXCLUSION_TYPES.contains(&name.as_str()))
+        }
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            match Self::unix_clipboard_targets() {
+                Ok(targets) => targets.iter().any(|target| target == KDE_PASSWORD_HINT),
+                Err(e) => {
+                    debug!("Could not list clipboard targets: {}", e);
+                    false
+                }
+            }
+        }
+        #[cfg(not(any(windows, unix)))]
+        {
+            false
+        }
+    }
+
+    #[cfg(windows)]
+    fn windows_excluded() -> bool {
+        use winapi::um::winbase::{GlobalLock, GlobalUnlock};
+        use winapi::um::winuser::{CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard, RegisterClipboardFormatW};
+        use std::ptr::null_mut;
+
+        let register = |name: &str| {
+            let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+            unsafe { RegisterClipboardFormatW(wide.as_ptr()) }
+        };
+
+        unsafe {
+            if WINDOWS_EXCLUSION_FORMATS
+                .iter()
+                .any(|name| IsClipboardFormatAvailable(register(name)) != 0)
+            {
+                return true;
+            }
+
+            // CanIncludeInClipboardHistory holds a DWORD; 0 means keep out of history
+            let history_format = register("CanIncludeInClipboardHistory");
+            if IsClipboardFormatAvailable(history_format) == 0 || OpenClipboard(null_mut()) == 0 {
+                return false;
+            }
+            let mut excluded = false;
+            let handle = GetClipboardData(history_format);
+            if !handle.is_null() {
+                let value = GlobalLock(handle) as *const u32;
+                if !value.is_null() {
+                    excluded = *value == 0;
+                    GlobalUnlock(handle);
+                }
+            }
+            CloseClipboard();
+            excluded
+        }
+    }
+
+    /// MIME types offered by the clipboard owner
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn unix_clipboard_targets() -> Result<Vec<String>> {
+        use std::env;
+        use std::process::Command;
+
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            let output = Command::new("wl-paste").arg("--list-types").output()?;
+            return Ok(String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect());
+        }
+
+        use x11rb::protocol::xproto::ConnectionExt;
+
+        let (conn, reply) = Self::x11_clipboard(b"TARGETS")?;
+        let Some(reply) = reply else { return Ok(Vec::new()) };
+        let mut names = Vec::new();
+        for atom in reply.value32().into_iter().flatten() {
+            let name = conn.get_atom_name(atom)?.reply()?.name;
+            names.push(String::from_utf8_lossy(&name).into_owned());
+        }
+        Ok(names)
+    }
+
+    /// The clipboard as MIME type `mime`, or None when the owner doesn't offer it
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn unix_clipboard_contents(mime: &str) -> Result<Option<Vec<u8>>> {
+        use std::env;
+        use std::process::Command;
+
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            let output = Command::new("wl-paste").args(["--no-newline", "--type", mime]).output()?;
+            return Ok(output.status.success().then_some(output.stdout));
+        }
+        Ok(Self::x11_clipboard(mime.as_bytes())?.1.map(|reply| reply.value))
+    }
+
+    /// Ask the X11 clipboard owner to convert the clipboard to `target`; the reply is None when
+    /// it can't. Content too large for a single property (an INCR transfer) comes back empty
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn x11_clipboard(target: &[u8]) -> Result<(x11rb::rust_connection::RustConnection, Option<x11rb::protocol::xproto::GetPropertyReply>)> {
+        use x11rb::connection::Connection;
+        use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, CreateWindowAux, WindowClass};
+        use x11rb::protocol::Event;
+        use x11rb::CURRENT_TIME;
+
+        let (conn, screen_num) = x11rb::connect(None)?;
+        let root = conn.setup().roots[screen_num].root;
+        let window = conn.generate_id()?;
+        conn.create_window(0, window, root, 0, 0, 1, 1, 0, WindowClass::INPUT_ONLY, 0, &CreateWindowAux::new())?;
+
+        let clipboard = conn.intern_atom(false, b"CLIPBOARD")?.reply()?.atom;
+        let target = conn.intern_atom(false, target)?.reply()?.atom;
+        let property = conn.intern_atom(false, b"CLIPBOARD_QR_SELECTION")?.reply()?.atom;
+        conn.convert_selection(window, clipboard, target, property, CURRENT_TIME)?;
+        conn.flush()?;
+
+        // The owner answers with SelectionNotify; don't hang on owners that never reply
+        let deadline = Instant::now() + Duration::from_millis(500);
+        loop {
+            match conn.poll_for_event()? {
+                Some(Event::SelectionNotify(event)) if event.requestor == window => {
+                    if event.property == x11rb::NONE {
+                        return Ok((conn, None));
+                    }
+                    break;
+                }
+                Some(_) => {}
+                None if Instant::now() >= deadline => {
+                    return Err(anyhow::anyhow!("Timed out waiting for the clipboard owner"));
+                }
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+
+        let reply = conn
+            .get_property(true, window, property, AtomEnum::ANY, 0, X11_MAX_PROPERTY_WORDS)?
+            .reply()?;
+        Ok((conn, Some(reply)))
+    }
+
+    /// RTF on the clipboard, which arboard doesn't read
+    fn read_rtf() -> Result<Option<String>> {
+        #[cfg(windows)]
+        {
+            Ok(Self::windows_rtf())
+        }
+        #[cfg(target_os = "macos")]
+        {
+            use objc2_app_kit::{NSPasteboard, NSPasteboardTypeRTF};
+
+            let rtf = unsafe { NSPasteboard::generalPasteboard().stringForType(NSPasteboardTypeRTF) };
+            Ok(rtf.map(|rtf| rtf.to_string()))
+        }
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            Ok(Self::unix_clipboard_contents("text/rtf")?.map(|rtf| String::from_utf8_lossy(&rtf).into_owned()))
+        }
+        #[cfg(not(any(windows, unix)))]
+        {
+            Ok(None)
+        }
+    }
+
+    #[cfg(windows)]
+    fn windows_rtf() -> Option<String> {
+        use winapi::um::winbase::{GlobalLock, GlobalSize, GlobalUnlock};
+        use winapi::um::winuser::{CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard, RegisterClipboardFormatW};
+        use std::ptr::null_mut;
+
+        let name: Vec<u16> = "Rich Text Format".encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            let format = RegisterClipboardFormatW(name.as_ptr());
+            if IsClipboardFormatAvailable(format) == 0 || OpenClipboard(null_mut()) == 0 {
+                return None;
+            }
+            let mut rtf = None;
+            let handle = GetClipboardData(format);
+            if !handle.is_null() {
+                let data = GlobalLock(handle) as *const u8;
+                if !data.is_null() {
+                    let bytes = std::slice::from_raw_parts(data, GlobalSize(handle));
+                    // The global block may be larger than the NUL-terminated RTF
+                    let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+                    rtf = Some(String::from_utf8_lossy(&bytes[..end]).into_owned());
+                    GlobalUnlock(handle);
+                }
+            }
+            CloseClipboard();
+            rtf
+        }
+    }
+
+    /// HTML, or failing that RTF, on the clipboard
+    fn read_rich_text(clipboard: &mut Clipboard) -> Option<RichContent> {
+        if let Ok(html) = clipboard.get().html() {
+            if !html.trim().is_empty() {
+                return Some(RichContent::from_html(&html));
+            }
+        }
+        match Self::read_rtf() {
+            Ok(rtf) => rtf.filter(|rtf| rtf.starts_with("{\\rtf")).map(|rtf| RichContent::from_rtf(&rtf)),
+            Err(e) => {
+                debug!("Could not read RTF from clipboard: {}", e);
+                None
+            }
+        }
+    }
+
+    fn read_data(&mut self) -> Result<ClipboardData> {
         match &mut self.clipboard {
             Some(clipboard) => {
                 // Try to get image first
-                if let Ok(image) = clipboard.get_image() {
+                if !self.formats.image {
+                    // Image reading disabled
+                } else if let Ok(image) = clipboard.get_image() {
                     debug!("Successfully read image from clipboard");
                     let img_buffer = ImageBuffer::from_raw(
                         image.width as u32,
@@ -366,22 +1028,49 @@ impl ClipboardHandler {
                     
                     return Ok(ClipboardData::Image(img_buffer));
                 }
+
+                if self.formats.files {
+                    match clipboard.get().file_list() {
+                        Ok(files) if !files.is_empty() => {
+                            debug!("Read a list of {} files from clipboard", files.len());
+                            return Ok(ClipboardData::Files(files));
+                        }
+                        _ => {}
+                    }
+                }
                 
                 // Try to get text
-                match clipboard.get_text() {
-                    Ok(text) => {
-                        debug!("Successfully read text from clipboard");
-                        if text.is_empty() {
-                            Ok(ClipboardData::Empty)
-                        } else {
-                            Ok(ClipboardData::Text(text))
+                let text_error = if self.formats.text {
+                    match clipboard.get_text() {
+                        Ok(text) if !text.is_empty() => {
+                            debug!("Successfully read text from clipboard");
+                            return Ok(ClipboardData::Text(text));
                         }
-                    },
-                    Err(e) => {
-                        warn!("Failed to read text from clipboard: {}", e);
-                        Ok(ClipboardData::Empty)
-                    },
+                        Ok(_) => None,
+                        Err(e) => Some(e),
+                    }
+                } else {
+                    None
+                };
+
+                // Browsers and word processors may only offer HTML or RTF, with images inlined
+                if let Some(rich) = self.formats.rich_text.then(|| Self::read_rich_text(clipboard)).flatten() {
+                    if self.formats.image && (rich.text.is_empty() || !self.formats.text) {
+                        if let Some(image) = rich.image() {
+                            debug!("Read an embedded image from rich clipboard content");
+                            return Ok(ClipboardData::Image(image));
+                        }
+                    }
+                    if self.formats.text && !rich.text.is_empty() {
+                        debug!("Read text from rich clipboard content");
+                        return Ok(ClipboardData::Text(rich.text));
+                    }
+                }
+
+                if let Some(e) = text_error {
+                    warn!("Failed to read text from clipboard: {}", e);
                 }
+                Ok(ClipboardData::Empty)
             },
             None => {
                 Err(anyhow::anyhow!("Clipboard not available"))
@@ -389,63 +1078,113 @@ impl ClipboardHandler {
         }
     }
 
-    pub fn has_changed(&mut self) -> Result<bool> {
-        let current_data = self.get_data()?;
-        let mut hasher = DefaultHasher::new();
-        
-        match &current_data {
-            ClipboardData::Text(text) => text.hash(&mut hasher),
-            ClipboardData::Image(image) => {
-                // Hash the image dimensions and first few pixels for change detection
-                (image.width(), image.height()).hash(&mut hasher);
-                if let Some(pixel) = image.get_pixel_checked(0, 0) {
-                    pixel.hash(&mut hasher);
-                }
-            },
-            ClipboardData::Empty => "empty".hash(&mut hasher),
+    fn content_hash(&self, data: &ClipboardData) -> u64 {
+        content_hash(data, self.normalization)
+    }
+
+    /// The clipboard content, if it changed since it was last read
+    ///
+    /// `verify` is set for the periodic reads that check on the listener; where the listener
+    /// reports the sequence number itself, those compare the content whatever it says.
+    fn read_if_changed(&mut self, verify: bool) -> Result<Option<ClipboardData>> {
+        self.read_if_changed_with(verify, Self::read_data)
+    }
+
+    fn read_if_changed_with(
+        &mut self,
+        verify: bool,
+        read: impl FnOnce(&mut Self) -> Result<ClipboardData>,
+    ) -> Result<Option<ClipboardData>> {
+        // Reading an image copies every pixel, so while the platform's counter says nothing
+        // was copied the content isn't read at all
+        let sequence = self.sequence_number();
+        let trusted = !(verify && SEQUENCE_FROM_LISTENER);
+        if trusted && sequence.is_some() && sequence == self.last_sequence {
+            return Ok(None);
         }
-        
-        let current_hash = hasher.finish();
-        let changed = current_hash != self.last_hash;
-        
-        if changed {
-            self.last_hash = current_hash;
-            self.last_check_time = SystemTime::now();
+        let current_data = read(self)?;
+        self.last_sequence = sequence;
+        let current_hash = self.content_hash(&current_data);
+        if current_hash == self.last_hash {
+            return Ok(None);
         }
-        
-        Ok(changed)
+        self.last_hash = current_hash;
+        Ok(Some(current_data))
     }
 
     pub fn get_data_if_changed(&mut self) -> Result<Option<ClipboardData>> {
         // Check for clipboard events first
+        #[allow(unused_mut)]
+        let mut event = false;
         #[cfg(any(windows, unix))]
         {
             if let Some(ref rx) = self.clipboard_channel {
-                if let Ok(_) = rx.try_recv() {
-                    // Clipboard changed, get the new data
-                    return Ok(Some(self.get_data()?));
+                for clipboard_event in rx.try_iter() {
+                    match clipboard_event {
+                        ClipboardEvent::Updated { formats } => {
+                            self.watchdog.heartbeat();
+                            // Skip updates carrying nothing we read
+                            event |= formats.overlaps(self.formats);
+                        }
+                        ClipboardEvent::OwnerChanged => {
+                            self.watchdog.heartbeat();
+                            event = true;
+                        }
+                        ClipboardEvent::ListenerDegraded(reason) => {
+                            warn!("Clipboard listener degraded to polling: {}", reason);
+                            self.watchdog.polling();
+                            self.degraded_reason = Some(reason);
+                        }
+                        ClipboardEvent::Poll => self.watchdog.polling(),
+                    }
                 }
             }
         }
 
-        // Fallback to polling
-        if self.has_changed()? {
-            Ok(Some(self.get_data()?))
-        } else {
-            Ok(None)
+        // Fallback to polling; always refresh the hash on an event so it isn't reported twice.
+        // A listener that reports changes itself is only double-checked now and then.
+        let now = Instant::now();
+        let mut fresh = None;
+        if event || self.watchdog.should_verify(self.last_verified, now) {
+            self.last_verified = now;
+            fresh = self.read_if_changed(!event)?;
+        }
+        let changed = fresh.is_some();
+        #[cfg(any(windows, unix))]
+        if self.clipboard_channel.is_some() {
+            let stopped = self.clipboard_thread.as_ref().is_some_and(|handle| handle.is_finished());
+            if let Some(reason) = self.watchdog.check(changed && !event, stopped, now) {
+                self.restart_listener(reason);
+            }
+        }
+        if changed || event {
+            self.pending_since = Some(Instant::now());
+        }
+
+        // Report once the clipboard has been stable for the debounce period
+        match self.pending_since {
+            Some(since) if since.elapsed() >= self.debounce => {
+                self.pending_since = None;
+                // Content read for this check is reported as it is rather than read again
+                match fresh {
+                    Some(data) => Ok(Some(Self::unless_excluded(data))),
+                    None => Ok(Some(self.get_data()?)),
+                }
+            }
+            _ => Ok(None),
         }
     }
 
     pub fn set_text(&mut self, text: &str) -> Result<()> {
+        let keep = self.keep_until_replaced;
         match &mut self.clipboard {
             Some(clipboard) => {
-                match clipboard.set_text(text) {
+                match Self::setter(clipboard, keep).text(text) {
                     Ok(()) => {
                         debug!("Successfully set text to clipboard");
                         // Update hash to prevent immediate change detection
-                        let mut hasher = DefaultHasher::new();
-                        text.hash(&mut hasher);
-                        self.last_hash = hasher.finish();
+                        self.last_hash = self.content_hash(&ClipboardData::Text(text.to_string()));
+                        self.last_sequence = self.sequence_number();
                         Ok(())
                     },
                     Err(e) => {
@@ -460,12 +1199,86 @@ impl ClipboardHandler {
         }
     }
 
-    pub fn is_available(&self) -> bool {
-        self.clipboard.is_some()
+    /// Put an RGBA image on the clipboard; the alpha channel is passed through unchanged
+    pub fn set_image(&mut self, image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<()> {
+        let data = arboard::ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: std::borrow::Cow::Borrowed(image.as_raw()),
+        };
+        let keep = self.keep_until_replaced;
+        let clipboard = self.clipboard.as_mut().ok_or_else(|| anyhow::anyhow!("Clipboard not available"))?;
+        Self::setter(clipboard, keep)
+            .image(data)
+            .map_err(|e| anyhow::anyhow!("Failed to set clipboard image: {}", e))?;
+        debug!("Successfully set {}x{} image to clipboard", image.width(), image.height());
+        // Don't report our own image as a clipboard change
+        self.last_hash = image_hash(image);
+        self.last_sequence = self.sequence_number();
+        Ok(())
+    }
+
+    /// Sets clipboard content, serving it until it is replaced when `keep` is set, see
+    /// [`ClipboardHandlerBuilder::keep_until_replaced`]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn setter(clipboard: &mut Clipboard, keep: bool) -> arboard::Set<'_> {
+        use arboard::SetExtLinux;
+        match keep {
+            true => clipboard.set().wait(),
+            false => clipboard.set(),
+        }
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    fn setter(clipboard: &mut Clipboard, _keep: bool) -> arboard::Set<'_> {
+        clipboard.set()
+    }
+
+    /// The interval callers should wait between calls to `get_data_if_changed`
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn formats(&self) -> ClipboardFormats {
+        self.formats
+    }
+
+    /// Change which formats are read; takes effect on the next check
+    pub fn set_formats(&mut self, formats: ClipboardFormats) {
+        if formats != self.formats {
+            info!(
+                "Clipboard formats changed: text={}, image={}, rich_text={}, files={}",
+                formats.text, formats.image, formats.rich_text, formats.files
+            );
+            self.formats = formats;
+            // What is read from the same content changes too
+            self.last_sequence = None;
+        }
+    }
+
+    /// Why the watchdog restarted the platform listener since the last call, if it did
+    pub fn take_listener_restart(&mut self) -> Option<String> {
+        self.restarted.take()
     }
 
-    pub fn get_last_check_time(&self) -> SystemTime {
-        self.last_check_time
+    /// Why the platform listener fell back to polling, if it did
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn degraded_reason(&self) -> Option<&str> {
+        self.degraded_reason.as_deref()
+    }
+
+    /// Whether a platform listener thread is running
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn has_listener(&self) -> bool {
+        #[cfg(any(windows, unix))]
+        {
+            self.clipboard_thread.as_ref().is_some_and(|handle| !handle.is_finished())
+        }
+        #[cfg(not(any(windows, unix)))]
+        {
+            false
+        }
     }
 }
 
@@ -473,12 +1286,68 @@ impl Drop for ClipboardHandler {
     fn drop(&mut self) {
         #[cfg(any(windows, unix))]
         {
-            // Cleanup clipboard listener thread
+            // Dropping the receiver stops polling threads on their next send; event-driven
+            // listeners block in the OS and are left to exit with the process, so don't join
+            self.clipboard_channel.take();
             if let Some(handle) = self.clipboard_thread.take() {
-                if let Err(e) = handle.join() {
-                    warn!("Failed to join clipboard listener thread: {:?}", e);
+                if handle.is_finished() {
+                    if let Err(e) = handle.join() {
+                        warn!("Clipboard listener thread panicked: {:?}", e);
+                    }
                 }
             }
         }
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipboard_handler_builder() {
+        let handler = ClipboardHandler::builder()
+            .poll_interval(Duration::from_millis(250))
+            .formats(ClipboardFormats::TEXT_ONLY)
+            .spawn_listener(false)
+            .debounce(Duration::from_millis(50))
+            .build();
+        assert_eq!(handler.poll_interval(), Duration::from_millis(250));
+        assert_eq!(handler.formats(), ClipboardFormats::TEXT_ONLY);
+        assert!(!handler.has_listener());
+        assert_eq!(handler.degraded_reason(), None);
+    }
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn test_verify_ignores_frozen_stamp() {
+        // Without WAYLAND_DISPLAY the X11 selection stamp is the sequence number
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return;
+        }
+        let mut handler = ClipboardHandler::builder().spawn_listener(false).build();
+        let text = |text: &str| {
+            let data = ClipboardData::Text(text.to_string());
+            move |_: &mut ClipboardHandler| Ok(data)
+        };
+        handler.selection_stamp.store(7 << 32 | 1000, Ordering::Relaxed);
+        assert!(handler.read_if_changed_with(false, text("first")).unwrap().is_some());
+
+        // The listener stalled: the stamp stays put while something else is copied
+        assert!(handler.read_if_changed_with(false, text("second")).unwrap().is_none());
+        let fresh = handler.read_if_changed_with(true, text("second")).unwrap();
+        assert!(matches!(fresh, Some(ClipboardData::Text(text)) if text == "second"));
+        assert!(handler.read_if_changed_with(true, text("second")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_clipboard_formats() {
+        let images = ClipboardFormats::content(false, true);
+        assert!(images.rich_text && images.files && !images.text);
+        assert!(!ClipboardFormats::content(true, false).files);
+        let html = ClipboardFormats { text: false, image: false, rich_text: true, files: false };
+        assert!(html.overlaps(images));
+        assert!(!html.overlaps(ClipboardFormats::TEXT_ONLY));
+        assert!(!ClipboardFormats::IMAGE_ONLY.overlaps(ClipboardFormats::TEXT_ONLY));
+    }
+}
@@ -0,0 +1,168 @@
+use chrono::{DateTime, Local};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+
+use crate::monitor::{MonitorEvent, ScanOutcome};
+use crate::redaction::Redactor;
+
+/// Entries kept unless `[monitor] clipboard_history` says otherwise
+pub const DEFAULT_CAPACITY: usize = 20;
+
+/// What was on the clipboard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardItem {
+    Text(String),
+    /// Only the size and what was decoded are kept, not the pixels
+    Image {
+        width: u32,
+        height: u32,
+        decoded: Option<String>,
+        fingerprint: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardHistoryEntry {
+    pub id: u64,
+    pub item: ClipboardItem,
+    pub copied_at: DateTime<Local>,
+}
+
+impl ClipboardHistoryEntry {
+    /// The text a QR code can be regenerated from; `None` for images without a QR code
+    pub fn qr_content(&self) -> Option<&str> {
+        match &self.item {
+            ClipboardItem::Text(text) => Some(text),
+            ClipboardItem::Image { decoded, .. } => decoded.as_deref(),
+        }
+    }
+
+    /// One line for menus and lists, e.g. "https://example.com…" or "Image 800x600: WIFI:…"
+    pub fn label(&self, redactor: &Redactor, max_chars: usize) -> String {
+        let label = match &self.item {
+            ClipboardItem::Text(text) => redactor.text_truncated(text, max_chars),
+            ClipboardItem::Image { width, height, decoded: Some(decoded), .. } => {
+                format!("Image {}x{}: {}", width, height, redactor.text_truncated(decoded, max_chars))
+            }
+            ClipboardItem::Image { width, height, decoded: None, .. } => format!("Image {}x{} (no QR code)", width, height),
+        };
+        label.replace(['\r', '\n', '\t'], " ")
+    }
+}
+
+/// The last few things copied, newest first, kept in memory only
+///
+/// Copying something already in the list moves it to the top instead of adding it again,
+/// so repeatedly copying between two texts doesn't push everything else out.
+#[derive(Debug, Clone)]
+pub struct ClipboardHistory {
+    capacity: usize,
+    next_id: u64,
+    entries: VecDeque<ClipboardHistoryEntry>,
+}
+
+impl Default for ClipboardHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl ClipboardHistory {
+    /// A history of up to `capacity` entries; 0 records nothing
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_id: 1,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record the content of `event`; returns whether the history changed
+    pub fn record(&mut self, event: &MonitorEvent) -> bool {
+        let item = match event {
+            MonitorEvent::Text(text) if !text.is_empty() => ClipboardItem::Text(text.clone()),
+            MonitorEvent::Image { image, scan } => {
+                let mut hasher = DefaultHasher::new();
+                image.dimensions().hash(&mut hasher);
+                image.as_raw().hash(&mut hasher);
+                ClipboardItem::Image {
+                    width: image.width(),
+                    height: image.height(),
+                    decoded: match scan {
+                        ScanOutcome::Decoded(content) => Some(content.clone()),
+                        _ => None,
+                    },
+                    fingerprint: hasher.finish(),
+                }
+            }
+            // Cleared and private content leave the history as it is
+            _ => return false,
+        };
+        self.push(item)
+    }
+
+    fn push(&mut self, item: ClipboardItem) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+        if self.entries.front().is_some_and(|newest| newest.item == item) {
+            return false;
+        }
+        let id = match self.entries.iter().position(|entry| entry.item == item) {
+            Some(index) => self.entries.remove(index).map_or(0, |entry| entry.id),
+            None => {
+                let id = self.next_id;
+                self.next_id += 1;
+                id
+            }
+        };
+        self.entries.push_front(ClipboardHistoryEntry { id, item, copied_at: Local::now() });
+        self.entries.truncate(self.capacity);
+        true
+    }
+
+    /// Newest first
+    pub fn entries(&self) -> impl Iterator<Item = &ClipboardHistoryEntry> {
+        self.entries.iter()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ClipboardHistoryEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_clipboard_history() {
+        let mut history = ClipboardHistory::new(3);
+        let text = |text: &str| MonitorEvent::Text(text.to_string());
+        assert!(history.record(&text("one")));
+        assert!(history.record(&text("two")));
+        // The same text again is not a new entry
+        assert!(!history.record(&text("two")));
+        assert!(!history.record(&MonitorEvent::Cleared));
+        // Copying an older text moves it to the top, keeping its id
+        assert!(history.record(&text("one")));
+        let ids: Vec<u64> = history.entries().map(|entry| entry.id).collect();
+        assert_eq!(ids, [1, 2]);
+
+        let image = MonitorEvent::Image {
+            image: RgbaImage::new(4, 3),
+            scan: ScanOutcome::Decoded("WIFI:S:home;;".to_string()),
+        };
+        assert!(history.record(&image));
+        assert!(history.record(&text("three")));
+        assert_eq!(history.entries().count(), 3);
+        let newest: Vec<Option<&str>> = history.entries().map(ClipboardHistoryEntry::qr_content).collect();
+        assert_eq!(newest, [Some("three"), Some("WIFI:S:home;;"), Some("one")]);
+        assert_eq!(history.get(3).unwrap().label(&Redactor::new(false), 40), "Image 4x3: WIFI:S:home;;");
+        assert!(history.get(2).is_none());
+
+        assert!(!ClipboardHistory::new(0).record(&text("one")));
+    }
+}
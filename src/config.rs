@@ -0,0 +1,614 @@
+use anyhow::Result;
+use qrcode::EcLevel;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+use url::Url;
+
+use crate::clipboard_handler::ClipboardFormats;
+use crate::clipboard_history;
+use crate::expiry::ExpiryConfig;
+use crate::history::{HistoryBackend, HistoryConfig};
+use crate::kdeconnect::KdeConnectConfig;
+use crate::locale::LocaleConfig;
+use crate::password_manager::PasswordManagerConfig;
+use crate::payload::PayloadTypeConfig;
+use crate::label::LabelTemplate;
+use crate::link_safety::LinkSafetyConfig;
+use crate::normalize::NormalizationProfile;
+use crate::notification::{CopyDecoded, NotificationPolicy};
+use crate::notifier::{NotifierConfig, NotifierKind};
+use crate::pipeline::PipelineRule;
+use crate::preprocess::PresetStore;
+use crate::privacy::SensitiveConfig;
+use crate::qr_generator::{QRGenerator, QrSymbology};
+use crate::qr_scanner::ScanSettings;
+use crate::matrix::QUIET_ZONE;
+use crate::qr_style::{Color, ModuleShape, QrStyle, StylePreset};
+use crate::renderer::PngRenderer;
+use crate::scheduler::TaskConfig;
+use crate::snippets::Snippet;
+use crate::snooze::SnoozeConfig;
+use crate::storage;
+use crate::transforms::{TextTransforms, TransformConfig};
+
+/// Settings the `settings` window edits, as (table, key); [`AppConfig::save_to`] writes
+/// only these and leaves the rest of the file, comments included, as it was
+pub const EDITABLE_SETTINGS: [(&str, &str); 24] = [
+    ("monitor", "text"),
+    ("monitor", "image"),
+    ("monitor", "poll_interval_ms"),
+    ("monitor", "clipboard_history"),
+    ("monitor", "copy_decoded"),
+    ("style", "preset"),
+    ("style", "foreground"),
+    ("style", "background"),
+    ("style", "error_correction"),
+    ("style", "symbology"),
+    ("style", "shape"),
+    ("style", "quiet_zone"),
+    ("style", "frame"),
+    ("style", "caption"),
+    ("style", "pixels_per_module"),
+    ("style", "target_size"),
+    ("output", "dir"),
+    ("notifications", "level"),
+    ("notifications", "desktop"),
+    ("privacy", "redact_content"),
+    ("snooze", "windows"),
+    ("snooze", "calendar"),
+    ("snooze", "pause_when"),
+    ("startup", "autostart"),
+];
+
+/// Application settings, read from `config.toml` in the platform config directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub guest: GuestConfig,
+    pub monitor: MonitorConfig,
+    pub privacy: PrivacyConfig,
+    pub console: ConsoleConfig,
+    pub notifications: NotificationConfig,
+    pub style: StyleConfig,
+    /// Where generated files go unless a path is given
+    pub output: OutputConfig,
+    pub remote: RemoteConfig,
+    pub agent: AgentConfig,
+    pub history: HistoryConfig,
+    pub label: LabelConfig,
+    /// When generated sensitive content is wiped
+    pub expiry: ExpiryConfig,
+    /// Sending to a phone paired with KDE Connect
+    pub kdeconnect: KdeConnectConfig,
+    /// How dates and numbers shown to the user are written
+    pub locale: LocaleConfig,
+    /// Checks run on URLs decoded from QR codes
+    pub links: LinkSafetyConfig,
+    /// Where scanned one-time passwords can be imported on request
+    pub password_manager: PasswordManagerConfig,
+    /// Extra kinds of content to recognize, tried before the built-in ones
+    pub payload_types: Vec<PayloadTypeConfig>,
+    /// Which processing steps run for each kind of clipboard content; first match wins
+    pub pipeline: Vec<PipelineRule>,
+    /// Frequently used QR content, available from the tray and the `snippet` command
+    pub snippets: Vec<Snippet>,
+    /// When monitoring pauses by itself, e.g. outside work hours
+    pub snooze: SnoozeConfig,
+    /// Starting with the desktop session
+    pub startup: StartupConfig,
+    /// Scheduled QR generation tasks, run while the tray app is running
+    pub tasks: Vec<TaskConfig>,
+    /// Applied in order to text decoded from QR codes, e.g. to strip tracking parameters
+    pub transforms: Vec<TransformConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// Turn copied text into QR codes
+    pub text: bool,
+    /// Scan copied images for QR codes
+    pub image: bool,
+    /// How copied text is canonicalized for change detection, history dedup and caching
+    pub normalization: NormalizationProfile,
+    /// Colour transparent parts of copied images are composited over before scanning
+    pub scan_background: Color,
+    /// Exit once the clipboard has been unreadable this long, so a supervisor can restart
+    /// the app; 0 keeps retrying forever
+    pub give_up_secs: u64,
+    /// Clipboard entries kept in memory for regenerating their QR codes later; 0 keeps none
+    pub clipboard_history: usize,
+    /// How often the clipboard is checked where the platform has no change events, and
+    /// how often listeners look for changes they missed, in milliseconds
+    pub poll_interval_ms: u64,
+    /// Put the text of QR codes decoded from copied images on the clipboard: always, ask
+    /// (a Copy button on the desktop notification) or never
+    pub copy_decoded: CopyDecoded,
+    /// When a copied image doesn't decode, retry it in greyscale, contrast stretched,
+    /// thresholded, sharpened, rotated and downscaled
+    pub scan_retries: bool,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            text: true,
+            image: true,
+            normalization: NormalizationProfile::default(),
+            scan_background: Color::WHITE,
+            give_up_secs: 60,
+            clipboard_history: clipboard_history::DEFAULT_CAPACITY,
+            poll_interval_ms: 100,
+            copy_decoded: CopyDecoded::default(),
+            scan_retries: true,
+        }
+    }
+}
+
+impl MonitorConfig {
+    pub fn formats(&self) -> ClipboardFormats {
+        if !self.text && !self.image {
+            warn!("Both text and image monitoring are disabled; monitoring both");
+            return ClipboardFormats::ALL;
+        }
+        ClipboardFormats::content(self.text, self.image)
+    }
+
+    pub fn give_up_after(&self) -> Option<Duration> {
+        (self.give_up_secs > 0).then(|| Duration::from_secs(self.give_up_secs))
+    }
+
+    /// At least 10 ms, so a typo doesn't keep a core busy
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms.max(10))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuestConfig {
+    /// Read-only mode for shared machines; see [`AppConfig::apply_guest_mode`]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StartupConfig {
+    /// Start the tray app at login; the tray installs or removes the login entry to match
+    /// when it starts and when Start at Login is toggled
+    pub autostart: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacyConfig {
+    /// Show only a type + length summary instead of clipboard/QR content
+    /// in logs, tray tooltips, console output and notifications
+    pub redact_content: bool,
+    /// Never write to disk: history stays in memory, scheduled tasks are off and
+    /// files are only saved to paths chosen explicitly; see [`AppConfig::apply_in_memory_mode`]
+    pub in_memory: bool,
+    /// Text that is never turned into a QR code or printed automatically
+    pub sensitive: SensitiveConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    /// Which events update the tray tooltip and are printed to the console
+    pub level: NotificationPolicy,
+    /// Print the session summary when the app exits (unless the level is silent)
+    pub goodbye: bool,
+    /// Show desktop notifications from the toast backends; the tray's Desktop Notifications
+    /// item switches them for the session
+    pub desktop: bool,
+    /// Where notifications go; empty picks defaults for the environment,
+    /// see [`NotifierConfig::defaults`]
+    pub backends: Vec<NotifierConfig>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            level: NotificationPolicy::default(),
+            goodbye: false,
+            desktop: true,
+            backends: Vec::new(),
+        }
+    }
+}
+
+impl NotificationConfig {
+    /// The configured backends, or the defaults for a desktop or terminal session
+    pub fn backends(&self, desktop: bool) -> Vec<NotifierConfig> {
+        if self.backends.is_empty() {
+            NotifierConfig::defaults(desktop)
+        } else {
+            self.backends.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsoleConfig {
+    /// Don't print clipboard events to the console in tray mode
+    pub silent: bool,
+    /// Minimum time between two printed events; faster changes are only counted
+    pub min_interval_ms: u64,
+}
+
+impl Default for ConsoleConfig {
+    fn default() -> Self {
+        Self {
+            silent: false,
+            min_interval_ms: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// Bearer token sent when pushing to another instance
+    pub token: Option<String>,
+}
+
+/// Agent mode, for running inside a VM or remote desktop session and forwarding
+/// its clipboard to the instance on the host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentConfig {
+    /// Base URL of the host instance; `agent --to` overrides it
+    pub host: Option<Url>,
+    /// How this guest identifies itself to the host (default: the machine name)
+    pub name: Option<String>,
+    /// Forward copied text as well as decoded QR codes
+    pub forward_text: bool,
+    /// Seconds between attempts to reach the host while it is unreachable
+    pub retry_secs: u64,
+    /// Changes kept while the host is unreachable; older ones are dropped
+    pub queue_size: usize,
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            host: None,
+            name: None,
+            forward_text: true,
+            retry_secs: 5,
+            queue_size: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelConfig {
+    /// Template used when `label` is run without `--template`
+    pub template: String,
+    /// Printer queue for `label --print`; the system default if unset
+    pub printer: Option<String>,
+    /// Additional label templates; these override built-ins with the same name
+    pub templates: Vec<LabelTemplate>,
+}
+
+impl Default for LabelConfig {
+    fn default() -> Self {
+        Self {
+            template: "zebra-2x1".to_string(),
+            printer: None,
+            templates: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StyleConfig {
+    /// Name of a built-in style preset
+    pub preset: String,
+    /// Custom colours overriding the preset; checked for scannable contrast
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    /// Error correction level of generated codes
+    pub error_correction: ErrorCorrection,
+    /// Generate Micro QR codes for text short enough, e.g. IDs and serial numbers
+    pub symbology: QrSymbology,
+    pub shape: ModuleShape,
+    /// Light modules around generated codes
+    pub quiet_zone: usize,
+    /// Modules of a dark frame around the quiet zone of saved PNGs and SVGs
+    pub frame: usize,
+    /// Text under the code in saved PNGs and SVGs, e.g. "Scan me"
+    pub caption: Option<String>,
+    /// Pixels per module of saved PNGs, copied images and the windows' codes
+    pub pixels_per_module: u32,
+    /// Side of those images in pixels instead, as near as whole pixels per module allow
+    pub target_size: Option<u32>,
+}
+
+impl Default for StyleConfig {
+    fn default() -> Self {
+        Self {
+            preset: StylePreset::default().name().to_string(),
+            foreground: None,
+            background: None,
+            error_correction: ErrorCorrection::M,
+            symbology: QrSymbology::default(),
+            shape: ModuleShape::default(),
+            quiet_zone: QUIET_ZONE,
+            frame: 0,
+            caption: None,
+            pixels_per_module: PngRenderer::default().scale,
+            target_size: None,
+        }
+    }
+}
+
+/// QR error correction level, from L (7% of the code can be damaged) to H (30%)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCorrection {
+    L,
+    #[default]
+    M,
+    Q,
+    H,
+}
+
+impl ErrorCorrection {
+    pub const ALL: [ErrorCorrection; 4] = [ErrorCorrection::L, ErrorCorrection::M, ErrorCorrection::Q, ErrorCorrection::H];
+
+    pub fn level(self) -> EcLevel {
+        match self {
+            ErrorCorrection::L => EcLevel::L,
+            ErrorCorrection::M => EcLevel::M,
+            ErrorCorrection::Q => EcLevel::Q,
+            ErrorCorrection::H => EcLevel::H,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    /// Folder for QR images and labels saved without an explicit path
+    pub dir: PathBuf,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self { dir: PathBuf::from("output") }
+    }
+}
+
+impl StyleConfig {
+    /// The configured style, falling back to the default colours if they are unknown or unscannable
+    pub fn resolve(&self) -> QrStyle {
+        self.colors()
+            .with_shape(self.shape)
+            .with_quiet_zone(self.quiet_zone)
+            .with_frame(self.frame)
+            .with_caption(self.caption.as_deref().unwrap_or_default())
+    }
+
+    fn colors(&self) -> QrStyle {
+        let preset = match self.preset.parse::<StylePreset>() {
+            Ok(preset) => preset,
+            Err(e) => {
+                warn!("Invalid style preset: {}. Using {}.", e, StylePreset::default());
+                StylePreset::default()
+            }
+        };
+        let base = preset.style();
+
+        if self.foreground.is_none() && self.background.is_none() {
+            return base;
+        }
+
+        let foreground = self.foreground.unwrap_or(base.foreground);
+        let background = self.background.unwrap_or(base.background);
+        match QrStyle::custom(foreground, background) {
+            Ok(style) => style,
+            Err(e) => {
+                warn!("Ignoring custom QR colours: {}", e);
+                base
+            }
+        }
+    }
+}
+
+impl AppConfig {
+    /// Location of the config file, e.g. `~/.config/clipboard-qr/config.toml`
+    pub fn config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("clipboard-qr").join("config.toml"))
+    }
+
+    /// Load the config file, falling back to defaults if it is missing or invalid
+    pub fn load() -> Self {
+        let Some(path) = Self::config_path() else {
+            warn!("No config directory available, using default settings");
+            return Self::default();
+        };
+
+        if !path.exists() {
+            return Self::default();
+        }
+
+        match Self::load_from(&path) {
+            Ok(config) => {
+                info!("Loaded config from {:?}", path);
+                config
+            }
+            Err(e) => {
+                warn!("Failed to load config from {:?}: {}. Using defaults.", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Turn off everything that stores, sends or acts on clipboard content unattended,
+    /// leaving on-demand generation and scanning
+    pub fn apply_guest_mode(&mut self) {
+        self.guest.enabled = true;
+        // History lives only as long as the process
+        self.history.backend = HistoryBackend::Memory;
+        self.remote.token = None;
+        self.links.expand_shorteners = false;
+        self.links.auto_open = false;
+        // Show-only: decoded content stays off the clipboard
+        self.monitor.copy_decoded = CopyDecoded::Never;
+        self.notifications
+            .backends
+            .retain(|backend| !matches!(backend.kind, NotifierKind::Webhook { .. }));
+        // A guest's clipboard shouldn't end up on the owner's phone
+        self.kdeconnect.enabled = false;
+        // Nor in the owner's password vault
+        self.password_manager.kind = None;
+        // Tasks write files and may fetch URLs on their own
+        if !self.tasks.is_empty() {
+            info!("Guest mode: {} scheduled task(s) disabled", self.tasks.len());
+            self.tasks.clear();
+        }
+    }
+
+    /// Enforce [`PrivacyConfig::in_memory`] for the rest of the process
+    pub fn apply_in_memory_mode(&mut self) {
+        self.privacy.in_memory = true;
+        self.history.backend = HistoryBackend::Memory;
+        if !self.tasks.is_empty() {
+            info!("In-memory mode: {} scheduled task(s) disabled", self.tasks.len());
+            self.tasks.clear();
+        }
+        storage::enable_in_memory_mode();
+    }
+
+    /// Scanner options from the monitor settings, transforms and saved preprocessing presets;
+    /// fails on an invalid transform pattern
+    pub fn scan_settings(&self) -> Result<ScanSettings> {
+        Ok(ScanSettings {
+            matte: self.monitor.scan_background,
+            transforms: TextTransforms::new(&self.transforms)?,
+            presets: PresetStore::load_default(),
+            retries: self.monitor.scan_retries,
+        })
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// A generator with the configured style, error correction level and output folder
+    pub fn generator(&self) -> QRGenerator {
+        QRGenerator::with_style(self.style.resolve())
+            .with_error_correction(self.style.error_correction.level())
+            .with_symbology(self.style.symbology)
+            .with_pixels_per_module(self.style.pixels_per_module)
+            .with_target_size(self.style.target_size)
+            .with_output_dir(&self.output.dir)
+    }
+
+    /// Write the [`EDITABLE_SETTINGS`] to the config file
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::config_path().ok_or_else(|| anyhow::anyhow!("No config directory available"))?;
+        storage::check_write("the config file", &path)?;
+        self.save_to(&path)?;
+        Ok(path)
+    }
+
+    /// Write the [`EDITABLE_SETTINGS`] into the file at `path`, keeping everything else in it
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        let existing = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut document: toml_edit::DocumentMut = existing.parse()?;
+        let values = toml::Value::try_from(self)?;
+        for (table, key) in EDITABLE_SETTINGS {
+            let value = values.get(table).and_then(|table| table.get(key));
+            if !document.contains_table(table) {
+                if value.is_none() {
+                    continue;
+                }
+                document.insert(table, toml_edit::table());
+            }
+            let Some(section) = document[table].as_table_mut() else {
+                return Err(anyhow::anyhow!("[{}] in {:?} is not a table", table, path));
+            };
+            match value {
+                Some(value) => {
+                    section[key] = toml_edit::value(value.to_string().parse::<toml_edit::Value>()?);
+                }
+                // Unset optional values, e.g. custom colours back to the preset's
+                None => {
+                    section.remove(key);
+                }
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, document.to_string())?;
+        info!("Saved settings to {:?}", path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snooze::SnoozeWindowConfig;
+
+    #[test]
+    fn test_save_settings() {
+        let dir = std::env::temp_dir().join(format!("clipboard-qr-config-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "# My settings\n[style]\nforeground = \"#1a1a1a\"\n\n[remote]\ntoken = \"abc\" # keep\n").unwrap();
+
+        let mut config = AppConfig::load_from(&path).unwrap();
+        config.monitor.poll_interval_ms = 250;
+        config.style.error_correction = ErrorCorrection::H;
+        config.style.foreground = None;
+        config.snooze.windows.push(SnoozeWindowConfig {
+            from: "18:00".to_string(),
+            to: "09:00".to_string(),
+            days: vec!["fri".to_string()],
+        });
+        config.save_to(&path).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.starts_with("# My settings"));
+        assert!(saved.contains("token = \"abc\" # keep"));
+        assert!(!saved.contains("foreground"));
+        let reloaded = AppConfig::load_from(&path).unwrap();
+        assert_eq!(reloaded.monitor.poll_interval_ms, 250);
+        assert_eq!(reloaded.style.error_correction, ErrorCorrection::H);
+        assert_eq!(reloaded.generator().error_correction(), EcLevel::H);
+        assert_eq!(reloaded.snooze.windows, config.snooze.windows);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_guest_mode() {
+        let mut config = AppConfig::default();
+        config.monitor.copy_decoded = CopyDecoded::Always;
+        config.remote.token = Some("abc".to_string());
+        config.links.auto_open = true;
+        config.kdeconnect.enabled = true;
+        config.apply_guest_mode();
+
+        assert!(config.guest.enabled);
+        assert_eq!(config.monitor.copy_decoded, CopyDecoded::Never);
+        assert_eq!(config.history.backend, HistoryBackend::Memory);
+        assert_eq!(config.remote.token, None);
+        assert!(!config.links.auto_open && !config.kdeconnect.enabled);
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::ConsoleConfig;
+
+/// Events held back by the rate limit since the last printed one
+#[derive(Default)]
+struct Suppressed {
+    last_printed: Option<Instant>,
+    count: usize,
+}
+
+impl Suppressed {
+    /// Print how many updates weren't shown, if any
+    fn flush(&mut self) {
+        if self.count > 0 {
+            println!("\n… {} clipboard update(s) not shown", self.count);
+            self.count = 0;
+        }
+    }
+}
+
+/// Decides whether a clipboard event should be printed to the console in tray mode.
+/// Repeats of the last printed content are dropped, and events arriving faster
+/// than the configured interval are counted instead of printed. The count is printed
+/// before the next event, or once the interval has passed without one, so the end of
+/// a burst isn't lost.
+pub struct ConsoleThrottle {
+    silent: bool,
+    min_interval: Duration,
+    last_hash: Option<u64>,
+    /// Shared with the thread printing the count when no event follows a burst
+    suppressed: Arc<Mutex<Suppressed>>,
+}
+
+impl ConsoleThrottle {
+    pub fn new(config: &ConsoleConfig) -> Self {
+        let min_interval = Duration::from_millis(config.min_interval_ms);
+        let suppressed = Arc::new(Mutex::new(Suppressed::default()));
+        if !config.silent && !min_interval.is_zero() {
+            let flusher = Arc::downgrade(&suppressed);
+            thread::spawn(move || loop {
+                thread::sleep(min_interval);
+                let Some(alive) = flusher.upgrade() else {
+                    break;
+                };
+                let Ok(mut suppressed) = alive.lock() else {
+                    break;
+                };
+                if suppressed.last_printed.is_some_and(|last| last.elapsed() >= min_interval) {
+                    suppressed.flush();
+                }
+            });
+        }
+        Self {
+            silent: config.silent,
+            min_interval,
+            last_hash: None,
+            suppressed,
+        }
+    }
+
+    /// Returns true if an event identified by `key` should be printed now
+    pub fn should_print(&mut self, key: &str) -> bool {
+        if self.silent {
+            return false;
+        }
+
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.last_hash == Some(hash) {
+            return false;
+        }
+
+        let Ok(mut suppressed) = self.suppressed.lock() else {
+            return true;
+        };
+        let now = Instant::now();
+        if let Some(last) = suppressed.last_printed {
+            if now.duration_since(last) < self.min_interval {
+                suppressed.count += 1;
+                return false;
+            }
+        }
+
+        suppressed.flush();
+        suppressed.last_printed = Some(now);
+        self.last_hash = Some(hash);
+        true
+    }
+}
+
+impl Drop for ConsoleThrottle {
+    fn drop(&mut self) {
+        // On shutdown the flushing thread may not get another turn
+        if let Ok(mut suppressed) = self.suppressed.lock() {
+            suppressed.flush();
+        }
+    }
+}
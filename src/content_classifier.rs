@@ -0,0 +1,298 @@
+use anyhow::Result;
+use regex::Regex;
+use std::sync::OnceLock;
+use url::Url;
+
+use crate::link_safety;
+use crate::payload::PayloadType;
+use crate::qr_templates::{WifiNetwork, WifiSecurity};
+use crate::totp::Totp;
+
+/// Phone numbers have at most 15 digits, see E.164; fewer than 7 are more likely something else
+const PHONE_DIGITS: std::ops::RangeInclusive<usize> = 7..=15;
+
+/// What decoded QR content is, in more detail than [`PayloadType`], with what's needed to act on it
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentClass {
+    Url(Url),
+    /// `mailto:`, `MATMSG:` or a bare address
+    Email {
+        address: String,
+        subject: Option<String>,
+        body: Option<String>,
+    },
+    /// `tel:` or a bare international or local number
+    Phone(String),
+    Wifi(WifiNetwork),
+    /// `geo:` coordinates
+    Geo { latitude: f64, longitude: f64 },
+    /// vCard or MECARD, with its name if it has one
+    Contact(Option<String>),
+    /// `otpauth://` setup, with its secret if it has one and a generator for time-based ones
+    Otp { secret: Option<String>, totp: Option<Totp> },
+    Text,
+}
+
+/// Something to do with classified content, offered in the tray and the camera window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmartAction {
+    OpenUrl(Url),
+    ComposeEmail(Url),
+    Call(Url),
+    ShowOnMap(Url),
+    /// Copy one field instead of the whole content
+    Copy { label: &'static str, text: String },
+    /// Copy the current one-time password, worked out when chosen
+    CopyCode(Totp),
+}
+
+impl SmartAction {
+    /// Menu and button text, e.g. "Open in Browser"
+    pub fn label(&self) -> String {
+        match self {
+            SmartAction::OpenUrl(_) => "Open in Browser".to_string(),
+            SmartAction::ComposeEmail(_) => "Compose Email".to_string(),
+            SmartAction::Call(_) => "Call".to_string(),
+            SmartAction::ShowOnMap(_) => "Show on Map".to_string(),
+            SmartAction::Copy { label, .. } => format!("Copy {}", label),
+            SmartAction::CopyCode(_) => "Copy Code".to_string(),
+        }
+    }
+
+    /// The URL handed to the system for all but [`SmartAction::Copy`] and [`SmartAction::CopyCode`]
+    pub fn url(&self) -> Option<&Url> {
+        match self {
+            SmartAction::OpenUrl(url) | SmartAction::ComposeEmail(url) | SmartAction::Call(url) | SmartAction::ShowOnMap(url) => Some(url),
+            SmartAction::Copy { .. } | SmartAction::CopyCode(_) => None,
+        }
+    }
+
+    /// What a copy action puts on the clipboard
+    pub fn copied_text(&self) -> Option<String> {
+        match self {
+            SmartAction::Copy { text, .. } => Some(text.clone()),
+            SmartAction::CopyCode(totp) => Some(totp.now().0),
+            _ => None,
+        }
+    }
+
+    /// Hand the URL to the default browser, mail client or dialer; copying is up to the caller,
+    /// which owns the clipboard
+    pub fn open(&self) -> Result<()> {
+        match self.url() {
+            Some(url) => link_safety::open_in_browser(url),
+            None => Err(anyhow::anyhow!("'{}' doesn't open anything", self.label())),
+        }
+    }
+}
+
+impl ContentClass {
+    pub fn classify(text: &str) -> Self {
+        let trimmed = text.trim();
+        let lower = trimmed.get(..7).unwrap_or(trimmed).to_ascii_lowercase();
+
+        if lower.starts_with("mailto:") {
+            if let Some(email) = parse_mailto(trimmed) {
+                return email;
+            }
+        } else if lower.starts_with("matmsg:") {
+            if let Some(email) = parse_matmsg(&trimmed[7..]) {
+                return email;
+            }
+        } else if lower.starts_with("tel:") {
+            return ContentClass::Phone(trimmed[4..].trim().to_string());
+        } else if lower.starts_with("geo:") {
+            if let Some(geo) = parse_geo(&trimmed[4..]) {
+                return geo;
+            }
+        } else if email_pattern().is_match(trimmed) {
+            return ContentClass::Email { address: trimmed.to_string(), subject: None, body: None };
+        } else if is_phone_number(trimmed) {
+            return ContentClass::Phone(trimmed.to_string());
+        }
+
+        match PayloadType::detect(text) {
+            PayloadType::Url => Url::parse(trimmed).map_or(ContentClass::Text, ContentClass::Url),
+            PayloadType::Wifi => WifiNetwork::parse(text).map_or(ContentClass::Text, ContentClass::Wifi),
+            PayloadType::Contact => ContentClass::Contact(contact_name(trimmed)),
+            PayloadType::Otp => ContentClass::Otp { secret: otp_secret(trimmed), totp: Totp::parse(trimmed).ok() },
+            // Custom types have no actions of their own
+            _ => ContentClass::Text,
+        }
+    }
+
+    /// Stable identifier, e.g. "email"
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentClass::Url(_) => "url",
+            ContentClass::Email { .. } => "email",
+            ContentClass::Phone(_) => "phone",
+            ContentClass::Wifi(_) => "wifi",
+            ContentClass::Geo { .. } => "geo",
+            ContentClass::Contact(_) => "contact",
+            ContentClass::Otp { .. } => "otp",
+            ContentClass::Text => "text",
+        }
+    }
+
+    /// What can be done with the content besides copying all of it, most useful first
+    pub fn actions(&self) -> Vec<SmartAction> {
+        match self {
+            ContentClass::Url(url) => vec![SmartAction::OpenUrl(url.clone())],
+            ContentClass::Email { address, subject, body } => {
+                let mut actions = Vec::new();
+                if let Ok(mut url) = Url::parse(&format!("mailto:{}", address)) {
+                    let fields = [("subject", subject), ("body", body)];
+                    let fields: Vec<_> = fields.into_iter().filter_map(|(name, value)| Some((name, value.as_ref()?))).collect();
+                    if !fields.is_empty() {
+                        url.query_pairs_mut().extend_pairs(fields);
+                    }
+                    actions.push(SmartAction::ComposeEmail(url));
+                }
+                actions.push(SmartAction::Copy { label: "Address", text: address.clone() });
+                actions
+            }
+            ContentClass::Phone(number) => {
+                let dialable: String = number.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect();
+                let mut actions: Vec<_> = Url::parse(&format!("tel:{}", dialable)).map(SmartAction::Call).into_iter().collect();
+                actions.push(SmartAction::Copy { label: "Number", text: dialable });
+                actions
+            }
+            ContentClass::Wifi(network) => {
+                let mut actions = vec![SmartAction::Copy { label: "Network Name", text: network.ssid.clone() }];
+                if network.security != WifiSecurity::None && !network.password.is_empty() {
+                    actions.push(SmartAction::Copy { label: "Password", text: network.password.clone() });
+                }
+                actions
+            }
+            ContentClass::Geo { latitude, longitude } => {
+                let mut actions = Vec::new();
+                let map = format!("https://www.openstreetmap.org/?mlat={}&mlon={}#map=16/{}/{}", latitude, longitude, latitude, longitude);
+                if let Ok(url) = Url::parse(&map) {
+                    actions.push(SmartAction::ShowOnMap(url));
+                }
+                actions.push(SmartAction::Copy { label: "Coordinates", text: format!("{}, {}", latitude, longitude) });
+                actions
+            }
+            ContentClass::Contact(name) => name
+                .iter()
+                .map(|name| SmartAction::Copy { label: "Name", text: name.clone() })
+                .collect(),
+            ContentClass::Otp { secret, totp } => {
+                let code = totp.iter().map(|totp| SmartAction::CopyCode(totp.clone()));
+                code.chain(secret.iter().map(|secret| SmartAction::Copy { label: "Secret", text: secret.clone() })).collect()
+            }
+            ContentClass::Text => Vec::new(),
+        }
+    }
+}
+
+fn email_pattern() -> &'static Regex {
+    static EMAIL: OnceLock<Regex> = OnceLock::new();
+    EMAIL.get_or_init(|| Regex::new(r"^[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}$").unwrap())
+}
+
+/// A lone number like "+49 30 1234567" or "(555) 123-4567", but not a date
+fn is_phone_number(text: &str) -> bool {
+    static PHONE: OnceLock<Regex> = OnceLock::new();
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    let pattern = PHONE.get_or_init(|| Regex::new(r"^\+?[0-9(][0-9 ()./-]*[0-9]$").unwrap());
+    let date = DATE.get_or_init(|| Regex::new(r"^\d{1,4}[-./]\d{1,2}[-./]\d{1,4}$").unwrap());
+    let digits = text.chars().filter(char::is_ascii_digit).count();
+    pattern.is_match(text) && PHONE_DIGITS.contains(&digits) && !date.is_match(text)
+}
+
+fn parse_mailto(text: &str) -> Option<ContentClass> {
+    let url = Url::parse(text).ok()?;
+    let address = url.path().to_string();
+    if address.is_empty() {
+        return None;
+    }
+    let query = |name: &str| url.query_pairs().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.into_owned());
+    Some(ContentClass::Email { address, subject: query("subject"), body: query("body") })
+}
+
+/// `TO:<address>;SUB:<subject>;BODY:<body>;;`
+fn parse_matmsg(body: &str) -> Option<ContentClass> {
+    let mut email = (None, None, None);
+    for field in body.split(';') {
+        match field.split_once(':') {
+            Some(("TO", value)) => email.0 = Some(value.to_string()),
+            Some(("SUB", value)) => email.1 = Some(value.to_string()),
+            Some(("BODY", value)) => email.2 = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    let (address, subject, body) = email;
+    Some(ContentClass::Email { address: address?, subject, body })
+}
+
+/// `<latitude>,<longitude>[,<altitude>][;parameters][?query]`
+fn parse_geo(body: &str) -> Option<ContentClass> {
+    let coordinates = body.split(['?', ';']).next()?;
+    let mut parts = coordinates.split(',').map(|part| part.trim().parse::<f64>());
+    let (latitude, longitude) = (parts.next()?.ok()?, parts.next()?.ok()?);
+    ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)).then_some(ContentClass::Geo { latitude, longitude })
+}
+
+/// `FN:` of a vCard, or `N:` of a MECARD
+fn contact_name(text: &str) -> Option<String> {
+    if let Some(mecard) = text.get(7..).filter(|_| text[..7].eq_ignore_ascii_case("MECARD:")) {
+        let name = mecard.split(';').find_map(|field| field.strip_prefix("N:"))?;
+        // "Last,First" reads better the other way round
+        return Some(match name.split_once(',') {
+            Some((last, first)) => format!("{} {}", first, last),
+            None => name.to_string(),
+        });
+    }
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix("FN:").or_else(|| line.trim().strip_prefix("FN;CHARSET=UTF-8:")))
+        .map(str::to_string)
+}
+
+fn otp_secret(text: &str) -> Option<String> {
+    let url = Url::parse(text).ok()?;
+    url.query_pairs().find(|(key, _)| key == "secret").map(|(_, value)| value.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_and_actions() {
+        let email = ContentClass::classify("mailto:sam@example.com?subject=Hello%20there");
+        assert_eq!(
+            email,
+            ContentClass::Email { address: "sam@example.com".to_string(), subject: Some("Hello there".to_string()), body: None }
+        );
+        let actions = email.actions();
+        assert_eq!(actions[0].label(), "Compose Email");
+        assert_eq!(actions[0].url().unwrap().as_str(), "mailto:sam@example.com?subject=Hello+there");
+        assert_eq!(ContentClass::classify("MATMSG:TO:sam@example.com;SUB:Hi;BODY:Lunch?;;").name(), "email");
+        assert_eq!(ContentClass::classify("sam@example.com").name(), "email");
+
+        assert_eq!(ContentClass::classify("tel:+1-555-123-4567"), ContentClass::Phone("+1-555-123-4567".to_string()));
+        assert_eq!(ContentClass::classify("+49 30 1234567").actions()[0].url().unwrap().as_str(), "tel:+49301234567");
+        // Too few digits for a phone number
+        assert_eq!(ContentClass::classify("10:30"), ContentClass::Text);
+        assert_eq!(ContentClass::classify("2024-05-01"), ContentClass::Text);
+
+        assert_eq!(ContentClass::classify("geo:52.52,13.405;u=35"), ContentClass::Geo { latitude: 52.52, longitude: 13.405 });
+        assert_eq!(ContentClass::classify("geo:120,0"), ContentClass::Text);
+
+        let wifi = ContentClass::classify("WIFI:S:home;T:WPA;P:secret123;;").actions();
+        assert_eq!(wifi[1], SmartAction::Copy { label: "Password", text: "secret123".to_string() });
+        assert_eq!(wifi[1].label(), "Copy Password");
+        assert_eq!(ContentClass::classify("BEGIN:VCARD\nVERSION:3.0\nFN:Sam Lee\nEND:VCARD"), ContentClass::Contact(Some("Sam Lee".to_string())));
+        assert_eq!(ContentClass::classify("MECARD:N:Lee,Sam;;"), ContentClass::Contact(Some("Sam Lee".to_string())));
+        let otp = ContentClass::classify("otpauth://totp/Example:sam?secret=JBSWY3DP&issuer=Example");
+        assert!(matches!(&otp, ContentClass::Otp { secret: Some(secret), totp: Some(totp) } if secret == "JBSWY3DP" && totp.name == "Example (sam)"));
+        let labels: Vec<String> = otp.actions().iter().map(SmartAction::label).collect();
+        assert_eq!(labels, ["Copy Code", "Copy Secret"]);
+        assert_eq!(otp.actions()[0].copied_text().unwrap().len(), 6);
+        assert_eq!(ContentClass::classify("https://example.com").actions()[0].label(), "Open in Browser");
+        assert!(ContentClass::classify("hello world").actions().is_empty());
+    }
+}
@@ -0,0 +1,355 @@
+use egui::{Color32, RichText, Ui};
+use serde_json::Value;
+use url::Url;
+
+const KEY_COLOR: Color32 = Color32::from_rgb(156, 220, 254);
+const STRING_COLOR: Color32 = Color32::from_rgb(206, 145, 120);
+const NUMBER_COLOR: Color32 = Color32::from_rgb(181, 206, 168);
+const KEYWORD_COLOR: Color32 = Color32::from_rgb(86, 156, 214);
+const TAG_COLOR: Color32 = Color32::from_rgb(78, 201, 176);
+
+/// The kind of structured content detected in a decoded payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Json,
+    Xml,
+    Url,
+    Plain,
+}
+
+/// A node in the foldable preview tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewNode {
+    pub label: String,
+    pub value: Option<PreviewValue>,
+    pub children: Vec<PreviewNode>,
+}
+
+/// A leaf value, tagged so the viewer can colour it
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreviewValue {
+    String(String),
+    Number(String),
+    Keyword(String),
+}
+
+impl PreviewNode {
+    fn branch(label: impl Into<String>, children: Vec<PreviewNode>) -> Self {
+        Self {
+            label: label.into(),
+            value: None,
+            children,
+        }
+    }
+
+    fn leaf(label: impl Into<String>, value: PreviewValue) -> Self {
+        Self {
+            label: label.into(),
+            value: Some(value),
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Guess what kind of structured content `text` holds
+pub fn detect_kind(text: &str) -> ContentKind {
+    let trimmed = text.trim();
+
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<Value>(trimmed).is_ok()
+    {
+        return ContentKind::Json;
+    }
+
+    if trimmed.starts_with('<') && parse_xml(trimmed).is_some() {
+        return ContentKind::Xml;
+    }
+
+    if let Ok(url) = Url::parse(trimmed) {
+        if !url.cannot_be_a_base() && url.query().is_some_and(|q| !q.is_empty()) {
+            return ContentKind::Url;
+        }
+    }
+
+    ContentKind::Plain
+}
+
+/// Build a foldable tree for structured content, or `None` for plain text
+pub fn build_tree(text: &str) -> Option<PreviewNode> {
+    let trimmed = text.trim();
+    match detect_kind(trimmed) {
+        ContentKind::Json => serde_json::from_str::<Value>(trimmed)
+            .ok()
+            .map(|value| json_node("JSON", &value)),
+        ContentKind::Xml => parse_xml(trimmed),
+        ContentKind::Url => Url::parse(trimmed).ok().map(|url| url_node(&url)),
+        ContentKind::Plain => None,
+    }
+}
+
+/// Pretty-print structured content; plain text is returned unchanged
+pub fn pretty_print(text: &str) -> String {
+    let trimmed = text.trim();
+    match detect_kind(trimmed) {
+        ContentKind::Json => serde_json::from_str::<Value>(trimmed)
+            .ok()
+            .and_then(|value| serde_json::to_string_pretty(&value).ok())
+            .unwrap_or_else(|| text.to_string()),
+        ContentKind::Xml | ContentKind::Url => match build_tree(trimmed) {
+            Some(node) => {
+                let mut out = String::new();
+                write_tree(&node, 0, &mut out);
+                out
+            }
+            None => text.to_string(),
+        },
+        ContentKind::Plain => text.to_string(),
+    }
+}
+
+/// Show `text` in a read-only viewer, as a foldable tree when it is structured
+pub fn show_preview(ui: &mut Ui, text: &str) {
+    match build_tree(text) {
+        Some(node) => {
+            egui::ScrollArea::vertical()
+                .id_salt("content_preview")
+                .max_height(300.0)
+                .show(ui, |ui| show_node(ui, &node, 0));
+        }
+        None => {
+            let mut readonly = text;
+            ui.add(
+                egui::TextEdit::multiline(&mut readonly)
+                    .desired_width(f32::INFINITY)
+                    .font(egui::TextStyle::Monospace),
+            );
+        }
+    }
+}
+
+fn show_node(ui: &mut Ui, node: &PreviewNode, depth: usize) {
+    if node.children.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label(RichText::new(format!("{}:", node.label)).monospace().color(KEY_COLOR));
+            if let Some(value) = &node.value {
+                ui.label(value_text(value));
+            }
+        });
+        return;
+    }
+
+    egui::CollapsingHeader::new(RichText::new(&node.label).monospace().color(TAG_COLOR))
+        .id_salt(&node.label)
+        .default_open(depth < 2)
+        .show(ui, |ui| {
+            if let Some(value) = &node.value {
+                ui.label(value_text(value));
+            }
+            // Siblings often share a label, e.g. array items or repeated XML elements
+            for (index, child) in node.children.iter().enumerate() {
+                ui.push_id(index, |ui| show_node(ui, child, depth + 1));
+            }
+        });
+}
+
+fn value_text(value: &PreviewValue) -> RichText {
+    match value {
+        PreviewValue::String(s) => RichText::new(format!("\"{}\"", s)).monospace().color(STRING_COLOR),
+        PreviewValue::Number(n) => RichText::new(n).monospace().color(NUMBER_COLOR),
+        PreviewValue::Keyword(k) => RichText::new(k).monospace().color(KEYWORD_COLOR),
+    }
+}
+
+fn write_tree(node: &PreviewNode, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    match &node.value {
+        Some(PreviewValue::String(s)) => out.push_str(&format!("{}{}: \"{}\"\n", indent, node.label, s)),
+        Some(PreviewValue::Number(s)) | Some(PreviewValue::Keyword(s)) => {
+            out.push_str(&format!("{}{}: {}\n", indent, node.label, s))
+        }
+        None => out.push_str(&format!("{}{}\n", indent, node.label)),
+    }
+    for child in &node.children {
+        write_tree(child, depth + 1, out);
+    }
+}
+
+fn json_node(label: &str, value: &Value) -> PreviewNode {
+    match value {
+        Value::Object(map) => PreviewNode::branch(
+            format!("{} {{{}}}", label, map.len()),
+            map.iter().map(|(k, v)| json_node(k, v)).collect(),
+        ),
+        Value::Array(items) => PreviewNode::branch(
+            format!("{} [{}]", label, items.len()),
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| json_node(&i.to_string(), v))
+                .collect(),
+        ),
+        Value::String(s) => PreviewNode::leaf(label, PreviewValue::String(s.clone())),
+        Value::Number(n) => PreviewNode::leaf(label, PreviewValue::Number(n.to_string())),
+        Value::Bool(b) => PreviewNode::leaf(label, PreviewValue::Keyword(b.to_string())),
+        Value::Null => PreviewNode::leaf(label, PreviewValue::Keyword("null".to_string())),
+    }
+}
+
+fn url_node(url: &Url) -> PreviewNode {
+    let mut children = vec![
+        PreviewNode::leaf("scheme", PreviewValue::Keyword(url.scheme().to_string())),
+    ];
+    if let Some(host) = url.host_str() {
+        children.push(PreviewNode::leaf("host", PreviewValue::String(host.to_string())));
+    }
+    if let Some(port) = url.port() {
+        children.push(PreviewNode::leaf("port", PreviewValue::Number(port.to_string())));
+    }
+    children.push(PreviewNode::leaf("path", PreviewValue::String(url.path().to_string())));
+
+    let params: Vec<PreviewNode> = url
+        .query_pairs()
+        .map(|(k, v)| PreviewNode::leaf(k.into_owned(), PreviewValue::String(v.into_owned())))
+        .collect();
+    children.push(PreviewNode::branch(format!("query [{}]", params.len()), params));
+
+    if let Some(fragment) = url.fragment() {
+        children.push(PreviewNode::leaf("fragment", PreviewValue::String(fragment.to_string())));
+    }
+
+    PreviewNode::branch("URL", children)
+}
+
+/// Minimal XML reader: enough to lay out elements, attributes and text for display.
+/// Returns `None` if the tags don't nest properly.
+fn parse_xml(text: &str) -> Option<PreviewNode> {
+    let mut stack: Vec<(String, PreviewNode)> = vec![("".to_string(), PreviewNode::branch("XML", Vec::new()))];
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = &after[after.find("-->")? + 3..];
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>")?;
+            let cdata = &after[..end];
+            stack.last_mut()?.1.children.push(PreviewNode::leaf("#cdata", PreviewValue::String(cdata.to_string())));
+            rest = &after[end + 3..];
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest = &rest[rest.find('>')? + 1..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>')?;
+            let name = after[..end].trim();
+            let (open_name, node) = stack.pop()?;
+            if open_name != name || stack.is_empty() {
+                return None;
+            }
+            stack.last_mut()?.1.children.push(node);
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let end = after.find('>')?;
+            let mut tag = &after[..end];
+            let self_closing = tag.ends_with('/');
+            if self_closing {
+                tag = &tag[..tag.len() - 1];
+            }
+            let (name, attrs) = split_tag(tag)?;
+            let mut node = PreviewNode::branch(format!("<{}>", name), attrs);
+            if self_closing {
+                if node.children.is_empty() {
+                    node.value = Some(PreviewValue::Keyword("(empty)".to_string()));
+                }
+                stack.last_mut()?.1.children.push(node);
+            } else {
+                stack.push((name.to_string(), node));
+            }
+            rest = &after[end + 1..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let content = rest[..end].trim();
+            if !content.is_empty() {
+                // Text outside the root element means this isn't XML
+                if stack.len() == 1 {
+                    return None;
+                }
+                stack.last_mut()?.1.children.push(PreviewNode::leaf("#text", PreviewValue::String(content.to_string())));
+            }
+            rest = &rest[end..];
+        }
+    }
+
+    if stack.len() != 1 {
+        return None;
+    }
+    let (_, root) = stack.pop()?;
+    if root.children.is_empty() {
+        None
+    } else {
+        Some(root)
+    }
+}
+
+fn split_tag(tag: &str) -> Option<(&str, Vec<PreviewNode>)> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = &tag[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut attrs = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let eq = rest.find('=')?;
+        let key = rest[..eq].trim();
+        let value_part = rest[eq + 1..].trim_start();
+        let quote = value_part.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let close = value_part[1..].find(quote)?;
+        let value = &value_part[1..close + 1];
+        attrs.push(PreviewNode::leaf(format!("@{}", key), PreviewValue::String(value.to_string())));
+        rest = value_part[close + 2..].trim_start();
+    }
+
+    Some((name, attrs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_kind() {
+        assert_eq!(detect_kind(r#"{"a": 1}"#), ContentKind::Json);
+        assert_eq!(detect_kind("<a><b>hi</b></a>"), ContentKind::Xml);
+        assert_eq!(detect_kind("https://example.com/p?x=1&y=2"), ContentKind::Url);
+        assert_eq!(detect_kind("https://example.com/p"), ContentKind::Plain);
+        assert_eq!(detect_kind("hello world"), ContentKind::Plain);
+        assert_eq!(detect_kind("<a><b></a>"), ContentKind::Plain);
+    }
+
+    #[test]
+    fn test_url_query_tree() {
+        let tree = build_tree("https://example.com/search?q=rust%20qr&page=2").unwrap();
+        let query = tree.children.iter().find(|n| n.label.starts_with("query")).unwrap();
+        assert_eq!(query.children.len(), 2);
+        assert_eq!(query.children[0].value, Some(PreviewValue::String("rust qr".to_string())));
+    }
+
+    #[test]
+    fn test_json_pretty_print() {
+        let pretty = pretty_print(r#"{"a":[1,2]}"#);
+        assert!(pretty.contains("\n"));
+        assert!(pretty.contains("\"a\""));
+    }
+
+    #[test]
+    fn test_xml_attributes() {
+        let tree = build_tree(r#"<?xml version="1.0"?><item id="7"><name>QR</name><empty/></item>"#).unwrap();
+        let item = &tree.children[0];
+        assert_eq!(item.label, "<item>");
+        assert_eq!(item.children[0].label, "@id");
+        assert_eq!(item.children.len(), 3);
+    }
+}
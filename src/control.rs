@@ -0,0 +1,315 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::clipboard_handler::ClipboardData;
+use crate::log_buffer;
+use crate::storage;
+use crate::stats::{Activity, SessionStats, SessionSummary};
+
+/// A command sent to a running instance, one JSON object per line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum ControlRequest {
+    /// Check that the instance is there
+    Ping,
+    /// Handle `text` as if it had been copied; only in test mode
+    InjectText { text: String },
+    /// Handle the image at `path` as if it had been copied; only in test mode
+    InjectImage { path: PathBuf },
+    /// Session statistics, recent log lines and activity, for `debug snapshot` and the
+    /// status window
+    Status,
+    /// Show the status window; sent by a second launch of the tray app, see
+    /// [`crate::single_instance`]
+    Activate,
+}
+
+impl ControlRequest {
+    /// Whether the request fakes clipboard content, which instances only accept in test mode
+    pub fn is_injection(&self) -> bool {
+        matches!(self, ControlRequest::InjectText { .. } | ControlRequest::InjectImage { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// What the request asked for, if it asked for anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl ControlResponse {
+    fn of(result: Result<Option<Value>>) -> Self {
+        match result {
+            Ok(data) => Self { ok: true, error: None, data },
+            Err(e) => Self {
+                ok: false,
+                error: Some(format!("{:#}", e)),
+                data: None,
+            },
+        }
+    }
+}
+
+/// What a running instance answers to [`ControlRequest::Status`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceStatus {
+    pub version: String,
+    pub stats: SessionSummary,
+    pub log: Vec<String>,
+    /// Missing from instances older than the status window
+    #[serde(default)]
+    pub activity: Activity,
+    /// Running in guest mode
+    #[serde(default)]
+    pub guest: bool,
+    /// Nothing is written to disk, see [`storage::in_memory_mode`]
+    #[serde(default)]
+    pub in_memory: bool,
+}
+
+impl InstanceStatus {
+    /// The status of this process
+    pub fn current(stats: &SessionStats) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            stats: stats.summary(),
+            log: log_buffer::recent(),
+            activity: stats.activity(),
+            guest: stats.guest(),
+            in_memory: storage::in_memory_mode(),
+        }
+    }
+}
+
+/// The control socket of the instance running as the current user
+pub fn default_socket_path() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("clipboard-qr.sock"),
+        // Without a per-user runtime directory, keep users apart by name
+        None => std::env::temp_dir().join(format!("clipboard-qr-{}.sock", whoami())),
+    }
+}
+
+/// Serve the bound control socket `server` for a running monitor, turning injections into
+/// clipboard content handed to `injector`, see [`crate::monitor::MonitorHandle::injector`];
+/// `activate` shows the instance's status window, where it has one
+pub fn serve_monitor(
+    mut server: ControlServer,
+    test_mode: bool,
+    injector: Sender<ClipboardData>,
+    stats: Arc<SessionStats>,
+    activate: Option<Box<dyn Fn() + Send>>,
+) -> Result<ControlServer> {
+    server.serve(test_mode, move |request| {
+        let data = match request {
+            ControlRequest::Ping => return Ok(None),
+            ControlRequest::Activate => {
+                let activate = activate.as_ref().ok_or_else(|| anyhow::anyhow!("This instance has no status window to show"))?;
+                activate();
+                return Ok(None);
+            }
+            ControlRequest::Status => return Ok(Some(serde_json::to_value(InstanceStatus::current(&stats))?)),
+            ControlRequest::InjectText { text } => ClipboardData::Text(text),
+            ControlRequest::InjectImage { path } => ClipboardData::Image(
+                image::open(&path)
+                    .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?
+                    .to_rgba8(),
+            ),
+        };
+        injector.send(data).map_err(|_| anyhow::anyhow!("The clipboard monitor has stopped"))?;
+        Ok(None)
+    })?;
+    Ok(server)
+}
+
+pub fn whoami() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "user".to_string())
+}
+
+#[cfg(unix)]
+pub use unix::{send, ControlServer};
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::thread;
+    use std::time::Duration;
+
+    /// How long a connection may sit idle; requests are handled one at a time, so a client
+    /// that never sends its line would otherwise hold up every later one
+    const CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
+
+    type RequestHandler = Box<dyn Fn(ControlRequest) -> Result<Option<Value>> + Send>;
+
+    /// Accepts control connections on a Unix socket readable only by the current user
+    ///
+    /// Binding the socket is what makes an instance the only one, see
+    /// [`crate::single_instance`]. The socket file is removed when the server is dropped.
+    /// The accept thread itself lives until the process exits.
+    pub struct ControlServer {
+        path: PathBuf,
+        /// Until [`ControlServer::serve`] hands it to the accept thread
+        listener: Option<UnixListener>,
+    }
+
+    impl ControlServer {
+        /// Listen on `path` without answering yet; fails if another instance listens there
+        pub fn bind(path: PathBuf) -> Result<Self> {
+            if path.exists() {
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(anyhow::anyhow!("Another instance is listening on {}", path.display()));
+                }
+                // Left behind by an instance that didn't shut down cleanly
+                fs::remove_file(&path)?;
+            }
+            let listener = UnixListener::bind(&path)?;
+            let server = Self { path, listener: Some(listener) };
+            fs::set_permissions(&server.path, fs::Permissions::from_mode(0o600))?;
+            Ok(server)
+        }
+
+        /// Call `handler` for each request on a background thread; injections are refused
+        /// unless `test_mode` is set. Requests sent since [`ControlServer::bind`] are
+        /// answered now
+        pub fn serve(&mut self, test_mode: bool, handler: impl Fn(ControlRequest) -> Result<Option<Value>> + Send + 'static) -> Result<()> {
+            let listener = self.listener.take().ok_or_else(|| anyhow::anyhow!("The control socket is already served"))?;
+            info!("Control socket listening on {:?}{}", self.path, if test_mode { " (test mode)" } else { "" });
+
+            let handler: RequestHandler = Box::new(handler);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            if let Err(e) = serve(stream, test_mode, &handler) {
+                                warn!("Control connection failed: {}", e);
+                            }
+                        }
+                        Err(e) => warn!("Control socket accept failed: {}", e),
+                    }
+                }
+            });
+            Ok(())
+        }
+    }
+
+    impl Drop for ControlServer {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    fn serve(stream: UnixStream, test_mode: bool, handler: &RequestHandler) -> Result<()> {
+        stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+        stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let result = serde_json::from_str::<ControlRequest>(&line)
+                .map_err(|e| anyhow::anyhow!("Invalid request: {}", e))
+                .and_then(|request| {
+                    if request.is_injection() && !test_mode {
+                        return Err(anyhow::anyhow!("Injecting events needs an instance started with --test-mode"));
+                    }
+                    handler(request)
+                });
+            writeln!(writer, "{}", serde_json::to_string(&ControlResponse::of(result))?)?;
+        }
+        Ok(())
+    }
+
+    /// Send one request to the instance listening on `path` and wait for its answer
+    pub fn send(path: &Path, request: &ControlRequest) -> Result<Option<Value>> {
+        let stream = UnixStream::connect(path)
+            .map_err(|e| anyhow::anyhow!("No running instance at {}: {}", path.display(), e))?;
+        let mut writer = stream.try_clone()?;
+        writeln!(writer, "{}", serde_json::to_string(request)?)?;
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        let response: ControlResponse = serde_json::from_str(&line)?;
+        match response.error {
+            Some(error) if !response.ok => Err(anyhow::anyhow!(error)),
+            _ => Ok(response.data),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub use fallback::{send, ControlServer};
+
+#[cfg(not(unix))]
+mod fallback {
+    use super::*;
+    use std::path::Path;
+
+    const UNSUPPORTED: &str = "The control socket is only available on Unix-like systems so far";
+
+    pub struct ControlServer;
+
+    impl ControlServer {
+        pub fn bind(_path: PathBuf) -> Result<Self> {
+            Err(anyhow::anyhow!(UNSUPPORTED))
+        }
+
+        pub fn serve(&mut self, _test_mode: bool, _handler: impl Fn(ControlRequest) -> Result<Option<Value>> + Send + 'static) -> Result<()> {
+            Err(anyhow::anyhow!(UNSUPPORTED))
+        }
+    }
+
+    pub fn send(_path: &Path, _request: &ControlRequest) -> Result<Option<Value>> {
+        Err(anyhow::anyhow!(UNSUPPORTED))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!("clipboard-qr-control-{}.sock", std::process::id()));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = seen.clone();
+        let mut server = ControlServer::bind(path.clone()).unwrap();
+        server
+            .serve(false, move |request| {
+                recorder.lock().unwrap().push(request);
+                Ok(None)
+            })
+            .unwrap();
+
+        send(&path, &ControlRequest::Ping).unwrap();
+        // A client that connects and never sends a line only holds up others until it times out
+        let _silent = std::os::unix::net::UnixStream::connect(&path).unwrap();
+        send(&path, &ControlRequest::Ping).unwrap();
+        // The socket is taken while the server lives
+        assert!(ControlServer::bind(path.clone()).is_err());
+        // Not in test mode, so injections are refused before reaching the handler
+        let error = send(&path, &ControlRequest::InjectText { text: "hi".to_string() }).unwrap_err();
+        assert!(error.to_string().contains("--test-mode"));
+        assert_eq!(*seen.lock().unwrap(), [ControlRequest::Ping, ControlRequest::Ping]);
+
+        drop(server);
+        assert!(!path.exists());
+    }
+}
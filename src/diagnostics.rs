@@ -0,0 +1,157 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+/// Why the app stopped, and the exit code it stops with
+///
+/// | Code | Meaning |
+/// |---|---|
+/// | 0 | Clean exit |
+/// | 1 | Any other error |
+/// | 2 | Invalid command line (reported by the argument parser) |
+/// | 3 | Invalid configuration |
+/// | 4 | The clipboard stayed unavailable |
+/// | 5 | The event loop or system tray failed |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    Config,
+    ClipboardUnavailable,
+    EventLoop,
+    Other,
+}
+
+impl FailureKind {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            FailureKind::Other => 1,
+            FailureKind::Config => 3,
+            FailureKind::ClipboardUnavailable => 4,
+            FailureKind::EventLoop => 5,
+        }
+    }
+
+    /// The kind attached to `error` with [`Fatal`], or `Other`
+    pub fn of(error: &anyhow::Error) -> Self {
+        error.downcast_ref::<Fatal>().map_or(FailureKind::Other, |fatal| fatal.kind)
+    }
+
+    /// Whether a supervisor should restart the app; configuration errors need a person
+    pub fn restartable(self) -> bool {
+        self != FailureKind::Config
+    }
+}
+
+/// An error that ends the app with a specific [`FailureKind`]
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct Fatal {
+    pub kind: FailureKind,
+    pub message: String,
+}
+
+impl Fatal {
+    pub fn new(kind: FailureKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+}
+
+/// Attach a [`FailureKind`] to errors on their way out of `main`
+pub trait FailureContext<T> {
+    fn fatal(self, kind: FailureKind) -> Result<T>;
+}
+
+impl<T, E: std::fmt::Display> FailureContext<T> for std::result::Result<T, E> {
+    fn fatal(self, kind: FailureKind) -> Result<T> {
+        // `{:#}` keeps the causes of an anyhow error on one line
+        self.map_err(|e| Fatal::new(kind, format!("{:#}", e)).into())
+    }
+}
+
+/// Written when a long-running mode exits with an error, for supervisors such as
+/// systemd or NSSM to alert on
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitReport {
+    pub kind: FailureKind,
+    pub exit_code: u8,
+    pub restartable: bool,
+    pub message: String,
+    /// The error and its causes, outermost first
+    pub causes: Vec<String>,
+    /// `tray`, `watch`, `kiosk` or `agent`
+    pub mode: String,
+    pub version: String,
+    pub pid: u32,
+    pub exited_at: DateTime<Local>,
+    pub uptime_secs: u64,
+}
+
+impl ExitReport {
+    pub fn new(error: &anyhow::Error, mode: &str, uptime: Duration) -> Self {
+        let kind = FailureKind::of(error);
+        Self {
+            kind,
+            exit_code: kind.exit_code(),
+            restartable: kind.restartable(),
+            message: error.to_string(),
+            causes: error.chain().map(ToString::to_string).collect(),
+            mode: mode.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            pid: std::process::id(),
+            exited_at: Local::now(),
+            uptime_secs: uptime.as_secs(),
+        }
+    }
+
+    /// e.g. `~/.local/share/clipboard-qr/last-exit.json`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("clipboard-qr").join("last-exit.json"))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        crate::storage::check_write("the exit report", path)?;
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        info!("Exit report written to {:?}", path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_kind() {
+        let fatal: anyhow::Error = Fatal::new(FailureKind::ClipboardUnavailable, "no display").into();
+        assert_eq!(FailureKind::of(&fatal), FailureKind::ClipboardUnavailable);
+        assert_eq!(FailureKind::of(&anyhow::anyhow!("boom")), FailureKind::Other);
+
+        let config: Result<()> = Err("bad pattern").fatal(FailureKind::Config);
+        let error = config.unwrap_err();
+        assert_eq!(FailureKind::of(&error), FailureKind::Config);
+        assert!(!FailureKind::Config.restartable());
+    }
+
+    #[test]
+    fn test_exit_report() {
+        let error = anyhow::Error::from(Fatal::new(FailureKind::EventLoop, "tray failed")).context("Tray mode stopped");
+        let report = ExitReport::new(&error, "tray", Duration::from_secs(90));
+        // Context on top of a Fatal keeps its kind
+        assert_eq!(report.kind, FailureKind::EventLoop);
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["kind"], "event_loop");
+        assert_eq!(json["exit_code"], 5);
+        assert_eq!(json["causes"][1], "tray failed");
+        assert_eq!(json["uptime_secs"], 90);
+    }
+}
@@ -0,0 +1,43 @@
+/// Opt the process into per-monitor DPI awareness so Windows doesn't bitmap-stretch
+/// our windows on scaled displays, which blurs QR modules and makes them hard to scan.
+/// Must run before any window is created.
+#[cfg(target_os = "windows")]
+pub fn enable_per_monitor_dpi_awareness() {
+    use winapi::shared::windef::{DPI_AWARENESS_CONTEXT, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2};
+    use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+    use tracing::{info, warn};
+    use winapi::um::winuser::SetProcessDPIAware;
+
+    type SetContext = unsafe extern "system" fn(DPI_AWARENESS_CONTEXT) -> i32;
+
+    unsafe {
+        // Looked up at runtime: SetProcessDpiAwarenessContext only exists on Windows 10 1703 and later
+        let user32: Vec<u16> = "user32.dll\0".encode_utf16().collect();
+        let module = GetModuleHandleW(user32.as_ptr());
+        let function = if module.is_null() {
+            std::ptr::null_mut()
+        } else {
+            GetProcAddress(module, b"SetProcessDpiAwarenessContext\0".as_ptr() as *const i8)
+        };
+
+        if !function.is_null() {
+            let set_context: SetContext = std::mem::transmute(function);
+            if set_context(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) != 0 {
+                info!("Enabled per-monitor DPI awareness");
+                return;
+            }
+        }
+
+        // Older Windows, or awareness was already set (e.g. by the manifest)
+        if SetProcessDPIAware() != 0 {
+            info!("Enabled system DPI awareness");
+        } else {
+            warn!("Could not enable DPI awareness; windows may be scaled by the system");
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn enable_per_monitor_dpi_awareness() {
+    // X11, Wayland and macOS report the scale factor to winit directly
+}
@@ -0,0 +1,145 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::clipboard_handler::ClipboardData;
+use crate::monitor::{MonitorEvent, ScanOutcome};
+
+/// What paused or resumed monitoring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseSource {
+    /// Pause Monitoring in the tray menu
+    User,
+    /// The `[snooze]` schedule
+    Schedule,
+}
+
+/// Something that happened to the clipboard or the monitor, for the tray, windows and
+/// anything else that follows it
+#[derive(Debug, Clone)]
+pub enum AppEvent {
+    /// New clipboard content was read; for [`ClipboardData::Excluded`] content,
+    /// `sensitive_rule` names the sensitive-content rule that hid it, if one did
+    ClipboardChanged { data: ClipboardData, sensitive_rule: Option<String> },
+    /// A QR code was decoded from the copied image, right after its `ClipboardChanged`
+    QrDecoded(String),
+    /// Reading the clipboard or scanning an image failed
+    Error(String),
+    /// Monitoring was paused (`true`) or resumed
+    MonitoringPaused { paused: bool, source: PauseSource },
+    /// A notification passed the notification level and pipeline; in tray mode this is
+    /// the `tooltip` backend, published after the events it is about
+    Notified,
+}
+
+impl AppEvent {
+    /// The events a monitor event stands for, in the order they are published
+    pub fn from_monitor(event: &MonitorEvent) -> Vec<AppEvent> {
+        let mut events = Vec::with_capacity(2);
+        if let Some(data) = event.clipboard_data() {
+            let sensitive_rule = match event {
+                MonitorEvent::Sensitive(rule) => Some(rule.clone()),
+                _ => None,
+            };
+            events.push(AppEvent::ClipboardChanged { data, sensitive_rule });
+        }
+        match event {
+            MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } => events.push(AppEvent::QrDecoded(content.clone())),
+            MonitorEvent::Image { scan: ScanOutcome::Failed(e), .. } => events.push(AppEvent::Error(format!("Failed to scan the image: {}", e))),
+            MonitorEvent::Error(e) => events.push(AppEvent::Error(e.clone())),
+            _ => {}
+        }
+        events
+    }
+}
+
+/// Broadcasts [`AppEvent`]s to every subscriber
+///
+/// Each subscriber has a channel of its own, so one that is slow to read misses nothing
+/// and holds nobody else up. Clones publish to the same subscribers.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<Arc<AppEvent>>>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> Receiver<Arc<AppEvent>> {
+        let (sender, receiver) = mpsc::channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    /// Call `f` with every event on a thread of its own, e.g. to wake an event loop; the
+    /// thread ends once every clone of the bus is dropped
+    pub fn forward(&self, mut f: impl FnMut(Arc<AppEvent>) + Send + 'static) -> JoinHandle<()> {
+        let events = self.subscribe();
+        thread::spawn(move || {
+            for event in events {
+                f(event);
+            }
+        })
+    }
+
+    /// Send `event` to every subscriber; subscribers that went away are forgotten
+    pub fn publish(&self, event: AppEvent) {
+        let event = Arc::new(event);
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Publish the events `event` stands for, see [`AppEvent::from_monitor`]
+    pub fn publish_monitor(&self, event: &MonitorEvent) {
+        for event in AppEvent::from_monitor(event) {
+            self.publish(event);
+        }
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().map_or(0, |subscribers| subscribers.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_bus() {
+        let bus = EventBus::new();
+        let tray = bus.subscribe();
+        let window = bus.subscribe();
+
+        let image = image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255]));
+        bus.publish_monitor(&MonitorEvent::Image { image, scan: ScanOutcome::Decoded("WIFI:S:home;;".to_string()) });
+        bus.publish_monitor(&MonitorEvent::Sensitive("card number".to_string()));
+        for events in [&tray, &window] {
+            let received: Vec<Arc<AppEvent>> = events.try_iter().collect();
+            assert_eq!(received.len(), 3);
+            assert!(matches!(&*received[0], AppEvent::ClipboardChanged { data: ClipboardData::Image(image), sensitive_rule: None } if image.width() == 4));
+            assert!(matches!(&*received[1], AppEvent::QrDecoded(content) if content == "WIFI:S:home;;"));
+            assert!(matches!(
+                &*received[2],
+                AppEvent::ClipboardChanged { data: ClipboardData::Excluded, sensitive_rule: Some(rule) } if rule == "card number"
+            ));
+        }
+
+        // Subscribers that went away are dropped on the next publish
+        drop(window);
+        bus.publish(AppEvent::MonitoringPaused { paused: true, source: PauseSource::User });
+        assert_eq!(bus.subscriber_count(), 1);
+        assert!(matches!(&*tray.recv().unwrap(), AppEvent::MonitoringPaused { paused: true, source: PauseSource::User }));
+
+        // Listener restarts publish nothing; read errors are published as they are
+        assert!(AppEvent::from_monitor(&MonitorEvent::ListenerRestarted("stuck".to_string())).is_empty());
+        assert!(matches!(&AppEvent::from_monitor(&MonitorEvent::Error("busy".to_string()))[..], [AppEvent::Error(e)] if e == "busy"));
+    }
+}
@@ -0,0 +1,300 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::history::SharedHistory;
+use crate::scheduler::parse_interval;
+use crate::storage;
+
+/// How often the janitor thread looks for expired entries and shutdown
+const TICK: Duration = Duration::from_secs(5);
+
+/// How long generated sensitive content is kept
+///
+/// ```toml
+/// [expiry]
+/// sensitive = "10m"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExpiryConfig {
+    /// Wipe history entries for one-time password secrets and Wi-Fi credentials after
+    /// this long, e.g. "10m"; kept until removed by hand if unset
+    pub sensitive: Option<String>,
+}
+
+impl ExpiryConfig {
+    pub fn sensitive_after(&self) -> Result<Option<Duration>> {
+        self.sensitive.as_deref().map(parse_interval).transpose()
+    }
+}
+
+/// Something to clean up once `expires_at` has passed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Expiring {
+    /// What this is, for logs; never the content itself
+    pub label: String,
+    pub expires_at: DateTime<Local>,
+    /// Exported files to delete
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<PathBuf>,
+    /// History entry to remove
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_id: Option<u64>,
+    /// The process an in-memory history entry lives in; once that process is gone
+    /// the id may belong to something else
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<u32>,
+}
+
+impl Expiring {
+    pub fn after(label: impl Into<String>, after: Duration) -> Self {
+        Self {
+            label: label.into(),
+            expires_at: Local::now() + ChronoDuration::from_std(after).unwrap_or(ChronoDuration::MAX),
+            files: Vec::new(),
+            history_id: None,
+            process: None,
+        }
+    }
+
+    pub fn with_file(mut self, path: &Path) -> Self {
+        // Stored absolute so the janitor finds it from any working directory
+        self.files.push(std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()));
+        self
+    }
+
+    /// `in_memory` marks an entry of a history that doesn't outlive this process
+    pub fn with_history_id(mut self, id: u64, in_memory: bool) -> Self {
+        self.history_id = Some(id);
+        self.process = in_memory.then(std::process::id);
+        self
+    }
+}
+
+/// Pending expiries, shared between the CLI, which adds them, and the janitor in the
+/// tray app, which carries them out
+///
+/// Every change re-reads the file first so entries added by other processes survive.
+/// In in-memory mode the ledger only lives as long as the process.
+#[derive(Debug, Default)]
+pub struct ExpiryLedger {
+    path: Option<PathBuf>,
+    entries: Vec<Expiring>,
+}
+
+impl ExpiryLedger {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("clipboard-qr").join("expiring.json"))
+    }
+
+    /// The ledger at the default path, or one kept in memory in in-memory mode
+    pub fn open_default() -> Self {
+        let path = if storage::in_memory_mode() { None } else { Self::default_path() };
+        Self::open(path)
+    }
+
+    pub fn open(path: Option<PathBuf>) -> Self {
+        let mut ledger = Self { path, entries: Vec::new() };
+        ledger.reload();
+        ledger
+    }
+
+    fn reload(&mut self) {
+        let Some(path) = &self.path else { return };
+        self.entries = match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable expiry ledger {:?}: {}", path, e);
+                Vec::new()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                warn!("Failed to read expiry ledger {:?}: {}", path, e);
+                return;
+            }
+        };
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, expiring: Expiring) -> Result<()> {
+        self.reload();
+        info!("{} expires at {}", expiring.label, expiring.expires_at.format("%H:%M:%S"));
+        self.entries.push(expiring);
+        self.save()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove and return every entry due at `now`, dropping entries of in-memory
+    /// histories of other processes
+    pub fn take_due(&mut self, now: DateTime<Local>) -> Result<Vec<Expiring>> {
+        self.reload();
+        let count = self.entries.len();
+        self.entries.retain(|expiring| expiring.process.is_none_or(|process| process == std::process::id()));
+        let stale = count - self.entries.len();
+        let (due, pending): (Vec<_>, _) = self.entries.drain(..).partition(|expiring| expiring.expires_at <= now);
+        self.entries = pending;
+        if !due.is_empty() || stale > 0 {
+            self.save()?;
+        }
+        Ok(due)
+    }
+}
+
+/// Carries out expiries on a background thread while the tray app runs
+pub struct Janitor {
+    ledger: Arc<Mutex<ExpiryLedger>>,
+    history: Option<SharedHistory>,
+    on_history_change: Option<Box<dyn Fn() + Send>>,
+}
+
+impl Janitor {
+    pub fn new(ledger: Arc<Mutex<ExpiryLedger>>) -> Self {
+        Self {
+            ledger,
+            history: None,
+            on_history_change: None,
+        }
+    }
+
+    /// The store expired history ids refer to; `on_change` runs after entries were removed
+    pub fn with_history(mut self, history: SharedHistory, on_change: impl Fn() + Send + 'static) -> Self {
+        self.history = Some(history);
+        self.on_history_change = Some(Box::new(on_change));
+        self
+    }
+
+    /// Remove everything due at `now`; failures are logged and not retried
+    pub fn sweep(&self, now: DateTime<Local>) {
+        let due = match self.ledger.lock().map(|mut ledger| ledger.take_due(now)) {
+            Ok(Ok(due)) => due,
+            Ok(Err(e)) => {
+                error!("Failed to update expiry ledger: {}", e);
+                return;
+            }
+            Err(_) => return,
+        };
+        let mut history_changed = false;
+        for expiring in due {
+            for path in &expiring.files {
+                match fs::remove_file(path) {
+                    Ok(()) => info!("Deleted expired {:?}", path),
+                    Err(e) if e.kind() == ErrorKind::NotFound => {}
+                    Err(e) => error!("Failed to delete expired {:?}: {}", path, e),
+                }
+            }
+            if let (Some(id), Some(history)) = (expiring.history_id, &self.history) {
+                if let Ok(mut history) = history.lock() {
+                    match history.remove(id) {
+                        Ok(()) => history_changed = true,
+                        Err(e) => error!("Failed to remove expired history entry {}: {}", id, e),
+                    }
+                }
+            }
+            info!("{} expired", expiring.label);
+        }
+        if let (true, Some(on_change)) = (history_changed, &self.on_history_change) {
+            on_change();
+        }
+    }
+
+    pub fn start(self) -> JanitorHandle {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+
+        let thread = thread::spawn(move || {
+            while thread_running.load(Ordering::Relaxed) {
+                self.sweep(Local::now());
+                thread::sleep(TICK);
+            }
+        });
+
+        JanitorHandle {
+            running,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Stops the janitor thread when dropped
+pub struct JanitorHandle {
+    running: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for JanitorHandle {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("Janitor thread panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{HistoryKind, HistoryStore, MemoryHistoryStore};
+
+    #[test]
+    fn test_janitor_sweep() {
+        let dir = std::env::temp_dir().join(format!("clipboard-qr-expiry-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let export = dir.join("wifi.png");
+        fs::write(&export, b"png").unwrap();
+
+        let mut store: Box<dyn HistoryStore> = Box::new(MemoryHistoryStore::new(10));
+        let secret = store.add(HistoryKind::Text, "otpauth://totp/me?secret=ABC").unwrap();
+        let hello = store.add(HistoryKind::Text, "hello").unwrap();
+        let history = Arc::new(Mutex::new(store));
+
+        let mut ledger = ExpiryLedger::open(Some(dir.join("expiring.json")));
+        ledger.add(Expiring::after("Wi-Fi export", Duration::ZERO).with_file(&export)).unwrap();
+        ledger.add(Expiring::after("OTP history entry", Duration::ZERO).with_history_id(secret.id, true)).unwrap();
+        ledger.add(Expiring::after("later", Duration::from_secs(3600))).unwrap();
+        // Left behind by an earlier process whose in-memory ids meant something else
+        let mut stale = Expiring::after("stale", Duration::ZERO).with_history_id(hello.id, true);
+        stale.process = Some(u32::MAX);
+        ledger.add(stale).unwrap();
+        // Another process sees what was added
+        assert_eq!(ExpiryLedger::open(Some(dir.join("expiring.json"))).len(), 4);
+
+        let ledger = Arc::new(Mutex::new(ledger));
+        let changed = Arc::new(AtomicBool::new(false));
+        let on_change = changed.clone();
+        Janitor::new(ledger.clone())
+            .with_history(history.clone(), move || on_change.store(true, Ordering::Relaxed))
+            .sweep(Local::now());
+        assert!(changed.load(Ordering::Relaxed));
+        assert!(!export.exists());
+        let remaining = history.lock().unwrap().recent(10).unwrap();
+        assert_eq!(remaining.iter().map(|entry| entry.content.as_str()).collect::<Vec<_>>(), ["hello"]);
+        assert_eq!(ledger.lock().unwrap().len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,293 @@
+use anyhow::Result;
+use image::{DynamicImage, ImageBuffer, Luma, Rgb, Rgba, RgbaImage};
+use std::fs::{self, File};
+use std::io::{BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::ColorType;
+use tracing::{debug, info, warn};
+
+use crate::monitor::ScanOutcome;
+use crate::qr_scanner::{load_gif_frames, load_image, DecodedCode, QRScanner};
+use crate::storage;
+
+/// Extensions offered when picking images to scan
+pub const IMAGE_EXTENSIONS: [&str; 9] = ["png", "jpg", "jpeg", "bmp", "gif", "webp", "tif", "tiff", "ico"];
+
+/// Scan result for one page of a file; single-image formats have one page
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageResult {
+    /// 1-based page number
+    pub page: usize,
+    pub outcome: ScanOutcome,
+}
+
+/// Scan every page of an image file for QR codes
+pub fn scan_file(scanner: &QRScanner, path: &Path) -> Result<Vec<PageResult>> {
+    if !is_tiff(path) {
+        let outcome = match scanner.scan_qr_from_file(path)? {
+            Some(content) => ScanOutcome::Decoded(content),
+            None => ScanOutcome::NotFound,
+        };
+        return Ok(vec![PageResult { page: 1, outcome }]);
+    }
+
+    info!("Scanning file {:?}", path);
+    let results = read_pages(path)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, page)| {
+            let outcome = match page {
+                Ok(image) => scan_image(scanner, &image),
+                Err(e) => ScanOutcome::Failed(e.to_string()),
+            };
+            debug!("Page {}: {:?}", index + 1, outcome);
+            PageResult { page: index + 1, outcome }
+        })
+        .collect();
+    Ok(results)
+}
+
+/// Every QR code on every page of an image file or frame of a GIF, as (1-based page, codes or why the page failed)
+pub fn scan_file_all(scanner: &QRScanner, path: &Path) -> Result<Vec<(usize, Result<Vec<DecodedCode>>)>> {
+    info!("Scanning file {:?} for all QR codes", path);
+    let results = read_pages(path)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, page)| (index + 1, page.and_then(|image| scanner.scan_multiple_qr_codes(&image))))
+        .collect();
+    Ok(results)
+}
+
+/// The pages of an image file, or the frames of a GIF; single-image formats have one
+fn read_pages(path: &Path) -> Result<Vec<Result<RgbaImage>>> {
+    if is_tiff(path) {
+        return read_tiff(path);
+    }
+    if is_gif(path) {
+        return Ok(load_gif_frames(path)?.into_iter().map(Ok).collect());
+    }
+    Ok(vec![Ok(load_image(path)?)])
+}
+
+/// Whether `path` has one of the [`IMAGE_EXTENSIONS`]
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.iter().any(|image| ext.eq_ignore_ascii_case(image)))
+}
+
+/// The one image file in a list of copied files, if that's all the list holds
+pub fn single_image(files: &[PathBuf]) -> Option<&Path> {
+    match files {
+        [file] if is_image(file) => Some(file),
+        _ => None,
+    }
+}
+
+/// "report.pdf", or "report.pdf and 2 more files", for a list of copied files
+pub fn describe_files(files: &[PathBuf]) -> String {
+    let name = |path: &PathBuf| path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+    match files {
+        [] => "no files".to_string(),
+        [file] => name(file),
+        [file, rest @ ..] => format!("{} and {} more file{}", name(file), rest.len(), if rest.len() == 1 { "" } else { "s" }),
+    }
+}
+
+/// GIFs are scanned frame by frame with [`QRScanner::scan_qr_from_gif`]
+pub fn is_gif(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case("gif"))
+}
+
+fn is_tiff(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tif") || ext.eq_ignore_ascii_case("tiff"))
+}
+
+fn scan_image(scanner: &QRScanner, image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> ScanOutcome {
+    match scanner.scan_qr_from_rgba(image) {
+        Ok(Some(content)) => ScanOutcome::Decoded(content),
+        Ok(None) => ScanOutcome::NotFound,
+        Err(e) => ScanOutcome::Failed(e.to_string()),
+    }
+}
+
+/// The folder images to scan were last picked from, so the file dialog opens there again
+#[derive(Debug, Default)]
+pub struct LastScanDir {
+    path: Option<PathBuf>,
+    dir: Option<PathBuf>,
+}
+
+impl LastScanDir {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("clipboard-qr").join("last-scan-dir.txt"))
+    }
+
+    /// The folder saved at the default path, or one kept in memory in in-memory mode
+    pub fn open_default() -> Self {
+        let path = if storage::in_memory_mode() { None } else { Self::default_path() };
+        Self::open(path)
+    }
+
+    pub fn open(path: Option<PathBuf>) -> Self {
+        let dir = match &path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(dir) => Some(PathBuf::from(dir.trim_end())).filter(|dir| dir.is_dir()),
+                Err(e) if e.kind() == ErrorKind::NotFound => None,
+                Err(e) => {
+                    warn!("Failed to read the last scan folder {:?}: {}", path, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        Self { path, dir }
+    }
+
+    /// The remembered folder, if it still exists
+    pub fn get(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Remember the folder `file` is in
+    pub fn remember(&mut self, file: &Path) -> Result<()> {
+        let Some(dir) = file.parent().filter(|dir| !dir.as_os_str().is_empty()) else { return Ok(()) };
+        if self.dir.as_deref() == Some(dir) {
+            return Ok(());
+        }
+        self.dir = Some(dir.to_path_buf());
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, dir.to_string_lossy().as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Decode each page of a multi-page TIFF; a page that can't be decoded is
+/// reported as failed without stopping the remaining pages
+fn read_tiff(path: &Path) -> Result<Vec<Result<RgbaImage>>> {
+    let mut decoder = Decoder::new(BufReader::new(File::open(path)?))?;
+    let mut pages = Vec::new();
+
+    loop {
+        pages.push(read_tiff_page(&mut decoder).map_err(|e| anyhow::anyhow!("Failed to decode page: {}", e)));
+
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()?;
+    }
+
+    Ok(pages)
+}
+
+fn read_tiff_page(decoder: &mut Decoder<BufReader<File>>) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    let (width, height) = decoder.dimensions()?;
+    let color_type = decoder.colortype()?;
+    let data = decoder.read_image()?;
+    let invalid = || anyhow::anyhow!("TIFF page data doesn't match its {}x{} size", width, height);
+
+    let image = match (color_type, data) {
+        // Bilevel fax-style scans, one bit per pixel with rows padded to a byte
+        (ColorType::Gray(1), DecodingResult::U8(bits)) => {
+            let row_bytes = (width as usize).div_ceil(8);
+            if bits.len() < row_bytes * height as usize {
+                return Err(invalid());
+            }
+            DynamicImage::ImageLuma8(ImageBuffer::from_fn(width, height, |x, y| {
+                let byte = bits[y as usize * row_bytes + x as usize / 8];
+                let bit = (byte >> (7 - x % 8)) & 1;
+                Luma([bit * 255])
+            }))
+        }
+        (ColorType::Gray(8), DecodingResult::U8(buffer)) => {
+            DynamicImage::ImageLuma8(ImageBuffer::<Luma<u8>, _>::from_raw(width, height, buffer).ok_or_else(invalid)?)
+        }
+        (ColorType::Gray(16), DecodingResult::U16(buffer)) => {
+            DynamicImage::ImageLuma16(ImageBuffer::<Luma<u16>, _>::from_raw(width, height, buffer).ok_or_else(invalid)?)
+        }
+        (ColorType::RGB(8), DecodingResult::U8(buffer)) => {
+            DynamicImage::ImageRgb8(ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, buffer).ok_or_else(invalid)?)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(buffer)) => {
+            DynamicImage::ImageRgb16(ImageBuffer::<Rgb<u16>, _>::from_raw(width, height, buffer).ok_or_else(invalid)?)
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(buffer)) => {
+            DynamicImage::ImageRgba8(ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, buffer).ok_or_else(invalid)?)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(buffer)) => {
+            DynamicImage::ImageRgba16(ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, buffer).ok_or_else(invalid)?)
+        }
+        (color_type, _) => return Err(anyhow::anyhow!("Unsupported TIFF colour type {:?}", color_type)),
+    };
+
+    Ok(image.to_rgba8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    #[test]
+    fn test_scan_multi_page_tiff() {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut buffer).unwrap();
+        let page = vec![255u8; 32 * 32];
+        encoder.write_image::<colortype::Gray8>(32, 32, &page).unwrap();
+        encoder.write_image::<colortype::Gray8>(32, 32, &page).unwrap();
+        encoder.write_image::<colortype::RGB8>(32, 32, &[255u8; 32 * 32 * 3]).unwrap();
+
+        let path = std::env::temp_dir().join(format!("clipboard-qr-test-{}.tiff", std::process::id()));
+        std::fs::write(&path, buffer.into_inner()).unwrap();
+        let results = scan_file(&QRScanner::new(), &path);
+        std::fs::remove_file(&path).ok();
+
+        let pages: Vec<usize> = results.unwrap().iter().map(|result| result.page).collect();
+        assert_eq!(pages, [1, 2, 3]);
+
+        let path = std::env::temp_dir().join(format!("clipboard-qr-test-{}.png", std::process::id()));
+        image::RgbaImage::from_pixel(32, 32, Rgba([255, 255, 255, 255])).save(&path).unwrap();
+        let results = scan_file_all(&QRScanner::new(), &path);
+        std::fs::remove_file(&path).ok();
+        let results = results.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.as_ref().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_copied_files() {
+        let image = PathBuf::from("/home/sam/Pictures/Receipt.PNG");
+        let document = PathBuf::from("/home/sam/report.pdf");
+        assert_eq!(single_image(std::slice::from_ref(&image)), Some(image.as_path()));
+        assert_eq!(single_image(std::slice::from_ref(&document)), None);
+        assert_eq!(single_image(&[image.clone(), image.clone()]), None);
+
+        assert_eq!(describe_files(std::slice::from_ref(&document)), "report.pdf");
+        assert_eq!(describe_files(&[document.clone(), image.clone()]), "report.pdf and 1 more file");
+        assert_eq!(describe_files(&[document, image.clone(), image]), "report.pdf and 2 more files");
+    }
+
+    #[test]
+    fn test_last_scan_dir() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("clipboard-qr-last-scan-dir-{}.txt", std::process::id()));
+        let mut last = LastScanDir::open(Some(path.clone()));
+        assert_eq!(last.get(), None);
+        last.remember(&dir.join("receipt.jpg")).unwrap();
+        // A bare file name has no folder to remember
+        last.remember(Path::new("receipt.jpg")).unwrap();
+        assert_eq!(LastScanDir::open(Some(path.clone())).get(), Some(dir.as_path()));
+
+        // Folders that were removed since are forgotten
+        fs::write(&path, dir.join("clipboard-qr-missing").to_string_lossy().as_bytes()).unwrap();
+        assert_eq!(LastScanDir::open(Some(path.clone())).get(), None);
+        fs::remove_file(&path).unwrap();
+    }
+}
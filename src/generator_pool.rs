@@ -0,0 +1,169 @@
+use anyhow::{anyhow, Result};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tracing::debug;
+
+use crate::qr_generator::QRGenerator;
+use crate::qr_style::QrStyle;
+
+/// Upper bound for the default worker count; encoding is cheap, more threads mostly add contention
+const MAX_DEFAULT_WORKERS: usize = 4;
+
+/// One code to generate with a named renderer, e.g. "png" or "svg"
+#[derive(Debug, Clone)]
+pub struct GenerationJob {
+    pub text: String,
+    pub format: String,
+}
+
+impl GenerationJob {
+    pub fn new(text: impl Into<String>, format: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            format: format.into(),
+        }
+    }
+}
+
+type Task = (GenerationJob, mpsc::Sender<Result<Vec<u8>>>);
+
+/// Long-lived generator threads for batch and API use
+///
+/// Each worker builds its generator, renderer registry and style once and keeps
+/// them for every job, instead of constructing everything per request. The matrix
+/// and output buffers of each code are still allocated per job; see
+/// `benches/generator_pool.rs` for what the pool saves.
+pub struct GeneratorPool {
+    sender: Option<mpsc::Sender<Task>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl GeneratorPool {
+    /// A pool with `workers` threads (at least one) rendering with `style`
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn new(workers: usize, style: QrStyle) -> Self {
+        Self::with_generator(workers, move || QRGenerator::with_style(style.clone()))
+    }
+
+    /// A pool sized to the machine, up to four threads
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_default_size(style: QrStyle) -> Self {
+        Self::new(Self::default_size(), style)
+    }
+
+    /// The worker count [`Self::with_default_size`] uses
+    pub fn default_size() -> usize {
+        thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_DEFAULT_WORKERS)
+    }
+
+    /// A pool whose workers each build their generator with `make_generator`,
+    /// e.g. to register custom renderers
+    pub fn with_generator(workers: usize, make_generator: impl Fn() -> QRGenerator + Send + Sync + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel::<Task>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let make_generator = Arc::new(make_generator);
+
+        let workers = (0..workers.max(1))
+            .map(|index| {
+                let receiver = Arc::clone(&receiver);
+                let make_generator = Arc::clone(&make_generator);
+                thread::spawn(move || {
+                    let generator = make_generator();
+                    // Warm up the encoder and renderers before the first real job
+                    let _ = generator.render("warm-up", "png");
+                    debug!("Generator worker {} ready", index);
+
+                    loop {
+                        // Hold the lock only while waiting for the next job
+                        let task = match receiver.lock() {
+                            Ok(receiver) => receiver.recv(),
+                            Err(_) => break,
+                        };
+                        let Ok((job, reply)) = task else {
+                            break;
+                        };
+                        let _ = reply.send(generator.render(&job.text, &job.format));
+                    }
+                    debug!("Generator worker {} stopped", index);
+                })
+            })
+            .collect();
+
+        Self {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queue a job; the result arrives on the returned channel
+    pub fn submit(&self, job: GenerationJob) -> mpsc::Receiver<Result<Vec<u8>>> {
+        let (reply, result) = mpsc::channel();
+        if let Some(sender) = &self.sender {
+            if let Err(mpsc::SendError((_, reply))) = sender.send((job, reply)) {
+                let _ = reply.send(Err(anyhow!("Generator pool has shut down")));
+            }
+        }
+        result
+    }
+
+    /// Generate one code, waiting for a free worker
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn generate(&self, job: GenerationJob) -> Result<Vec<u8>> {
+        self.submit(job)
+            .recv()
+            .map_err(|_| anyhow!("Generator worker stopped unexpectedly"))?
+    }
+
+    /// Generate many codes in parallel; results are in the order of `jobs`
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn generate_all(&self, jobs: impl IntoIterator<Item = GenerationJob>) -> Vec<Result<Vec<u8>>> {
+        let pending: Vec<_> = jobs.into_iter().map(|job| self.submit(job)).collect();
+        pending
+            .into_iter()
+            .map(|result| {
+                result
+                    .recv()
+                    .map_err(|_| anyhow!("Generator worker stopped unexpectedly"))?
+            })
+            .collect()
+    }
+}
+
+impl Drop for GeneratorPool {
+    fn drop(&mut self) {
+        // Closing the queue lets the workers finish their current job and exit
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_all_keeps_order() {
+        let pool = GeneratorPool::new(3, QrStyle::default());
+        let jobs = (0..300).map(|i| GenerationJob::new(format!("https://example.com/item/{}", i), "svg"));
+        let results = pool.generate_all(jobs);
+
+        assert_eq!(results.len(), 300);
+        let expected = QRGenerator::new().generate_svg("https://example.com/item/42").unwrap();
+        assert_eq!(results[42].as_ref().unwrap(), expected.as_bytes());
+    }
+
+    #[test]
+    fn test_job_errors() {
+        let pool = GeneratorPool::new(1, QrStyle::default());
+        assert!(pool.generate(GenerationJob::new("", "png")).is_err());
+        assert!(pool.generate(GenerationJob::new("hi", "bmp")).is_err());
+        assert!(pool.generate(GenerationJob::new("hi", "png")).is_ok());
+        assert!(GeneratorPool::with_default_size(QrStyle::default()).generate(GenerationJob::new("hi", "svg")).is_ok());
+    }
+}
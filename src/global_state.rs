@@ -1,17 +1,48 @@
-use crate::clipboard_handler::ClipboardData;
+use crate::clipboard_handler::{content_hash, ClipboardData};
+use crate::clipboard_history::ClipboardHistory;
+use crate::normalize::NormalizationProfile;
+use crate::notification::NotificationPolicy;
 
-// Global clipboard state shared between threads
+/// Settings and data the tray shares with the monitor thread; changes themselves travel on
+/// the [`EventBus`](crate::event_bus::EventBus)
 #[derive(Clone)]
 pub struct GlobalClipboardState {
     pub last_data: Option<ClipboardData>,
-    pub has_changed: bool,
+    /// [`content_hash`] of `last_data`, so the tray and windows agree on what counts as a change
+    pub last_hash: Option<u64>,
+    pub notification_policy: NotificationPolicy,
+    /// Desktop notifications are shown, see [`Notifier::pops_up`](crate::notifier::Notifier::pops_up)
+    pub desktop_notifications: bool,
+    /// Recent clipboard entries, for regenerating their QR codes from the tray
+    pub clipboard_history: ClipboardHistory,
+}
+
+impl Default for GlobalClipboardState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GlobalClipboardState {
     pub fn new() -> Self {
         Self {
             last_data: None,
-            has_changed: false,
+            last_hash: None,
+            notification_policy: NotificationPolicy::default(),
+            desktop_notifications: true,
+            clipboard_history: ClipboardHistory::default(),
+        }
+    }
+
+    /// Take `data` as the current clipboard content unless it hashes the same as the last;
+    /// returns whether it changed
+    pub fn set_data(&mut self, data: ClipboardData, normalization: NormalizationProfile) -> bool {
+        let hash = content_hash(&data, normalization);
+        if self.last_hash == Some(hash) {
+            return false;
         }
+        self.last_data = Some(data);
+        self.last_hash = Some(hash);
+        true
     }
 } 
\ No newline at end of file
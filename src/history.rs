@@ -0,0 +1,677 @@
+use anyhow::Result;
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use chrono::{DateTime, Local};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+use url::Url;
+
+/// Environment variable holding the passphrase for `[history] encrypt`
+pub const PASSPHRASE_ENV: &str = "CLIPBOARD_QR_HISTORY_PASSPHRASE";
+
+/// Prefix of encrypted content in the history database
+const ENCRYPTED_PREFIX: &str = "enc1:";
+
+/// Encrypted into the database so a wrong passphrase is noticed on opening, not on reading
+const PASSPHRASE_CHECK: &str = "clipboard-qr history";
+
+/// What produced a history entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    /// Text copied to the clipboard
+    Text,
+    /// Content decoded from a QR code in a clipboard image
+    QrScan,
+}
+
+impl HistoryKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HistoryKind::Text => "text",
+            HistoryKind::QrScan => "qr_scan",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(HistoryKind::Text),
+            "qr_scan" => Some(HistoryKind::QrScan),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: u64,
+    pub kind: HistoryKind,
+    pub content: String,
+    pub created_at: DateTime<Local>,
+}
+
+/// Storage for clipboard and scan history
+pub trait HistoryStore: Send {
+    /// Record new content and return the stored entry
+    fn add(&mut self, kind: HistoryKind, content: &str) -> Result<HistoryEntry>;
+
+    /// Up to `limit` entries, newest first
+    fn recent(&self, limit: usize) -> Result<Vec<HistoryEntry>>;
+
+    fn remove(&mut self, id: u64) -> Result<()>;
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    fn clear(&mut self) -> Result<()>;
+}
+
+/// A store used from several threads, e.g. the monitor and the expiry janitor
+pub type SharedHistory = Arc<Mutex<Box<dyn HistoryStore>>>;
+
+/// Adds entries to a [`SharedHistory`] on a thread of its own, so the monitor doesn't wait
+/// on a slow store such as the REST backend
+///
+/// `on_added` runs on that thread after each entry is stored; failures are logged there. The
+/// thread ends once the writer is dropped and every queued entry is written.
+pub struct HistoryWriter {
+    queue: mpsc::Sender<(HistoryKind, String)>,
+}
+
+impl HistoryWriter {
+    pub fn start(store: SharedHistory, on_added: impl Fn(&HistoryEntry) + Send + 'static) -> Self {
+        let (queue, pending) = mpsc::channel::<(HistoryKind, String)>();
+        thread::spawn(move || {
+            for (kind, content) in pending {
+                match store.lock().map(|mut store| store.add(kind, &content)) {
+                    Ok(Ok(entry)) => on_added(&entry),
+                    Ok(Err(e)) => error!("Failed to record history: {}", e),
+                    Err(_) => break,
+                }
+            }
+        });
+        Self { queue }
+    }
+
+    /// Queue `content` to be recorded
+    pub fn add(&self, kind: HistoryKind, content: &str) -> Result<()> {
+        self.queue
+            .send((kind, content.to_string()))
+            .map_err(|_| anyhow::anyhow!("The history writer has stopped"))
+    }
+}
+
+/// Entries picked by id, or everything newer than `since`, oldest first
+///
+/// Only the newest `max_entries` are searched, which covers everything local stores keep.
+pub fn select_entries(
+    store: &dyn HistoryStore,
+    max_entries: usize,
+    ids: &[u64],
+    since: Option<Duration>,
+) -> Result<Vec<HistoryEntry>> {
+    let mut entries = store.recent(max_entries)?;
+    if let Some(since) = since {
+        let cutoff = Local::now() - chrono::Duration::from_std(since)?;
+        entries.retain(|entry| entry.created_at >= cutoff);
+    } else {
+        let missing: Vec<String> = ids
+            .iter()
+            .filter(|id| !entries.iter().any(|entry| entry.id == **id))
+            .map(u64::to_string)
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow::anyhow!("No history entries with id {}", missing.join(", ")));
+        }
+        entries.retain(|entry| ids.contains(&entry.id));
+    }
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Which [`HistoryStore`] to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryBackend {
+    /// Kept only while the app is running
+    Memory,
+    /// SQLite database in the local data directory
+    #[default]
+    Sqlite,
+    /// A central server speaking the REST API of [`RemoteHistoryStore`]
+    Remote,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub backend: HistoryBackend,
+    /// Database file for the SQLite backend (default: `<data dir>/clipboard-qr/history.db`)
+    pub path: Option<PathBuf>,
+    /// Base URL for the remote backend
+    pub url: Option<Url>,
+    /// Bearer token for the remote backend
+    pub token: Option<String>,
+    /// Oldest entries beyond this are discarded by local backends
+    pub max_entries: usize,
+    /// Encrypt the content of the SQLite database with the passphrase in
+    /// `CLIPBOARD_QR_HISTORY_PASSPHRASE`; entries written before are encrypted on opening
+    pub encrypt: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            backend: HistoryBackend::default(),
+            path: None,
+            url: None,
+            token: None,
+            max_entries: 500,
+            encrypt: false,
+        }
+    }
+}
+
+impl HistoryConfig {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("clipboard-qr").join("history.db"))
+    }
+
+    /// Open the configured store
+    pub fn open(&self) -> Result<Box<dyn HistoryStore>> {
+        match self.backend {
+            HistoryBackend::Memory => Ok(Box::new(MemoryHistoryStore::new(self.max_entries))),
+            HistoryBackend::Sqlite => {
+                let path = self
+                    .path
+                    .clone()
+                    .or_else(Self::default_path)
+                    .ok_or_else(|| anyhow::anyhow!("No data directory available for the history database"))?;
+                let passphrase = if self.encrypt { Some(passphrase()?) } else { None };
+                Ok(Box::new(SqliteHistoryStore::open(&path, self.max_entries, passphrase.as_deref())?))
+            }
+            HistoryBackend::Remote if self.encrypt => {
+                Err(anyhow::anyhow!("[history] encrypt only applies to the sqlite backend"))
+            }
+            HistoryBackend::Remote => {
+                let url = self
+                    .url
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("The remote history backend requires [history] url"))?;
+                Ok(Box::new(RemoteHistoryStore::new(url, self.token.clone())?))
+            }
+        }
+    }
+}
+
+fn passphrase() -> Result<String> {
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) if !passphrase.is_empty() => Ok(passphrase),
+        _ => Err(anyhow::anyhow!("[history] encrypt is on, but {} is not set", PASSPHRASE_ENV)),
+    }
+}
+
+/// ChaCha20-Poly1305 with a key derived from a passphrase by Argon2id
+struct HistoryCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl HistoryCipher {
+    fn new(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Failed to derive the history key: {}", e))?;
+        Ok(Self { cipher: ChaCha20Poly1305::new(&key.into()) })
+    }
+
+    /// `enc1:` and the base64 of a random nonce followed by the ciphertext
+    fn encrypt(&self, text: &str) -> Result<String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, text.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt a history entry"))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, base64::engine::general_purpose::STANDARD.encode(sealed)))
+    }
+
+    fn decrypt(&self, stored: &str) -> Result<String> {
+        let sealed = stored
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .and_then(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded).ok())
+            .filter(|sealed| sealed.len() > 12)
+            .ok_or_else(|| anyhow::anyhow!("Malformed encrypted history entry"))?;
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let text = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt a history entry; wrong passphrase?"))?;
+        Ok(String::from_utf8(text)?)
+    }
+}
+
+/// History kept in memory, newest first
+pub struct MemoryHistoryStore {
+    entries: VecDeque<HistoryEntry>,
+    max_entries: usize,
+    next_id: u64,
+}
+
+impl MemoryHistoryStore {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            max_entries,
+            next_id: 1,
+        }
+    }
+}
+
+impl HistoryStore for MemoryHistoryStore {
+    fn add(&mut self, kind: HistoryKind, content: &str) -> Result<HistoryEntry> {
+        let entry = HistoryEntry {
+            id: self.next_id,
+            kind,
+            content: content.to_string(),
+            created_at: Local::now(),
+        };
+        self.next_id += 1;
+        self.entries.push_front(entry.clone());
+        self.entries.truncate(self.max_entries);
+        Ok(entry)
+    }
+
+    fn recent(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        Ok(self.entries.iter().take(limit).cloned().collect())
+    }
+
+    fn remove(&mut self, id: u64) -> Result<()> {
+        self.entries.retain(|entry| entry.id != id);
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.entries.clear();
+        Ok(())
+    }
+}
+
+/// History in a local SQLite database, with the content encrypted if opened with a passphrase
+pub struct SqliteHistoryStore {
+    conn: Connection,
+    max_entries: usize,
+    cipher: Option<HistoryCipher>,
+}
+
+impl SqliteHistoryStore {
+    pub fn open(path: &Path, max_entries: usize, passphrase: Option<&str>) -> Result<Self> {
+        crate::storage::check_write("the history database", path)?;
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        info!("Opening history database {:?}", path);
+        Self::with_connection(Connection::open(path)?, max_entries, passphrase)
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn open_in_memory(max_entries: usize) -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?, max_entries, None)
+    }
+
+    fn with_connection(conn: Connection, max_entries: usize, passphrase: Option<&str>) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 kind TEXT NOT NULL,
+                 content TEXT NOT NULL,
+                 created_at TEXT NOT NULL,
+                 encrypted INTEGER NOT NULL DEFAULT 0
+             );
+             CREATE TABLE IF NOT EXISTS meta (
+                 key TEXT PRIMARY KEY,
+                 value TEXT NOT NULL
+             )",
+        )?;
+        // Databases from before rows were flagged only told encrypted content by its prefix
+        let legacy = !conn
+            .prepare("SELECT name FROM pragma_table_info('history') WHERE name = 'encrypted'")?
+            .exists([])?;
+        if legacy {
+            conn.execute_batch("ALTER TABLE history ADD COLUMN encrypted INTEGER NOT NULL DEFAULT 0")?;
+        }
+        let mut store = Self { conn, max_entries, cipher: None };
+        let check = store.meta("check")?;
+        match (passphrase, check) {
+            (None, None) => {}
+            (None, Some(_)) => {
+                return Err(anyhow::anyhow!("The history database is encrypted; set [history] encrypt = true and {}", PASSPHRASE_ENV));
+            }
+            (Some(passphrase), check) => store.unlock(passphrase, check, legacy)?,
+        }
+        Ok(store)
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        let mut statement = self.conn.prepare("SELECT value FROM meta WHERE key = ?1")?;
+        let mut rows = statement.query_map(params![key], |row| row.get::<_, String>(0))?;
+        Ok(rows.next().transpose()?)
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn
+            .execute("INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)", params![key, value])?;
+        Ok(())
+    }
+
+    /// Derive the key, check it against the stored check value or store one, and encrypt
+    /// entries written without encryption; `legacy` databases have their encrypted rows
+    /// flagged first
+    fn unlock(&mut self, passphrase: &str, check: Option<String>, legacy: bool) -> Result<()> {
+        let salt = match self.meta("salt")? {
+            Some(salt) => base64::engine::general_purpose::STANDARD.decode(salt)?,
+            None => {
+                let mut salt = vec![0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                self.set_meta("salt", &base64::engine::general_purpose::STANDARD.encode(&salt))?;
+                salt
+            }
+        };
+        let cipher = HistoryCipher::new(passphrase, &salt)?;
+        match check {
+            Some(check) => {
+                if cipher.decrypt(&check).ok().as_deref() != Some(PASSPHRASE_CHECK) {
+                    return Err(anyhow::anyhow!("Wrong passphrase for the history database"));
+                }
+            }
+            None => self.set_meta("check", &cipher.encrypt(PASSPHRASE_CHECK)?)?,
+        }
+
+        // Overwrite what rows held before instead of leaving it in free pages
+        self.conn.execute_batch("PRAGMA secure_delete = ON")?;
+        let plain: Vec<(i64, String)> = {
+            let mut statement = self.conn.prepare("SELECT id, content FROM history WHERE encrypted = 0")?;
+            let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        // Only the key opens real ciphertext, whatever plain text happens to start with
+        let (sealed, plain): (Vec<_>, Vec<_>) = plain
+            .into_iter()
+            .partition(|(_, content)| legacy && cipher.decrypt(content).is_ok());
+        if !plain.is_empty() {
+            info!("Encrypting {} history entries", plain.len());
+        }
+        if !sealed.is_empty() || !plain.is_empty() {
+            let transaction = self.conn.transaction()?;
+            for (id, _) in &sealed {
+                transaction.execute("UPDATE history SET encrypted = 1 WHERE id = ?1", params![id])?;
+            }
+            for (id, content) in &plain {
+                transaction.execute(
+                    "UPDATE history SET content = ?1, encrypted = 1 WHERE id = ?2",
+                    params![cipher.encrypt(content)?, id],
+                )?;
+            }
+            transaction.commit()?;
+        }
+        if !plain.is_empty() {
+            // Pages freed before secure_delete was on may still hold plain text
+            self.conn.execute_batch("VACUUM")?;
+        }
+        self.cipher = Some(cipher);
+        Ok(())
+    }
+}
+
+impl HistoryStore for SqliteHistoryStore {
+    fn add(&mut self, kind: HistoryKind, content: &str) -> Result<HistoryEntry> {
+        let created_at = Local::now();
+        let stored = match &self.cipher {
+            Some(cipher) => cipher.encrypt(content)?,
+            None => content.to_string(),
+        };
+        self.conn.execute(
+            "INSERT INTO history (kind, content, created_at, encrypted) VALUES (?1, ?2, ?3, ?4)",
+            params![kind.as_str(), stored, created_at.to_rfc3339(), self.cipher.is_some()],
+        )?;
+        let id = self.conn.last_insert_rowid() as u64;
+
+        self.conn.execute(
+            "DELETE FROM history WHERE id NOT IN (SELECT id FROM history ORDER BY id DESC LIMIT ?1)",
+            params![self.max_entries as i64],
+        )?;
+
+        Ok(HistoryEntry {
+            id,
+            kind,
+            content: content.to_string(),
+            created_at,
+        })
+    }
+
+    fn recent(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT id, kind, content, created_at, encrypted FROM history ORDER BY id DESC LIMIT ?1")?;
+        let rows = statement.query_map(params![limit as i64], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, bool>(4)?,
+            ))
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (id, kind, content, created_at, encrypted) = row?;
+            let content = match (&self.cipher, encrypted) {
+                (Some(cipher), true) => cipher.decrypt(&content)?,
+                (None, true) => return Err(anyhow::anyhow!("History entry {} is encrypted", id)),
+                (_, false) => content,
+            };
+            entries.push(HistoryEntry {
+                id: id as u64,
+                kind: HistoryKind::parse(&kind)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown history entry kind '{}'", kind))?,
+                content,
+                created_at: DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Local),
+            });
+        }
+        Ok(entries)
+    }
+
+    fn remove(&mut self, id: u64) -> Result<()> {
+        self.conn.execute("DELETE FROM history WHERE id = ?1", params![id as i64])?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.conn.execute("DELETE FROM history", [])?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct NewRemoteEntry<'a> {
+    kind: HistoryKind,
+    content: &'a str,
+}
+
+/// History on a central server
+///
+/// The server is expected to provide:
+/// - `POST {url}/history` with `{"kind", "content"}`, returning the created [`HistoryEntry`]
+/// - `GET {url}/history?limit=N`, returning entries newest first
+/// - `DELETE {url}/history/{id}` and `DELETE {url}/history`
+pub struct RemoteHistoryStore {
+    endpoint: Url,
+    token: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl RemoteHistoryStore {
+    pub fn new(base: &Url, token: Option<String>) -> Result<Self> {
+        let mut base = base.clone();
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(10))
+            .user_agent(concat!("clipboard-qr/", env!("CARGO_PKG_VERSION")))
+            .build();
+
+        Ok(Self {
+            endpoint: base.join("history")?,
+            token: token.filter(|token| !token.is_empty()),
+            agent,
+        })
+    }
+
+    fn request(&self, method: &str, url: &Url) -> ureq::Request {
+        let request = self.agent.request(method, url.as_str());
+        match &self.token {
+            Some(token) => request.set("Authorization", &format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+}
+
+impl HistoryStore for RemoteHistoryStore {
+    fn add(&mut self, kind: HistoryKind, content: &str) -> Result<HistoryEntry> {
+        let body = serde_json::to_string(&NewRemoteEntry { kind, content })?;
+        let response = self
+            .request("POST", &self.endpoint)
+            .set("Content-Type", "application/json")
+            .send_string(&body)?;
+        Ok(serde_json::from_str(&response.into_string()?)?)
+    }
+
+    fn recent(&self, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let response = self
+            .request("GET", &self.endpoint)
+            .query("limit", &limit.to_string())
+            .call()?;
+        Ok(serde_json::from_str(&response.into_string()?)?)
+    }
+
+    fn remove(&mut self, id: u64) -> Result<()> {
+        let url = Url::parse(&format!("{}/{}", self.endpoint, id))?;
+        self.request("DELETE", &url).call()?;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.request("DELETE", &self.endpoint).call()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(store: &mut dyn HistoryStore) {
+        store.add(HistoryKind::Text, "first").unwrap();
+        let second = store.add(HistoryKind::QrScan, "second").unwrap();
+        store.add(HistoryKind::Text, "third").unwrap();
+
+        // Limited to two entries, newest first
+        let recent = store.recent(10).unwrap();
+        let contents: Vec<&str> = recent.iter().map(|entry| entry.content.as_str()).collect();
+        assert_eq!(contents, ["third", "second"]);
+        assert_eq!(recent[1].kind, HistoryKind::QrScan);
+
+        store.remove(second.id).unwrap();
+        assert_eq!(store.recent(10).unwrap().len(), 1);
+
+        store.clear().unwrap();
+        assert!(store.recent(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        exercise(&mut MemoryHistoryStore::new(2));
+    }
+
+    #[test]
+    fn test_history_writer() {
+        let store: SharedHistory = Arc::new(Mutex::new(Box::new(MemoryHistoryStore::new(10))));
+        let (added, stored) = mpsc::channel();
+        let writer = HistoryWriter::start(store.clone(), move |entry| added.send(entry.content.clone()).unwrap());
+        writer.add(HistoryKind::Text, "first").unwrap();
+        writer.add(HistoryKind::QrScan, "second").unwrap();
+        let timeout = Duration::from_secs(5);
+        assert_eq!(stored.recv_timeout(timeout).unwrap(), "first");
+        assert_eq!(stored.recv_timeout(timeout).unwrap(), "second");
+        assert_eq!(store.lock().unwrap().recent(10).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_sqlite_store() {
+        exercise(&mut SqliteHistoryStore::open_in_memory(2).unwrap());
+    }
+
+    #[test]
+    fn test_encrypted_sqlite_store() {
+        let path = std::env::temp_dir().join(format!("clipboard-qr-history-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let mut store = SqliteHistoryStore::open(&path, 10, None).unwrap();
+            store.add(HistoryKind::Text, "written before encryption").unwrap();
+            // Plain text that looks like ciphertext is still encrypted
+            store.add(HistoryKind::Text, "enc1:not really").unwrap();
+        }
+        {
+            let mut store = SqliteHistoryStore::open(&path, 10, Some("correct horse")).unwrap();
+            store.add(HistoryKind::QrScan, "WIFI:S:home;P:secret;;").unwrap();
+        }
+
+        // Reloaded with the passphrase; nothing readable without it
+        let store = SqliteHistoryStore::open(&path, 10, Some("correct horse")).unwrap();
+        let contents: Vec<String> = store.recent(10).unwrap().into_iter().map(|entry| entry.content).collect();
+        assert_eq!(contents, ["WIFI:S:home;P:secret;;", "enc1:not really", "written before encryption"]);
+        let raw: Vec<String> = {
+            let mut statement = store.conn.prepare("SELECT content FROM history").unwrap();
+            let rows = statement.query_map([], |row| row.get(0)).unwrap();
+            rows.map(|row| row.unwrap()).collect()
+        };
+        assert!(raw.iter().all(|content| content.starts_with(ENCRYPTED_PREFIX) && !content.contains("secret") && !content.contains("really")));
+        // Rows of databases from before the flag are told apart by whether the key opens them
+        store.conn.execute_batch("ALTER TABLE history DROP COLUMN encrypted").unwrap();
+        drop(store);
+        let store = SqliteHistoryStore::open(&path, 10, Some("correct horse")).unwrap();
+        assert_eq!(store.recent(10).unwrap()[1].content, "enc1:not really");
+        drop(store);
+        assert!(SqliteHistoryStore::open(&path, 10, Some("wrong")).is_err());
+        assert!(SqliteHistoryStore::open(&path, 10, None).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_select_entries() {
+        let mut store = MemoryHistoryStore::new(10);
+        for content in ["a", "b", "c"] {
+            store.add(HistoryKind::Text, content).unwrap();
+        }
+
+        let selected = select_entries(&store, 10, &[3, 1], None).unwrap();
+        let contents: Vec<&str> = selected.iter().map(|entry| entry.content.as_str()).collect();
+        assert_eq!(contents, ["a", "c"]);
+
+        assert_eq!(select_entries(&store, 10, &[], Some(Duration::from_secs(3600))).unwrap().len(), 3);
+        assert!(select_entries(&store, 10, &[7], None).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use tracing::{info, warn};
+
+use crate::monitor::MonitorEvent;
+use crate::normalize::NormalizationProfile;
+
+const SERVICE: &str = "org.kde.kdeconnect";
+/// Seconds to wait for the KDE Connect daemon, so an unreachable phone doesn't stall the monitor
+const TIMEOUT_SECS: &str = "5";
+
+/// Sending clipboard content to a phone paired with KDE Connect
+///
+/// ```toml
+/// [kdeconnect]
+/// enabled = true
+/// device = "Pixel 7"
+/// send = "decoded"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KdeConnectConfig {
+    /// Send to the phone from tray and watch mode
+    pub enabled: bool,
+    /// Device id or name; the first reachable paired device if unset
+    pub device: Option<String>,
+    pub send: SendMode,
+}
+
+/// What is sent to the phone
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SendMode {
+    /// Content decoded from QR codes in copied images
+    #[default]
+    Decoded,
+    /// Decoded content and copied text
+    All,
+}
+
+/// A paired device known to the KDE Connect daemon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub id: String,
+    pub name: String,
+}
+
+/// Talks to the KDE Connect daemon over the session D-Bus, through `gdbus`
+///
+/// Content sent this way lands in the phone's clipboard. The other direction needs no
+/// code here: KDE Connect's clipboard plugin puts text copied on the phone into the
+/// desktop clipboard, where the monitor turns it into a QR code like any other copy.
+#[derive(Debug, Clone)]
+pub struct KdeConnect {
+    device: Device,
+}
+
+impl KdeConnect {
+    /// Paired devices that are currently reachable
+    pub fn devices() -> Result<Vec<Device>> {
+        let ids = parse_string_list(&call("/modules/kdeconnect", "org.kde.kdeconnect.daemon.devices", &["true", "true"])?);
+        Ok(ids
+            .into_iter()
+            .map(|id| {
+                let path = format!("/modules/kdeconnect/devices/{}", id);
+                let name = call(&path, "org.freedesktop.DBus.Properties.Get", &["org.kde.kdeconnect.device", "name"])
+                    .ok()
+                    .and_then(|output| parse_string_list(&output).into_iter().next())
+                    .unwrap_or_else(|| id.clone());
+                Device { id, name }
+            })
+            .collect())
+    }
+
+    /// The device with `id_or_name`, or the first reachable one
+    pub fn connect(id_or_name: Option<&str>) -> Result<Self> {
+        let devices = Self::devices()?;
+        let device = match id_or_name {
+            Some(wanted) => devices
+                .into_iter()
+                .find(|device| device.id == wanted || device.name.eq_ignore_ascii_case(wanted))
+                .ok_or_else(|| anyhow::anyhow!("No reachable KDE Connect device '{}'; see `phone --list`", wanted))?,
+            None => devices
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("No reachable device is paired with KDE Connect"))?,
+        };
+        info!("Using KDE Connect device '{}' ({})", device.name, device.id);
+        Ok(Self { device })
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Put `text` on the phone's clipboard
+    pub fn send_clipboard(&self, text: &str) -> Result<()> {
+        let path = format!("/modules/kdeconnect/devices/{}/clipboard", self.device.id);
+        call(&path, "org.kde.kdeconnect.device.clipboard.sendClipboard", &[&quote(text)])?;
+        Ok(())
+    }
+}
+
+/// Decides which monitor events go to the phone
+pub struct PhoneBridge {
+    device: Option<String>,
+    /// Connected on first use and again after a failed send, since phones come and go
+    phone: Option<KdeConnect>,
+    send: SendMode,
+    normalization: NormalizationProfile,
+    last_sent: Option<String>,
+}
+
+impl PhoneBridge {
+    /// `None` unless enabled
+    pub fn new(config: &KdeConnectConfig, normalization: NormalizationProfile) -> Option<Self> {
+        config.enabled.then(|| Self {
+            device: config.device.clone(),
+            phone: None,
+            send: config.send,
+            normalization,
+            last_sent: None,
+        })
+    }
+
+    /// Send the event's content if the mode covers it; the same content isn't sent twice
+    /// in a row, so text the phone itself put on the clipboard doesn't bounce back
+    pub fn offer(&mut self, event: &MonitorEvent) {
+        let content = match (event, self.send) {
+            (MonitorEvent::Text(_), SendMode::Decoded) => return,
+            (event, _) => match event.content() {
+                Some(content) => content,
+                None => return,
+            },
+        };
+        if self.last_sent.as_deref().is_some_and(|last| self.normalization.same(last, content)) {
+            return;
+        }
+        let phone = match self.phone.take().map(Ok).unwrap_or_else(|| KdeConnect::connect(self.device.as_deref())) {
+            Ok(phone) => phone,
+            Err(e) => {
+                warn!("Not sending to phone: {}", e);
+                return;
+            }
+        };
+        match phone.send_clipboard(content) {
+            Ok(()) => {
+                info!("Sent clipboard content to '{}'", phone.device().name);
+                self.last_sent = Some(content.to_string());
+                self.phone = Some(phone);
+            }
+            Err(e) => warn!("Failed to send to '{}': {}", phone.device().name, e),
+        }
+    }
+}
+
+fn call(object_path: &str, method: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new("gdbus")
+        .args(["call", "--session", "--timeout", TIMEOUT_SECS, "--dest", SERVICE, "--object-path", object_path, "--method", method])
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run gdbus, which KDE Connect integration needs: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "KDE Connect call {} failed: {}",
+            method,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// A string in GVariant text format, so gdbus doesn't read e.g. "true" as a boolean
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'").replace('\n', "\\n"))
+}
+
+/// Every single-quoted string in gdbus output such as `(['a1b2', 'c3d4'],)` or `(<'Pixel 7'>,)`
+fn parse_string_list(output: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = output.chars();
+    while let Some(c) = chars.next() {
+        if c != '\'' {
+            continue;
+        }
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    escaped => string.extend(escaped),
+                },
+                '\'' => break,
+                c => string.push(c),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gvariant_strings() {
+        assert_eq!(parse_string_list("(['a1b2c3', 'd4e5'],)\n"), ["a1b2c3", "d4e5"]);
+        assert_eq!(parse_string_list("(<'Anna\\'s phone'>,)\n"), ["Anna's phone"]);
+        assert!(parse_string_list("(@as [],)\n").is_empty());
+
+        let text = "it's C:\\temp\nline two";
+        assert_eq!(parse_string_list(&quote(text)), [text]);
+    }
+}
@@ -0,0 +1,466 @@
+use anyhow::Result;
+use eframe::egui;
+use eframe::egui::emath::GuiRounding;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::clipboard_handler::{ClipboardData, ClipboardHandler};
+use crate::clipboard_history::ClipboardHistory;
+use crate::event_bus::{AppEvent, EventBus};
+use crate::locale;
+use crate::monitor::{ClipboardMonitor, MonitorHandle};
+use crate::normalize::NormalizationProfile;
+use crate::own_windows::WindowTracker;
+use crate::privacy::SensitiveFilter;
+use crate::qr_generator::QRGenerator;
+use crate::qr_scanner::ScanSettings;
+use crate::redaction::Redactor;
+use crate::renderer::{self, SvgRenderer};
+use crate::settings;
+use crate::svg_export::SvgExportDialog;
+use crate::viewing::{self, ViewingDistance, ViewingMemory};
+
+const CAPTION_CHARS: usize = 80;
+/// Room kept below the code for the caption, in points
+const CAPTION_HEIGHT: f32 = 48.0;
+/// Space around the code in a windowed kiosk, in points
+const WINDOW_MARGIN: f32 = 48.0;
+/// Narrow enough for small codes, wide enough for the caption and title bar
+const MIN_WINDOW_WIDTH: f32 = 320.0;
+const HISTORY_LABEL_CHARS: usize = 40;
+
+pub struct KioskOptions {
+    /// Style and error correction of the codes shown
+    pub generator: QRGenerator,
+    pub normalization: NormalizationProfile,
+    /// See [`MonitorConfig::poll_interval`](crate::config::MonitorConfig::poll_interval)
+    pub poll_interval: Duration,
+    pub scan: ScanSettings,
+    /// Size preset to start with; the one last used on the display if unset
+    pub viewing: Option<ViewingDistance>,
+    /// Clipboard entries kept for the History panel
+    pub history_size: usize,
+    /// Text never shown as a code
+    pub sensitive: SensitiveFilter,
+}
+
+/// Run a read-only window that always shows the clipboard as a QR code, sized for the
+/// viewing preset; D switches presets, H shows the last clipboard entries to bring back, C copies the code as an image, S saves it
+/// and E exports it as SVG with a chosen size, colours and captions;
+/// text too long for one code is shown as a gallery of parts paged with the arrow keys
+pub fn run(options: KioskOptions, redactor: Redactor) -> Result<()> {
+    info!("Starting kiosk mode");
+
+    let native = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("Clipboard QR")
+            .with_fullscreen(options.viewing.unwrap_or(ViewingDistance::Room).fullscreen())
+            .with_decorations(false),
+        ..Default::default()
+    };
+
+    eframe::run_native(
+        "Clipboard QR Kiosk",
+        native,
+        Box::new(move |cc| Ok(Box::new(KioskApp::new(cc, options, redactor)))),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to start kiosk window: {}", e))
+}
+
+struct KioskApp {
+    /// Clipboard changes from the monitor thread, read each frame
+    events: Receiver<Arc<AppEvent>>,
+    /// The text to show
+    content: Option<String>,
+    /// The sensitive-content rule the clipboard matched, shown instead of a code
+    sensitive: Option<String>,
+    generator: QRGenerator,
+    redactor: Redactor,
+    normalization: NormalizationProfile,
+    /// The text the current texture was rendered from
+    shown: Option<String>,
+    /// The codes `shown` takes, more than one if it is too long for a single code
+    parts: Vec<String>,
+    /// Index into `parts` of the code on display
+    page: usize,
+    texture: Option<egui::TextureHandle>,
+    /// Modules across the code in `texture`, quiet zone included
+    modules: u32,
+    viewing: ViewingDistance,
+    /// Set from the command line, so remembered choices don't override it
+    explicit_viewing: bool,
+    memory: ViewingMemory,
+    /// Key of the display the window is on, once known
+    display: Option<String>,
+    /// The window mode and inner size last asked for
+    window: Option<(bool, egui::Vec2)>,
+    history: Arc<Mutex<ClipboardHistory>>,
+    /// The History panel is open
+    show_history: bool,
+    /// For copying the shown code as an image
+    clipboard: ClipboardHandler,
+    /// Where the code was last saved, shown in the History panel
+    saved: Option<PathBuf>,
+    monitor: MonitorHandle,
+    export: SvgExportDialog,
+    /// Keeps screen scans from reading the shown code back
+    tracker: WindowTracker,
+}
+
+impl KioskApp {
+    fn new(cc: &eframe::CreationContext<'_>, options: KioskOptions, redactor: Redactor) -> Self {
+        let KioskOptions { generator, normalization, poll_interval, scan, viewing, history_size, sensitive } = options;
+        let export = SvgExportDialog::new(generator.style(), &SvgRenderer::default());
+        let bus = EventBus::new();
+        let events = bus.subscribe();
+        let history = Arc::new(Mutex::new(ClipboardHistory::new(history_size)));
+        let thread_history = history.clone();
+        let ctx = cc.egui_ctx.clone();
+
+        // Repaint only when the clipboard changes; the window otherwise stays idle
+        let monitor = ClipboardMonitor::new()
+            .with_handler(ClipboardHandler::builder().normalization(normalization).poll_interval(poll_interval))
+            .with_scanner(move || scan.scanner())
+            .with_sensitive_filter(sensitive)
+            .start(move |event, _context| {
+                if let Ok(mut history) = thread_history.lock() {
+                    history.record(event);
+                }
+                bus.publish_monitor(event);
+                ctx.request_repaint();
+            });
+
+        Self {
+            events,
+            content: None,
+            sensitive: None,
+            generator,
+            redactor,
+            normalization,
+            shown: None,
+            parts: Vec::new(),
+            page: 0,
+            texture: None,
+            modules: 0,
+            viewing: viewing.unwrap_or(ViewingDistance::Room),
+            explicit_viewing: viewing.is_some(),
+            memory: ViewingMemory::open_default(),
+            display: None,
+            window: None,
+            history,
+            show_history: false,
+            clipboard: ClipboardHandler::builder().spawn_listener(false).build(),
+            saved: None,
+            monitor,
+            export,
+            tracker: WindowTracker::new(),
+        }
+    }
+
+    /// Follow the clipboard changes published since the last frame
+    fn follow_events(&mut self) {
+        for event in self.events.try_iter() {
+            match &*event {
+                AppEvent::ClipboardChanged { data: ClipboardData::Text(text), .. } => {
+                    self.content = Some(text.clone());
+                    self.sensitive = None;
+                }
+                // Images without a code and copied files leave the previous one up
+                AppEvent::ClipboardChanged { data: ClipboardData::Image(_) | ClipboardData::Files(_), .. } => {}
+                // Stop showing the previous code rather than leave it up next to private content
+                AppEvent::ClipboardChanged { sensitive_rule, .. } => {
+                    self.content = None;
+                    self.sensitive = sensitive_rule.clone();
+                }
+                AppEvent::QrDecoded(content) => {
+                    self.content = Some(content.clone());
+                    self.sensitive = None;
+                }
+                AppEvent::Error(_) | AppEvent::MonitoringPaused { .. } | AppEvent::Notified => {}
+            }
+        }
+    }
+
+    /// Regenerate the QR texture if the clipboard text changed since the last frame
+    fn refresh(&mut self, ctx: &egui::Context) {
+        self.follow_events();
+        let current = self.content.clone();
+        let unchanged = match (&current, &self.shown) {
+            (Some(current), Some(shown)) => self.normalization.same(current, shown),
+            (current, shown) => current == shown,
+        };
+        if unchanged {
+            return;
+        }
+
+        self.parts = current.as_deref().map(|text| self.generator.parts(text)).unwrap_or_default();
+        self.shown = current;
+        self.show_page(ctx, 0);
+    }
+
+    /// Render part `page` of the shown text
+    fn show_page(&mut self, ctx: &egui::Context, page: usize) {
+        self.page = page;
+        self.texture = match self.parts.get(page).filter(|part| !part.is_empty()).map(|part| self.generator.encode(part)) {
+            Some(Ok(code)) => {
+                let image = self.generator.rasterize_code(&code);
+                self.modules = image.width() / self.generator.scale_for(&code);
+                let size = [image.width() as usize, image.height() as usize];
+                Some(ctx.load_texture("kiosk-qr", egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()), egui::TextureOptions::NEAREST))
+            }
+            Some(Err(e)) => {
+                error!("Failed to generate QR code: {}", e);
+                None
+            }
+            _ => None,
+        };
+    }
+
+    /// Page through a multi-part code with the arrow or page keys
+    fn update_page(&mut self, ctx: &egui::Context) {
+        if self.parts.len() < 2 || ctx.wants_keyboard_input() {
+            return;
+        }
+        let (next, previous) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowRight) || i.key_pressed(egui::Key::PageDown),
+                i.key_pressed(egui::Key::ArrowLeft) || i.key_pressed(egui::Key::PageUp),
+            )
+        });
+        let page = match (next, previous) {
+            (true, false) => (self.page + 1) % self.parts.len(),
+            (false, true) => (self.page + self.parts.len() - 1) % self.parts.len(),
+            _ => return,
+        };
+        self.show_page(ctx, page);
+    }
+
+    /// Pick up the preset remembered for the display, and switch presets on D
+    fn update_viewing(&mut self, ctx: &egui::Context) {
+        let monitor = ctx.input(|i| i.viewport().monitor_size);
+        if let (None, Some(size)) = (&self.display, monitor) {
+            let key = viewing::display_key(size.x, size.y);
+            if let Some(remembered) = self.memory.get(&key).filter(|_| !self.explicit_viewing) {
+                self.viewing = remembered;
+            }
+            info!("Kiosk viewing distance: {} on display {}", self.viewing.label(), key);
+            self.display = Some(key);
+        }
+
+        if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::D)) {
+            self.viewing = self.viewing.next();
+            info!("Kiosk viewing distance: {}", self.viewing.label());
+            if let Some(display) = &self.display {
+                if let Err(e) = self.memory.remember(display, self.viewing) {
+                    error!("Failed to remember viewing distance: {}", e);
+                }
+            }
+        }
+    }
+
+    /// The History panel, H toggles it; picking an entry shows its code until the clipboard changes
+    fn history_panel(&mut self, ctx: &egui::Context) {
+        if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::H)) {
+            self.show_history = !self.show_history;
+        }
+        if !self.show_history {
+            return;
+        }
+        let entries: Vec<_> = match self.history.lock() {
+            Ok(history) => history.entries().cloned().collect(),
+            Err(_) => return,
+        };
+        let mut picked = None;
+        let mut copy = false;
+        let mut save = false;
+        egui::SidePanel::right("kiosk-history").resizable(false).show(ctx, |ui| {
+            copy = ui.add_enabled(self.texture.is_some(), egui::Button::new("Copy QR as image")).clicked();
+            save = ui.add_enabled(self.texture.is_some(), egui::Button::new("Save QR as…")).clicked();
+            if ui.add_enabled(self.texture.is_some(), egui::Button::new("Export SVG…")).clicked() {
+                self.export.open = true;
+            }
+            if ui.button("Settings…").clicked() {
+                if let Err(e) = settings::open_window() {
+                    error!("{}", e);
+                }
+            }
+            if let Some(saved) = &self.saved {
+                ui.label(format!("Saved to {}", saved.display()));
+            }
+            ui.separator();
+            ui.heading("History");
+            ui.add_space(4.0);
+            if entries.is_empty() {
+                ui.label("Nothing copied yet");
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for entry in &entries {
+                    let label = format!("{}  {}", locale::current().time(&entry.copied_at), entry.label(&self.redactor, HISTORY_LABEL_CHARS));
+                    let button = egui::Button::new(label).wrap_mode(egui::TextWrapMode::Truncate);
+                    if ui.add_enabled(entry.qr_content().is_some(), button).clicked() {
+                        picked = entry.qr_content().map(str::to_string);
+                    }
+                }
+            });
+        });
+        if copy {
+            self.copy_image();
+        }
+        if save {
+            self.save_as();
+        }
+        if let Some(text) = picked {
+            self.content = Some(text);
+            ctx.request_repaint();
+        }
+    }
+
+    /// Put the shown code on the clipboard as an image, to paste into a chat
+    ///
+    /// The monitor then sees the image and decodes the same text, so the display stays put.
+    fn copy_image(&mut self) {
+        let Some(text) = self.parts.get(self.page) else { return };
+        let copied = self.generator.generate_rgba(text).and_then(|image| self.clipboard.set_image(&image));
+        match copied {
+            Ok(()) => info!("Copied the shown QR code to the clipboard as an image"),
+            Err(e) => error!("Failed to copy QR code image: {}", e),
+        }
+    }
+
+    /// Ask where to save the shown code and save it there; the dialog confirms overwriting
+    fn save_as(&mut self) {
+        let Some(text) = self.parts.get(self.page) else { return };
+        let path = rfd::FileDialog::new()
+            .set_title("Save QR code")
+            .set_file_name(QRGenerator::default_file_name(text))
+            .add_filter("PNG image", &["png"])
+            .add_filter("SVG image", &["svg"])
+            .add_filter("PDF document", &["pdf"])
+            .save_file();
+        let Some(path) = path else { return };
+        match self.generator.save_qr_image_to(text, &path) {
+            Ok(path) => self.saved = Some(path),
+            Err(e) => error!("Failed to save QR code to {:?}: {}", path, e),
+        }
+    }
+
+    /// Side of the code in points for the current preset within `available` points
+    fn code_side(&self, ctx: &egui::Context, available: f32) -> f32 {
+        if self.texture.is_none() {
+            return available;
+        }
+        let side = self.viewing.code_side(self.modules, available);
+        // Whole physical pixels per module, or modules blur on fractional display scales
+        renderer::crisp_display_size(side, ctx.pixels_per_point(), self.modules)
+    }
+
+    /// Go fullscreen for far presets, or shrink the window around the code for the desk
+    fn apply_window(&mut self, ctx: &egui::Context) {
+        let fullscreen = self.viewing.fullscreen();
+        let size = if fullscreen {
+            egui::Vec2::ZERO
+        } else {
+            let screen = ctx.input(|i| i.viewport().monitor_size).unwrap_or(egui::vec2(1280.0, 800.0));
+            let side = self.code_side(ctx, screen.x.min(screen.y) * 0.8);
+            egui::vec2((side + WINDOW_MARGIN).max(MIN_WINDOW_WIDTH), side + CAPTION_HEIGHT + WINDOW_MARGIN)
+        };
+        if self.window == Some((fullscreen, size)) {
+            return;
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(fullscreen));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(!fullscreen));
+        if !fullscreen {
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+        }
+        self.window = Some((fullscreen, size));
+    }
+}
+
+impl eframe::App for KioskApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.refresh(ctx);
+        self.update_page(ctx);
+        self.update_viewing(ctx);
+        self.apply_window(ctx);
+        self.tracker.update(ctx);
+
+        // Letters typed into the export dialog aren't shortcuts
+        let shortcut = |key| !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(key));
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            match self.export.open {
+                true => self.export.open = false,
+                false => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+            }
+        }
+        if shortcut(egui::Key::C) && self.texture.is_some() {
+            self.copy_image();
+        }
+        if shortcut(egui::Key::S) && self.texture.is_some() {
+            self.save_as();
+        }
+        if shortcut(egui::Key::E) && self.texture.is_some() {
+            self.export.open = true;
+        }
+        if let Some(text) = self.parts.get(self.page).filter(|_| self.export.open) {
+            self.export.show(ctx, &self.generator, text);
+        }
+        if shortcut(egui::Key::P) {
+            let paused = !self.monitor.is_paused();
+            self.monitor.set_paused(paused);
+            info!("Kiosk monitoring {}", if paused { "paused" } else { "resumed" });
+        }
+        if self.viewing.fullscreen() && !self.show_history {
+            ctx.set_cursor_icon(egui::CursorIcon::None);
+        }
+        self.history_panel(ctx);
+
+        let background = self.generator.style().background;
+        let foreground = self.generator.style().foreground;
+        let frame = egui::Frame::new().fill(egui::Color32::from_rgb(background.r, background.g, background.b));
+        let text_color = egui::Color32::from_rgb(foreground.r, foreground.g, foreground.b);
+        let skipped = self.sensitive.clone();
+
+        egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
+            let available = ui.available_size();
+            let side = self.code_side(ctx, (available.x.min(available.y - CAPTION_HEIGHT) * 0.9).max(0.0));
+            let caption_size = self.viewing.caption_size();
+
+            ui.vertical_centered(|ui| {
+                ui.add_space(((available.y - side - CAPTION_HEIGHT) / 2.0).max(0.0));
+                match (&self.texture, &self.shown) {
+                    (Some(texture), Some(text)) => {
+                        let (rect, _) = ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::hover());
+                        // Start on a physical pixel so module edges line up with the screen grid
+                        let rect = rect.round_to_pixels(ctx.pixels_per_point());
+                        egui::Image::new(texture).paint_at(ui, rect);
+                        ui.add_space(8.0);
+                        let mut caption = self.redactor.text_truncated(text, CAPTION_CHARS).replace(['\r', '\n', '\t'], " ");
+                        if self.parts.len() > 1 {
+                            caption = format!("Part {}/{} (← →)  {}", self.page + 1, self.parts.len(), caption);
+                        }
+                        if self.monitor.is_paused() {
+                            caption = format!("⏸ Paused (P resumes)  {}", caption);
+                        }
+                        ui.label(egui::RichText::new(caption).size(caption_size).color(text_color));
+                    }
+                    (None, Some(_)) => {
+                        ui.label(egui::RichText::new("Clipboard content is too long for a QR code").size(caption_size).color(text_color));
+                    }
+                    _ if self.monitor.is_paused() => {
+                        ui.label(egui::RichText::new("⏸ Paused, press P to resume").size(caption_size).color(text_color));
+                    }
+                    _ => {
+                        let message = match &skipped {
+                            Some(rule) => format!("🔒 Sensitive content skipped ({})", rule),
+                            None => "Copy text or a QR image to show it here".to_string(),
+                        };
+                        ui.label(egui::RichText::new(message).size(caption_size).color(text_color));
+                    }
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,254 @@
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use anyhow::Result;
+use image::{imageops, GrayImage, ImageBuffer, Luma};
+use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use tracing::info;
+
+const MM_PER_INCH: f32 = 25.4;
+
+/// Caption text height relative to the printable label height
+const CAPTION_HEIGHT_RATIO: f32 = 0.14;
+
+/// Physical layout of a label stock
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LabelTemplate {
+    pub name: String,
+    pub width_mm: f32,
+    pub height_mm: f32,
+    /// Printer resolution
+    pub dpi: u32,
+    pub margin_mm: f32,
+    /// Print the encoded text under the code
+    pub caption: bool,
+    /// Clockwise rotation of the finished label: 0, 90, 180 or 270
+    pub rotation: u16,
+}
+
+impl Default for LabelTemplate {
+    fn default() -> Self {
+        Self {
+            name: "custom".to_string(),
+            width_mm: 50.8,
+            height_mm: 25.4,
+            dpi: 203,
+            margin_mm: 2.0,
+            caption: true,
+            rotation: 0,
+        }
+    }
+}
+
+impl LabelTemplate {
+    fn builtin(name: &str, width_mm: f32, height_mm: f32, dpi: u32, rotation: u16) -> Self {
+        Self {
+            name: name.to_string(),
+            width_mm,
+            height_mm,
+            dpi,
+            rotation,
+            ..Default::default()
+        }
+    }
+
+    /// Templates for common label printers
+    pub fn builtins() -> Vec<LabelTemplate> {
+        vec![
+            // Zebra and other thermal printers, 203 dpi
+            Self::builtin("zebra-2x1", 50.8, 25.4, 203, 0),
+            Self::builtin("zebra-4x6", 101.6, 152.4, 203, 0),
+            // Dymo LabelWriter; address labels feed short edge first
+            Self::builtin("dymo-30252", 89.0, 28.0, 300, 90),
+            Self::builtin("dymo-30336", 54.0, 25.0, 300, 90),
+            // Brother QL die-cut labels
+            Self::builtin("brother-dk11201", 90.0, 29.0, 300, 90),
+            Self::builtin("brother-dk11204", 54.0, 17.0, 300, 90),
+        ]
+    }
+
+    /// Find a template by name, custom templates taking precedence over built-ins
+    pub fn find(name: &str, custom: &[LabelTemplate]) -> Option<LabelTemplate> {
+        custom
+            .iter()
+            .cloned()
+            .chain(Self::builtins())
+            .find(|template| template.name == name)
+    }
+
+    fn mm_to_px(&self, mm: f32) -> u32 {
+        (mm / MM_PER_INCH * self.dpi as f32).round() as u32
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !matches!(self.rotation, 0 | 90 | 180 | 270) {
+            return Err(anyhow::anyhow!("Label rotation must be 0, 90, 180 or 270, not {}", self.rotation));
+        }
+        if self.width_mm <= 2.0 * self.margin_mm || self.height_mm <= 2.0 * self.margin_mm {
+            return Err(anyhow::anyhow!("Label '{}' margins leave no printable area", self.name));
+        }
+        if self.dpi == 0 {
+            return Err(anyhow::anyhow!("Label DPI must be greater than zero"));
+        }
+        Ok(())
+    }
+
+    /// Render a monochrome label image at the printer's resolution
+    pub fn render(&self, text: &str) -> Result<GrayImage> {
+        if text.is_empty() {
+            return Err(anyhow::anyhow!("No text to generate QR code"));
+        }
+        self.validate()?;
+
+        let width = self.mm_to_px(self.width_mm);
+        let height = self.mm_to_px(self.height_mm);
+        let margin = self.mm_to_px(self.margin_mm);
+        let mut label = ImageBuffer::from_pixel(width, height, Luma([255u8]));
+
+        let caption_height = if self.caption {
+            ((height - 2 * margin) as f32 * CAPTION_HEIGHT_RATIO).round() as u32
+        } else {
+            0
+        };
+        let available = (width - 2 * margin).min(height - 2 * margin - caption_height);
+
+        // Whole pixels per module keep edges sharp on thermal printers
+        let code = QrCode::new(text)?;
+        let modules = code.width() as u32 + 8;
+        let module_px = available / modules;
+        if module_px == 0 {
+            return Err(anyhow::anyhow!(
+                "Content is too long to print on a {}x{} mm label at {} dpi",
+                self.width_mm,
+                self.height_mm,
+                self.dpi
+            ));
+        }
+        let qr = code
+            .render::<Luma<u8>>()
+            .module_dimensions(module_px, module_px)
+            .build();
+
+        let x = (width - qr.width()) / 2;
+        let y = margin + (height - 2 * margin - caption_height - qr.height()) / 2;
+        imageops::overlay(&mut label, &qr, x as i64, y as i64);
+
+        if self.caption {
+            draw_caption(&mut label, text, margin, height - margin - caption_height, caption_height);
+        }
+
+        Ok(match self.rotation {
+            90 => imageops::rotate90(&label),
+            180 => imageops::rotate180(&label),
+            270 => imageops::rotate270(&label),
+            _ => label,
+        })
+    }
+
+    pub fn save(&self, text: &str, path: &Path) -> Result<()> {
+        self.render(text)?.save(path)?;
+        info!("Label saved to: {:?}", path);
+        Ok(())
+    }
+}
+
+/// Draw one line of centred text, shrinking it to fit the label width
+fn draw_caption(label: &mut GrayImage, text: &str, margin: u32, top: u32, height: u32) {
+    let font = FontRef::try_from_slice(epaint_default_fonts::HACK_REGULAR).expect("embedded font is valid");
+    let text = text.lines().next().unwrap_or_default();
+    let max_width = (label.width() - 2 * margin) as f32;
+
+    let line_width = |scale: PxScale| {
+        let scaled = font.as_scaled(scale);
+        text.chars().map(|c| scaled.h_advance(font.glyph_id(c))).sum::<f32>()
+    };
+    let mut scale = PxScale::from(height as f32);
+    let width = line_width(scale);
+    if width > max_width {
+        scale = PxScale::from(height as f32 * max_width / width);
+    }
+
+    let scaled = font.as_scaled(scale);
+    let mut x = margin as f32 + (max_width - line_width(scale)) / 2.0;
+    let baseline = top as f32 + (height as f32 + scaled.ascent() + scaled.descent()) / 2.0;
+
+    for c in text.chars() {
+        let glyph_id = font.glyph_id(c);
+        let glyph = glyph_id.with_scale_and_position(scale, ab_glyph::point(x, baseline));
+        x += scaled.h_advance(glyph_id);
+
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px >= 0 && py >= 0 && (px as u32) < label.width() && (py as u32) < label.height() {
+                    let pixel = label.get_pixel_mut(px as u32, py as u32);
+                    let ink = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+                    pixel[0] = pixel[0].min(255 - ink);
+                }
+            });
+        }
+    }
+}
+
+/// Send an image file to a printer queue, or the default printer if `printer` is None
+pub fn print_file(path: &Path, printer: Option<&str>, copies: u32) -> Result<()> {
+    #[cfg(windows)]
+    let status = {
+        let _ = copies;
+        let mut command = Command::new("mspaint");
+        command.arg("/pt").arg(path);
+        if let Some(printer) = printer {
+            command.arg(printer);
+        }
+        command.status()?
+    };
+
+    #[cfg(not(windows))]
+    let status = {
+        let mut command = Command::new("lp");
+        if let Some(printer) = printer {
+            command.args(["-d", printer]);
+        }
+        command
+            .args(["-n", &copies.to_string(), "-o", "fit-to-page"])
+            .arg(path)
+            .status()?
+    };
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("Print command exited with {}", status));
+    }
+    info!("Sent {:?} to printer {}", path, printer.unwrap_or("(default)"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_label_size() {
+        let template = LabelTemplate::find("zebra-2x1", &[]).unwrap();
+        let label = template.render("SKU-000123").unwrap();
+        assert_eq!((label.width(), label.height()), (406, 203));
+
+        // Rotated templates swap the image dimensions
+        let template = LabelTemplate::find("dymo-30336", &[]).unwrap();
+        let label = template.render("SKU-000123").unwrap();
+        assert_eq!((label.width(), label.height()), (295, 638));
+    }
+
+    #[test]
+    fn test_label_too_small() {
+        let template = LabelTemplate {
+            width_mm: 10.0,
+            height_mm: 10.0,
+            ..Default::default()
+        };
+        assert!(template.render(&"x".repeat(1000)).is_err());
+    }
+}
@@ -1,24 +1,248 @@
+// The QR logic, without GUI or clipboard dependencies (the `core` feature)
+#[cfg(feature = "core")]
+pub mod barcode_generator;
+#[cfg(feature = "core")]
+pub mod matrix;
+#[cfg(feature = "core")]
+pub mod preprocess;
+#[cfg(feature = "core")]
+pub mod qr_chunker;
+#[cfg(feature = "core")]
 pub mod qr_generator;
+#[cfg(feature = "core")]
 pub mod qr_scanner;
+#[cfg(feature = "core")]
+pub mod qr_style;
+#[cfg(feature = "core")]
+pub mod qr_templates;
+#[cfg(feature = "core")]
+pub mod redaction;
+#[cfg(feature = "core")]
+pub mod renderer;
+#[cfg(feature = "core")]
+pub mod scan_trace;
+#[cfg(feature = "core")]
+pub mod storage;
+#[cfg(feature = "core")]
+pub mod transforms;
+
+// The desktop app: windows, the clipboard monitor and everything built on them (the `gui` feature)
+#[cfg(feature = "gui")]
+pub mod about;
+#[cfg(feature = "gui")]
+pub mod autostart;
+#[cfg(feature = "gui")]
+pub mod batch;
+#[cfg(feature = "camera")]
+pub mod camera_scanner;
+#[cfg(feature = "gui")]
+pub mod capacity;
+#[cfg(feature = "gui")]
 pub mod clipboard_handler;
+#[cfg(feature = "gui")]
+pub mod clipboard_history;
+#[cfg(feature = "gui")]
+pub mod config;
+#[cfg(feature = "gui")]
+pub mod content_classifier;
+#[cfg(feature = "gui")]
+pub mod content_preview;
+#[cfg(feature = "gui")]
+pub mod control;
+#[cfg(feature = "gui")]
+pub mod diagnostics;
+#[cfg(feature = "gui")]
+pub mod event_bus;
+#[cfg(feature = "gui")]
+pub mod expiry;
+#[cfg(feature = "gui")]
+pub mod file_scan;
+#[cfg(feature = "gui")]
+pub mod generator_pool;
+#[cfg(feature = "gui")]
 pub mod global_state;
+#[cfg(feature = "gui")]
+pub mod history;
+#[cfg(feature = "gui")]
+pub mod kdeconnect;
+#[cfg(feature = "gui")]
+pub mod label;
+#[cfg(feature = "gui")]
+pub mod link_safety;
+#[cfg(feature = "gui")]
+pub mod locale;
+#[cfg(feature = "gui")]
+pub mod log_buffer;
+#[cfg(feature = "gui")]
+pub mod monitor;
+#[cfg(feature = "gui")]
+pub mod normalize;
+#[cfg(feature = "gui")]
+pub mod notification;
+#[cfg(feature = "gui")]
+pub mod notifier;
+#[cfg(feature = "gui")]
+pub mod own_windows;
+#[cfg(feature = "gui")]
+pub mod password_manager;
+#[cfg(feature = "gui")]
+pub mod payload;
+#[cfg(feature = "gui")]
+pub mod pipeline;
+#[cfg(feature = "gui")]
+pub mod popup;
+#[cfg(feature = "gui")]
+pub mod privacy;
+#[cfg(feature = "gui")]
+pub mod region;
+#[cfg(feature = "gui")]
+pub mod remote;
+#[cfg(feature = "gui")]
+pub mod report;
+#[cfg(feature = "gui")]
+pub mod rich_text;
+#[cfg(feature = "gui")]
+pub mod scan_window;
+#[cfg(feature = "gui")]
+pub mod scheduler;
+#[cfg(feature = "gui")]
+pub mod self_check;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "gui")]
+pub mod settings;
+#[cfg(feature = "gui")]
+pub mod single_instance;
+#[cfg(feature = "gui")]
+pub mod snapshot;
+#[cfg(feature = "gui")]
+pub mod snippets;
+#[cfg(feature = "gui")]
+pub mod snooze;
+#[cfg(feature = "gui")]
+pub mod stats;
+#[cfg(feature = "gui")]
+pub mod status_window;
+#[cfg(feature = "gui")]
+pub mod svg_export;
+#[cfg(feature = "gui")]
+pub mod thumbnails;
+#[cfg(feature = "gui")]
+pub mod totp;
+#[cfg(feature = "gui")]
+pub mod viewing;
+#[cfg(all(feature = "wayland-data-control", unix, not(target_os = "macos")))]
+pub mod wayland_clipboard;
+#[cfg(feature = "gui")]
+pub mod wifi_form;
 
+#[cfg(feature = "core")]
 pub use qr_generator::QRGenerator;
+// Codes passed to `QRGenerator::render_code` must come from the same qrcode version
+#[cfg(feature = "core")]
+pub use qrcode;
+#[cfg(feature = "core")]
 pub use qr_scanner::QRScanner;
-pub use clipboard_handler::ClipboardHandler;
+#[cfg(feature = "gui")]
+pub use clipboard_handler::{ClipboardEvent, ClipboardHandler};
+#[cfg(feature = "gui")]
 pub use global_state::GlobalClipboardState;
+#[cfg(feature = "gui")]
+pub use monitor::{ClipboardMonitor, MonitorEvent};
 
-#[cfg(test)]
+#[cfg(all(test, feature = "core"))]
 mod tests {
     use super::*;
 
     #[test]
+    #[cfg(feature = "gui")]
     fn test_clipboard_handler_creation() {
-        let handler = ClipboardHandler::new();
+        let _handler = ClipboardHandler::new();
         // Note: clipboard availability depends on the system
         // We just test that it can be created
     }
 
+    #[test]
+    #[cfg(feature = "gui")]
+    fn test_content_hash() {
+        use clipboard_handler::{content_hash, ClipboardData};
+        use normalize::NormalizationProfile;
+
+        let image = image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 255, 255, 255]));
+        let mut other = image.clone();
+        other.put_pixel(7, 7, image::Rgba([0, 0, 0, 255]));
+        let hash = |image: &image::RgbaImage| content_hash(&ClipboardData::Image(image.clone()), NormalizationProfile::Raw);
+        // Images of the same size that only differ away from the first pixel
+        assert_ne!(hash(&image), hash(&other));
+        assert_eq!(hash(&other), hash(&other.clone()));
+        assert_eq!(clipboard_handler::image_hash(&other), hash(&other));
+
+        let mut state = GlobalClipboardState::new();
+        assert!(state.set_data(ClipboardData::Image(image.clone()), NormalizationProfile::Raw));
+        assert!(!state.set_data(ClipboardData::Image(image), NormalizationProfile::Raw));
+        assert!(state.set_data(ClipboardData::Image(other), NormalizationProfile::Raw));
+    }
+
+    #[test]
+    #[cfg(feature = "gui")]
+    fn test_listener_watchdog() {
+        use clipboard_handler::ListenerWatchdog;
+        use std::time::{Duration, Instant};
+
+        let start = Instant::now();
+        let mut watchdog = ListenerWatchdog::default();
+        // A listener event arriving shortly after polling saw the change is fine
+        assert_eq!(watchdog.check(true, false, start), None);
+        watchdog.heartbeat();
+        assert_eq!(watchdog.check(false, false, start + Duration::from_secs(5)), None);
+
+        // No event at all for a change polling saw two seconds ago
+        assert_eq!(watchdog.check(true, false, start), None);
+        assert!(watchdog.check(false, false, start + Duration::from_secs(2)).is_some());
+        assert!(watchdog.check(false, true, start).is_some());
+        assert!(watchdog.check(false, true, start).is_some());
+        // Left to polling after three restarts
+        assert_eq!(watchdog.check(false, true, start), None);
+        assert_eq!(watchdog.restarts(), 3);
+
+        let mut polling = ListenerWatchdog::default();
+        polling.polling();
+        assert_eq!(polling.check(true, true, start + Duration::from_secs(10)), None);
+        assert!(polling.should_verify(start, start));
+
+        // Once the listener reports changes itself, polls only read the clipboard now and then
+        let mut trusted = ListenerWatchdog::default();
+        assert!(trusted.should_verify(start, start));
+        trusted.heartbeat();
+        assert!(!trusted.should_verify(start, start + Duration::from_millis(500)));
+        assert!(trusted.should_verify(start, start + Duration::from_secs(1)));
+    }
+
+    #[test]
+    #[cfg(feature = "gui")]
+    fn test_monitor_pause() {
+        use std::time::Duration;
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = ClipboardMonitor::new()
+            .with_handler(ClipboardHandler::builder().spawn_listener(false).poll_interval(Duration::from_millis(10)))
+            .start(move |event, _| {
+                if let MonitorEvent::Text(text) = event {
+                    let _ = tx.send(text.clone());
+                }
+            });
+        handle.set_paused(true);
+        assert!(handle.is_paused());
+        // Let the thread reach the pause before anything is copied
+        std::thread::sleep(Duration::from_millis(100));
+        handle.injector().send(clipboard_handler::ClipboardData::Text("secret".to_string())).unwrap();
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+        handle.set_paused(false);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(2)).unwrap(), "secret");
+        handle.stop();
+    }
+
     #[test]
     fn test_qr_generator_with_empty_text() {
         let generator = QRGenerator::new();
@@ -44,7 +268,7 @@ mod tests {
 
     #[test]
     fn test_qr_scanner_creation() {
-        let scanner = QRScanner::new();
+        let _scanner = QRScanner::new();
         // Test that scanner can be created
     }
 
@@ -0,0 +1,345 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{info, warn};
+use url::{Host, Url};
+
+/// Hosts whose only job is redirecting somewhere else
+const KNOWN_SHORTENERS: &[&str] = &[
+    "bit.ly", "buff.ly", "cutt.ly", "goo.gl", "is.gd", "ow.ly", "rb.gy", "rebrand.ly", "s.id", "shorturl.at",
+    "t.co", "t.ly", "tiny.cc", "tinyurl.com", "v.gd",
+];
+
+/// Shorteners are sometimes chained; give up after this many hops
+const MAX_REDIRECTS: usize = 5;
+
+const EXPANSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinkSafetyConfig {
+    /// Follow redirects of known URL shorteners to show the real destination;
+    /// this sends a HEAD request to the shortener
+    pub expand_shorteners: bool,
+    /// File with one blocked host per line; subdomains are blocked too
+    pub blocklist: Option<PathBuf>,
+    /// Open decoded links in the browser when screening found nothing suspicious
+    pub auto_open: bool,
+}
+
+impl Default for LinkSafetyConfig {
+    fn default() -> Self {
+        Self {
+            expand_shorteners: true,
+            blocklist: None,
+            auto_open: false,
+        }
+    }
+}
+
+/// Something about a link worth telling the user before they open it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkWarning {
+    /// An internationalized domain name, which can imitate another domain
+    Punycode { ascii: String, unicode: String },
+    /// A host label mixing Latin with Cyrillic or Greek letters, a classic homograph
+    MixedScripts(String),
+    /// The host, or a domain it belongs to, is on the local blocklist
+    Blocklisted(String),
+    /// The link went through a URL shortener
+    Shortened { via: String },
+    ExpansionFailed(String),
+    /// A scheme other than http(s), e.g. `javascript:` or `data:`
+    UnusualScheme(String),
+    /// `user@host` URLs can make the real host hard to spot
+    Credentials,
+    IpAddress(String),
+}
+
+impl LinkWarning {
+    /// Informational warnings don't block auto-open
+    pub fn is_informational(&self) -> bool {
+        matches!(self, LinkWarning::Shortened { .. })
+    }
+}
+
+impl fmt::Display for LinkWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkWarning::Punycode { ascii, unicode } => {
+                write!(f, "internationalized domain {} is displayed as {}", ascii, unicode)
+            }
+            LinkWarning::MixedScripts(label) => write!(f, "'{}' mixes Latin with look-alike letters", label),
+            LinkWarning::Blocklisted(host) => write!(f, "{} is on the blocklist", host),
+            LinkWarning::Shortened { via } => write!(f, "shortened link via {}", via),
+            LinkWarning::ExpansionFailed(e) => write!(f, "could not resolve the shortened link: {}", e),
+            LinkWarning::UnusualScheme(scheme) => write!(f, "unusual link type '{}:'", scheme),
+            LinkWarning::Credentials => f.write_str("the link contains a user name before the host"),
+            LinkWarning::IpAddress(ip) => write!(f, "the link points to a bare IP address ({})", ip),
+        }
+    }
+}
+
+/// The outcome of screening one link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkReport {
+    pub original: Url,
+    /// Where the link ends up after expanding shorteners
+    pub destination: Url,
+    pub warnings: Vec<LinkWarning>,
+}
+
+impl LinkReport {
+    /// No warnings beyond informational ones
+    pub fn is_safe(&self) -> bool {
+        self.warnings.iter().all(LinkWarning::is_informational)
+    }
+}
+
+/// Checks decoded URLs before they are offered for opening
+pub struct LinkScreener {
+    config: LinkSafetyConfig,
+    blocklist: HashSet<String>,
+    agent: ureq::Agent,
+}
+
+impl LinkScreener {
+    /// A missing or unreadable blocklist is logged and treated as empty
+    pub fn new(config: LinkSafetyConfig) -> Self {
+        let blocklist = match &config.blocklist {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(contents) => parse_blocklist(&contents),
+                Err(e) => {
+                    warn!("Failed to read link blocklist {:?}: {}", path, e);
+                    HashSet::new()
+                }
+            },
+            None => HashSet::new(),
+        };
+        info!("Link screening: {} blocked host(s)", blocklist.len());
+
+        let agent = ureq::AgentBuilder::new()
+            .redirects(0)
+            .timeout(EXPANSION_TIMEOUT)
+            .build();
+        Self { config, blocklist, agent }
+    }
+
+    pub fn config(&self) -> &LinkSafetyConfig {
+        &self.config
+    }
+
+    /// Screen `text` if it is a single URL
+    pub fn screen_text(&self, text: &str) -> Option<LinkReport> {
+        let text = text.trim();
+        if text.contains(char::is_whitespace) || !text.contains(':') {
+            return None;
+        }
+        let url = Url::parse(text).ok()?;
+        // "mailto:", "tel:", "WIFI:" and similar payloads aren't links to open
+        if url.cannot_be_a_base() && !matches!(url.scheme(), "javascript" | "data") {
+            return None;
+        }
+        Some(self.screen(&url))
+    }
+
+    pub fn screen(&self, url: &Url) -> LinkReport {
+        let mut warnings = Vec::new();
+        let mut destination = url.clone();
+
+        if self.config.expand_shorteners && is_shortener(url) {
+            warnings.push(LinkWarning::Shortened {
+                via: url.host_str().unwrap_or_default().to_string(),
+            });
+            match self.expand(url) {
+                Ok(expanded) => destination = expanded,
+                Err(e) => warnings.push(LinkWarning::ExpansionFailed(e.to_string())),
+            }
+        }
+
+        warnings.extend(check_url(&destination, &self.blocklist));
+        LinkReport {
+            original: url.clone(),
+            destination,
+            warnings,
+        }
+    }
+
+    /// Follow redirects without fetching any page content
+    fn expand(&self, url: &Url) -> Result<Url> {
+        let mut current = url.clone();
+        for _ in 0..MAX_REDIRECTS {
+            let response = self.agent.head(current.as_str()).call()?;
+            if !(300..400).contains(&response.status()) {
+                return Ok(current);
+            }
+            let location = response
+                .header("location")
+                .ok_or_else(|| anyhow::anyhow!("redirect without a Location header"))?;
+            current = current.join(location)?;
+            if !is_shortener(&current) {
+                return Ok(current);
+            }
+        }
+        Err(anyhow::anyhow!("more than {} redirects", MAX_REDIRECTS))
+    }
+}
+
+/// Hosts from a blocklist file, lowercased; blank lines and `#` comments are skipped
+pub fn parse_blocklist(contents: &str) -> HashSet<String> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim().trim_end_matches('.'))
+        .filter(|line| !line.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
+
+fn is_shortener(url: &Url) -> bool {
+    url.host_str()
+        .map(|host| host.trim_start_matches("www."))
+        .is_some_and(|host| KNOWN_SHORTENERS.contains(&host))
+}
+
+/// Checks that need no network access
+fn check_url(url: &Url, blocklist: &HashSet<String>) -> Vec<LinkWarning> {
+    let mut warnings = Vec::new();
+
+    if !matches!(url.scheme(), "http" | "https") {
+        warnings.push(LinkWarning::UnusualScheme(url.scheme().to_string()));
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        warnings.push(LinkWarning::Credentials);
+    }
+
+    match url.host() {
+        Some(Host::Domain(host)) => {
+            let host = host.to_ascii_lowercase();
+            if host.split('.').any(|label| label.starts_with("xn--")) {
+                let (unicode, _) = idna::domain_to_unicode(&host);
+                for label in unicode.split('.').filter(|label| mixes_scripts(label)) {
+                    warnings.push(LinkWarning::MixedScripts(label.to_string()));
+                }
+                warnings.push(LinkWarning::Punycode { ascii: host.clone(), unicode });
+            }
+            if let Some(blocked) = blocked_domain(&host, blocklist) {
+                warnings.push(LinkWarning::Blocklisted(blocked.to_string()));
+            }
+        }
+        Some(Host::Ipv4(ip)) => warnings.push(ip_warning(IpAddr::V4(ip), blocklist)),
+        Some(Host::Ipv6(ip)) => warnings.push(ip_warning(IpAddr::V6(ip), blocklist)),
+        None => {}
+    }
+    warnings
+}
+
+fn ip_warning(ip: IpAddr, blocklist: &HashSet<String>) -> LinkWarning {
+    if blocklist.contains(&ip.to_string()) {
+        LinkWarning::Blocklisted(ip.to_string())
+    } else {
+        LinkWarning::IpAddress(ip.to_string())
+    }
+}
+
+/// The blocklist entry matching `host` or one of its parent domains
+fn blocked_domain<'a>(host: &'a str, blocklist: &HashSet<String>) -> Option<&'a str> {
+    let mut domain = host;
+    loop {
+        if blocklist.contains(domain) {
+            return Some(domain);
+        }
+        domain = domain.split_once('.')?.1;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+}
+
+fn script_of(c: char) -> Option<Script> {
+    match c as u32 {
+        0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F => Some(Script::Latin),
+        0x370..=0x3FF => Some(Script::Greek),
+        0x400..=0x52F => Some(Script::Cyrillic),
+        _ => None,
+    }
+}
+
+/// Latin letters next to Greek or Cyrillic ones in one label, e.g. "pаypal" with a Cyrillic "а"
+fn mixes_scripts(label: &str) -> bool {
+    let scripts: HashSet<Script> = label.chars().filter_map(script_of).collect();
+    scripts.contains(&Script::Latin) && scripts.len() > 1
+}
+
+/// Open `url` with the desktop's default handler
+pub fn open_in_browser(url: &Url) -> Result<()> {
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = std::process::Command::new("open");
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = std::process::Command::new("xdg-open");
+
+    command.arg(url.as_str()).spawn()?;
+    // Paths and queries may carry tokens; the log only gets where the link goes
+    info!("Opened a {} link to {}", url.scheme(), url.host_str().unwrap_or("no host"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screener(blocklist: &str) -> LinkScreener {
+        let mut screener = LinkScreener::new(LinkSafetyConfig {
+            expand_shorteners: false,
+            ..Default::default()
+        });
+        screener.blocklist = parse_blocklist(blocklist);
+        screener
+    }
+
+    #[test]
+    fn test_homographs_and_blocklist() {
+        let screener = screener("# phishing\nevil.example\n\n");
+        assert!(screener.screen_text("https://example.com/login").unwrap().is_safe());
+
+        // "pаypal.com" with a Cyrillic "а"
+        let report = screener.screen_text("https://p\u{430}ypal.com/").unwrap();
+        assert!(report.destination.host_str().unwrap().starts_with("xn--"));
+        assert!(report.warnings.contains(&LinkWarning::MixedScripts("p\u{430}ypal".to_string())));
+        assert!(!report.is_safe());
+
+        let report = screener.screen_text("https://login.evil.example/").unwrap();
+        assert_eq!(report.warnings, [LinkWarning::Blocklisted("evil.example".to_string())]);
+    }
+
+    #[test]
+    fn test_suspicious_urls() {
+        let screener = screener("");
+        assert!(screener.screen_text("hello world").is_none());
+        assert!(screener.screen_text("mailto:someone@example.com").is_none());
+        assert_eq!(
+            screener.screen_text("javascript:alert(1)").unwrap().warnings,
+            [LinkWarning::UnusualScheme("javascript".to_string())]
+        );
+        assert!(screener
+            .screen_text("https://example.com@192.168.1.1/")
+            .unwrap()
+            .warnings
+            .contains(&LinkWarning::Credentials));
+        assert!(is_shortener(&Url::parse("https://www.bit.ly/abc").unwrap()));
+    }
+}
@@ -0,0 +1,198 @@
+use chrono::{DateTime, TimeZone};
+use pure_rust_locales::{locale_match, Locale};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+/// Set once at startup; see [`init`]
+static CURRENT: OnceLock<LocaleFormat> = OnceLock::new();
+
+/// How dates, times and numbers shown to the user are written
+///
+/// ```toml
+/// [locale]
+/// name = "de_DE"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocaleConfig {
+    /// e.g. "de_DE", "ja-JP" or "fr"; the system locale if unset, and ISO 8601 dates
+    /// with plain numbers for "C" or "POSIX"
+    pub name: Option<String>,
+}
+
+/// Formats for one locale
+///
+/// The C/POSIX locale keeps the ISO 8601 layout the app used before it knew about locales,
+/// which is also what scripts reading its output expect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocaleFormat {
+    locale: Locale,
+}
+
+impl Default for LocaleFormat {
+    fn default() -> Self {
+        Self { locale: Locale::POSIX }
+    }
+}
+
+impl LocaleFormat {
+    /// The locale called `name`, accepting POSIX ("de_DE.UTF-8") and BCP 47 ("de-DE") spellings
+    /// and bare languages; `None` if it isn't known
+    pub fn named(name: &str) -> Option<Self> {
+        parse_locale(name).map(|locale| Self { locale })
+    }
+
+    /// The configured locale, else the system one, else POSIX
+    pub fn from_config(config: &LocaleConfig) -> Self {
+        if let Some(name) = &config.name {
+            match Self::named(name) {
+                Some(format) => return format,
+                None => warn!("Unknown locale '{}' in [locale], using the system locale", name),
+            }
+        }
+        sys_locale::get_locale().and_then(|name| Self::named(&name)).unwrap_or_default()
+    }
+
+    pub fn name(&self) -> String {
+        self.locale.to_string()
+    }
+
+    fn is_posix(&self) -> bool {
+        self.locale == Locale::POSIX
+    }
+
+    /// e.g. "14:05:09" or "02:05:09 PM"
+    pub fn time<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        self.format(at, "%H:%M:%S", "%X")
+    }
+
+    /// e.g. "2024-03-01 14:05:09" or "01.03.2024 14:05:09"
+    pub fn date_time<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        self.format(at, "%Y-%m-%d %H:%M:%S", "%x %X")
+    }
+
+    fn format<Tz: TimeZone>(&self, at: &DateTime<Tz>, posix: &str, localized: &str) -> String
+    where
+        Tz::Offset: Display,
+    {
+        if self.is_posix() {
+            at.format(posix).to_string()
+        } else {
+            at.format_localized(localized, self.locale).to_string()
+        }
+    }
+
+    /// A count with the locale's digit grouping, e.g. "12,345" or "12.345"
+    pub fn number(&self, value: u64) -> String {
+        let separator = locale_match!(self.locale => LC_NUMERIC::THOUSANDS_SEP);
+        let digits = value.to_string();
+        if separator.is_empty() {
+            return digits;
+        }
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3 * separator.len());
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push_str(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
+
+fn parse_locale(name: &str) -> Option<Locale> {
+    // "de_DE.UTF-8@euro" -> "de_DE@euro", "de-DE" -> "de_DE"
+    let (base, modifier) = match name.trim().split_once('@') {
+        Some((base, modifier)) => (base, Some(modifier)),
+        None => (name.trim(), None),
+    };
+    let base = base.split('.').next().unwrap_or(base).replace('-', "_");
+    if base.is_empty() || base.eq_ignore_ascii_case("C") || base.eq_ignore_ascii_case("POSIX") {
+        return Some(Locale::POSIX);
+    }
+
+    let mut parts = base.split('_');
+    let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+    // BCP 47 may put a script between language and region, as in "zh-Hans-CN"
+    let region = parts.rfind(|part| part.len() == 2 || part.chars().all(|c| c.is_ascii_digit()));
+    let region = match region {
+        Some(region) => region.to_ascii_uppercase(),
+        None => default_region(&language),
+    };
+    let candidate = format!("{}_{}", language, region);
+    let with_modifier = modifier.map(|modifier| format!("{}@{}", candidate, modifier));
+    with_modifier
+        .and_then(|name| Locale::try_from(name.as_str()).ok())
+        .or_else(|| Locale::try_from(candidate.as_str()).ok())
+}
+
+/// The region a bare language most likely means
+fn default_region(language: &str) -> String {
+    match language {
+        "en" => "US",
+        "zh" => "CN",
+        "ja" => "JP",
+        "ko" => "KR",
+        "sv" => "SE",
+        "da" => "DK",
+        "cs" => "CZ",
+        "el" => "GR",
+        "uk" => "UA",
+        "he" => "IL",
+        "hi" => "IN",
+        "vi" => "VN",
+        "nb" => "NO",
+        _ => return language.to_ascii_uppercase(),
+    }
+    .to_string()
+}
+
+/// Pick the locale used for the rest of the process; later calls are ignored
+pub fn init(config: &LocaleConfig) {
+    let format = LocaleFormat::from_config(config);
+    if CURRENT.set(format).is_ok() {
+        info!("Formatting dates and numbers for locale {}", format.name());
+    }
+}
+
+/// The locale picked by [`init`], or the system locale if it wasn't called
+pub fn current() -> LocaleFormat {
+    *CURRENT.get_or_init(|| LocaleFormat::from_config(&LocaleConfig::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, TimeZone};
+
+    #[test]
+    fn test_locale_formats() {
+        let at = FixedOffset::east_opt(3600).unwrap().with_ymd_and_hms(2024, 3, 1, 14, 5, 9).unwrap();
+
+        let posix = LocaleFormat::named("C.UTF-8").unwrap();
+        assert_eq!(posix.date_time(&at), "2024-03-01 14:05:09");
+        assert_eq!(posix.number(1234567), "1234567");
+
+        let german = LocaleFormat::named("de-DE").unwrap();
+        assert_eq!(german.name(), "de_DE");
+        assert_eq!(german.date_time(&at), "01.03.2024 14:05:09");
+        assert_eq!(german.number(1234567), "1.234.567");
+
+        let american = LocaleFormat::named("en").unwrap();
+        assert_eq!(american.time(&at), "02:05:09 PM");
+        assert_eq!(american.date_time(&at), "03/01/2024 02:05:09 PM");
+        assert_eq!(american.number(999), "999");
+        assert_eq!(american.number(1000), "1,000");
+
+        assert_eq!(LocaleFormat::named("zh-Hans-CN").unwrap().name(), "zh_CN");
+        assert!(LocaleFormat::named("xx_YY").is_none());
+    }
+}
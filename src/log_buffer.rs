@@ -0,0 +1,85 @@
+use regex::Regex;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Mutex, OnceLock};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Log lines kept for `debug snapshot`
+const CAPACITY: usize = 500;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Log output that goes to standard error as before and is also kept in memory, so a
+/// running instance can hand its recent log to `debug snapshot`
+///
+/// ```ignore
+/// tracing_subscriber::fmt().with_writer(log_buffer::LogTee).init();
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogTee;
+
+impl<'a> MakeWriter<'a> for LogTee {
+    type Writer = LogLine;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogLine::default()
+    }
+}
+
+/// One formatted event, stored when the subscriber is done writing it
+#[derive(Debug, Default)]
+pub struct LogLine {
+    text: Vec<u8>,
+}
+
+impl Write for LogLine {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.text.extend_from_slice(buf);
+        io::stderr().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+impl Drop for LogLine {
+    fn drop(&mut self) {
+        if self.text.is_empty() {
+            return;
+        }
+        let text = String::from_utf8_lossy(&self.text);
+        let Ok(mut recent) = RECENT.lock() else { return };
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            if recent.len() == CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(strip_ansi(line));
+        }
+    }
+}
+
+/// Remove terminal colour codes
+fn strip_ansi(line: &str) -> String {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    let ansi = ANSI.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*m").unwrap());
+    ansi.replace_all(line, "").into_owned()
+}
+
+/// The most recent log lines of this process, oldest first
+pub fn recent() -> Vec<String> {
+    RECENT.lock().map(|recent| recent.iter().cloned().collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_tee() {
+        let mut line = LogTee.make_writer();
+        line.write_all(b"\x1b[32m INFO\x1b[0m clipboard_qr: first\n").unwrap();
+        drop(line);
+        assert!(recent().iter().any(|line| line == " INFO clipboard_qr: first"));
+    }
+}
@@ -1,42 +1,243 @@
 use anyhow::Result;
+use std::process::ExitCode;
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Duration;
-use tracing::{error, info};
+use std::time::Instant;
+use tracing::{error, info, warn};
 use winit::{
     event::WindowEvent,
-    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
 };
 
+mod about;
+mod agent;
+mod autostart;
+mod barcode_generator;
+mod batch;
+#[cfg(feature = "camera")]
+mod camera_scanner;
+mod capacity;
+mod cli;
 mod clipboard_handler;
+mod clipboard_history;
+mod config;
+mod content_classifier;
+mod content_preview;
+mod console_output;
+mod control;
+mod diagnostics;
+mod dpi;
+mod event_bus;
+mod expiry;
+mod file_scan;
+mod generator_pool;
 mod global_state;
+mod history;
+mod kdeconnect;
+mod link_safety;
+mod locale;
+mod log_buffer;
+mod monitor;
+mod normalize;
+mod notification;
+mod notifier;
+mod own_windows;
+mod password_manager;
+mod payload;
+mod pipeline;
+mod popup;
+mod preprocess;
+mod privacy;
+mod qr_chunker;
 mod qr_generator;
 mod qr_scanner;
+mod qr_style;
+mod qr_templates;
+mod prompt;
+mod push;
+mod redaction;
+mod region;
+mod remote;
+mod renderer;
+mod report;
+mod rich_text;
+mod scan_trace;
+mod scan_window;
+mod scheduler;
+mod self_check;
+#[cfg(feature = "server")]
+mod server;
+mod settings;
+mod single_instance;
+mod snapshot;
+mod snippets;
+mod snooze;
+mod stats;
+mod status_window;
+mod thumbnails;
+mod storage;
+mod svg_export;
+mod totp;
+mod transforms;
 mod tray;
+mod tune;
+mod viewing;
+#[cfg(all(feature = "wayland-data-control", unix, not(target_os = "macos")))]
+mod wayland_clipboard;
 mod hide_console;
+mod label;
+mod matrix;
+mod kiosk;
+mod watch;
+mod wifi_form;
 
-use clipboard_handler::ClipboardHandler;
+use clap::Parser;
+use barcode_generator::{Barcode, BarcodeSymbology};
+use cli::{Cli, Command, DebugAction, HistoryAction, Template};
+use clipboard_handler::{image_hash, ClipboardData, ClipboardHandler};
+use clipboard_history::ClipboardHistory;
+use config::AppConfig;
+use content_classifier::SmartAction;
+use control::{ControlRequest, ControlServer, InstanceStatus};
+use diagnostics::{ExitReport, Fatal, FailureContext, FailureKind};
+use console_output::ConsoleThrottle;
 use global_state::GlobalClipboardState;
+use event_bus::{AppEvent, EventBus, PauseSource};
+use expiry::{Expiring, ExpiryLedger, Janitor};
+use history::{HistoryBackend, HistoryKind, HistoryWriter, SharedHistory};
+use kdeconnect::{KdeConnect, PhoneBridge};
+use link_safety::{LinkReport, LinkScreener};
+use monitor::{ClipboardMonitor, MonitorEvent, MonitorHandle, ScanOutcome};
+use notification::NotificationKind;
+use notifier::{Notification, Notifiers};
+use password_manager::{OtpAuth, PasswordManager};
+use payload::PayloadType;
+use pipeline::Pipeline;
+use privacy::SensitiveFilter;
 use qr_generator::QRGenerator;
-use qr_scanner::QRScanner;
-use tray::SystemTray;
-use tray_icon::{menu::MenuEvent, TrayIconEvent};
+use qr_scanner::{QRScanner, ScanSettings, Symbology};
+use qr_style::Color;
+use qr_templates::{WifiNetwork, WifiSecurity};
+use redaction::Redactor;
+use region::{Region, RegionHistory};
+use renderer::SvgRenderer;
+use report::QrReport;
+use scan_trace::ScanTrace;
+use scheduler::Scheduler;
+use self_check::Capabilities;
+use snippets::Snippet;
+use snooze::SnoozeSchedule;
+use stats::SessionStats;
+use std::path::{Path, PathBuf};
+use tray::{RegionRequest, SystemTray, TooltipNotifier};
+use tray_icon::{menu::MenuEvent, MouseButton, MouseButtonState, TrayIconEvent};
 
 use winit::application::ApplicationHandler;
 use winit::window::{Window, WindowId};
 
 use hide_console::hide_console_if_needed;
+use agent::AgentOptions;
+use push::PushOptions;
+use kiosk::KioskOptions;
+use watch::WatchOptions;
+
+/// Newest history entries shown in the tray's History submenu
+const HISTORY_MENU_ENTRIES: usize = 10;
 
 #[derive(Debug)]
 enum UserEvent {
     TrayIconEvent(TrayIconEvent),
     MenuEvent(MenuEvent),
+    /// Ctrl+C or a termination request from the OS
+    Shutdown,
+    /// The monitor gave up on the clipboard
+    ClipboardLost(String),
+    /// Something was published on the event bus
+    Bus(Arc<AppEvent>),
+    /// Entries were added to or removed from the history
+    HistoryChanged,
+    /// Something new was copied into the in-memory clipboard history
+    ClipboardHistoryChanged,
+    /// A screen region was captured
+    RegionsChanged,
+    /// Another launch of the app asked this instance to show itself
+    Activated,
 }
 
-#[derive(Default)]
 struct App {
     window: Option<Window>,
     system_tray: Option<SystemTray>,
+    monitor: Option<MonitorHandle>,
+    snippets: Vec<Snippet>,
+    generator: Arc<QRGenerator>,
+    /// Read for the tray's History submenu
+    history: Option<SharedHistory>,
+    /// Target of the tray's one-time password import
+    password_manager: Option<PasswordManager>,
+    /// Screen regions captured from the tray, for re-scanning
+    regions: Arc<Mutex<RegionHistory>>,
+    /// For QR codes on screen captures
+    scan: ScanSettings,
+    /// Screens links opened from the tray's Actions submenu
+    links: Arc<LinkScreener>,
+    /// For copies from the tray; kept, as X11 and Wayland clipboard content only lasts as
+    /// long as the handler that set it
+    clipboard: Arc<Mutex<ClipboardHandler>>,
+    proxy: EventLoopProxy<UserEvent>,
+    /// The QR popup process, while it may still be open
+    popup: Option<std::process::Child>,
+    /// For the status window, and told about pauses from the tray
+    stats: Arc<SessionStats>,
+    /// Where tray actions such as pausing are published
+    events: EventBus,
+    /// Why the event loop was stopped, if it wasn't the user
+    failure: Option<String>,
+}
+
+impl App {
+    /// Put `text` on the clipboard with the tray's own handler
+    fn copy(&self, text: &str) -> Result<()> {
+        self.clipboard.lock().map_err(|_| anyhow::anyhow!("Clipboard handler poisoned"))?.set_text(text)
+    }
+
+    fn show_status(&self) {
+        if let Err(e) = status_window::open_window(&InstanceStatus::current(&self.stats)) {
+            error!("{}", e);
+        }
+    }
+
+    /// Open the QR popup, or close it if it is already open
+    fn toggle_popup(&mut self) {
+        if self.close_popup() {
+            return;
+        }
+        self.open_popup(None);
+    }
+
+    /// Show the code of `text` in the QR popup, in place of any it shows now
+    fn show_in_popup(&mut self, text: &str) {
+        self.close_popup();
+        self.open_popup(Some(text));
+    }
+
+    fn open_popup(&mut self, text: Option<&str>) {
+        match popup::open_window(text) {
+            Ok(child) => self.popup = Some(child),
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    /// Close the QR popup; returns whether it was open
+    fn close_popup(&mut self) -> bool {
+        let Some(mut popup) = self.popup.take() else {
+            return false;
+        };
+        if !matches!(popup.try_wait(), Ok(None)) {
+            return false;
+        }
+        let _ = popup.kill();
+        let _ = popup.wait();
+        true
+    }
 }
 
 impl ApplicationHandler<UserEvent> for App {
@@ -78,13 +279,148 @@ impl ApplicationHandler<UserEvent> for App {
         match event {
             UserEvent::MenuEvent(menu_event) => {
                 info!("Menu event: {:?}", menu_event);
-                if menu_event.id == self.system_tray.as_ref().unwrap().quit_id {
+                let tray = self.system_tray.as_ref().unwrap();
+                if menu_event.id == tray.quit_id {
                     info!("Quit menu item selected");
                     event_loop.exit();
+                } else if let Some(index) = tray.snippet_index(&menu_event.id.0) {
+                    // Dialogs block, so placeholders are asked for off the event loop
+                    let snippet = self.snippets[index].clone();
+                    let generator = self.generator.clone();
+                    std::thread::spawn(move || use_snippet_from_tray(&snippet, &generator));
+                } else if menu_event.id == tray.show_qr_id {
+                    self.toggle_popup();
+                } else if menu_event.id == tray.status_id {
+                    self.show_status();
+                } else if menu_event.id == tray.about_id {
+                    if let Err(e) = about::open_window() {
+                        error!("{}", e);
+                    }
+                } else if menu_event.id == tray.settings_id {
+                    if let Err(e) = settings::open_window() {
+                        error!("{}", e);
+                    }
+                } else if menu_event.id == tray.wifi_template_id {
+                    if let Err(e) = wifi_form::open_window() {
+                        error!("{}", e);
+                    }
+                } else if menu_event.id == tray.import_otp_id {
+                    if let (Some(manager), Some(content)) = (self.password_manager.clone(), tray.otp_to_import()) {
+                        // The confirmation dialog blocks
+                        std::thread::spawn(move || import_otp_from_tray(&manager, &content));
+                    }
+                } else if let Some(request) = tray.region_request(&menu_event.id.0) {
+                    let regions = self.regions.clone();
+                    let scan = self.scan.clone();
+                    let clipboard = self.clipboard.clone();
+                    let proxy = self.proxy.clone();
+                    // Selecting a region with the mouse and scanning a whole screen both block
+                    std::thread::spawn(move || match scan_screen_from_tray(request, &regions, &scan, &clipboard) {
+                        Ok(true) => {
+                            let _ = proxy.send_event(UserEvent::RegionsChanged);
+                        }
+                        Ok(false) => info!("Region selection cancelled"),
+                        Err(e) => println!("❌ Failed to scan the screen: {}", e),
+                    });
+                } else if let Some(content) = tray.clipboard_history_content(&menu_event.id.0) {
+                    // The console is hidden in tray mode; the popup shows the code instead
+                    self.show_in_popup(&content);
+                } else if let Some(action) = tray.action(&menu_event.id.0).cloned() {
+                    match action.copied_text() {
+                        Some(text) => {
+                            if let Err(e) = self.copy(&text) {
+                                error!("Failed to copy: {}", e);
+                            }
+                        }
+                        // Screening may expand a shortener and ask for confirmation
+                        None => {
+                            let links = self.links.clone();
+                            std::thread::spawn(move || open_from_tray(&action, &links));
+                        }
+                    }
+                } else if let Some(content) = tray.history_content(&menu_event.id.0) {
+                    if let Err(e) = self.copy(content) {
+                        error!("Failed to copy history entry: {}", e);
+                    }
+                } else if tray.select_notification_policy(&menu_event.id.0) || tray.toggle_desktop_notifications(&menu_event.id.0) {
+                    // Applied through the shared clipboard state
+                } else if menu_event.id == tray.pause_id {
+                    let paused = tray.toggle_pause();
+                    if let Some(monitor) = &self.monitor {
+                        monitor.set_paused(paused);
+                    }
+                    self.stats.set_paused(paused);
+                    self.events.publish(AppEvent::MonitoringPaused { paused, source: PauseSource::User });
+                } else if menu_event.id == tray.autostart_id {
+                    let enabled = tray.toggle_autostart();
+                    if let Err(e) = set_autostart(enabled) {
+                        error!("Failed to {} starting at login: {:#}", if enabled { "turn on" } else { "turn off" }, e);
+                        tray.set_autostart(!enabled);
+                    }
+                } else if menu_event.id == tray.monitor_text_id || menu_event.id == tray.monitor_image_id {
+                    let formats = tray.monitored_formats(&menu_event.id.0);
+                    if let Some(monitor) = &self.monitor {
+                        monitor.set_formats(formats);
+                    }
                 }
             }
             UserEvent::TrayIconEvent(tray_event) => {
                 info!("Tray event: {:?}", tray_event);
+                if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = tray_event {
+                    self.toggle_popup();
+                }
+            }
+            UserEvent::Shutdown => {
+                info!("Shutdown requested");
+                event_loop.exit();
+            }
+            UserEvent::ClipboardLost(reason) => {
+                self.failure = Some(reason);
+                event_loop.exit();
+            }
+            UserEvent::HistoryChanged => {
+                let entries = match self.history.as_ref().map(|history| history.lock().map(|store| store.recent(HISTORY_MENU_ENTRIES))) {
+                    Some(Ok(Ok(entries))) => entries,
+                    Some(Ok(Err(e))) => {
+                        error!("Failed to read history: {}", e);
+                        return;
+                    }
+                    _ => return,
+                };
+                let scale = event_loop.primary_monitor().map_or(1.0, |monitor| monitor.scale_factor());
+                if let Some(tray) = self.system_tray.as_mut() {
+                    if let Err(e) = tray.show_history(&entries, scale) {
+                        warn!("Failed to update the History menu: {}", e);
+                    }
+                }
+            }
+            UserEvent::ClipboardHistoryChanged => {
+                if let Some(tray) = self.system_tray.as_mut() {
+                    if let Err(e) = tray.show_clipboard_history() {
+                        warn!("Failed to update the Clipboard History menu: {}", e);
+                    }
+                }
+            }
+            UserEvent::RegionsChanged => {
+                let scale = event_loop.primary_monitor().map_or(1.0, |monitor| monitor.scale_factor());
+                let result = match (self.regions.lock(), self.system_tray.as_mut()) {
+                    (Ok(regions), Some(tray)) => tray.show_regions(regions.regions(), scale),
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    warn!("Failed to update the Recent Regions menu: {}", e);
+                }
+            }
+            UserEvent::Activated => {
+                info!("Another launch asked for this instance");
+                self.show_status();
+            }
+            UserEvent::Bus(event) => {
+                if let Some(tray) = self.system_tray.as_mut() {
+                    if let Err(e) = tray.handle_event(&event) {
+                        error!("Failed to update tray icon: {}", e);
+                    }
+                }
             }
         }
     }
@@ -92,109 +428,1400 @@ impl ApplicationHandler<UserEvent> for App {
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
         event_loop.set_control_flow(ControlFlow::Wait);
         info!("About to wait");
-        if let Some(ref mut tray) = self.system_tray {
-            if let Err(e) = tray.update_icon() {
-                error!("Failed to update tray icon: {}", e);
-            }
-        }
     }
 }
 
 
-fn main() -> Result<()> {
-    hide_console_if_needed();
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if cli.command.is_none() {
+        hide_console_if_needed();
+    }
+    dpi::enable_per_monitor_dpi_awareness();
 
     // Initialize logging
-    tracing_subscriber::fmt::init();
+    tracing_subscriber::fmt().with_writer(log_buffer::LogTee).init();
     info!("Starting Clipboard QR Application");
 
+    let started = Instant::now();
+    let mode = daemon_mode(&cli);
+    let report_path = cli.exit_report.clone().or_else(ExitReport::default_path);
+    let Err(e) = run(cli) else {
+        return ExitCode::SUCCESS;
+    };
+
+    eprintln!("Error: {:?}", e);
+    let kind = FailureKind::of(&e);
+    // Only long-running modes have a supervisor that reads the report
+    if let (Some(mode), Some(path)) = (mode.filter(|_| !storage::in_memory_mode()), report_path) {
+        if let Err(write_error) = ExitReport::new(&e, mode, started.elapsed()).write(&path) {
+            error!("Failed to write exit report to {:?}: {}", path, write_error);
+        }
+    }
+    ExitCode::from(kind.exit_code())
+}
+
+/// The long-running mode `cli` starts, if any
+fn daemon_mode(cli: &Cli) -> Option<&'static str> {
+    match cli.command {
+        Some(Command::Watch { .. }) => Some("watch"),
+        Some(Command::Agent { .. }) => Some("agent"),
+        None if cli.kiosk => Some("kiosk"),
+        None => Some("tray"),
+        Some(_) => None,
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let mut config = AppConfig::load();
+    if cli.silent_console {
+        config.console.silent = true;
+    }
+    if let Some(preset) = cli.style {
+        config.style.preset = preset.name().to_string();
+        config.style.foreground = None;
+        config.style.background = None;
+    }
+    if let Some(shape) = cli.shape {
+        config.style.shape = shape;
+    }
+    if let Some(symbology) = cli.symbology {
+        config.style.symbology = symbology;
+    }
+    if let Some(quiet_zone) = cli.quiet_zone {
+        config.style.quiet_zone = quiet_zone;
+    }
+    if let Some(frame) = cli.frame {
+        config.style.frame = frame;
+    }
+    if let Some(caption) = cli.caption {
+        config.style.caption = Some(caption).filter(|caption| !caption.trim().is_empty());
+    }
+    if let Some(pixels) = cli.module_size {
+        config.style.pixels_per_module = pixels;
+        config.style.target_size = None;
+    }
+    if let Some(size) = cli.target_size {
+        config.style.target_size = Some(size);
+    }
+    if cli.guest || config.guest.enabled {
+        config.apply_guest_mode();
+        info!("Guest mode enabled");
+    }
+    if cli.in_memory || config.privacy.in_memory {
+        config.apply_in_memory_mode();
+    }
+    locale::init(&config.locale);
+    payload::register_configured(&config.payload_types).fatal(FailureKind::Config)?;
+    let redactor = Redactor::new(config.privacy.redact_content);
+    if redactor.is_enabled() {
+        info!("Content redaction enabled");
+    }
+
+    let stats = Arc::new(SessionStats::new());
+    stats.set_guest(config.guest.enabled);
+    let normalization = config.monitor.normalization;
+    let scan = config.scan_settings().fatal(FailureKind::Config)?;
+    let give_up = config.monitor.give_up_after();
+    let snooze = SnoozeSchedule::new(&config.snooze).fatal(FailureKind::Config)?;
+    let sensitive = SensitiveFilter::new(&config.privacy.sensitive).fatal(FailureKind::Config)?;
+    let control_socket = cli.control_socket.clone().unwrap_or_else(control::default_socket_path);
+    let level = config.notifications.level;
+    // A terminal already shows every event, so watch mode only gets configured backends
+    let watch_notifiers = || {
+        let mut notifiers = Notifiers::from_config(&config.notifications.backends(false), None);
+        notifiers.set_quiet(!config.notifications.desktop);
+        notifiers
+    };
+    // For `watch` and for the tray falling back to it
+    let watch_options = |term: bool, show_qr: bool, snooze: Option<SnoozeSchedule>, control: Option<ControlServer>| WatchOptions {
+        term,
+        show_qr,
+        normalization,
+        scan: scan.clone(),
+        give_up,
+        snooze,
+        level,
+        notifiers: watch_notifiers(),
+        phone: PhoneBridge::new(&config.kdeconnect, normalization),
+        control,
+        test_mode: cli.test_mode,
+        generator: config.generator(),
+        poll_interval: config.monitor.poll_interval(),
+        sensitive: sensitive.clone(),
+        copy_decoded: config.monitor.copy_decoded,
+        scheduler: Scheduler::new(&config.tasks, config.generator()).with_normalization(normalization),
+    };
+    let long_running = matches!(cli.command, Some(Command::Watch { .. }) | None);
+    let result = match cli.command {
+        Some(Command::Watch { term, show_qr }) => {
+            let control = ControlServer::bind(control_socket.clone()).map_err(|e| warn!("Control socket unavailable: {}", e)).ok();
+            watch::run(watch_options(term, show_qr, snooze, control), redactor, stats.clone())
+        }
+        Some(Command::Report { text, output }) => run_report(&config, text, output),
+        Some(Command::Svg { text, output, size, foreground, background, title, footer, dialog }) => {
+            run_svg(&config, text, output, SvgRenderer { min_size: size, title, footer }, (foreground, background), dialog)
+        }
+        Some(Command::Batch { input, out_dir, format, csv, text_column, name_column, jobs }) => {
+            run_batch(&config, input, out_dir, format, (csv, text_column, name_column), jobs)
+        }
+        Some(Command::Scan { files, .. }) if files.is_empty() => scan_window::run(scan.clone(), redactor),
+        Some(Command::Scan { files, all }) => run_scan(&files, all, &scan, redactor),
+        Some(Command::Popup { text, text_from_stdin }) => {
+            let text = match text {
+                _ if text_from_stdin => popup::read_text(),
+                Some(text) => Ok(text),
+                None => read_clipboard_text(),
+            };
+            popup::run(config.generator(), text, &sensitive, redactor)
+        }
+        Some(Command::Settings) if config.guest.enabled || storage::in_memory_mode() => {
+            Err(anyhow::anyhow!("Settings can't be saved in guest or in-memory mode"))
+        }
+        // Reloaded, so options given on this command line don't end up in the file
+        Some(Command::Settings) => settings::run(AppConfig::load()),
+        Some(Command::Status { initial_from_stdin }) => {
+            let initial = initial_from_stdin.then(status_window::read_initial_status).transpose()?;
+            status_window::run(control_socket, initial, redactor)
+        }
+        Some(Command::About) => about::run(),
+        Some(Command::Camera { index, list }) => run_camera(index, list, &scan, redactor),
+        Some(Command::Capture { region, last, list, screen, copy }) => run_capture(&config, CaptureOptions { region, last, list, screen, copy }, &scan, redactor),
+        Some(Command::Tune { file }) => run_tune(file, scan.clone(), redactor),
+        Some(Command::Heatmap { file, output }) => run_heatmap(file, output, &scan, redactor),
+        Some(Command::Label { text, template, list, no_caption, rotate, output, print, printer, copies }) => {
+            run_label(&config, LabelOptions { text, template, list, no_caption, rotate, output, print, printer, copies })
+        }
+        Some(Command::Barcode { text, kind, output }) => run_barcode(&config, text, kind, output),
+        Some(Command::History { action }) => run_history(&config, action, redactor),
+        Some(Command::Debug { action }) => run_debug(&config, action, &control_socket),
+        Some(Command::Plan { text, bytes, size, distance }) => run_plan(text, bytes, size, distance),
+        Some(Command::Snippet { name, list, values, output, expire, copy }) => run_snippet(&config, name, list, values, output, expire, copy),
+        Some(Command::Template { template }) => run_template(&config, template),
+        Some(Command::Push { .. }) if config.guest.enabled => {
+            Err(anyhow::anyhow!("Sending to other instances is disabled in guest mode"))
+        }
+        Some(Command::Push { to, token, watch, text }) => {
+            let token = token.or_else(|| config.remote.token.clone());
+            push::run(PushOptions { to, token, watch, text, scan: scan.clone() }, redactor)
+        }
+        Some(Command::ImportOtp { .. }) if config.guest.enabled => {
+            Err(anyhow::anyhow!("Importing into a password manager is disabled in guest mode"))
+        }
+        Some(Command::ImportOtp { uri }) => run_import_otp(&config, uri, &scan),
+        Some(Command::Phone { .. }) if config.guest.enabled => {
+            Err(anyhow::anyhow!("Sending to a phone is disabled in guest mode"))
+        }
+        Some(Command::Phone { list, device, text }) => run_phone(&config, list, device, text, &scan, redactor),
+        Some(Command::Serve { .. }) if config.guest.enabled => {
+            Err(anyhow::anyhow!("The API server is disabled in guest mode"))
+        }
+        Some(Command::Serve { bind, token }) => run_server(&config, bind, token.or_else(|| config.remote.token.clone()), &scan),
+        Some(Command::Agent { .. }) if config.guest.enabled => {
+            Err(anyhow::anyhow!("Agent mode is disabled in guest mode"))
+        }
+        Some(Command::Agent { to, token, name }) => {
+            let token = token.or_else(|| config.remote.token.clone());
+            AgentOptions::new(&config.agent, to, token, name)
+                .and_then(|options| agent::run(AgentOptions { scan: scan.clone(), give_up, ..options }, redactor))
+        }
+        None if cli.kiosk => kiosk::run(KioskOptions {
+            generator: config.generator(),
+            normalization,
+            poll_interval: config.monitor.poll_interval(),
+            scan: scan.clone(),
+            viewing: cli.viewing,
+            history_size: config.monitor.clipboard_history,
+            sensitive: sensitive.clone(),
+        }, redactor),
+        None => {
+            // A second launch only brings up the running instance's status window
+            let Some(mut instance) = single_instance::acquire(&control_socket)? else {
+                println!("Clipboard QR is already running; showing its status window");
+                return Ok(());
+            };
+            if !cli.force_tray {
+                if let Some(problem) = Capabilities::detect().tray_problem() {
+                    warn!("Tray mode unavailable: {}", problem);
+                    warn!("Falling back to `watch --term`; use --force-tray to start the tray anyway");
+                    let watched = watch::run(watch_options(true, true, snooze, instance.take_control()), redactor, stats.clone());
+                    return finish_session(watched, &stats, &config);
+                }
+            }
+            run_tray(&config, scan, snooze, instance.take_control(), cli.test_mode, redactor, stats.clone())
+        }
+    };
+
+    if long_running {
+        finish_session(result, &stats, &config)
+    } else {
+        result
+    }
+}
+
+/// Log what a long-running session did, so it's clear the daemon actually worked
+fn finish_session(result: Result<()>, stats: &SessionStats, config: &AppConfig) -> Result<()> {
+    let summary = stats.summary();
+    info!("Session summary: {}", summary);
+    if config.notifications.goodbye && config.notifications.level != notification::NotificationPolicy::Silent {
+        println!("👋 Clipboard QR session ended: {}", summary);
+    }
+    result
+}
+
+fn read_clipboard_text() -> Result<String> {
+    let mut handler = ClipboardHandler::builder().spawn_listener(false).build();
+    match handler.get_data()? {
+        ClipboardData::Text(text) => Ok(text),
+        ClipboardData::Excluded => Err(anyhow::anyhow!("Clipboard content is marked private by its source")),
+        _ => Err(anyhow::anyhow!("Clipboard does not contain text")),
+    }
+}
+
+fn run_report(config: &AppConfig, text: Option<String>, output: Option<PathBuf>) -> Result<()> {
+    let text = match text {
+        Some(text) => text,
+        None => read_clipboard_text()?,
+    };
+    let generator = config.generator();
+    let report = QrReport::new(&generator, &text)?;
+
+    let explicit = output.is_some();
+    let path = output.unwrap_or_else(|| {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        PathBuf::from("output").join(format!("qr_report_{:x}.md", hasher.finish()))
+    });
+    if !explicit {
+        storage::check_write("the report", &path)?;
+    }
+    report.save_markdown(&path)?;
+    println!("✅ Report written to {}", path.display());
+    Ok(())
+}
+
+fn run_svg(
+    config: &AppConfig,
+    text: Option<String>,
+    output: Option<PathBuf>,
+    svg: SvgRenderer,
+    (foreground, background): (Option<Color>, Option<Color>),
+    dialog: bool,
+) -> Result<()> {
+    let text = match text {
+        Some(text) => text,
+        None => read_clipboard_text()?,
+    };
+    let mut generator = config.generator();
+    if foreground.is_some() || background.is_some() {
+        let style = (generator.style().foreground, generator.style().background);
+        generator = generator.with_colors(foreground.unwrap_or(style.0), background.unwrap_or(style.1))?;
+    }
+    if dialog {
+        return svg_export::run(generator, text, svg);
+    }
+
+    let svg = generator.generate_svg_with(&text, &svg)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, svg)?;
+            println!("✅ SVG written to {}", path.display());
+        }
+        None => println!("{}", svg),
+    }
+    Ok(())
+}
+
+fn run_batch(config: &AppConfig, input: PathBuf, out_dir: PathBuf, format: String, csv: (bool, String, String), jobs: Option<usize>) -> Result<()> {
+    use std::io::Read;
+
+    let generator = config.generator();
+    if generator.renderers().get(&format).is_none() {
+        return Err(anyhow::anyhow!("Unknown output format '{}', expected one of: {}", format, generator.renderers().names().join(", ")));
+    }
+    let (csv, text_column, name_column) = csv;
+    let content = if input.as_os_str() == "-" {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        content
+    } else {
+        std::fs::read_to_string(&input).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", input.display(), e))?
+    };
+    let is_csv = csv || input.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    let items = if is_csv { batch::parse_csv(&content, &text_column, &name_column)? } else { batch::parse_lines(&content) };
+    if items.is_empty() {
+        return Err(anyhow::anyhow!("Nothing to generate in {}", input.display()));
+    }
+
+    let config = config.clone();
+    let pool = generator_pool::GeneratorPool::with_generator(jobs.unwrap_or_else(generator_pool::GeneratorPool::default_size), move || config.generator());
+    let started = Instant::now();
+    let report = batch::run(&pool, &items, &out_dir, &format, |done, total| {
+        eprint!("\r⏳ {}/{}", done, total);
+        if done == total {
+            eprintln!();
+        }
+    })?;
+
+    for (line, error) in &report.failed {
+        eprintln!("❌ Line {}: {}", line, error);
+    }
+    println!(
+        "✅ Wrote {} QR code(s) to {} in {:.1}s",
+        report.written.len(),
+        out_dir.display(),
+        started.elapsed().as_secs_f32()
+    );
+    if !report.failed.is_empty() {
+        return Err(anyhow::anyhow!("{} of {} codes failed", report.failed.len(), items.len()));
+    }
+    Ok(())
+}
+
+fn run_plan(text: Option<String>, bytes: Option<usize>, size: Option<f32>, distance: Option<f32>) -> Result<()> {
+    let payload = match (text, bytes) {
+        (_, Some(bytes)) => capacity::Payload::Bytes(bytes),
+        (Some(text), None) => capacity::Payload::Text(text),
+        (None, None) => capacity::Payload::Text(read_clipboard_text()?),
+    };
+    let plan = capacity::plan(capacity::PlanInput {
+        payload,
+        print_size_mm: size,
+        distance_mm: distance,
+    })?;
+    println!("{}", plan);
+    if size.is_none() && distance.is_none() {
+        println!("Add --size and --distance to check a print against a scanning distance");
+    }
+    Ok(())
+}
+
+fn run_tune(file: Option<PathBuf>, scan: ScanSettings, redactor: Redactor) -> Result<()> {
+    tune::run(read_image(file)?, scan, redactor)
+}
+
+fn run_heatmap(file: Option<PathBuf>, output: Option<PathBuf>, scan: &ScanSettings, redactor: Redactor) -> Result<()> {
+    let output = match output {
+        Some(output) => output,
+        None => {
+            let output = PathBuf::from("scan-heatmap.png");
+            storage::check_write("the heatmap", &output)?;
+            output
+        }
+    };
+    let image = read_image(file)?;
+    // Trace what the scanner actually sees, after compositing transparency
+    let image = qr_scanner::composite_over(&image, scan.matte);
+    let trace = ScanTrace::run(&image);
+    trace.save(&image, &output)?;
+
+    println!("{}", trace);
+    match trace.decoded() {
+        Some(content) => println!("✅ Decoded: {}", totp::console_text(content, &redactor)),
+        None => println!("❌ No candidate decoded"),
+    }
+    println!("Annotated image saved to {}", output.display());
+    println!("  blue: dark after thresholding, where finder patterns were searched for");
+    println!("  orange: finder pattern centres; green/red: candidate regions that decoded/failed");
+    Ok(())
+}
+
+/// An image file, or the image on the clipboard
+fn read_image(file: Option<PathBuf>) -> Result<image::RgbaImage> {
+    match file {
+        Some(path) => Ok(image::open(&path)?.to_rgba8()),
+        None => match ClipboardHandler::builder().spawn_listener(false).build().get_data()? {
+            ClipboardData::Image(image) => Ok(image),
+            ClipboardData::Files(files) if file_scan::single_image(&files).is_some() => qr_scanner::load_image(&files[0]),
+            _ => Err(anyhow::anyhow!("No image on the clipboard; copy one or pass a file")),
+        },
+    }
+}
+
+struct LabelOptions {
+    text: Option<String>,
+    template: Option<String>,
+    list: bool,
+    no_caption: bool,
+    rotate: Option<u16>,
+    output: Option<PathBuf>,
+    print: bool,
+    printer: Option<String>,
+    copies: u32,
+}
+
+fn run_label(config: &AppConfig, options: LabelOptions) -> Result<()> {
+    if options.list {
+        let mut templates = config.label.templates.clone();
+        templates.extend(label::LabelTemplate::builtins());
+        for template in templates {
+            println!(
+                "{:<18} {:>6.1} x {:<6.1} mm  {} dpi  rotation {}",
+                template.name, template.width_mm, template.height_mm, template.dpi, template.rotation
+            );
+        }
+        return Ok(());
+    }
+
+    let name = options.template.as_deref().unwrap_or(&config.label.template);
+    let mut template = label::LabelTemplate::find(name, &config.label.templates)
+        .ok_or_else(|| anyhow::anyhow!("Unknown label template '{}', see `label --list`", name))?;
+    if options.no_caption {
+        template.caption = false;
+    }
+    if let Some(rotation) = options.rotate {
+        template.rotation = rotation;
+    }
+
+    let text = match options.text {
+        Some(text) => text,
+        None => read_clipboard_text()?.trim().to_string(),
+    };
+
+    let explicit = options.output.is_some();
+    let path = options.output.unwrap_or_else(|| {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let dir = if options.print { std::env::temp_dir() } else { config.output.dir.clone() };
+        dir.join(format!("label_{:x}.png", hasher.finish()))
+    });
+    if !explicit {
+        storage::check_write("the label", &path)?;
+    }
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    template.save(&text, &path)?;
+
+    if options.print {
+        let printer = options.printer.or_else(|| config.label.printer.clone());
+        label::print_file(&path, printer.as_deref(), options.copies)?;
+        println!("✅ Sent {} label to {}", template.name, printer.as_deref().unwrap_or("the default printer"));
+    } else {
+        println!("✅ Label written to {}", path.display());
+    }
+    Ok(())
+}
+
+fn run_barcode(config: &AppConfig, text: Option<String>, kind: BarcodeSymbology, output: Option<PathBuf>) -> Result<()> {
+    let text = match text {
+        Some(text) => text,
+        None => read_clipboard_text()?.trim().to_string(),
+    };
+    let barcode = Barcode::encode(kind, &text)?;
+
+    let explicit = output.is_some();
+    let path = output.unwrap_or_else(|| {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let mut hasher = DefaultHasher::new();
+        (kind.name(), &text).hash(&mut hasher);
+        config.output.dir.join(format!("barcode_{:x}.png", hasher.finish()))
+    });
+    if !explicit {
+        storage::check_write("the barcode", &path)?;
+    }
+    let bytes = barcode.render_for_path(&path, config.generator().style())?;
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    std::fs::write(&path, bytes)?;
+    println!("✅ {} barcode written to {}", kind.label(), path.display());
+    Ok(())
+}
+
+fn run_history(config: &AppConfig, action: HistoryAction, redactor: Redactor) -> Result<()> {
+    let store = config.history.open()?;
+    match action {
+        HistoryAction::List { limit } => {
+            for entry in store.recent(limit)? {
+                println!(
+                    "{:>6}  {}  {:<7}  {}",
+                    entry.id,
+                    locale::current().date_time(&entry.created_at),
+                    entry.kind.as_str(),
+                    redactor.text_truncated(&entry.content, 60)
+                );
+            }
+        }
+        HistoryAction::Export { ids, since, output, copy } => {
+            let since = since.as_deref().map(scheduler::parse_interval).transpose()?;
+            let entries = history::select_entries(store.as_ref(), config.history.max_entries, &ids, since)?;
+            if entries.is_empty() {
+                return Err(anyhow::anyhow!("No history entries to export"));
+            }
+
+            let generator = config.generator();
+            let copied = match entries.as_slice() {
+                [entry] if copy => Some(entry),
+                _ if copy => return Err(anyhow::anyhow!("--copy takes a single entry, got {}", entries.len())),
+                _ => None,
+            };
+            if let Some(output) = output {
+                let is_pdf = output.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+                if is_pdf {
+                    let texts: Vec<&str> = entries.iter().map(|entry| entry.content.as_str()).collect();
+                    generator.save_pdf_document(&texts, &output)?;
+                } else {
+                    for entry in &entries {
+                        generator.save_qr_image_to(&entry.content, &output.join(format!("history_{}.png", entry.id)))?;
+                    }
+                }
+                println!("✅ Exported {} QR code(s) to {}", entries.len(), output.display());
+            }
+            // Last, as it may wait until something else is copied
+            if let Some(entry) = copied {
+                let image = generator.generate_rgba(&entry.content)?;
+                copy_before_exit(ClipboardData::Image(image), &format!("QR code of entry {}", entry.id))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_snippet(
+    config: &AppConfig,
+    name: Option<String>,
+    list: bool,
+    values: Vec<(String, String)>,
+    output: Option<PathBuf>,
+    expire: Option<String>,
+    copy: bool,
+) -> Result<()> {
+    if list {
+        if config.snippets.is_empty() {
+            println!("No snippets configured; add [[snippets]] entries to the config file");
+        }
+        for snippet in &config.snippets {
+            println!("{:<18} {}", snippet.name, snippet.placeholders().join(", "));
+        }
+        return Ok(());
+    }
+
+    let name = name.unwrap_or_default();
+    let snippet = Snippet::find(&config.snippets, &name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown snippet '{}', see `snippet --list`", name))?;
+    let text = snippet
+        .fill(|placeholder, default| match values.iter().find(|(name, _)| name == placeholder) {
+            Some((_, value)) => Some(value.clone()),
+            None => prompt::terminal(placeholder, default),
+        })?
+        .ok_or_else(|| anyhow::anyhow!("Cancelled"))?;
+
+    let generator = config.generator();
+    match output {
+        Some(path) => {
+            let expire = expire
+                .or_else(|| snippet.expire.clone())
+                .map(|expire| scheduler::parse_interval(&expire))
+                .transpose()?;
+            if let (Some(_), Some(ledger)) = (expire, ExpiryLedger::default_path()) {
+                // The tray app couldn't learn about the file, so don't leave it behind unnoticed
+                storage::check_write("the expiry ledger", &ledger)?;
+            }
+            generator.save_qr_image_to(&text, &path)?;
+            println!("✅ QR code written to {}", path.display());
+            if let Some(after) = expire {
+                let expiring = Expiring::after(format!("Snippet '{}' export", snippet.name), after).with_file(&path);
+                let expires_at = expiring.expires_at;
+                ExpiryLedger::open_default().add(expiring)?;
+                println!("🕒 Will be deleted at {} by the tray app", locale::current().time(&expires_at));
+            }
+        }
+        None if copy => {}
+        None => {
+            println!("{}", text);
+            generator.print_qr_terminal(&text)?;
+        }
+    }
+    // Last, as it may wait until something else is copied
+    if copy {
+        copy_before_exit(ClipboardData::Image(generator.generate_rgba(&text)?), "QR code")?;
+    }
+    Ok(())
+}
+
+fn run_template(config: &AppConfig, template: Template) -> Result<()> {
+    let Template::Wifi { ssid, password, security, hidden, output, copy } = template;
+    let Some(ssid) = ssid else {
+        return wifi_form::run(config.generator());
+    };
+    let password = match (password, security) {
+        (_, WifiSecurity::None) => String::new(),
+        (Some(password), _) => password,
+        (None, _) => prompt::terminal("Password", None).ok_or_else(|| anyhow::anyhow!("Cancelled"))?,
+    };
+    let network = WifiNetwork { hidden, ..WifiNetwork::new(ssid, security, password) };
+    network.validate()?;
+    let text = network.payload();
+
+    let generator = config.generator();
+    match output {
+        Some(path) => {
+            generator.save_qr_image_to(&text, &path)?;
+            println!("✅ QR code written to {}", path.display());
+        }
+        None if copy => {}
+        None => {
+            println!("{}", text);
+            generator.print_qr_terminal(&text)?;
+        }
+    }
+    // Last, as it may wait until something else is copied
+    if copy {
+        copy_before_exit(ClipboardData::Image(generator.generate_rgba(&text)?), "QR code")?;
+    }
+    Ok(())
+}
+
+fn run_debug(config: &AppConfig, action: DebugAction, socket: &Path) -> Result<()> {
+    let (request, done) = match action {
+        DebugAction::Snapshot { output, include_content } => return run_snapshot(config, socket, output, include_content),
+        DebugAction::Ping => (ControlRequest::Ping, "Instance is running".to_string()),
+        DebugAction::InjectText { text } => (ControlRequest::InjectText { text }, "Injected text".to_string()),
+        DebugAction::InjectImage { file } => {
+            // The instance opens the file itself, possibly from another working directory
+            let path = std::path::absolute(&file)?;
+            let done = format!("Injected {}", file.display());
+            (ControlRequest::InjectImage { path }, done)
+        }
+    };
+    control::send(socket, &request)?;
+    println!("✅ {}", done);
+    Ok(())
+}
+
+fn run_snapshot(config: &AppConfig, socket: &Path, output: Option<PathBuf>, include_content: bool) -> Result<()> {
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("clipboard-qr-snapshot-{}.zip", chrono::Local::now().format("%Y%m%d-%H%M%S"))));
+    let mut snapshot = snapshot::Snapshot::new(include_content);
+    snapshot.add_system(config);
+    snapshot.add_config(AppConfig::config_path().as_deref());
+    let status = control::send(socket, &ControlRequest::Status).and_then(|data| {
+        let data = data.ok_or_else(|| anyhow::anyhow!("The instance is too old to report its status"))?;
+        Ok(serde_json::from_value::<InstanceStatus>(data)?)
+    });
+    if let Err(e) = &status {
+        warn!("No statistics or log from a running instance: {:#}", e);
+    }
+    snapshot.add_instance(status, log_buffer::recent());
+    snapshot.add_exit_report(ExitReport::default_path().as_deref());
+    if include_content {
+        match ClipboardHandler::builder().spawn_listener(false).build().get_data()? {
+            ClipboardData::Text(text) => snapshot.add("clipboard.txt", text),
+            _ => warn!("No text on the clipboard to include"),
+        }
+    }
+    snapshot.write(&output)?;
+    println!("✅ Wrote {} ({})", output.display(), snapshot.names().collect::<Vec<_>>().join(", "));
+    if include_content {
+        println!("⚠️ It contains your clipboard text; check it before sharing");
+    }
+    Ok(())
+}
+
+fn run_phone(config: &AppConfig, list: bool, device: Option<String>, text: Option<String>, scan: &ScanSettings, redactor: Redactor) -> Result<()> {
+    if list {
+        let devices = KdeConnect::devices()?;
+        if devices.is_empty() {
+            println!("No reachable devices are paired with KDE Connect");
+        }
+        for device in devices {
+            println!("{:<20} {}", device.id, device.name);
+        }
+        return Ok(());
+    }
+
+    let phone = KdeConnect::connect(device.or_else(|| config.kdeconnect.device.clone()).as_deref())?;
+    let text = match text {
+        Some(text) => text,
+        None => push::read_clipboard(scan)?.text,
+    };
+    phone.send_clipboard(&text)?;
+    println!("✅ Sent {} to {}", redactor.text(&text), phone.device().name);
+    Ok(())
+}
+
+fn run_import_otp(config: &AppConfig, uri: Option<String>, scan: &ScanSettings) -> Result<()> {
+    let manager = PasswordManager::new(&config.password_manager)?
+        .ok_or_else(|| anyhow::anyhow!("No password manager configured; set [password_manager] kind in the config file"))?;
+    let uri = match uri {
+        Some(uri) => uri,
+        None => push::read_clipboard(scan)?.text,
+    };
+    let otp = OtpAuth::parse(&uri)?;
+    let command = manager.prepare(&otp);
+    let question = format!(
+        "Import the one-time password for {} into {} as '{}'?",
+        otp.describe(),
+        manager.name(),
+        command.name
+    );
+    if !prompt::terminal_confirm(&question) {
+        println!("Cancelled");
+        return Ok(());
+    }
+    manager.import(&otp)?;
+    println!("✅ Imported {} into {}", otp.describe(), manager.name());
+    Ok(())
+}
+
+/// Open what a smart action from the tray points to; links are screened first, like decoded ones
+fn open_from_tray(action: &SmartAction, links: &LinkScreener) {
+    let result = match action {
+        SmartAction::OpenUrl(url) => {
+            let report = links.screen(url);
+            if !report.is_safe() {
+                let warnings: Vec<String> = report.warnings.iter().map(ToString::to_string).collect();
+                let question = format!("{}\n\n{}\n\nOpen it anyway?", report.destination, warnings.join("\n"));
+                if !prompt::confirm("Suspicious link", &question) {
+                    info!("Opening a suspicious link cancelled");
+                    return;
+                }
+            }
+            link_safety::open_in_browser(&report.destination)
+        }
+        action => action.open(),
+    };
+    if let Err(e) = result {
+        println!("❌ {} failed: {}", action.label(), e);
+    }
+}
+
+/// Confirm and import a one-time password chosen from the tray menu
+fn import_otp_from_tray(manager: &PasswordManager, content: &str) {
+    let otp = match OtpAuth::parse(content) {
+        Ok(otp) => otp,
+        Err(e) => {
+            println!("❌ Can't import: {}", e);
+            return;
+        }
+    };
+    let command = manager.prepare(&otp);
+    let question = format!(
+        "Import the one-time password for {} into {} as '{}'?",
+        otp.describe(),
+        manager.name(),
+        command.name
+    );
+    if !prompt::confirm("Import one-time password", &question) {
+        info!("One-time password import cancelled");
+        return;
+    }
+    match manager.import(&otp) {
+        Ok(_) => println!("🔑 Imported {} into {}", otp.describe(), manager.name()),
+        Err(e) => println!("❌ Failed to import {}: {}", otp.describe(), e),
+    }
+}
+
+#[cfg(feature = "camera")]
+fn run_camera(index: u32, list: bool, scan: &ScanSettings, redactor: Redactor) -> Result<()> {
+    if list {
+        let cameras = camera_scanner::list()?;
+        if cameras.is_empty() {
+            println!("No cameras found");
+        }
+        for (index, name) in cameras {
+            println!("{:>3}  {}", index, name);
+        }
+        return Ok(());
+    }
+    camera_scanner::run(index, scan.clone(), redactor)
+}
+
+#[cfg(not(feature = "camera"))]
+fn run_camera(_index: u32, _list: bool, _scan: &ScanSettings, _redactor: Redactor) -> Result<()> {
+    Err(anyhow::anyhow!("This build has no camera support; rebuild with `cargo build --features camera`"))
+}
+
+#[cfg(feature = "server")]
+fn run_server(config: &AppConfig, bind: std::net::SocketAddr, token: Option<String>, scan: &ScanSettings) -> Result<()> {
+    let workers = generator_pool::GeneratorPool::default_size();
+    let generator_config = config.clone();
+    let pool = generator_pool::GeneratorPool::with_generator(workers, move || generator_config.generator());
+    let sensitive = SensitiveFilter::new(&config.privacy.sensitive).fatal(FailureKind::Config)?;
+    server::run(server::ServerOptions { bind, token, workers, scan: scan.clone(), sensitive }, pool)
+}
+
+#[cfg(not(feature = "server"))]
+fn run_server(_config: &AppConfig, _bind: std::net::SocketAddr, _token: Option<String>, _scan: &ScanSettings) -> Result<()> {
+    Err(anyhow::anyhow!("This build has no API server; rebuild with `cargo build --features server`"))
+}
+
+struct CaptureOptions {
+    region: Option<Region>,
+    last: bool,
+    list: bool,
+    screen: bool,
+    copy: bool,
+}
+
+fn run_capture(config: &AppConfig, options: CaptureOptions, scan: &ScanSettings, redactor: Redactor) -> Result<()> {
+    let mut regions = RegionHistory::open_default(!config.guest.enabled);
+    if options.list {
+        if regions.regions().is_empty() {
+            println!("No screen regions captured yet");
+        }
+        for captured in regions.regions() {
+            println!("{}  {}", locale::current().date_time(&captured.captured_at), captured.region);
+        }
+        return Ok(());
+    }
+
+    let (image, label) = if options.screen {
+        (region::capture_screen()?, "Screen".to_string())
+    } else {
+        let region = match (options.region, options.last) {
+            (Some(region), _) => region,
+            (None, true) => regions
+                .last()
+                .ok_or_else(|| anyhow::anyhow!("No screen region captured yet; run `capture` without --last first"))?,
+            (None, false) => match region::select()? {
+                Some(region) => region,
+                None => {
+                    println!("Cancelled");
+                    return Ok(());
+                }
+            },
+        };
+        let image = region::capture(region)?;
+        if let Err(e) = regions.remember(region, &image) {
+            warn!("Failed to remember screen region {}: {}", region, e);
+        }
+        (image, region.to_string())
+    };
+    let Some(content) = scan.scanner().scan_qr_from_rgba(&image)? else {
+        println!("❌ {}: no QR code found", label);
+        return Ok(());
+    };
+    println!("✅ {}: {}", label, totp::console_text(&content, &redactor));
+    if options.copy {
+        copy_before_exit(ClipboardData::Text(content), "Decoded content")?;
+    }
+    Ok(())
+}
+
+/// Put `data`, described as `what`, on the clipboard for a command that exits afterwards;
+/// on X11 and Wayland the content goes with the process, so it waits until something else
+/// is copied
+fn copy_before_exit(data: ClipboardData, what: &str) -> Result<()> {
+    let mut handler = ClipboardHandler::builder().spawn_listener(false).keep_until_replaced(true).build();
+    let waits = cfg!(all(unix, not(target_os = "macos")));
+    if waits {
+        println!("📋 {} copied to the clipboard; keeping it there until something else is copied", what);
+    }
+    match data {
+        ClipboardData::Image(image) => handler.set_image(&image)?,
+        ClipboardData::Text(text) => handler.set_text(&text)?,
+        _ => return Err(anyhow::anyhow!("Only text and images can be copied")),
+    }
+    if !waits {
+        println!("📋 {} copied to the clipboard", what);
+    }
+    Ok(())
+}
+
+/// Capture the screen or a region of it as chosen from the tray, remembering regions, and
+/// put the QR code found on it on the clipboard with `clipboard`, where the monitor reports it
+/// like any copied text; false if the user cancelled the selection
+fn scan_screen_from_tray(request: RegionRequest, regions: &Mutex<RegionHistory>, scan: &ScanSettings, clipboard: &Mutex<ClipboardHandler>) -> Result<bool> {
+    let region = match request {
+        RegionRequest::Screen => None,
+        RegionRequest::Capture(region) => Some(region),
+        RegionRequest::Select => match region::select()? {
+            Some(region) => Some(region),
+            None => return Ok(false),
+        },
+    };
+    let image = match region {
+        Some(region) => {
+            let image = region::capture(region)?;
+            if let Ok(mut regions) = regions.lock() {
+                if let Err(e) = regions.remember(region, &image) {
+                    warn!("Failed to remember screen region {}: {}", region, e);
+                }
+            }
+            image
+        }
+        None => region::capture_screen()?,
+    };
+    match scan.scanner().scan_qr_from_rgba(&image)? {
+        Some(content) => clipboard.lock().map_err(|_| anyhow::anyhow!("Clipboard handler poisoned"))?.set_text(&content)?,
+        None => println!("\n❌ No QR code found on the screen"),
+    }
+    Ok(true)
+}
+
+/// Fill a snippet chosen from the tray menu with dialogs and show its QR code
+fn use_snippet_from_tray(snippet: &Snippet, generator: &QRGenerator) {
+    let title = format!("Snippet: {}", snippet.name);
+    let text = match snippet.fill(|placeholder, default| prompt::dialog(&title, placeholder, default)) {
+        Ok(Some(text)) => text,
+        Ok(None) => {
+            info!("Snippet '{}' cancelled", snippet.name);
+            return;
+        }
+        Err(e) => {
+            error!("Failed to fill snippet '{}': {}", snippet.name, e);
+            return;
+        }
+    };
+
+    println!("\n📋 Snippet '{}':", snippet.name);
+    if let Err(e) = generator.print_qr_terminal(&text) {
+        println!("❌ Failed to generate QR code: {}", e);
+    }
+}
+
+/// Start at login or stop, as toggled from the tray, and keep `[startup] autostart` in step
+/// so the next start doesn't undo it
+fn set_autostart(enabled: bool) -> Result<()> {
+    autostart::set(enabled)?;
+    let mut config = AppConfig::load();
+    config.startup.autostart = enabled;
+    if let Err(e) = config.save() {
+        if let Err(undo) = autostart::set(!enabled) {
+            warn!("Failed to restore the login entry: {:#}", undo);
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn run_scan(files: &[PathBuf], all: bool, scan: &ScanSettings, redactor: Redactor) -> Result<()> {
+    let scanner = scan.scanner();
+    let mut failed = false;
+
+    for path in files {
+        if all {
+            failed |= !print_all_codes(&scanner, path, &redactor);
+            continue;
+        }
+        if file_scan::is_gif(path) {
+            failed |= !print_gif_codes(&scanner, path, &redactor);
+            continue;
+        }
+
+        let results = match file_scan::scan_file(&scanner, path) {
+            Ok(results) => results,
+            Err(e) => {
+                println!("❌ {}: {}", path.display(), e);
+                failed = true;
+                continue;
+            }
+        };
+
+        for result in &results {
+            let label = if results.len() > 1 {
+                format!("{} page {}", path.display(), result.page)
+            } else {
+                path.display().to_string()
+            };
+            match &result.outcome {
+                ScanOutcome::Decoded(content) => {
+                    println!("✅ {}: {}", label, totp::console_text(content, &redactor));
+                    qr_templates::print_wifi(content, &redactor);
+                }
+                ScanOutcome::NotFound | ScanOutcome::Skipped => println!("❌ {}: no QR code found", label),
+                ScanOutcome::Failed(e) => println!("❌ {}: {}", label, e),
+            }
+        }
+    }
+
+    if failed {
+        return Err(anyhow::anyhow!("Some files could not be scanned"));
+    }
+    Ok(())
+}
+
+/// Print every code in every page of `path` with where it is; false if the file couldn't be read
+fn print_all_codes(scanner: &QRScanner, path: &Path, redactor: &Redactor) -> bool {
+    let results = match file_scan::scan_file_all(scanner, path) {
+        Ok(results) => results,
+        Err(e) => {
+            println!("❌ {}: {}", path.display(), e);
+            return false;
+        }
+    };
+    let pages = results.len();
+    let unit = if file_scan::is_gif(path) { "frame" } else { "page" };
+    for (page, codes) in results {
+        let label = if pages > 1 {
+            format!("{} {} {}", path.display(), unit, page)
+        } else {
+            path.display().to_string()
+        };
+        match codes {
+            Ok(codes) if codes.is_empty() => println!("❌ {}: no QR code found", label),
+            Ok(codes) => {
+                for code in codes {
+                    let bounds = code.bounds;
+                    let kind = match code.symbology {
+                        Symbology::QrCode => String::new(),
+                        symbology => format!("{} ", symbology.label()),
+                    };
+                    println!("✅ {}: {} ({}at {}x{}+{}+{})", label, totp::console_text(&code.content, redactor), kind, bounds.width, bounds.height, bounds.x, bounds.y);
+                    qr_templates::print_wifi(&code.content, redactor);
+                }
+            }
+            Err(e) => println!("❌ {}: {}", label, e),
+        }
+    }
+    true
+}
+
+/// Print the distinct codes in the frames of an animated GIF; false if it couldn't be read
+fn print_gif_codes(scanner: &QRScanner, path: &Path, redactor: &Redactor) -> bool {
+    let codes = match scanner.scan_qr_from_gif(path) {
+        Ok(codes) => codes,
+        Err(e) => {
+            println!("❌ {}: {}", path.display(), e);
+            return false;
+        }
+    };
+    if codes.is_empty() {
+        println!("❌ {}: no QR code found", path.display());
+    }
+    for content in codes {
+        println!("✅ {}: {}", path.display(), totp::console_text(&content, redactor));
+        qr_templates::print_wifi(&content, redactor);
+    }
+    true
+}
+
+/// Show where a decoded link really goes and anything suspicious about it
+fn print_link_report(report: &LinkReport, redactor: &Redactor) {
+    if report.destination != report.original {
+        println!("🔗 Destination: {}", redactor.text(report.destination.as_str()));
+    }
+    for warning in &report.warnings {
+        let icon = if warning.is_informational() { "ℹ️ " } else { "⚠️ " };
+        println!("{} {}", icon, warning);
+    }
+    if !report.is_safe() {
+        println!("⚠️  Check this link carefully before opening it");
+    }
+}
+
+fn run_tray(config: &AppConfig, scan: ScanSettings, snooze: Option<SnoozeSchedule>, control: Option<ControlServer>, test_mode: bool, redactor: Redactor, stats: Arc<SessionStats>) -> Result<()> {
     // Create event loop with user events
-    let event_loop = EventLoop::<UserEvent>::with_user_event().build()?;
+    let event_loop = EventLoop::<UserEvent>::with_user_event().build().fatal(FailureKind::EventLoop)?;
     event_loop.set_control_flow(ControlFlow::Wait);
     info!("Event loop created successfully");
 
     // Create global clipboard state
-    let clipboard_state = Arc::new(Mutex::new(GlobalClipboardState::new()));
+    let mut initial_state = GlobalClipboardState::new();
+    initial_state.notification_policy = config.notifications.level;
+    initial_state.desktop_notifications = config.notifications.desktop;
+    initial_state.clipboard_history = ClipboardHistory::new(config.monitor.clipboard_history);
+    let clipboard_state = Arc::new(Mutex::new(initial_state));
     let clipboard_state_clone = clipboard_state.clone();
+    // Each event wakes the event loop, so the tray follows changes as they happen
+    let events = EventBus::new();
+    let bus_proxy = event_loop.create_proxy();
+    let _forwarder = events.forward(move |event| {
+        let _ = bus_proxy.send_event(UserEvent::Bus(event));
+    });
+
+    // Shared with the janitor, which wipes expired entries
+    let history = match config.history.open() {
+        Ok(store) => Some(Arc::new(Mutex::new(store))),
+        Err(e) => {
+            error!("Failed to open history store: {}", e);
+            None
+        }
+    };
+    let sensitive_after = config.expiry.sensitive_after().fatal(FailureKind::Config)?;
+    let in_memory_history = config.history.backend == HistoryBackend::Memory;
+    let ledger = ExpiryLedger::open_default();
+    if !ledger.is_empty() {
+        info!("{} pending expiries", ledger.len());
+    }
+    let ledger = Arc::new(Mutex::new(ledger));
+    let mut janitor = Janitor::new(ledger.clone());
+    if let Some(history) = &history {
+        let proxy = event_loop.create_proxy();
+        janitor = janitor.with_history(history.clone(), move || {
+            let _ = proxy.send_event(UserEvent::HistoryChanged);
+        });
+    }
+    let _janitor = janitor.start();
 
     // Start background clipboard monitoring thread
-    let _background_thread = thread::spawn(move || {
-        let qr_generator = QRGenerator::new();
-        let qr_scanner = QRScanner::new();
-        let mut clipboard_handler = ClipboardHandler::new();
-        info!("Background clipboard monitoring thread started");
-
-        loop {
-            // Check for clipboard changes
-            match clipboard_handler.get_data_if_changed() {
-                Ok(Some(new_data)) => {
-                    // Update global state
-                    if let Ok(mut state) = clipboard_state_clone.lock() {
-                        state.last_data = Some(new_data.clone());
-                        state.has_changed = true;
+    let config_policy = config.notifications.level;
+    let copy_decoded = config.monitor.copy_decoded;
+    let mut console = ConsoleThrottle::new(&config.console);
+    let formats = config.monitor.formats();
+    let normalization = config.monitor.normalization;
+    let links = Arc::new(LinkScreener::new(config.links.clone()));
+    let tray_links = links.clone();
+    let pipeline = Pipeline::new(config.pipeline.clone());
+    // Consecutive copies of the same content are recorded once
+    let mut last_recorded: Option<(HistoryKind, u64)> = None;
+    let desktop = Capabilities::detect().display.is_some();
+    let mut notifiers = Notifiers::from_config(
+        &config.notifications.backends(desktop),
+        Some(Box::new(TooltipNotifier::new(events.clone()))),
+    );
+    let mut phone = PhoneBridge::new(&config.kdeconnect, normalization);
+    let proxy = event_loop.create_proxy();
+    let snooze_events = events.clone();
+    let monitor_events = events.clone();
+    let history_proxy = event_loop.create_proxy();
+    let clipboard_history_proxy = event_loop.create_proxy();
+    // Sensitive entries are scheduled for expiry once stored
+    let history_writer = history.clone().map(|store| {
+        let ledger = ledger.clone();
+        HistoryWriter::start(store, move |entry| {
+            let _ = history_proxy.send_event(UserEvent::HistoryChanged);
+            let payload = PayloadType::detect(&entry.content);
+            if let (true, Some(after)) = (payload.is_sensitive(), sensitive_after) {
+                let expiring = Expiring::after(format!("{} history entry {}", payload.label(), entry.id), after)
+                    .with_history_id(entry.id, in_memory_history);
+                if let Ok(mut ledger) = ledger.lock() {
+                    if let Err(e) = ledger.add(expiring) {
+                        error!("Failed to schedule history entry {} for expiry: {}", entry.id, e);
                     }
+                }
+            }
+        })
+    });
+    let control_stats = stats.clone();
+    let snooze_stats = stats.clone();
+    let app_stats = stats.clone();
+    let tray_scan = scan.clone();
+    let monitor = ClipboardMonitor::new()
+        .with_handler(ClipboardHandler::builder().formats(formats).normalization(normalization).poll_interval(config.monitor.poll_interval()))
+        .with_scanner(move || scan.scanner())
+        .with_generator(config.generator())
+        .with_pipeline(pipeline.clone())
+        .with_sensitive_filter(SensitiveFilter::new(&config.privacy.sensitive).fatal(FailureKind::Config)?)
+        .give_up_after(config.monitor.give_up_after(), move |reason| {
+            let _ = proxy.send_event(UserEvent::ClipboardLost(reason));
+        })
+        .with_snooze(snooze, move |snoozed| {
+            snooze_stats.set_snoozed(snoozed);
+            snooze_events.publish(AppEvent::MonitoringPaused { paused: snoozed, source: PauseSource::Schedule });
+        })
+        .start(move |event, context| {
+        stats.record(event);
+        let steps = pipeline.steps_for(event);
+
+        // Publish the change before notifying; notifications, the tooltip included, only
+        // follow events the notification policy allows
+        let kind = NotificationKind::of(event);
+        let mut policy = config_policy;
+        if let Ok(mut state) = clipboard_state_clone.lock() {
+            policy = state.notification_policy;
+            notifiers.set_quiet(!state.desktop_notifications);
+            if let Some(data) = event.clipboard_data() {
+                if state.set_data(data, normalization) {
                     info!("Clipboard data updated in background thread");
+                }
+            }
+            if steps.history && state.clipboard_history.record(event) {
+                let _ = clipboard_history_proxy.send_event(UserEvent::ClipboardHistoryChanged);
+            }
+        }
+        monitor_events.publish_monitor(event);
+        if policy.allows(kind) && steps.notify {
+            notifiers.dispatch(&copy_decoded.apply(Notification::of(event, &redactor)));
+        }
+        let copied = match copy_decoded.to_copy(event).map(|content| context.handler.set_text(content)) {
+            Some(Ok(())) => {
+                info!("Copied decoded QR content to the clipboard");
+                true
+            }
+            Some(Err(e)) => {
+                error!("Failed to copy decoded QR content: {}", e);
+                false
+            }
+            None => false,
+        };
 
-                    match &new_data {
-                        crate::clipboard_handler::ClipboardData::Text(text) => {
-                            println!("\n🔄 Clipboard text updated: {}", text);
-                            println!("QR Code:");
-                            if let Err(e) = qr_generator.print_qr_terminal(&text) {
-                                println!("❌ Failed to generate QR code: {}", e);
+        let recorded = match event {
+            _ if !steps.history => None,
+            MonitorEvent::Text(text) => Some((HistoryKind::Text, text)),
+            MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } => Some((HistoryKind::QrScan, content)),
+            _ => None,
+        };
+        if let (Some(writer), Some((kind, content))) = (&history_writer, recorded) {
+            let key = (kind, normalization.key(content));
+            if last_recorded != Some(key) {
+                match writer.add(kind, content) {
+                    Ok(()) => last_recorded = Some(key),
+                    Err(e) => error!("Failed to record history: {}", e),
+                }
+            }
+        }
+        if let Some(phone) = phone.as_mut() {
+            phone.offer(event);
+        }
+
+        if !policy.allows(kind) || !(steps.notify || steps.generate) {
+            return;
+        }
+
+        match event {
+            MonitorEvent::Text(text) => {
+                if !console.should_print(text) {
+                    return;
+                }
+                if steps.notify {
+                    println!("\n🔄 Clipboard text updated: {}", redactor.text(text));
+                }
+                if steps.generate {
+                    println!("QR Code:");
+                    match context.generator.print_qr_terminal(text) {
+                        Ok(()) => stats.record_generated(),
+                        Err(e) => println!("❌ Failed to generate QR code: {}", e),
+                    }
+                }
+            }
+            MonitorEvent::Image { image, scan } => {
+                let key = match scan {
+                    ScanOutcome::Decoded(content) => content.clone(),
+                    // Images of the same size aren't the same image
+                    _ => format!("image {:x}", image_hash(image)),
+                };
+                if !console.should_print(&key) {
+                    return;
+                }
+
+                if steps.notify {
+                    println!(
+                        "\n🔄 Clipboard image updated ({}x{})",
+                        image.width(),
+                        image.height()
+                    );
+                }
+
+                match scan {
+                    ScanOutcome::Decoded(content) => {
+                        if steps.notify {
+                            println!("✅ QR code detected in clipboard image!");
+                            println!("Content: {}", totp::console_text(content, &redactor));
+                            if copied {
+                                println!("📋 Copied to the clipboard");
                             }
-                        }
-                        crate::clipboard_handler::ClipboardData::Image(image) => {
-                            println!(
-                                "\n🔄 Clipboard image updated ({}x{})",
-                                image.width(),
-                                image.height()
-                            );
-                            println!("Scanning for QR codes...");
-
-                            match qr_scanner.scan_qr_from_rgba(&image) {
-                                Ok(Some(content)) => {
-                                    println!("✅ QR code detected in clipboard image!");
-                                    println!("Content: {}", content);
-
-                                    // Also display QR code for the detected content
-                                    println!("QR Code for detected content:");
-                                    if let Err(e) = qr_generator.print_qr_terminal(&content) {
-                                        println!("❌ Failed to generate QR code: {}", e);
+                            qr_templates::print_wifi(content, &redactor);
+                            if let Some(report) = links.screen_text(content) {
+                                print_link_report(&report, &redactor);
+                                if links.config().auto_open && report.is_safe() {
+                                    if let Err(e) = link_safety::open_in_browser(&report.destination) {
+                                        println!("❌ Failed to open link: {}", e);
                                     }
                                 }
-                                Ok(None) => {
-                                    println!("❌ No QR code found in clipboard image");
-                                }
-                                Err(e) => {
-                                    println!("❌ Error scanning QR code: {}", e);
-                                }
                             }
                         }
-                        crate::clipboard_handler::ClipboardData::Empty => {
-                            println!("\n🔄 Clipboard cleared");
+
+                        // Also display QR code for the detected content
+                        if steps.generate {
+                            println!("QR Code for detected content:");
+                            match context.generator.print_qr_terminal(content) {
+                                Ok(()) => stats.record_generated(),
+                                Err(e) => println!("❌ Failed to generate QR code: {}", e),
+                            }
                         }
                     }
+                    ScanOutcome::NotFound if steps.notify => {
+                        println!("❌ No QR code found in clipboard image");
+                    }
+                    ScanOutcome::Failed(e) if steps.notify => {
+                        println!("❌ Error scanning QR code: {}", e);
+                    }
+                    _ => {}
                 }
-                Ok(None) => {
-                    // No change, continue monitoring
+            }
+            MonitorEvent::Files(files) => {
+                let files = file_scan::describe_files(files);
+                if steps.notify && console.should_print(&files) {
+                    println!("\n📁 Files copied: {}", redactor.text(&files));
                 }
-                Err(e) => {
-                    error!("Error checking clipboard: {}", e);
+            }
+            MonitorEvent::Cleared => {
+                if console.should_print("") {
+                    println!("\n🔄 Clipboard cleared");
+                }
+            }
+            MonitorEvent::Excluded => {
+                if console.should_print("excluded") {
+                    println!("\n🔒 Clipboard content marked private by its source; ignored");
+                }
+            }
+            MonitorEvent::Sensitive(rule) => {
+                if console.should_print(rule) {
+                    println!("\n🔒 Sensitive content skipped ({})", rule);
+                }
+            }
+            MonitorEvent::Error(e) => {
+                if console.should_print(e) {
+                    println!("\n❌ Failed to read clipboard: {}", e);
+                }
+            }
+            MonitorEvent::ListenerRestarted(reason) => {
+                if console.should_print(reason) {
+                    println!("\n⚠️ Clipboard listener restarted: {}", reason);
                 }
             }
-
-            // Sleep to avoid excessive CPU usage
-            thread::sleep(Duration::from_millis(100));
         }
     });
 
-    let system_tray = Some(SystemTray::new(clipboard_state.clone()).unwrap());
+    let scheduler = Scheduler::new(&config.tasks, config.generator())
+        .with_normalization(config.monitor.normalization);
+    let _scheduler = (!scheduler.is_empty()).then(|| scheduler.start());
+    let activate_proxy = event_loop.create_proxy();
+    let activate = move || {
+        let _ = activate_proxy.send_event(UserEvent::Activated);
+    };
+    if let Err(e) = single_instance::on_activate(activate.clone()) {
+        warn!("Other launches can't bring up this instance: {}", e);
+    }
+    let _control = control.and_then(|server| {
+        control::serve_monitor(server, test_mode, monitor.injector(), control_stats, Some(Box::new(activate)))
+            .map_err(|e| warn!("Control socket unavailable: {}", e))
+            .ok()
+    });
+
+    let password_manager = PasswordManager::new(&config.password_manager).fatal(FailureKind::Config)?;
+    // The login entry follows the config, and points at this executable if it moved
+    if !config.guest.enabled && !storage::in_memory_mode() {
+        if let Err(e) = autostart::set(config.startup.autostart) {
+            warn!("Failed to update the login entry: {:#}", e);
+        }
+    }
+    let autostart = autostart::is_enabled().unwrap_or(config.startup.autostart);
+    let system_tray = Some(
+        SystemTray::new(
+            clipboard_state.clone(),
+            redactor,
+            formats,
+            config.guest.enabled,
+            &config.snippets,
+            password_manager.is_some(),
+            autostart,
+        )
+        .fatal(FailureKind::EventLoop)?,
+    );
     // Set up tray event handlers
     let proxy = event_loop.create_proxy();
     TrayIconEvent::set_event_handler(Some(move |event| {
         let _ = proxy.send_event(UserEvent::TrayIconEvent(event));
     }));
     let proxy = event_loop.create_proxy();
+    if let Err(e) = ctrlc::set_handler(move || {
+        let _ = proxy.send_event(UserEvent::Shutdown);
+    }) {
+        warn!("Failed to install Ctrl+C handler: {}", e);
+    }
+    let proxy = event_loop.create_proxy();
     MenuEvent::set_event_handler(Some(move |event| {
         let _ = proxy.send_event(UserEvent::MenuEvent(event));
     }));
@@ -202,10 +1829,30 @@ fn main() -> Result<()> {
     let mut app = App {
         window: None,
         system_tray,
+        monitor: Some(monitor),
+        snippets: config.snippets.clone(),
+        generator: Arc::new(config.generator()),
+        history,
+        password_manager,
+        regions: Arc::new(Mutex::new(RegionHistory::open_default(!config.guest.enabled))),
+        scan: tray_scan,
+        links: tray_links,
+        clipboard: Arc::new(Mutex::new(ClipboardHandler::builder().spawn_listener(false).build())),
+        proxy: event_loop.create_proxy(),
+        popup: None,
+        stats: app_stats,
+        events,
+        failure: None,
     };
+    // Fill the History and Recent Regions submenus from earlier sessions
+    let _ = event_loop.create_proxy().send_event(UserEvent::HistoryChanged);
+    let _ = event_loop.create_proxy().send_event(UserEvent::RegionsChanged);
 
-    event_loop.run_app(&mut app)?;
+    event_loop.run_app(&mut app).fatal(FailureKind::EventLoop)?;
     info!("Hidden window created for event loop");
 
-    Ok(())
+    match app.failure {
+        Some(reason) => Err(Fatal::new(FailureKind::ClipboardUnavailable, format!("Clipboard unavailable: {}", reason)).into()),
+        None => Ok(()),
+    }
 }
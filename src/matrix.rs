@@ -0,0 +1,119 @@
+use qrcode::{Color as ModuleColor, EcLevel, QrCode, Version};
+
+/// Light modules the QR specification requires around a code
+pub const QUIET_ZONE: usize = 4;
+
+/// The module grid of an encoded code, for rendering to targets this crate has no
+/// renderer for: e-ink panels, LED matrices, game engines
+///
+/// Coordinates are in modules with the origin at the top-left of the code itself;
+/// the quiet zone isn't stored but is reported so callers can leave room for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitMatrix {
+    width: usize,
+    /// Row by row, `true` for dark modules
+    modules: Vec<bool>,
+    version: Version,
+    error_correction: EcLevel,
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+impl BitMatrix {
+    pub fn from_code(code: &QrCode) -> Self {
+        Self {
+            width: code.width(),
+            modules: code.to_colors().into_iter().map(|color| color == ModuleColor::Dark).collect(),
+            version: code.version(),
+            error_correction: code.error_correction_level(),
+        }
+    }
+
+    /// Modules per side, without the quiet zone
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Light modules to leave on each side
+    pub fn quiet_zone(&self) -> usize {
+        QUIET_ZONE
+    }
+
+    /// Modules per side including the quiet zone on both sides
+    pub fn padded_width(&self) -> usize {
+        self.width + 2 * QUIET_ZONE
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn error_correction(&self) -> EcLevel {
+        self.error_correction
+    }
+
+    /// Whether the module at `(x, y)` is dark; `false` outside the code
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.width && self.modules[y * self.width + x]
+    }
+
+    /// Like [`Self::get`], with `(0, 0)` at the outer corner of the quiet zone
+    pub fn get_padded(&self, x: usize, y: usize) -> bool {
+        match (x.checked_sub(QUIET_ZONE), y.checked_sub(QUIET_ZONE)) {
+            (Some(x), Some(y)) => self.get(x, y),
+            _ => false,
+        }
+    }
+
+    /// The code row by row, without the quiet zone
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> {
+        self.modules.chunks(self.width)
+    }
+
+    /// Every module including the quiet zone, row by row
+    pub fn to_padded_vec(&self) -> Vec<bool> {
+        let size = self.padded_width();
+        (0..size * size).map(|index| self.get_padded(index % size, index / size)).collect()
+    }
+
+    /// Rows packed eight modules to a byte, most significant bit first, each row padded
+    /// to a whole byte; the layout most monochrome displays and LED drivers expect
+    pub fn to_packed_rows(&self) -> Vec<u8> {
+        let stride = self.width.div_ceil(8);
+        let mut packed = vec![0u8; stride * self.width];
+        for (y, row) in self.rows().enumerate() {
+            for (x, _) in row.iter().enumerate().filter(|(_, dark)| **dark) {
+                packed[y * stride + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        packed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bit_matrix() {
+        let code = QrCode::with_error_correction_level("hello", EcLevel::M).unwrap();
+        let matrix = BitMatrix::from_code(&code);
+        assert_eq!(matrix.width(), 21);
+        assert_eq!(matrix.padded_width(), matrix.width() + 2 * matrix.quiet_zone());
+        assert_eq!(matrix.version(), Version::Normal(1));
+        assert_eq!(matrix.error_correction(), EcLevel::M);
+
+        // Finder pattern corner is dark, its separator light, and the quiet zone light
+        assert!(matrix.get(0, 0));
+        assert!(!matrix.get(7, 0));
+        assert!(matrix.get_padded(4, 4));
+        assert!(!matrix.get_padded(3, 4));
+        assert!(!matrix.get(21, 0));
+        assert_eq!(matrix.to_padded_vec().iter().filter(|dark| **dark).count(), matrix.rows().flatten().filter(|dark| **dark).count());
+
+        // 21 modules fit in three bytes per row; the top row starts with seven dark finder modules
+        let packed = matrix.to_packed_rows();
+        assert_eq!(packed.len(), 3 * 21);
+        assert_eq!(packed[0] & 0xFE, 0xFE);
+        assert_eq!(packed[2] & 0x07, 0);
+    }
+}
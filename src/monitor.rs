@@ -0,0 +1,417 @@
+use chrono::Local;
+use image::{ImageBuffer, Rgba};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::clipboard_handler::{ClipboardData, ClipboardFormats, ClipboardHandler, ClipboardHandlerBuilder};
+use crate::file_scan;
+use crate::pipeline::Pipeline;
+use crate::privacy::SensitiveFilter;
+use crate::qr_generator::QRGenerator;
+use crate::qr_scanner::{self, QRScanner};
+use crate::snooze::SnoozeSchedule;
+
+/// Result of scanning a clipboard image for a QR code
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanOutcome {
+    Decoded(String),
+    NotFound,
+    Failed(String),
+    /// The pipeline has no scan step for images
+    Skipped,
+}
+
+/// Something the monitor observed on the clipboard
+#[derive(Debug, Clone)]
+pub enum MonitorEvent {
+    Text(String),
+    Image {
+        image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+        scan: ScanOutcome,
+    },
+    /// Files were copied that aren't a single image to scan
+    Files(Vec<PathBuf>),
+    Cleared,
+    /// The clipboard holds content its source excluded from monitoring
+    Excluded,
+    /// The clipboard text, or the content of a QR code in the clipboard image, matches the
+    /// named sensitive-content rule; like excluded content it is neither shown nor encoded
+    Sensitive(String),
+    /// Reading the clipboard failed
+    Error(String),
+    /// The platform clipboard listener got stuck and was restarted, for the given reason
+    ListenerRestarted(String),
+}
+
+impl MonitorEvent {
+    /// The clipboard content behind this event, if any
+    pub fn clipboard_data(&self) -> Option<ClipboardData> {
+        match self {
+            MonitorEvent::Text(text) => Some(ClipboardData::Text(text.clone())),
+            MonitorEvent::Image { image, .. } => Some(ClipboardData::Image(image.clone())),
+            MonitorEvent::Files(files) => Some(ClipboardData::Files(files.clone())),
+            MonitorEvent::Cleared => Some(ClipboardData::Empty),
+            MonitorEvent::Excluded | MonitorEvent::Sensitive(_) => Some(ClipboardData::Excluded),
+            MonitorEvent::Error(_) | MonitorEvent::ListenerRestarted(_) => None,
+        }
+    }
+
+    /// Copied text, or the content of a QR code decoded from a copied image
+    pub fn content(&self) -> Option<&str> {
+        match self {
+            MonitorEvent::Text(text) => Some(text),
+            MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } => Some(content),
+            _ => None,
+        }
+    }
+}
+
+/// Resources available to the event callback on the monitor thread
+pub struct MonitorContext<'a> {
+    pub generator: &'a QRGenerator,
+    pub handler: &'a mut ClipboardHandler,
+}
+
+/// How often a snoozed monitor checks whether the snooze is over
+const SNOOZE_TICK: Duration = Duration::from_secs(1);
+
+type ScannerFactory = Box<dyn Fn() -> QRScanner + Send>;
+type FailureHandler = Box<dyn FnOnce(String) + Send>;
+type SnoozeHandler = Box<dyn FnMut(bool) + Send>;
+
+/// Watches the clipboard on a background thread, scans images for QR codes
+/// and reports every change to a callback
+pub struct ClipboardMonitor {
+    handler: ClipboardHandlerBuilder,
+    scanner: ScannerFactory,
+    generator: QRGenerator,
+    pipeline: Pipeline,
+    sensitive: SensitiveFilter,
+    give_up: Option<(Duration, FailureHandler)>,
+    snooze: Option<(SnoozeSchedule, SnoozeHandler)>,
+}
+
+impl Default for ClipboardMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClipboardMonitor {
+    pub fn new() -> Self {
+        Self {
+            handler: ClipboardHandler::builder(),
+            scanner: Box::new(QRScanner::new),
+            generator: QRGenerator::new(),
+            pipeline: Pipeline::default(),
+            sensitive: SensitiveFilter::default(),
+            give_up: None,
+            snooze: None,
+        }
+    }
+
+    /// Options for the ClipboardHandler created on the monitor thread
+    pub fn with_handler(mut self, handler: ClipboardHandlerBuilder) -> Self {
+        self.handler = handler;
+        self
+    }
+
+    /// The scanner is built on the monitor thread, so it is supplied as a factory
+    pub fn with_scanner(mut self, factory: impl Fn() -> QRScanner + Send + 'static) -> Self {
+        self.scanner = Box::new(factory);
+        self
+    }
+
+    pub fn with_generator(mut self, generator: QRGenerator) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    /// Decides whether images are scanned
+    pub fn with_pipeline(mut self, pipeline: Pipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Report text matching `filter` as [`MonitorEvent::Sensitive`] instead of its content
+    pub fn with_sensitive_filter(mut self, filter: SensitiveFilter) -> Self {
+        self.sensitive = filter;
+        self
+    }
+
+    /// Stop monitoring once the clipboard has been unreadable for `after` without a break,
+    /// calling `handler` with the last error; with `None` the monitor retries forever
+    pub fn give_up_after(mut self, after: Option<Duration>, handler: impl FnOnce(String) + Send + 'static) -> Self {
+        self.give_up = after.map(|after| (after, Box::new(handler) as FailureHandler));
+        self
+    }
+
+    /// Stop reading the clipboard while `schedule` says so, calling `on_change` with `true`
+    /// when a snooze starts and `false` when it ends; with `None` the monitor never snoozes
+    ///
+    /// Content copied during a snooze is never reported, not even once it ends.
+    pub fn with_snooze(mut self, schedule: Option<SnoozeSchedule>, on_change: impl FnMut(bool) + Send + 'static) -> Self {
+        self.snooze = schedule.map(|schedule| (schedule, Box::new(on_change) as SnoozeHandler));
+        self
+    }
+
+    /// Start monitoring on a new thread
+    pub fn start<F>(self, mut callback: F) -> MonitorHandle
+    where
+        F: FnMut(&MonitorEvent, &mut MonitorContext) + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        let formats = Arc::new(Mutex::new(None));
+        let thread_formats = formats.clone();
+        let paused = Arc::new(AtomicBool::new(false));
+        let thread_paused = paused.clone();
+        let (injector, injected): (Sender<ClipboardData>, Receiver<ClipboardData>) = mpsc::channel();
+
+        let thread = thread::spawn(move || {
+            let scanner = (self.scanner)();
+            let mut handler = self.handler.build();
+            let mut give_up = self.give_up;
+            let mut failing_since: Option<Instant> = None;
+            let mut snooze = self.snooze;
+            let mut snoozed = false;
+            let mut was_paused = false;
+            info!("Clipboard monitoring started");
+
+            while thread_running.load(Ordering::Relaxed) {
+                if let Some(formats) = thread_formats.lock().ok().and_then(|mut formats| formats.take()) {
+                    handler.set_formats(formats);
+                }
+
+                let now_paused = thread_paused.load(Ordering::Relaxed);
+                if now_paused != was_paused {
+                    was_paused = now_paused;
+                    if now_paused {
+                        info!("Clipboard monitoring paused");
+                    } else {
+                        // Swallow whatever was copied while paused
+                        let _ = handler.get_data_if_changed();
+                        info!("Clipboard monitoring resumed");
+                    }
+                }
+                if now_paused {
+                    thread::sleep(handler.poll_interval());
+                    continue;
+                }
+
+                if let Some((schedule, on_change)) = &mut snooze {
+                    let now_snoozed = schedule.is_snoozed(Local::now());
+                    if now_snoozed != snoozed {
+                        snoozed = now_snoozed;
+                        if snoozed {
+                            info!("Clipboard monitoring snoozed by schedule");
+                        } else {
+                            // Swallow whatever was copied while snoozed
+                            let _ = handler.get_data_if_changed();
+                            info!("Clipboard monitoring resumed");
+                        }
+                        on_change(snoozed);
+                    }
+                    if snoozed {
+                        thread::sleep(handler.poll_interval().max(SNOOZE_TICK));
+                        continue;
+                    }
+                }
+
+                // Synthetic content goes through exactly the same path as real copies
+                let data = match injected.try_recv() {
+                    Ok(data) => {
+                        info!("Handling injected clipboard content");
+                        Ok(Some(data))
+                    }
+                    Err(_) => handler.get_data_if_changed(),
+                };
+                if let Some(reason) = handler.take_listener_restart() {
+                    let mut context = MonitorContext {
+                        generator: &self.generator,
+                        handler: &mut handler,
+                    };
+                    callback(&MonitorEvent::ListenerRestarted(reason), &mut context);
+                }
+                let event = match data {
+                    Ok(Some(data)) => Some(Self::to_event(data, &scanner, &self.pipeline, &self.sensitive)),
+                    Ok(None) => None,
+                    Err(e) => {
+                        error!("Error checking clipboard: {}", e);
+                        Some(MonitorEvent::Error(e.to_string()))
+                    }
+                };
+
+                match &event {
+                    Some(MonitorEvent::Error(_)) => {
+                        failing_since.get_or_insert_with(Instant::now);
+                    }
+                    _ => failing_since = None,
+                }
+
+                if let Some(event) = &event {
+                    let mut context = MonitorContext {
+                        generator: &self.generator,
+                        handler: &mut handler,
+                    };
+                    callback(event, &mut context);
+                }
+
+                if let (Some(since), Some(MonitorEvent::Error(reason))) = (failing_since, &event) {
+                    if give_up.as_ref().is_some_and(|(after, _)| since.elapsed() >= *after) {
+                        error!("Clipboard unavailable for {:?}; giving up", since.elapsed());
+                        if let Some((_, on_failure)) = give_up.take() {
+                            on_failure(reason.clone());
+                        }
+                        break;
+                    }
+                }
+
+                // Sleep to avoid excessive CPU usage
+                thread::sleep(handler.poll_interval());
+            }
+
+            info!("Clipboard monitoring stopped");
+        });
+
+        MonitorHandle {
+            running,
+            formats,
+            paused,
+            injector,
+            thread: Some(thread),
+        }
+    }
+
+    fn to_event(data: ClipboardData, scanner: &QRScanner, pipeline: &Pipeline, sensitive: &SensitiveFilter) -> MonitorEvent {
+        let event = Self::read_event(data, scanner, pipeline);
+        match event.content().and_then(|content| sensitive.check(content)) {
+            Some(rule) => {
+                info!("Skipped sensitive clipboard content ({})", rule);
+                MonitorEvent::Sensitive(rule.to_string())
+            }
+            None => event,
+        }
+    }
+
+    fn read_event(data: ClipboardData, scanner: &QRScanner, pipeline: &Pipeline) -> MonitorEvent {
+        match data {
+            ClipboardData::Text(text) => MonitorEvent::Text(text),
+            ClipboardData::Image(image) => Self::image_event(image, scanner, pipeline),
+            // A single copied image file is scanned as if the image itself had been copied
+            ClipboardData::Files(files) => match file_scan::single_image(&files).map(qr_scanner::load_image) {
+                Some(Ok(image)) => Self::image_event(image, scanner, pipeline),
+                Some(Err(e)) => {
+                    warn!("Failed to load the copied image {:?}: {}", files[0], e);
+                    MonitorEvent::Files(files)
+                }
+                None => MonitorEvent::Files(files),
+            },
+            ClipboardData::Empty => MonitorEvent::Cleared,
+            ClipboardData::Excluded => MonitorEvent::Excluded,
+        }
+    }
+
+    fn image_event(image: ImageBuffer<Rgba<u8>, Vec<u8>>, scanner: &QRScanner, pipeline: &Pipeline) -> MonitorEvent {
+        if !pipeline.steps_for_image().scan {
+            return MonitorEvent::Image { image, scan: ScanOutcome::Skipped };
+        }
+        let scan = match scanner.scan_code_from_rgba(&image) {
+            Ok(Some((content, symbology))) => {
+                info!("Decoded a {} from the clipboard image", symbology.label());
+                ScanOutcome::Decoded(content)
+            }
+            Ok(None) => ScanOutcome::NotFound,
+            Err(e) => ScanOutcome::Failed(e.to_string()),
+        };
+        MonitorEvent::Image { image, scan }
+    }
+}
+
+/// Controls a running [`ClipboardMonitor`]; monitoring stops when this is dropped
+pub struct MonitorHandle {
+    running: Arc<AtomicBool>,
+    /// Format change requested by the owner, applied on the next poll
+    formats: Arc<Mutex<Option<ClipboardFormats>>>,
+    paused: Arc<AtomicBool>,
+    injector: Sender<ClipboardData>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl MonitorHandle {
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn is_running(&self) -> bool {
+        self.thread.as_ref().is_some_and(|thread| !thread.is_finished())
+    }
+
+    /// Restrict monitoring to the given clipboard formats
+    pub fn set_formats(&self, formats: ClipboardFormats) {
+        if let Ok(mut pending) = self.formats.lock() {
+            *pending = Some(formats);
+        }
+    }
+
+    /// Stop reading the clipboard until resumed, e.g. while copying passwords; content
+    /// copied in the meantime is never reported, as with a snooze
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Feeds content to the monitor as if it had been copied, for tests and demos;
+    /// it is picked up on the next poll, ahead of the real clipboard
+    pub fn injector(&self) -> Sender<ClipboardData> {
+        self.injector.clone()
+    }
+
+    /// Ask the monitor thread to stop and wait for it
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    /// Block until the monitor thread exits
+    pub fn join(mut self) {
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("Clipboard monitor thread panicked");
+            }
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("Clipboard monitor thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for MonitorHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monitor_start_stop() {
+        let handle = ClipboardMonitor::new()
+            .with_handler(ClipboardHandler::builder().spawn_listener(false))
+            .start(|_, _| {});
+        assert!(handle.is_running());
+        handle.stop();
+    }
+}
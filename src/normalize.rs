@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// How text is canonicalized before it is compared, hashed or used as a key
+///
+/// Each profile includes the ones before it: `nfc` also trims, and
+/// `collapse-whitespace` also trims and NFC-normalizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NormalizationProfile {
+    /// Compare text byte for byte
+    #[default]
+    Raw,
+    /// Ignore leading and trailing whitespace
+    Trim,
+    /// Also treat composed and decomposed Unicode (e.g. "é" vs "e\u{301}") as equal
+    Nfc,
+    /// Also treat any run of whitespace, including line breaks, as a single space
+    CollapseWhitespace,
+}
+
+impl NormalizationProfile {
+    pub const ALL: [NormalizationProfile; 4] = [
+        NormalizationProfile::Raw,
+        NormalizationProfile::Trim,
+        NormalizationProfile::Nfc,
+        NormalizationProfile::CollapseWhitespace,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NormalizationProfile::Raw => "raw",
+            NormalizationProfile::Trim => "trim",
+            NormalizationProfile::Nfc => "nfc",
+            NormalizationProfile::CollapseWhitespace => "collapse-whitespace",
+        }
+    }
+
+    /// The canonical form of `text`; borrows when nothing changes
+    pub fn apply<'a>(self, text: &'a str) -> Cow<'a, str> {
+        if self == NormalizationProfile::Raw {
+            return Cow::Borrowed(text);
+        }
+
+        let text = text.trim();
+        let text: Cow<str> = if self >= NormalizationProfile::Nfc && is_nfc_quick(text.chars()) != IsNormalized::Yes {
+            Cow::Owned(text.nfc().collect())
+        } else {
+            Cow::Borrowed(text)
+        };
+
+        if self >= NormalizationProfile::CollapseWhitespace && needs_collapse(&text) {
+            return Cow::Owned(text.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        text
+    }
+
+    /// Hash of the canonical form, for change detection, dedup and cache keys
+    pub fn key(self, text: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.apply(text).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Whether two texts are the same under this profile
+    pub fn same(self, a: &str, b: &str) -> bool {
+        self.apply(a) == self.apply(b)
+    }
+}
+
+/// Anything other than single spaces between words
+fn needs_collapse(text: &str) -> bool {
+    let mut previous_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if c != ' ' || previous_space {
+                return true;
+            }
+            previous_space = true;
+        } else {
+            previous_space = false;
+        }
+    }
+    false
+}
+
+impl FromStr for NormalizationProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|profile| profile.name() == s)
+            .ok_or_else(|| format!("unknown normalization profile '{}', expected raw, trim, nfc or collapse-whitespace", s))
+    }
+}
+
+impl fmt::Display for NormalizationProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let text = "  Caf\u{65}\u{301}\r\n  menu\t ";
+        assert_eq!(NormalizationProfile::Raw.apply(text), text);
+        assert_eq!(NormalizationProfile::Trim.apply(text), "Caf\u{65}\u{301}\r\n  menu");
+        assert_eq!(NormalizationProfile::Nfc.apply(text), "Café\r\n  menu");
+        assert_eq!(NormalizationProfile::CollapseWhitespace.apply(text), "Café menu");
+        assert!(matches!(NormalizationProfile::CollapseWhitespace.apply("already clean"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_keys() {
+        assert_ne!(NormalizationProfile::Raw.key("hi "), NormalizationProfile::Raw.key("hi"));
+        assert_eq!(NormalizationProfile::Trim.key("hi "), NormalizationProfile::Trim.key("hi"));
+        assert!(NormalizationProfile::Nfc.same("e\u{301}", "é"));
+        assert!(!NormalizationProfile::Trim.same("e\u{301}", "é"));
+        assert_eq!("nfc".parse(), Ok(NormalizationProfile::Nfc));
+    }
+}
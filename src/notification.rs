@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::monitor::{MonitorEvent, ScanOutcome};
+use crate::notifier::Notification;
+
+/// How important an event is for notification purposes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The clipboard changed (text copied, image without a code, cleared)
+    Update,
+    /// A QR code was decoded from a clipboard image
+    Decoded,
+    /// Reading the clipboard or scanning an image failed
+    Error,
+}
+
+impl NotificationKind {
+    pub fn of(event: &MonitorEvent) -> Self {
+        match event {
+            MonitorEvent::Text(_) | MonitorEvent::Files(_) | MonitorEvent::Cleared | MonitorEvent::Excluded | MonitorEvent::Sensitive(_) => NotificationKind::Update,
+            MonitorEvent::Image { scan, .. } => match scan {
+                ScanOutcome::Decoded(_) => NotificationKind::Decoded,
+                ScanOutcome::NotFound | ScanOutcome::Skipped => NotificationKind::Update,
+                ScanOutcome::Failed(_) => NotificationKind::Error,
+            },
+            MonitorEvent::Error(_) | MonitorEvent::ListenerRestarted(_) => NotificationKind::Error,
+        }
+    }
+}
+
+/// Which events are announced through tray tooltips, console output and other notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotificationPolicy {
+    #[default]
+    All,
+    /// Only QR codes decoded from clipboard images
+    DecodesOnly,
+    ErrorsOnly,
+    Silent,
+}
+
+impl NotificationPolicy {
+    pub const ALL: [NotificationPolicy; 4] = [
+        NotificationPolicy::All,
+        NotificationPolicy::DecodesOnly,
+        NotificationPolicy::ErrorsOnly,
+        NotificationPolicy::Silent,
+    ];
+
+    pub fn allows(self, kind: NotificationKind) -> bool {
+        match self {
+            NotificationPolicy::All => true,
+            NotificationPolicy::DecodesOnly => kind == NotificationKind::Decoded,
+            NotificationPolicy::ErrorsOnly => kind == NotificationKind::Error,
+            NotificationPolicy::Silent => false,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NotificationPolicy::All => "all",
+            NotificationPolicy::DecodesOnly => "decodes-only",
+            NotificationPolicy::ErrorsOnly => "errors-only",
+            NotificationPolicy::Silent => "silent",
+        }
+    }
+
+    /// Label for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            NotificationPolicy::All => "All Events",
+            NotificationPolicy::DecodesOnly => "Only Decoded QR Codes",
+            NotificationPolicy::ErrorsOnly => "Only Errors",
+            NotificationPolicy::Silent => "Silent",
+        }
+    }
+}
+
+impl FromStr for NotificationPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name() == s)
+            .ok_or_else(|| format!("unknown notification level '{}', expected all, decodes-only, errors-only or silent", s))
+    }
+}
+
+impl fmt::Display for NotificationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// What happens to the text of a QR code decoded from a clipboard image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CopyDecoded {
+    /// Replace the image on the clipboard with the decoded text right away
+    Always,
+    /// Offer it through a Copy button on the desktop notification, where the platform has one
+    #[default]
+    Ask,
+    /// Leave the clipboard alone
+    Never,
+}
+
+impl CopyDecoded {
+    pub const ALL: [CopyDecoded; 3] = [CopyDecoded::Always, CopyDecoded::Ask, CopyDecoded::Never];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CopyDecoded::Always => "always",
+            CopyDecoded::Ask => "ask",
+            CopyDecoded::Never => "never",
+        }
+    }
+
+    /// Label for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyDecoded::Always => "Copy Automatically",
+            CopyDecoded::Ask => "Ask in Notification",
+            CopyDecoded::Never => "Never Copy",
+        }
+    }
+
+    /// The decoded text to put on the clipboard now for `event`
+    pub fn to_copy(self, event: &MonitorEvent) -> Option<&str> {
+        match event {
+            MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } if self == CopyDecoded::Always => Some(content),
+            _ => None,
+        }
+    }
+
+    /// `notification` with its Copy button only when asking
+    pub fn apply(self, mut notification: Notification) -> Notification {
+        if self != CopyDecoded::Ask {
+            notification.copy = None;
+        }
+        notification
+    }
+}
+
+impl FromStr for CopyDecoded {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name() == s)
+            .ok_or_else(|| format!("unknown copy mode '{}', expected always, ask or never", s))
+    }
+}
+
+impl fmt::Display for CopyDecoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_filters() {
+        use NotificationKind::*;
+        assert!(NotificationPolicy::All.allows(Update));
+        assert!(NotificationPolicy::DecodesOnly.allows(Decoded));
+        assert!(!NotificationPolicy::DecodesOnly.allows(Update));
+        assert!(NotificationPolicy::ErrorsOnly.allows(Error));
+        assert!(!NotificationPolicy::ErrorsOnly.allows(Decoded));
+        assert!(!NotificationPolicy::Silent.allows(Error));
+    }
+
+    #[test]
+    fn test_event_kinds() {
+        assert_eq!(NotificationKind::of(&MonitorEvent::Text("hi".to_string())), NotificationKind::Update);
+        assert_eq!(NotificationKind::of(&MonitorEvent::Error("oops".to_string())), NotificationKind::Error);
+        let scan = ScanOutcome::Decoded("hi".to_string());
+        let image = image::ImageBuffer::new(1, 1);
+        assert_eq!(NotificationKind::of(&MonitorEvent::Image { image, scan }), NotificationKind::Decoded);
+    }
+
+    #[test]
+    fn test_copy_decoded() {
+        let image = image::ImageBuffer::new(1, 1);
+        let decoded = MonitorEvent::Image { image, scan: ScanOutcome::Decoded("https://example.com".to_string()) };
+        assert_eq!(CopyDecoded::Always.to_copy(&decoded), Some("https://example.com"));
+        assert_eq!(CopyDecoded::Ask.to_copy(&decoded), None);
+        assert_eq!(CopyDecoded::Always.to_copy(&MonitorEvent::Text("hi".to_string())), None);
+
+        let notification = Notification::of(&decoded, &crate::redaction::Redactor::default());
+        assert!(CopyDecoded::Ask.apply(notification.clone()).copy.is_some());
+        assert!(CopyDecoded::Always.apply(notification.clone()).copy.is_none());
+        assert!(CopyDecoded::Never.apply(notification).copy.is_none());
+        assert_eq!("never".parse::<CopyDecoded>(), Ok(CopyDecoded::Never));
+    }
+}
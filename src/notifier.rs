@@ -0,0 +1,461 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+use std::process::Command;
+#[cfg(all(unix, not(target_os = "macos")))]
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tracing::{info, warn};
+use url::Url;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::clipboard_handler::ClipboardHandler;
+use crate::file_scan;
+use crate::monitor::{MonitorEvent, ScanOutcome};
+use crate::notification::{NotificationKind, NotificationPolicy};
+use crate::redaction::Redactor;
+
+/// Something worth telling the user about
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub time: DateTime<Local>,
+    /// Content a Copy button on the notification puts on the clipboard; unredacted, so it
+    /// is never sent anywhere
+    #[serde(skip)]
+    pub copy: Option<String>,
+}
+
+impl Notification {
+    pub fn new(kind: NotificationKind, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            kind,
+            title: title.into(),
+            body: body.into(),
+            time: Local::now(),
+            copy: None,
+        }
+    }
+
+    /// The notification for a monitor event, with content passed through `redactor`
+    pub fn of(event: &MonitorEvent, redactor: &Redactor) -> Self {
+        let kind = NotificationKind::of(event);
+        let (title, body) = match event {
+            MonitorEvent::Text(text) => ("Clipboard text updated", redactor.text_truncated(text, 120)),
+            MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } => ("QR code decoded", redactor.text_truncated(content, 120)),
+            MonitorEvent::Image { scan: ScanOutcome::Failed(e), .. } => ("QR scan failed", e.clone()),
+            MonitorEvent::Image { image, .. } => ("Clipboard image updated", format!("{}x{}, no QR code", image.width(), image.height())),
+            MonitorEvent::Error(e) => ("Failed to read clipboard", e.clone()),
+            MonitorEvent::ListenerRestarted(reason) => ("Clipboard listener restarted", reason.clone()),
+            MonitorEvent::Files(files) => ("Files copied", redactor.text_truncated(&file_scan::describe_files(files), 120)),
+            MonitorEvent::Cleared => ("Clipboard cleared", String::new()),
+            MonitorEvent::Excluded => ("Clipboard content ignored", "marked private by its source".to_string()),
+            MonitorEvent::Sensitive(rule) => ("Sensitive content skipped", rule.clone()),
+        };
+        let mut notification = Self::new(kind, title, body);
+        if let MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } = event {
+            notification.copy = Some(content.clone());
+        }
+        notification
+    }
+}
+
+/// A place notifications are delivered to
+pub trait Notifier: Send {
+    /// Short name for logs, e.g. "webhook"
+    fn name(&self) -> &str;
+
+    fn notify(&self, notification: &Notification) -> Result<()>;
+
+    /// Shows something on screen, like a toast; these can be switched off from the tray
+    fn pops_up(&self) -> bool {
+        false
+    }
+}
+
+/// Native desktop notifications: the notification server over D-Bus on Linux, `osascript`
+/// on macOS and a PowerShell toast on Windows
+///
+/// Only the Linux ones have a Copy button for decoded content; notification servers without
+/// actions simply leave it out.
+#[derive(Default)]
+pub struct ToastNotifier {
+    /// Made on the first Copy and kept, as X11 and Wayland clipboard content only lasts as
+    /// long as the handler that set it
+    #[cfg(all(unix, not(target_os = "macos")))]
+    clipboard: Arc<Mutex<Option<ClipboardHandler>>>,
+}
+
+impl Notifier for ToastNotifier {
+    fn name(&self) -> &str {
+        "toast"
+    }
+
+    fn pops_up(&self) -> bool {
+        true
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        let mut toast = notify_rust::Notification::new();
+        toast.appname("Clipboard QR").summary(&notification.title).body(&notification.body);
+        if notification.copy.is_some() {
+            toast.action("copy", "Copy");
+        }
+        let handle = toast.show()?;
+        if let Some(content) = notification.copy.clone() {
+            let clipboard = self.clipboard.clone();
+            // Blocks until the notification is clicked or closed, so the monitor thread doesn't wait
+            std::thread::spawn(move || {
+                handle.wait_for_action(|action| {
+                    if action != "copy" {
+                        return;
+                    }
+                    let Ok(mut clipboard) = clipboard.lock() else {
+                        return;
+                    };
+                    let handler = clipboard.get_or_insert_with(|| ClipboardHandler::builder().spawn_listener(false).build());
+                    match handler.set_text(&content) {
+                        Ok(()) => info!("Copied decoded content from the notification"),
+                        Err(e) => warn!("Failed to copy from the notification: {}", e),
+                    }
+                });
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        let (title, body) = (notification.title.as_str(), notification.body.as_str());
+        #[cfg(windows)]
+        let mut command = {
+            // Single-quoted PowerShell strings only need their quotes doubled
+            let quote = |text: &str| text.replace('\'', "''").replace(['<', '>', '&'], " ");
+            let script = format!(
+                "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+                 $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+                 $text = $xml.GetElementsByTagName('text'); \
+                 $text.Item(0).AppendChild($xml.CreateTextNode('{}')) > $null; \
+                 $text.Item(1).AppendChild($xml.CreateTextNode('{}')) > $null; \
+                 [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('Clipboard QR').Show([Windows.UI.Notifications.ToastNotification]::new($xml))",
+                quote(title),
+                quote(body)
+            );
+            let mut command = Command::new("powershell");
+            command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+            command
+        };
+        #[cfg(target_os = "macos")]
+        let mut command = {
+            let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+            let mut command = Command::new("osascript");
+            command
+                .arg("-e")
+                .arg(format!("display notification \"{}\" with title \"{}\"", quote(body), quote(title)));
+            command
+        };
+        // Don't wait; the monitor thread shouldn't stall on a notification daemon
+        command.spawn()?;
+        Ok(())
+    }
+}
+
+/// Prints notifications, for terminals and service logs
+pub struct ConsoleNotifier;
+
+impl Notifier for ConsoleNotifier {
+    fn name(&self) -> &str {
+        "console"
+    }
+
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        println!("🔔 {}: {}", notification.title, notification.body);
+        Ok(())
+    }
+}
+
+/// Notifications waiting for a slow webhook before new ones are dropped
+const WEBHOOK_QUEUE: usize = 32;
+
+/// POSTs each notification as JSON, e.g. to a chat or monitoring webhook
+///
+/// Requests are sent one at a time from a thread of its own, so the monitor never waits on
+/// the network; failures are logged there. The thread ends once the notifier is dropped
+/// and the queue is empty.
+pub struct WebhookNotifier {
+    url: Url,
+    queue: mpsc::SyncSender<String>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: Url, token: Option<String>) -> Self {
+        let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build();
+        let (queue, pending) = mpsc::sync_channel::<String>(WEBHOOK_QUEUE);
+        let target = url.clone();
+        thread::spawn(move || {
+            for body in pending {
+                let mut request = agent.post(target.as_str()).set("Content-Type", "application/json");
+                if let Some(token) = &token {
+                    request = request.set("Authorization", &format!("Bearer {}", token));
+                }
+                if let Err(e) = request.send_string(&body) {
+                    warn!("Webhook {} failed: {}", target, e);
+                }
+            }
+        });
+        Self { url, queue }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        self.queue.try_send(serde_json::to_string(notification)?).map_err(|e| match e {
+            mpsc::TrySendError::Full(_) => anyhow::anyhow!("Webhook {} is falling behind; notification dropped", self.url),
+            mpsc::TrySendError::Disconnected(_) => anyhow::anyhow!("Webhook {} sender has stopped", self.url),
+        })
+    }
+}
+
+/// Discards everything; what a `none` backend stands for, so logs show it was chosen
+pub struct NullNotifier;
+
+impl Notifier for NullNotifier {
+    fn name(&self) -> &str {
+        "none"
+    }
+
+    fn notify(&self, _notification: &Notification) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A backend in the `[[notifications.backends]]` config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum NotifierKind {
+    Toast,
+    /// The tray icon tooltip; only in tray mode
+    Tooltip,
+    Console,
+    Webhook {
+        url: Url,
+        /// Sent as a bearer token
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+    },
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotifierConfig {
+    #[serde(flatten)]
+    pub kind: NotifierKind,
+    /// Narrower level for this backend, e.g. toasts only for decoded codes;
+    /// `[notifications] level` applies first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<NotificationPolicy>,
+}
+
+impl NotifierConfig {
+    pub fn new(kind: NotifierKind, level: Option<NotificationPolicy>) -> Self {
+        Self { kind, level }
+    }
+
+    /// Backends used when none are configured: the tooltip and toasts for decoded codes
+    /// on a desktop, nothing extra in a terminal, which already prints every event
+    pub fn defaults(desktop: bool) -> Vec<Self> {
+        if desktop {
+            vec![
+                Self::new(NotifierKind::Tooltip, None),
+                Self::new(NotifierKind::Toast, Some(NotificationPolicy::DecodesOnly)),
+            ]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Every configured backend with its level
+#[derive(Default)]
+pub struct Notifiers {
+    backends: Vec<(Box<dyn Notifier>, Option<NotificationPolicy>)>,
+    /// Leave out the backends that pop up on screen
+    quiet: bool,
+}
+
+impl Notifiers {
+    /// Build the configured backends; `tooltip` supplies the tray's, and is `None` outside tray mode
+    pub fn from_config(configs: &[NotifierConfig], tooltip: Option<Box<dyn Notifier>>) -> Self {
+        let mut notifiers = Self::default();
+        let mut tooltip = tooltip;
+        for config in configs {
+            let notifier: Box<dyn Notifier> = match &config.kind {
+                NotifierKind::Toast => Box::new(ToastNotifier::default()),
+                NotifierKind::Tooltip => match tooltip.take() {
+                    Some(tooltip) => tooltip,
+                    None => continue,
+                },
+                NotifierKind::Console => Box::new(ConsoleNotifier),
+                NotifierKind::Webhook { url, token } => Box::new(WebhookNotifier::new(url.clone(), token.clone())),
+                NotifierKind::None => Box::new(NullNotifier),
+            };
+            notifiers.add(notifier, config.level);
+        }
+        info!("Notification backends: {}", notifiers.names().join(", "));
+        notifiers
+    }
+
+    pub fn add(&mut self, notifier: Box<dyn Notifier>, level: Option<NotificationPolicy>) {
+        self.backends.push((notifier, level));
+    }
+
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.backends.iter().map(|(notifier, _)| notifier.name()).collect()
+    }
+
+    /// Deliver to every backend whose level allows it; failures are logged, not returned,
+    /// so one unreachable webhook doesn't silence the rest
+    pub fn dispatch(&self, notification: &Notification) {
+        for (notifier, level) in &self.backends {
+            if level.is_some_and(|level| !level.allows(notification.kind)) || (self.quiet && notifier.pops_up()) {
+                continue;
+            }
+            if let Err(e) = notifier.notify(notification) {
+                warn!("{} notification failed: {}", notifier.name(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Notifier for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn notify(&self, notification: &Notification) -> Result<()> {
+            self.0.lock().unwrap().push(notification.title.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_config() {
+        let config: Vec<NotifierConfig> = toml::from_str::<toml::Table>(
+            r#"
+            backends = [
+                { type = "toast", level = "decodes-only" },
+                { type = "webhook", url = "https://hooks.example.com/qr", token = "t0k" },
+                { type = "tooltip" },
+            ]
+            "#,
+        )
+        .unwrap()["backends"]
+            .clone()
+            .try_into()
+            .unwrap();
+        assert_eq!(config[0], NotifierConfig::new(NotifierKind::Toast, Some(NotificationPolicy::DecodesOnly)));
+        assert!(matches!(&config[1].kind, NotifierKind::Webhook { token: Some(token), .. } if token == "t0k"));
+
+        // Without a tray the tooltip backend is skipped
+        let notifiers = Notifiers::from_config(&config, None);
+        assert_eq!(notifiers.names(), ["toast", "webhook"]);
+        assert!(NotifierConfig::defaults(false).is_empty());
+    }
+
+    #[test]
+    fn test_webhook_does_not_block() {
+        // Accepts connections but never answers, like a hung endpoint
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/hook", listener.local_addr().unwrap())).unwrap();
+        let webhook = WebhookNotifier::new(url, None);
+        let started = std::time::Instant::now();
+        let notification = Notification::new(NotificationKind::Decoded, "QR code decoded", "");
+        for _ in 0..WEBHOOK_QUEUE {
+            webhook.notify(&notification).unwrap();
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+        // With the sender stuck on the first request, the queue fills up
+        assert!((0..2).any(|_| webhook.notify(&notification).is_err()));
+    }
+
+    #[test]
+    fn test_dispatch_levels() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut notifiers = Notifiers::default();
+        notifiers.add(Box::new(Recorder(seen.clone())), Some(NotificationPolicy::DecodesOnly));
+        notifiers.add(Box::new(NullNotifier), None);
+
+        let redactor = Redactor::new(true);
+        let text = Notification::of(&MonitorEvent::Text("secret".to_string()), &redactor);
+        assert!(!text.body.contains("secret"));
+        notifiers.dispatch(&text);
+        let decoded = MonitorEvent::Image {
+            image: image::RgbaImage::new(1, 1),
+            scan: ScanOutcome::Decoded("https://example.com".to_string()),
+        };
+        notifiers.dispatch(&Notification::of(&decoded, &redactor));
+        assert_eq!(*seen.lock().unwrap(), ["QR code decoded"]);
+    }
+
+    struct Popup(Arc<Mutex<Vec<String>>>);
+
+    impl Notifier for Popup {
+        fn name(&self) -> &str {
+            "popup"
+        }
+
+        fn notify(&self, notification: &Notification) -> Result<()> {
+            self.0.lock().unwrap().push(notification.title.clone());
+            Ok(())
+        }
+
+        fn pops_up(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_quiet_and_copy() {
+        let decoded = MonitorEvent::Image {
+            image: image::RgbaImage::new(1, 1),
+            scan: ScanOutcome::Decoded("https://example.com".to_string()),
+        };
+        let notification = Notification::of(&decoded, &Redactor::new(true));
+        // The copy button gets the real content, webhooks never do
+        assert_eq!(notification.copy.as_deref(), Some("https://example.com"));
+        assert!(!serde_json::to_string(&notification).unwrap().contains("example.com"));
+
+        let popups = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut notifiers = Notifiers::default();
+        notifiers.add(Box::new(Popup(popups.clone())), None);
+        notifiers.add(Box::new(Recorder(seen.clone())), None);
+        notifiers.set_quiet(true);
+        notifiers.dispatch(&notification);
+        notifiers.set_quiet(false);
+        notifiers.dispatch(&notification);
+        assert_eq!(popups.lock().unwrap().len(), 1);
+        assert_eq!(seen.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,125 @@
+use eframe::egui;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+use crate::control;
+use crate::region::Region;
+
+/// How often a shown window confirms where it is, without repainting
+const HEARTBEAT: Duration = Duration::from_secs(2);
+/// Entries not confirmed for this long belong to windows that crashed or hung
+const STALE_AFTER: Duration = Duration::from_secs(5);
+
+/// Where each window of the app writes its place on the screen, one file per process
+fn dir() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("clipboard-qr-windows"),
+        None => std::env::temp_dir().join(format!("clipboard-qr-windows-{}", control::whoami())),
+    }
+}
+
+/// The places of the app's windows currently on the screen, in physical pixels, so screen
+/// captures can leave out the codes the app shows itself
+///
+/// Wayland doesn't tell windows where they are, so none are known there.
+pub fn visible() -> Vec<Region> {
+    let Ok(entries) = fs::read_dir(dir()) else {
+        return Vec::new();
+    };
+    let now = SystemTime::now();
+    entries
+        .flatten()
+        .filter(|entry| {
+            let modified = entry.metadata().and_then(|metadata| metadata.modified());
+            modified.is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() < STALE_AFTER)
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok()?.trim().parse().ok())
+        .collect()
+}
+
+/// Publishes where a window of this process is for [`visible`]; call [`WindowTracker::update`]
+/// every frame. The entry is removed when the tracker is dropped
+pub struct WindowTracker {
+    path: PathBuf,
+    /// The window's place while it is shown; the heartbeat thread rewrites it under the lock
+    shown: Arc<Mutex<Option<Region>>>,
+}
+
+impl WindowTracker {
+    pub fn new() -> Self {
+        let path = dir().join(std::process::id().to_string());
+        let shown = Arc::new(Mutex::new(None::<Region>));
+        let heartbeat = Arc::downgrade(&shown);
+        let heartbeat_path = path.clone();
+        thread::spawn(move || loop {
+            thread::sleep(HEARTBEAT);
+            let Some(alive) = heartbeat.upgrade() else {
+                break;
+            };
+            let Ok(shown) = alive.lock() else {
+                break;
+            };
+            if let Some(region) = *shown {
+                write(&heartbeat_path, region);
+            }
+        });
+        Self { path, shown }
+    }
+
+    /// Record where the window is now; a minimized window or one with an unknown position
+    /// hides nothing
+    pub fn update(&mut self, ctx: &egui::Context) {
+        let place = ctx.input(|i| {
+            let viewport = i.viewport();
+            let scale = viewport.native_pixels_per_point.unwrap_or(1.0);
+            viewport.outer_rect.filter(|_| viewport.minimized != Some(true)).map(|rect| Region {
+                x: (rect.min.x * scale).round() as i32,
+                y: (rect.min.y * scale).round() as i32,
+                width: (rect.width() * scale).round().max(1.0) as u32,
+                height: (rect.height() * scale).round().max(1.0) as u32,
+            })
+        });
+        let Ok(mut shown) = self.shown.lock() else {
+            return;
+        };
+        if *shown == place {
+            return;
+        }
+        *shown = place;
+        match place {
+            Some(region) => write(&self.path, region),
+            None => {
+                let _ = fs::remove_file(&self.path);
+            }
+        }
+    }
+}
+
+impl Default for WindowTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for WindowTracker {
+    fn drop(&mut self) {
+        if let Ok(mut shown) = self.shown.lock() {
+            *shown = None;
+        }
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn write(path: &Path, region: Region) {
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, region.to_string()));
+    if let Err(e) = written {
+        debug!("Failed to record the window's place in {}: {}", path.display(), e);
+    }
+}
@@ -0,0 +1,289 @@
+use anyhow::Result;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tracing::info;
+use url::Url;
+
+/// Placeholders that carry the secret, which stay off the command line because every user
+/// on the machine can read a process's arguments
+const SECRET_PLACEHOLDERS: [&str; 2] = ["{uri}", "{secret}"];
+
+/// Where scanned one-time password setups are imported; only ever on request and after
+/// the user confirmed
+///
+/// ```toml
+/// [password_manager]
+/// kind = "command"
+/// command = ["keepassxc-cli", "add", "--totp", "vault.kdbx", "{name}"]
+/// input = "{secret}\n"
+/// name = "{issuer} ({account})"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasswordManagerConfig {
+    /// pass (with the pass-otp extension), bitwarden (the `bw` CLI, unlocked), or command;
+    /// importing is off if unset
+    pub kind: Option<PasswordManagerKind>,
+    /// Program and arguments for kind = "command"; may use {name}, {issuer} and {account}
+    pub command: Vec<String>,
+    /// What kind = "command" writes to the program's standard input; may also use {uri} and {secret}
+    pub input: String,
+    /// Entry name template
+    pub name: String,
+}
+
+impl Default for PasswordManagerConfig {
+    fn default() -> Self {
+        Self {
+            kind: None,
+            command: Vec::new(),
+            input: "{uri}\n".to_string(),
+            name: "otp/{issuer}/{account}".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordManagerKind {
+    Pass,
+    Bitwarden,
+    Command,
+}
+
+/// The parts of an `otpauth://` URI that name the account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtpAuth {
+    /// The whole URI, secret included
+    pub uri: String,
+    /// "totp" or "hotp"
+    pub method: String,
+    pub issuer: String,
+    pub account: String,
+    secret: String,
+}
+
+impl OtpAuth {
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.starts_with("otpauth-migration://") {
+            return Err(anyhow::anyhow!(
+                "Authenticator export batches can't be imported one by one; import the export in the password manager itself"
+            ));
+        }
+        let url = Url::parse(text).map_err(|_| anyhow::anyhow!("Not an otpauth:// URI"))?;
+        if url.scheme() != "otpauth" {
+            return Err(anyhow::anyhow!("Not an otpauth:// URI"));
+        }
+        let method = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        if method != "totp" && method != "hotp" {
+            return Err(anyhow::anyhow!("Unknown one-time password type '{}'", method));
+        }
+        let query = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned());
+        let secret = query("secret").filter(|secret| !secret.is_empty()).ok_or_else(|| anyhow::anyhow!("The otpauth URI has no secret"))?;
+
+        // The label is "Issuer:account" or just "account"
+        let label = percent_decode(url.path().trim_start_matches('/'));
+        let (label_issuer, account) = match label.split_once(':') {
+            Some((issuer, account)) => (Some(issuer.trim().to_string()), account.trim().to_string()),
+            None => (None, label.trim().to_string()),
+        };
+        let issuer = query("issuer").or(label_issuer).unwrap_or_default();
+        Ok(Self {
+            uri: text.to_string(),
+            method,
+            issuer,
+            account,
+            secret,
+        })
+    }
+
+    /// "GitHub (sam@example.com)", for confirmations and logs; never the secret
+    pub fn describe(&self) -> String {
+        match (self.issuer.is_empty(), self.account.is_empty()) {
+            (false, false) => format!("{} ({})", self.issuer, self.account),
+            (false, true) => self.issuer.clone(),
+            (true, _) => self.account.clone(),
+        }
+    }
+
+    /// The base32 secret; keep it out of logs and the console
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// `template` with {issuer} and {account} filled in, safe for command lines
+    fn fill_public(&self, template: &str) -> String {
+        template.replace("{issuer}", &self.issuer).replace("{account}", &self.account)
+    }
+
+    /// `template` with every placeholder filled in, secret included
+    fn fill(&self, template: &str) -> String {
+        // Secrets first, so an issuer that happens to read "{secret}" stays literal
+        let template = template.replace("{uri}", &self.uri).replace("{secret}", &self.secret);
+        self.fill_public(&template)
+    }
+}
+
+/// Undo the percent-encoding of a URI path
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// A prepared import: the program, its arguments and what it reads on standard input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub input: String,
+    /// Entry name, for the confirmation
+    pub name: String,
+}
+
+/// A configured password manager
+#[derive(Debug, Clone)]
+pub struct PasswordManager {
+    kind: PasswordManagerKind,
+    config: PasswordManagerConfig,
+}
+
+impl PasswordManager {
+    /// `None` if importing isn't configured; fails on a command that would leak the secret
+    pub fn new(config: &PasswordManagerConfig) -> Result<Option<Self>> {
+        let Some(kind) = config.kind else { return Ok(None) };
+        if kind == PasswordManagerKind::Command {
+            if config.command.is_empty() {
+                return Err(anyhow::anyhow!("[password_manager] kind = \"command\" needs a command"));
+            }
+            if config.command.iter().any(|arg| SECRET_PLACEHOLDERS.iter().any(|secret| arg.contains(secret))) {
+                return Err(anyhow::anyhow!(
+                    "[password_manager] command must not contain {{uri}} or {{secret}}, other users could read them in the process list; pass them through input instead"
+                ));
+            }
+        }
+        Ok(Some(Self { kind, config: config.clone() }))
+    }
+
+    /// e.g. "pass" or "keepassxc-cli"
+    pub fn name(&self) -> &str {
+        match self.kind {
+            PasswordManagerKind::Pass => "pass",
+            PasswordManagerKind::Bitwarden => "Bitwarden",
+            PasswordManagerKind::Command => &self.config.command[0],
+        }
+    }
+
+    pub fn prepare(&self, otp: &OtpAuth) -> ImportCommand {
+        let name = otp.fill_public(&self.config.name);
+        let (program, args, input) = match self.kind {
+            PasswordManagerKind::Pass => (
+                "pass".to_string(),
+                vec!["otp".to_string(), "insert".to_string(), name.clone()],
+                format!("{}\n", otp.uri),
+            ),
+            PasswordManagerKind::Bitwarden => {
+                let item = serde_json::json!({
+                    "type": 1,
+                    "name": name,
+                    "login": { "username": otp.account, "totp": otp.uri },
+                });
+                // `bw create item` reads the base64-encoded item from standard input
+                let encoded = base64::engine::general_purpose::STANDARD.encode(item.to_string());
+                ("bw".to_string(), vec!["create".to_string(), "item".to_string()], encoded)
+            }
+            PasswordManagerKind::Command => {
+                let mut command = self.config.command.iter().map(|arg| otp.fill_public(arg).replace("{name}", &name));
+                let program = command.next().unwrap_or_default();
+                (program, command.collect(), otp.fill(&self.config.input))
+            }
+        };
+        ImportCommand { program, args, input, name }
+    }
+
+    /// Run the import; the caller must have asked the user first
+    pub fn import(&self, otp: &OtpAuth) -> Result<ImportCommand> {
+        let command = self.prepare(otp);
+        let mut child = Command::new(&command.program)
+            .args(&command.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", command.program, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(command.input.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "{} failed: {}",
+                command.program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        info!("Imported one-time password {} into {}", otp.describe(), self.name());
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepare_import() {
+        let otp = OtpAuth::parse("otpauth://totp/GitHub:sam%40example.com?secret=JBSWY3DP&issuer=GitHub").unwrap();
+        assert_eq!(otp.describe(), "GitHub (sam@example.com)");
+        assert!(OtpAuth::parse("otpauth://totp/me").is_err());
+        assert!(OtpAuth::parse("otpauth-migration://offline?data=abc").is_err());
+
+        let pass = PasswordManager::new(&PasswordManagerConfig {
+            kind: Some(PasswordManagerKind::Pass),
+            ..Default::default()
+        })
+        .unwrap()
+        .unwrap();
+        let command = pass.prepare(&otp);
+        assert_eq!(command.args, ["otp", "insert", "otp/GitHub/sam@example.com"]);
+        assert_eq!(command.input, format!("{}\n", otp.uri));
+
+        let custom = PasswordManagerConfig {
+            kind: Some(PasswordManagerKind::Command),
+            command: vec!["vault".to_string(), "add".to_string(), "{name}".to_string()],
+            input: "{secret}".to_string(),
+            name: "{issuer}".to_string(),
+        };
+        let command = PasswordManager::new(&custom).unwrap().unwrap().prepare(&otp);
+        assert_eq!(command.program, "vault");
+        assert_eq!(command.args, ["add", "GitHub"]);
+        assert_eq!(command.input, "JBSWY3DP");
+
+        // The secret must never become an argument
+        let leaky = PasswordManagerConfig {
+            command: vec!["vault".to_string(), "{uri}".to_string()],
+            ..custom
+        };
+        assert!(PasswordManager::new(&leaky).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize, Serializer};
+use std::sync::RwLock;
+use tracing::info;
+use url::Url;
+
+use crate::qr_style::Color;
+use crate::qr_templates::WifiNetwork;
+
+/// Detectors added with [`register`], tried in order before the built-in types
+static REGISTRY: RwLock<Vec<&'static dyn PayloadDetector>> = RwLock::new(Vec::new());
+
+/// Recognizes one kind of content the built-in types don't know, such as internal ticket
+/// references, so it gets its own label, badge and pipeline rules
+pub trait PayloadDetector: Send + Sync {
+    /// Short identifier used in config files and JSON, e.g. "ticket"
+    fn name(&self) -> &str;
+
+    /// What users see, e.g. "Ticket"
+    fn label(&self) -> &str;
+
+    fn glyph(&self) -> &str {
+        "🏷"
+    }
+
+    /// Colour of the tray icon badge; none by default
+    fn badge_colour(&self) -> Option<Color> {
+        None
+    }
+
+    /// Whether the content is a secret, see [`PayloadType::is_sensitive`]
+    fn is_sensitive(&self) -> bool {
+        false
+    }
+
+    fn detects(&self, text: &str) -> bool;
+
+    /// A short rendering of `text` for the tray and console, e.g. "OPS-1234"; the text
+    /// itself by default
+    fn summary(&self, _text: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Add a detector and return the type it reports; a detector with the name of an earlier
+/// one replaces it and keeps its type
+pub fn register(detector: Box<dyn PayloadDetector>) -> PayloadType {
+    // Registrations happen a handful of times at startup and live as long as the process
+    let detector: &'static dyn PayloadDetector = Box::leak(detector);
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    let index = match registry.iter().position(|existing| existing.name() == detector.name()) {
+        Some(index) => {
+            registry[index] = detector;
+            index
+        }
+        None => {
+            registry.push(detector);
+            registry.len() - 1
+        }
+    };
+    info!("Registered payload type '{}'", detector.name());
+    PayloadType::Custom(index)
+}
+
+fn registered(index: usize) -> Option<&'static dyn PayloadDetector> {
+    REGISTRY.read().unwrap_or_else(|e| e.into_inner()).get(index).copied()
+}
+
+/// A payload type defined in the config file by a regular expression
+///
+/// ```toml
+/// [[payload_types]]
+/// name = "ticket"
+/// label = "Ticket"
+/// pattern = '^(?:https://tickets\.example\.com/browse/)?([A-Z]+-\d+)$'
+/// summary = "Ticket $1"
+/// glyph = "🎫"
+/// colour = "#0B5CAD"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PayloadTypeConfig {
+    pub name: String,
+    pub label: String,
+    /// Matched against the whole trimmed text
+    pub pattern: String,
+    /// Shown instead of the content, with `$1`-style references to capture groups
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glyph: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colour: Option<Color>,
+    /// Expire it like Wi-Fi credentials, see `[expiry]`
+    #[serde(default)]
+    pub sensitive: bool,
+}
+
+/// The detector for a [`PayloadTypeConfig`]
+#[derive(Debug)]
+pub struct PatternDetector {
+    config: PayloadTypeConfig,
+    pattern: Regex,
+}
+
+impl PatternDetector {
+    pub fn new(config: PayloadTypeConfig) -> Result<Self> {
+        if config.name.is_empty() || BUILT_IN_NAMES.contains(&config.name.as_str()) {
+            return Err(anyhow::anyhow!("Invalid payload type name '{}'", config.name));
+        }
+        let pattern = Regex::new(&config.pattern)
+            .map_err(|e| anyhow::anyhow!("Invalid pattern for payload type '{}': {}", config.name, e))?;
+        Ok(Self { config, pattern })
+    }
+}
+
+impl PayloadDetector for PatternDetector {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn label(&self) -> &str {
+        &self.config.label
+    }
+
+    fn glyph(&self) -> &str {
+        self.config.glyph.as_deref().unwrap_or("🏷")
+    }
+
+    fn badge_colour(&self) -> Option<Color> {
+        self.config.colour
+    }
+
+    fn is_sensitive(&self) -> bool {
+        self.config.sensitive
+    }
+
+    fn detects(&self, text: &str) -> bool {
+        self.pattern.is_match(text.trim())
+    }
+
+    fn summary(&self, text: &str) -> Option<String> {
+        let template = self.config.summary.as_deref()?;
+        let captures = self.pattern.captures(text.trim())?;
+        let mut summary = String::new();
+        captures.expand(template, &mut summary);
+        Some(summary)
+    }
+}
+
+/// Register the payload types from the config file
+pub fn register_configured(types: &[PayloadTypeConfig]) -> Result<()> {
+    for config in types {
+        register(Box::new(PatternDetector::new(config.clone())?));
+    }
+    Ok(())
+}
+
+const BUILT_IN_NAMES: [&str; 5] = ["url", "wifi", "contact", "otp", "text"];
+
+/// What a piece of clipboard text or decoded QR content is, at a glance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadType {
+    Url,
+    /// `WIFI:` network credentials
+    Wifi,
+    /// vCard or MECARD
+    Contact,
+    /// `otpauth://` one-time password setup
+    Otp,
+    Text,
+    /// A type added with [`register`], by registration order
+    Custom(usize),
+}
+
+impl PayloadType {
+    pub fn detect(text: &str) -> Self {
+        let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = registry.iter().position(|detector| detector.detects(text)) {
+            return PayloadType::Custom(index);
+        }
+        drop(registry);
+
+        let trimmed = text.trim_start();
+        let starts_with = |prefix: &str| {
+            trimmed
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        };
+
+        if starts_with("WIFI:") {
+            PayloadType::Wifi
+        } else if starts_with("BEGIN:VCARD") || starts_with("MECARD:") {
+            PayloadType::Contact
+        } else if starts_with("otpauth://") || starts_with("otpauth-migration://") {
+            PayloadType::Otp
+        } else if Url::parse(trimmed.trim_end()).is_ok_and(|url| !url.cannot_be_a_base()) {
+            PayloadType::Url
+        } else {
+            PayloadType::Text
+        }
+    }
+
+    /// Stable identifier for config files and JSON, e.g. "wifi"
+    pub fn name(self) -> &'static str {
+        match self {
+            PayloadType::Url => "url",
+            PayloadType::Wifi => "wifi",
+            PayloadType::Contact => "contact",
+            PayloadType::Otp => "otp",
+            PayloadType::Text => "text",
+            PayloadType::Custom(index) => registered(index).map_or("custom", |detector| detector.name()),
+        }
+    }
+
+    pub fn glyph(self) -> &'static str {
+        match self {
+            PayloadType::Url => "🔗",
+            PayloadType::Wifi => "📶",
+            PayloadType::Contact => "👤",
+            PayloadType::Otp => "🔑",
+            PayloadType::Text => "📝",
+            PayloadType::Custom(index) => registered(index).map_or("🏷", |detector| detector.glyph()),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PayloadType::Url => "Link",
+            PayloadType::Wifi => "Wi-Fi",
+            PayloadType::Contact => "Contact",
+            PayloadType::Otp => "One-time password",
+            PayloadType::Text => "Text",
+            PayloadType::Custom(index) => registered(index).map_or("Custom", |detector| detector.label()),
+        }
+    }
+
+    /// Secrets that shouldn't linger in history or exported files
+    pub fn is_sensitive(self) -> bool {
+        match self {
+            PayloadType::Wifi | PayloadType::Otp => true,
+            PayloadType::Custom(index) => registered(index).is_some_and(|detector| detector.is_sensitive()),
+            _ => false,
+        }
+    }
+
+    /// How to show `text` of this type in the tray and console; `None` shows the text itself
+    pub fn summary(self, text: &str) -> Option<String> {
+        match self {
+            PayloadType::Wifi => WifiNetwork::parse(text).map(|network| network.summary()),
+            PayloadType::Custom(index) => registered(index)?.summary(text),
+            _ => None,
+        }
+    }
+
+    /// Colour of the tray icon badge; plain text gets none
+    pub fn badge_colour(self) -> Option<Color> {
+        match self {
+            PayloadType::Url => Some(Color { r: 46, g: 160, b: 67 }),
+            PayloadType::Wifi => Some(Color { r: 130, g: 80, b: 223 }),
+            PayloadType::Contact => Some(Color { r: 219, g: 109, b: 40 }),
+            PayloadType::Otp => Some(Color { r: 207, g: 34, b: 46 }),
+            PayloadType::Text => None,
+            PayloadType::Custom(index) => registered(index)?.badge_colour(),
+        }
+    }
+}
+
+impl Serialize for PayloadType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(PayloadType::detect("https://example.com/a"), PayloadType::Url);
+        assert_eq!(PayloadType::detect("WIFI:T:WPA;S:home;P:secret;;"), PayloadType::Wifi);
+        assert_eq!(PayloadType::detect("wifi:S:cafe;;"), PayloadType::Wifi);
+        assert_eq!(PayloadType::detect("BEGIN:VCARD\nVERSION:3.0\nFN:Sam\nEND:VCARD"), PayloadType::Contact);
+        assert_eq!(PayloadType::detect("MECARD:N:Sam;;"), PayloadType::Contact);
+        assert_eq!(PayloadType::detect("otpauth://totp/Example:sam?secret=JBSWY3DP"), PayloadType::Otp);
+        assert_eq!(PayloadType::detect("meeting at 10:30"), PayloadType::Text);
+        // `mailto:` and similar can't be a base, so they read as text rather than links
+        assert_eq!(PayloadType::detect("mailto:sam@example.com"), PayloadType::Text);
+        assert_eq!(PayloadType::detect("ñ"), PayloadType::Text);
+    }
+
+    #[test]
+    fn test_custom_payload_type() {
+        let config: PayloadTypeConfig = toml::from_str(
+            r##"
+            name = "ticket"
+            label = "Ticket"
+            pattern = '^(?:https://tickets\.example\.com/browse/)?([A-Z]+-\d+)$'
+            summary = "Ticket $1"
+            colour = "#0B5CAD"
+            "##,
+        )
+        .unwrap();
+        let ticket = register(Box::new(PatternDetector::new(config).unwrap()));
+        assert_eq!(PayloadType::detect("OPS-1234"), ticket);
+        // Custom types win over the built-in ones they overlap with
+        let link = "https://tickets.example.com/browse/OPS-1234";
+        assert_eq!(PayloadType::detect(link), ticket);
+        assert_eq!(ticket.summary(link).as_deref(), Some("Ticket OPS-1234"));
+        assert_eq!(ticket.name(), "ticket");
+        assert_eq!(ticket.label(), "Ticket");
+        assert_eq!(serde_json::to_string(&ticket).unwrap(), "\"ticket\"");
+        assert_eq!(PayloadType::detect("ops-1234"), PayloadType::Text);
+
+        let reserved = PayloadTypeConfig {
+            name: "url".to_string(),
+            label: "Link".to_string(),
+            pattern: ".*".to_string(),
+            summary: None,
+            glyph: None,
+            colour: None,
+            sensitive: false,
+        };
+        assert!(PatternDetector::new(reserved).is_err());
+    }
+}
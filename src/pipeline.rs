@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use url::Url;
+
+use crate::monitor::MonitorEvent;
+use crate::payload::PayloadType;
+
+/// One thing the app can do with new clipboard content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PipelineStep {
+    /// Look for a QR code in an image
+    Scan,
+    /// Show a QR code for the text, or for the content decoded from an image
+    Generate,
+    /// Update the tray tooltip and print the change to the console
+    Notify,
+    /// Record the content in the history store
+    History,
+}
+
+/// Which clipboard content a rule applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentMatch {
+    Text,
+    /// Text that is a single http(s) URL
+    Url,
+    Image,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PipelineRule {
+    #[serde(rename = "match")]
+    pub content: ContentMatch,
+    /// Only text of this payload type, by name such as "wifi" or a type from `[[payload_types]]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<String>,
+    pub steps: Vec<PipelineStep>,
+}
+
+/// The steps selected for one piece of content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Steps {
+    pub scan: bool,
+    pub generate: bool,
+    pub notify: bool,
+    pub history: bool,
+}
+
+impl Steps {
+    pub const ALL: Self = Self { scan: true, generate: true, notify: true, history: true };
+
+    fn from_list(steps: &[PipelineStep]) -> Self {
+        Self {
+            scan: steps.contains(&PipelineStep::Scan),
+            generate: steps.contains(&PipelineStep::Generate),
+            notify: steps.contains(&PipelineStep::Notify),
+            history: steps.contains(&PipelineStep::History),
+        }
+    }
+}
+
+/// Decides which processing steps run for each clipboard change
+///
+/// The first rule matching the content wins; content no rule matches gets every step.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    rules: Vec<PipelineRule>,
+}
+
+impl Pipeline {
+    pub fn new(rules: Vec<PipelineRule>) -> Self {
+        for rule in &rules {
+            if rule.content != ContentMatch::Image && rule.steps.contains(&PipelineStep::Scan) {
+                warn!("Pipeline rule for {:?} has a scan step, which only applies to images", rule.content);
+            }
+        }
+        Self { rules }
+    }
+
+    pub fn steps_for_text(&self, text: &str) -> Steps {
+        let is_url = is_web_url(text);
+        let payload = PayloadType::detect(text).name();
+        self.first_match(|rule| {
+            (rule.content == ContentMatch::Text || (is_url && rule.content == ContentMatch::Url))
+                && rule.payload.as_ref().is_none_or(|name| name == payload)
+        })
+    }
+
+    pub fn steps_for_image(&self) -> Steps {
+        self.first_match(|rule| rule.content == ContentMatch::Image)
+    }
+
+    /// Steps for a monitor event; events without content get every step
+    pub fn steps_for(&self, event: &MonitorEvent) -> Steps {
+        match event {
+            MonitorEvent::Text(text) => self.steps_for_text(text),
+            MonitorEvent::Image { .. } => self.steps_for_image(),
+            _ => Steps::ALL,
+        }
+    }
+
+    fn first_match(&self, matches: impl Fn(&PipelineRule) -> bool) -> Steps {
+        self.rules
+            .iter()
+            .find(|rule| matches(rule))
+            .map_or(Steps::ALL, |rule| Steps::from_list(&rule.steps))
+    }
+}
+
+fn is_web_url(text: &str) -> bool {
+    let text = text.trim();
+    !text.contains(char::is_whitespace)
+        && Url::parse(text).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let pipeline: Vec<PipelineRule> = toml::from_str::<toml::Table>(
+            r#"
+            rules = [
+                { match = "url", steps = ["generate", "notify"] },
+                { match = "text", steps = ["generate"] },
+                { match = "image", steps = ["scan"] },
+            ]
+            "#,
+        )
+        .unwrap()["rules"]
+            .clone()
+            .try_into()
+            .unwrap();
+        let pipeline = Pipeline::new(pipeline);
+
+        let url = pipeline.steps_for_text("https://example.com");
+        assert!(url.generate && url.notify && !url.history);
+        let text = pipeline.steps_for_text("hello");
+        assert!(text.generate && !text.notify);
+        assert_eq!(pipeline.steps_for_image(), Steps { scan: true, generate: false, notify: false, history: false });
+    }
+
+    #[test]
+    fn test_defaults_to_every_step() {
+        let pipeline = Pipeline::new(vec![PipelineRule { content: ContentMatch::Url, payload: None, steps: vec![] }]);
+        assert_eq!(pipeline.steps_for_text("hello"), Steps::ALL);
+        assert_eq!(pipeline.steps_for_image(), Steps::ALL);
+        assert_eq!(pipeline.steps_for(&MonitorEvent::Cleared), Steps::ALL);
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use eframe::egui;
+use std::io::{Read, Write};
+use std::process::{Child, Stdio};
+use tracing::{error, info};
+
+use crate::barcode_generator::{Barcode, BarcodeSymbology};
+use crate::own_windows::WindowTracker;
+use crate::privacy::SensitiveFilter;
+use crate::qr_generator::QRGenerator;
+use crate::redaction::Redactor;
+
+/// Largest side of the code in points; the window is sized around it
+const CODE_SIDE: f32 = 280.0;
+
+/// Characters of the text shown under the code
+const CAPTION_CHARS: usize = 80;
+
+/// Height of the symbol dropdown above the code
+const PICKER_HEIGHT: f32 = 28.0;
+
+/// Pixels per module of barcodes in the window
+const BARCODE_SCALE: u32 = 2;
+
+/// Show the QR code of `text` in a small always-on-top window until it is closed or Esc is
+/// pressed; a dropdown switches to a 1D barcode. Sensitive text is named but not shown
+pub fn run(generator: QRGenerator, text: Result<String>, sensitive: &SensitiveFilter, redactor: Redactor) -> Result<()> {
+    info!("Opening QR popup");
+    let content = match text {
+        Ok(text) => match sensitive.check(&text) {
+            Some(rule) => Err(format!("Sensitive content not shown ({})", rule)),
+            None => Ok(text),
+        },
+        Err(e) => Err(e.to_string()),
+    };
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("Clipboard QR")
+            .with_inner_size([CODE_SIDE + 32.0, CODE_SIDE + 72.0 + PICKER_HEIGHT])
+            .with_resizable(false)
+            .with_always_on_top(),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Clipboard QR",
+        options,
+        Box::new(move |_cc| Ok(Box::new(PopupApp {
+            generator,
+            content,
+            redactor,
+            kind: None,
+            problem: None,
+            texture: None,
+            tracker: WindowTracker::new(),
+        }))),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to open the QR popup: {}", e))
+}
+
+/// Open the popup in its own process, since the tray's event loop can't host egui
+pub fn open_window(text: Option<&str>) -> Result<Child> {
+    let exe = std::env::current_exe()?;
+    let Some(text) = text else {
+        return std::process::Command::new(exe)
+            .arg("popup")
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to open the QR popup: {}", e));
+    };
+    // Written to standard input rather than the command line, where other users could see it
+    let mut child = std::process::Command::new(exe)
+        .args(["popup", "--text-from-stdin"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to open the QR popup: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    Ok(child)
+}
+
+/// The text handed over by [`open_window`]
+pub fn read_text() -> Result<String> {
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+    Ok(text)
+}
+
+struct PopupApp {
+    generator: QRGenerator,
+    /// The text to show, or why there is none
+    content: Result<String, String>,
+    redactor: Redactor,
+    /// Barcode to show instead of the QR code
+    kind: Option<BarcodeSymbology>,
+    /// Why the text can't be shown as the chosen symbol; the text stays for the others
+    problem: Option<String>,
+    texture: Option<egui::TextureHandle>,
+    /// Keeps screen scans from reading the code back
+    tracker: WindowTracker,
+}
+
+impl PopupApp {
+    /// Render the code on the first frame and after the symbol changes
+    fn load(&mut self, ctx: &egui::Context) {
+        let Ok(text) = &self.content else {
+            return;
+        };
+        if self.texture.is_some() || self.problem.is_some() {
+            return;
+        }
+        let image = match self.kind {
+            None => self.generator.generate_qr_image(text),
+            Some(kind) => Barcode::encode(kind, text.trim()).map(|barcode| Some(barcode.rasterize(self.generator.style(), BARCODE_SCALE))),
+        };
+        match image {
+            Ok(Some(image)) => {
+                let size = [image.width() as usize, image.height() as usize];
+                let image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+                self.texture = Some(ctx.load_texture("popup-qr", image, egui::TextureOptions::NEAREST));
+            }
+            Ok(None) => self.content = Err("The clipboard text is empty".to_string()),
+            Err(e) => {
+                let name = self.kind.map_or("a QR code", BarcodeSymbology::label);
+                error!("Failed to generate {}: {}", name, e);
+                self.problem = Some(format!("Can't show this text as {}: {}", name, e));
+            }
+        }
+    }
+
+    /// Dropdown of the QR code and the barcode symbologies
+    fn symbol_picker(&mut self, ui: &mut egui::Ui) {
+        let selected = self.kind.map_or("QR Code", BarcodeSymbology::label);
+        let before = self.kind;
+        egui::ComboBox::from_label("Symbol").selected_text(selected).show_ui(ui, |ui| {
+            ui.selectable_value(&mut self.kind, None, "QR Code");
+            for kind in BarcodeSymbology::ALL {
+                ui.selectable_value(&mut self.kind, Some(kind), kind.label());
+            }
+        });
+        if self.kind != before {
+            self.texture = None;
+            self.problem = None;
+        }
+    }
+}
+
+impl eframe::App for PopupApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        self.load(ctx);
+        self.tracker.update(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.content.is_ok() {
+                self.symbol_picker(ui);
+            }
+            ui.vertical_centered(|ui| match (&self.content, &self.problem, &self.texture) {
+                (Ok(text), None, Some(texture)) => {
+                    let width = CODE_SIDE.min(ui.available_width());
+                    let [w, h] = texture.size();
+                    ui.add(egui::Image::new(texture).fit_to_exact_size(egui::vec2(width, width * h as f32 / w as f32)));
+                    ui.label(self.redactor.text_truncated(text, CAPTION_CHARS));
+                }
+                (Err(message), _, _) | (Ok(_), Some(message), _) => {
+                    ui.add_space(CODE_SIDE / 2.0);
+                    ui.label(message);
+                }
+                (Ok(_), None, None) => {}
+            });
+        });
+    }
+}
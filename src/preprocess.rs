@@ -0,0 +1,335 @@
+use anyhow::Result;
+use image::imageops::{self, FilterType};
+use image::{GrayImage, Luma, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::storage;
+
+/// Image adjustments that help the decoder with stubborn images: low contrast
+/// photos, tiny screenshots or codes on busy backgrounds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreprocessSettings {
+    /// Resize factor applied first; above 1 helps with codes only a few pixels per module
+    pub scale: f32,
+    /// Contrast multiplier around mid-grey; 1 leaves the image unchanged
+    pub contrast: f32,
+    /// Turn the image into pure black and white at this grey level
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<u8>,
+    /// Swap dark and light, for light codes on a dark background
+    pub invert: bool,
+}
+
+impl Default for PreprocessSettings {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            contrast: 1.0,
+            threshold: None,
+            invert: false,
+        }
+    }
+}
+
+impl PreprocessSettings {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The adjusted image, in greyscale; also what the tuning window previews
+    pub fn apply(&self, image: &RgbaImage) -> RgbaImage {
+        let mut grey = imageops::grayscale(image);
+        if self.scale > 0.0 && (self.scale - 1.0).abs() > f32::EPSILON {
+            let width = ((grey.width() as f32 * self.scale).round() as u32).max(1);
+            let height = ((grey.height() as f32 * self.scale).round() as u32).max(1);
+            // Nearest keeps module edges hard when enlarging
+            let filter = if self.scale > 1.0 { FilterType::Nearest } else { FilterType::Triangle };
+            grey = imageops::resize(&grey, width, height, filter);
+        }
+
+        for Luma([value]) in grey.pixels_mut() {
+            let mut adjusted = (*value as f32 - 128.0) * self.contrast + 128.0;
+            if let Some(threshold) = self.threshold {
+                adjusted = if adjusted >= threshold as f32 { 255.0 } else { 0.0 };
+            }
+            if self.invert {
+                adjusted = 255.0 - adjusted;
+            }
+            *value = adjusted.round().clamp(0.0, 255.0) as u8;
+        }
+
+        to_rgba(&grey)
+    }
+}
+
+/// Smallest side an image is halved down to by [`RetryStep::Downscale`]
+const MIN_DOWNSCALED_SIDE: u32 = 400;
+
+/// Grey levels below the neighbourhood mean a pixel has to be to turn black in
+/// [`RetryStep::AdaptiveThreshold`]; keeps flat areas and noise white
+const ADAPTIVE_OFFSET: i64 = 7;
+
+/// A built-in adjustment tried when an image doesn't decode as copied, aimed at phone
+/// photos: uneven lighting, washed-out colours, blur, a sideways camera or too much detail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryStep {
+    Grayscale,
+    /// Spread the darkest to lightest grey over the full range
+    ContrastStretch,
+    /// Black and white against the mean of each pixel's neighbourhood, so shadows across
+    /// the code don't swallow half of it
+    AdaptiveThreshold,
+    Sharpen,
+    /// Clockwise by 90, 180 or 270 degrees
+    Rotate(u16),
+    /// Half the size, down to [`MIN_DOWNSCALED_SIDE`]; photos resolve paper texture that
+    /// confuses the detector
+    Downscale,
+}
+
+impl RetryStep {
+    /// In the order they are tried, cheapest and most often helpful first
+    pub const SEQUENCE: [RetryStep; 8] = [
+        RetryStep::Grayscale,
+        RetryStep::ContrastStretch,
+        RetryStep::AdaptiveThreshold,
+        RetryStep::Sharpen,
+        RetryStep::Rotate(90),
+        RetryStep::Rotate(180),
+        RetryStep::Rotate(270),
+        RetryStep::Downscale,
+    ];
+
+    /// The adjusted image, or `None` when the step can't change it, e.g. downscaling an
+    /// image that is already small
+    pub fn apply(self, image: &RgbaImage) -> Option<RgbaImage> {
+        match self {
+            RetryStep::Grayscale => Some(to_rgba(&imageops::grayscale(image))),
+            RetryStep::ContrastStretch => contrast_stretch(imageops::grayscale(image)).map(|grey| to_rgba(&grey)),
+            RetryStep::AdaptiveThreshold => Some(to_rgba(&adaptive_threshold(&imageops::grayscale(image)))),
+            RetryStep::Sharpen => Some(imageops::unsharpen(image, 1.5, 8)),
+            RetryStep::Rotate(90) => Some(imageops::rotate90(image)),
+            RetryStep::Rotate(180) => Some(imageops::rotate180(image)),
+            RetryStep::Rotate(270) => Some(imageops::rotate270(image)),
+            RetryStep::Rotate(_) => None,
+            RetryStep::Downscale => {
+                let (width, height) = (image.width() / 2, image.height() / 2);
+                (width.min(height) >= MIN_DOWNSCALED_SIDE).then(|| imageops::resize(image, width, height, FilterType::Triangle))
+            }
+        }
+    }
+}
+
+impl fmt::Display for RetryStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetryStep::Grayscale => f.write_str("grayscale"),
+            RetryStep::ContrastStretch => f.write_str("contrast stretch"),
+            RetryStep::AdaptiveThreshold => f.write_str("adaptive threshold"),
+            RetryStep::Sharpen => f.write_str("sharpen"),
+            RetryStep::Rotate(degrees) => write!(f, "rotate {}°", degrees),
+            RetryStep::Downscale => f.write_str("downscale"),
+        }
+    }
+}
+
+/// `grey` stretched so its darkest pixel is black and its lightest white; `None` when it
+/// already is, or is a single grey
+fn contrast_stretch(mut grey: GrayImage) -> Option<GrayImage> {
+    let (low, high) = grey.pixels().fold((u8::MAX, u8::MIN), |(low, high), Luma([value])| (low.min(*value), high.max(*value)));
+    if high <= low || (low == 0 && high == 255) {
+        return None;
+    }
+    let range = (high - low) as u32;
+    for Luma([value]) in grey.pixels_mut() {
+        *value = ((*value - low) as u32 * 255 / range) as u8;
+    }
+    Some(grey)
+}
+
+/// Black where a pixel is darker than the mean of the square around it, a sixteenth of the
+/// shorter side across, by more than [`ADAPTIVE_OFFSET`]; white elsewhere
+fn adaptive_threshold(grey: &GrayImage) -> GrayImage {
+    let (width, height) = (grey.width() as usize, grey.height() as usize);
+    let radius = (width.min(height) / 32).max(3);
+
+    // Summed-area table with a zero row and column in front
+    let mut sums = vec![0i64; (width + 1) * (height + 1)];
+    for y in 0..height {
+        let mut row = 0i64;
+        for x in 0..width {
+            row += grey.get_pixel(x as u32, y as u32)[0] as i64;
+            sums[(y + 1) * (width + 1) + x + 1] = sums[y * (width + 1) + x + 1] + row;
+        }
+    }
+
+    GrayImage::from_fn(width as u32, height as u32, |x, y| {
+        let (x, y) = (x as usize, y as usize);
+        let (left, top) = (x.saturating_sub(radius), y.saturating_sub(radius));
+        let (right, bottom) = ((x + radius + 1).min(width), (y + radius + 1).min(height));
+        let sum = sums[bottom * (width + 1) + right] - sums[top * (width + 1) + right] - sums[bottom * (width + 1) + left]
+            + sums[top * (width + 1) + left];
+        let mean = sum / ((right - left) * (bottom - top)) as i64;
+        let value = grey.get_pixel(x as u32, y as u32)[0] as i64;
+        Luma([if value < mean - ADAPTIVE_OFFSET { 0 } else { 255 }])
+    })
+}
+
+fn to_rgba(grey: &GrayImage) -> RgbaImage {
+    RgbaImage::from_fn(grey.width(), grey.height(), |x, y| {
+        let Luma([value]) = *grey.get_pixel(x, y);
+        Rgba([value, value, value, 255])
+    })
+}
+
+/// Preprocessing settings saved under a name after they decoded a difficult image
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedPreset {
+    pub name: String,
+    #[serde(flatten)]
+    pub settings: PreprocessSettings,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PresetFile {
+    #[serde(default)]
+    presets: Vec<NamedPreset>,
+}
+
+/// Saved presets, kept in their own file next to `config.toml` so saving one
+/// doesn't rewrite the hand-edited config
+pub struct PresetStore {
+    path: PathBuf,
+    presets: Vec<NamedPreset>,
+}
+
+impl PresetStore {
+    /// e.g. `~/.config/clipboard-qr/presets.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("clipboard-qr").join("presets.toml"))
+    }
+
+    /// Load the presets at `path`; a missing file is an empty store
+    pub fn open(path: &Path) -> Result<Self> {
+        let presets = if path.exists() {
+            toml::from_str::<PresetFile>(&fs::read_to_string(path)?)?.presets
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path: path.to_path_buf(), presets })
+    }
+
+    /// Presets from the default location, logging and skipping an unreadable file
+    pub fn load_default() -> Vec<NamedPreset> {
+        let Some(path) = Self::default_path() else {
+            return Vec::new();
+        };
+        match Self::open(&path) {
+            Ok(store) => store.presets,
+            Err(e) => {
+                warn!("Failed to load preprocessing presets from {:?}: {}", path, e);
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn presets(&self) -> &[NamedPreset] {
+        &self.presets
+    }
+
+    /// Add a preset, replacing one with the same name, and write the file
+    pub fn save(&mut self, name: &str, settings: PreprocessSettings) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("Preset name is empty"));
+        }
+        let preset = NamedPreset { name: name.to_string(), settings };
+        match self.presets.iter_mut().find(|existing| existing.name.eq_ignore_ascii_case(name)) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+
+        storage::check_write("preprocessing presets", &self.path)?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = PresetFile { presets: self.presets.clone() };
+        fs::write(&self.path, toml::to_string_pretty(&file)?)?;
+        info!("Saved preprocessing preset '{}' to {:?}", name, self.path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([100, 100, 100, 255]));
+        image.put_pixel(1, 0, Rgba([160, 160, 160, 255]));
+
+        let unchanged = PreprocessSettings::default().apply(&image);
+        assert_eq!(unchanged.get_pixel(0, 0), &Rgba([100, 100, 100, 255]));
+
+        let settings = PreprocessSettings { scale: 2.0, threshold: Some(128), ..Default::default() };
+        let binarized = settings.apply(&image);
+        assert_eq!(binarized.dimensions(), (4, 2));
+        assert_eq!(binarized.get_pixel(1, 1), &Rgba([0, 0, 0, 255]));
+        assert_eq!(binarized.get_pixel(2, 0), &Rgba([255, 255, 255, 255]));
+
+        let inverted = PreprocessSettings { contrast: 2.0, invert: true, ..Default::default() }.apply(&image);
+        assert_eq!(inverted.get_pixel(0, 0), &Rgba([183, 183, 183, 255]));
+    }
+
+    #[test]
+    fn test_retry_steps() {
+        // A dim, low contrast image with a shadow over its right half
+        let image = RgbaImage::from_fn(64, 32, |x, y| {
+            let shade = if x < 32 { 0 } else { 40 };
+            let value = if (x / 4 + y / 4) % 2 == 0 { 90 } else { 130 } - shade;
+            Rgba([value, value, value, 255])
+        });
+
+        let stretched = RetryStep::ContrastStretch.apply(&image).unwrap();
+        assert_eq!((stretched.get_pixel(0, 0)[0], stretched.get_pixel(4, 0)[0]), (127, 255));
+        assert_eq!(stretched.get_pixel(32, 0), &Rgba([0, 0, 0, 255]));
+        assert!(RetryStep::ContrastStretch.apply(&stretched).is_none());
+
+        // Dark squares stay black on both sides of the shadow, light ones turn white
+        let binarized = RetryStep::AdaptiveThreshold.apply(&image).unwrap();
+        for (x, y, value) in [(1, 1, 0), (5, 1, 255), (33, 1, 0), (37, 1, 255)] {
+            assert_eq!(binarized.get_pixel(x, y)[0], value, "({}, {})", x, y);
+        }
+
+        let rotated = RetryStep::Rotate(90).apply(&image).unwrap();
+        assert_eq!(rotated.dimensions(), (32, 64));
+        assert_eq!(rotated.get_pixel(31, 0), image.get_pixel(0, 0));
+        assert!(RetryStep::Rotate(45).apply(&image).is_none());
+        assert!(RetryStep::Downscale.apply(&image).is_none());
+        assert_eq!(RetryStep::Downscale.apply(&RgbaImage::new(1000, 900)).unwrap().dimensions(), (500, 450));
+    }
+
+    #[test]
+    fn test_preset_store() {
+        let path = std::env::temp_dir().join(format!("clipboard-qr-presets-{}.toml", std::process::id()));
+        let mut store = PresetStore::open(&path).unwrap();
+        assert!(store.presets().is_empty());
+
+        let receipt = PreprocessSettings { threshold: Some(90), ..Default::default() };
+        store.save("Receipt", receipt).unwrap();
+        store.save("receipt", PreprocessSettings { scale: 3.0, ..receipt }).unwrap();
+        assert!(store.save("  ", receipt).is_err());
+
+        let reopened = PresetStore::open(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(reopened.presets().len(), 1);
+        assert_eq!(reopened.presets()[0].settings.scale, 3.0);
+        assert_eq!(reopened.presets()[0].settings.threshold, Some(90));
+    }
+}
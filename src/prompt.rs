@@ -0,0 +1,145 @@
+use std::io::{self, BufRead, Write};
+use std::process::Command;
+use tracing::warn;
+
+/// Ask for a value in a small native dialog; `None` if cancelled or no dialog is available
+pub fn dialog(title: &str, label: &str, default: Option<&str>) -> Option<String> {
+    let default = default.unwrap_or_default();
+
+    #[cfg(windows)]
+    let commands = {
+        let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+        let script = format!(
+            "Add-Type -AssemblyName Microsoft.VisualBasic; [Microsoft.VisualBasic.Interaction]::InputBox({}, {}, {})",
+            quote(label),
+            quote(title),
+            quote(default)
+        );
+        vec![("powershell", vec!["-NoProfile".to_string(), "-Command".to_string(), script])]
+    };
+
+    #[cfg(target_os = "macos")]
+    let commands = {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "text returned of (display dialog {} default answer {} with title {})",
+            quote(label),
+            quote(default),
+            quote(title)
+        );
+        vec![("osascript", vec!["-e".to_string(), script])]
+    };
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let commands = vec![
+        (
+            "zenity",
+            vec![
+                "--entry".to_string(),
+                format!("--title={}", title),
+                format!("--text={}", label),
+                format!("--entry-text={}", default),
+            ],
+        ),
+        (
+            "kdialog",
+            vec!["--title".to_string(), title.to_string(), "--inputbox".to_string(), label.to_string(), default.to_string()],
+        ),
+    ];
+
+    for (program, args) in commands {
+        match Command::new(program).args(&args).output() {
+            // Non-zero exit status means the dialog was cancelled
+            Ok(output) if output.status.success() => {
+                let value = String::from_utf8_lossy(&output.stdout).trim_end_matches(['\r', '\n']).to_string();
+                // The Windows InputBox returns an empty string when cancelled
+                return if cfg!(windows) && value.is_empty() { None } else { Some(value) };
+            }
+            Ok(_) => return None,
+            Err(e) => warn!("Could not run {}: {}", program, e),
+        }
+    }
+    warn!("No dialog program available to ask for '{}'", label);
+    None
+}
+
+/// Ask for a value on the terminal; an empty answer takes the default
+pub fn terminal(label: &str, default: Option<&str>) -> Option<String> {
+    match default {
+        Some(default) => print!("{} [{}]: ", label, default),
+        None => print!("{}: ", label),
+    }
+    io::stdout().flush().ok()?;
+
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line).ok()? == 0 {
+        // End of input
+        return None;
+    }
+    let value = line.trim_end_matches(['\r', '\n']);
+    match (value.is_empty(), default) {
+        (true, Some(default)) => Some(default.to_string()),
+        _ => Some(value.to_string()),
+    }
+}
+
+/// Ask a yes/no question in a native dialog; false if declined or no dialog is available
+pub fn confirm(title: &str, question: &str) -> bool {
+    #[cfg(windows)]
+    let commands = {
+        let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; [System.Windows.Forms.MessageBox]::Show({}, {}, 'YesNo')",
+            quote(question),
+            quote(title)
+        );
+        vec![("powershell", vec!["-NoProfile".to_string(), "-Command".to_string(), script])]
+    };
+
+    #[cfg(target_os = "macos")]
+    let commands = {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "button returned of (display dialog {} with title {} buttons {{\"No\", \"Yes\"}} default button \"No\")",
+            quote(question),
+            quote(title)
+        );
+        vec![("osascript", vec!["-e".to_string(), script])]
+    };
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let commands = vec![
+        (
+            "zenity",
+            vec!["--question".to_string(), format!("--title={}", title), format!("--text={}", question)],
+        ),
+        ("kdialog", vec!["--title".to_string(), title.to_string(), "--yesno".to_string(), question.to_string()]),
+    ];
+
+    for (program, args) in commands {
+        match Command::new(program).args(&args).output() {
+            Ok(output) if output.status.success() => {
+                // zenity and kdialog answer with the exit status, the others print the button
+                let answer = String::from_utf8_lossy(&output.stdout);
+                return answer.trim().is_empty() || answer.trim() == "Yes";
+            }
+            Ok(_) => return false,
+            Err(e) => warn!("Could not run {}: {}", program, e),
+        }
+    }
+    warn!("No dialog program available to confirm '{}'", question);
+    false
+}
+
+/// Ask a yes/no question on the terminal; only "y" or "yes" confirms
+pub fn terminal_confirm(question: &str) -> bool {
+    print!("{} [y/N]: ", question);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut line = String::new();
+    if io::stdin().lock().read_line(&mut line).is_err() {
+        return false;
+    }
+    matches!(line.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
@@ -0,0 +1,81 @@
+use anyhow::Result;
+use tracing::{error, info};
+use url::Url;
+
+use crate::clipboard_handler::{ClipboardData, ClipboardHandler};
+use crate::file_scan;
+use crate::monitor::{ClipboardMonitor, MonitorEvent, ScanOutcome};
+use crate::qr_scanner::ScanSettings;
+use crate::redaction::Redactor;
+use crate::remote::{PushClient, PushPayload, PushSource};
+
+pub struct PushOptions {
+    pub to: Url,
+    pub token: Option<String>,
+    pub watch: bool,
+    pub text: Option<String>,
+    pub scan: ScanSettings,
+}
+
+/// Forward clipboard text or decoded QR content to another instance
+pub fn run(options: PushOptions, redactor: Redactor) -> Result<()> {
+    let client = PushClient::new(&options.to, options.token)?;
+
+    if !options.watch {
+        let payload = match options.text {
+            Some(text) => PushPayload { text, source: PushSource::Clipboard, origin: None },
+            None => read_clipboard(&options.scan)?,
+        };
+        client.push(&payload)?;
+        println!("✅ Sent {} to {}", redactor.text(&payload.text), client.endpoint());
+        return Ok(());
+    }
+
+    info!("Forwarding clipboard changes to {}", client.endpoint());
+    println!("Forwarding clipboard changes to {} (Ctrl+C to stop)", client.endpoint());
+
+    let scan = options.scan;
+    ClipboardMonitor::new()
+        .with_scanner(move || scan.scanner())
+        .start(move |event, _context| {
+            let payload = match event {
+                MonitorEvent::Text(text) => PushPayload { text: text.clone(), source: PushSource::Clipboard, origin: None },
+                MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } => {
+                    PushPayload { text: content.clone(), source: PushSource::QrScan, origin: None }
+                }
+                _ => return,
+            };
+
+            match client.push(&payload) {
+                Ok(()) => println!("➡️  {}", redactor.text(&payload.text)),
+                Err(e) => {
+                    error!("Push failed: {}", e);
+                    println!("❌ {}", e);
+                }
+            }
+        })
+        .join();
+
+    Ok(())
+}
+
+/// The clipboard text, or the content of a QR code in a clipboard image
+pub fn read_clipboard(scan: &ScanSettings) -> Result<PushPayload> {
+    let mut handler = ClipboardHandler::builder().spawn_listener(false).build();
+    match handler.get_data()? {
+        ClipboardData::Text(text) => Ok(PushPayload { text, source: PushSource::Clipboard, origin: None }),
+        ClipboardData::Image(image) => match scan.scanner().scan_qr_from_rgba(&image)? {
+            Some(text) => Ok(PushPayload { text, source: PushSource::QrScan, origin: None }),
+            None => Err(anyhow::anyhow!("No QR code found in clipboard image")),
+        },
+        ClipboardData::Files(files) => {
+            let path = file_scan::single_image(&files).ok_or_else(|| anyhow::anyhow!("Clipboard holds copied files, not an image"))?;
+            match scan.scanner().scan_qr_from_file(path)? {
+                Some(text) => Ok(PushPayload { text, source: PushSource::QrScan, origin: None }),
+                None => Err(anyhow::anyhow!("No QR code found in {}", path.display())),
+            }
+        }
+        ClipboardData::Empty => Err(anyhow::anyhow!("Clipboard is empty")),
+        ClipboardData::Excluded => Err(anyhow::anyhow!("Clipboard content is marked private by its source")),
+    }
+}
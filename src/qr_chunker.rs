@@ -0,0 +1,156 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+/// Start of every part: `QRPART:<index>/<total>:<id>:` followed by the part's text
+const HEADER_PREFIX: &str = "QRPART:";
+
+/// Text per part, in bytes; keeps each code around version 27, which phones still scan easily
+pub const PART_BYTES: usize = 1000;
+
+/// Sets being collected at once; scanning parts of a newer set pushes out the oldest
+const PENDING_SETS: usize = 4;
+
+/// One code of a multi-part sequence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part<'a> {
+    /// 1-based
+    pub index: usize,
+    pub total: usize,
+    /// Shared by all parts of a set, so parts of different texts don't mix
+    pub id: &'a str,
+    pub data: &'a str,
+}
+
+impl<'a> Part<'a> {
+    /// The part header and data in `text`, or `None` for an ordinary code
+    pub fn parse(text: &'a str) -> Option<Self> {
+        let rest = text.strip_prefix(HEADER_PREFIX)?;
+        let (position, rest) = rest.split_once(':')?;
+        let (id, data) = rest.split_once(':')?;
+        let (index, total) = position.split_once('/')?;
+        let (index, total) = (index.parse().ok()?, total.parse().ok()?);
+        (index >= 1 && index <= total).then_some(Self { index, total, id, data })
+    }
+}
+
+/// Split `text` into parts of at most `part_bytes` bytes of text each, with headers,
+/// never splitting a character; text that fits is returned as is
+pub fn split(text: &str, part_bytes: usize) -> Vec<String> {
+    if text.len() <= part_bytes {
+        return vec![text.to_string()];
+    }
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = part_bytes.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        // A single character wider than a part still has to go somewhere
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        let (piece, tail) = rest.split_at(end);
+        pieces.push(piece);
+        rest = tail;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    let id = format!("{:08x}", hasher.finish() as u32);
+    let total = pieces.len();
+    pieces
+        .iter()
+        .enumerate()
+        .map(|(index, piece)| format!("{}{}/{}:{}:{}", HEADER_PREFIX, index + 1, total, id, piece))
+        .collect()
+}
+
+/// What scanning one more code gave
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reassembly {
+    /// Not part of a multi-part sequence
+    Single,
+    /// A part was added; more are needed
+    Partial { received: usize, total: usize },
+    /// The last missing part arrived; the whole text, in order
+    Complete(String),
+}
+
+#[derive(Debug)]
+struct PendingSet {
+    id: String,
+    total: usize,
+    parts: BTreeMap<usize, String>,
+}
+
+/// Collects scanned parts in any order until a set is complete
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    pending: VecDeque<PendingSet>,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, text: &str) -> Reassembly {
+        let Some(part) = Part::parse(text) else {
+            return Reassembly::Single;
+        };
+        let position = self.pending.iter().position(|set| set.id == part.id && set.total == part.total);
+        let index = match position {
+            Some(index) => index,
+            None => {
+                if self.pending.len() == PENDING_SETS {
+                    self.pending.pop_front();
+                }
+                self.pending.push_back(PendingSet {
+                    id: part.id.to_string(),
+                    total: part.total,
+                    parts: BTreeMap::new(),
+                });
+                self.pending.len() - 1
+            }
+        };
+        let set = &mut self.pending[index];
+        set.parts.insert(part.index, part.data.to_string());
+        if set.parts.len() < set.total {
+            return Reassembly::Partial { received: set.parts.len(), total: set.total };
+        }
+        match self.pending.remove(index) {
+            Some(set) => Reassembly::Complete(set.parts.into_values().collect()),
+            None => Reassembly::Single,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_reassemble() {
+        assert_eq!(split("short", 10), ["short"]);
+
+        let text = "ä".repeat(12);
+        let parts = split(&text, 5);
+        assert_eq!(parts.len(), 6);
+        let first = Part::parse(&parts[0]).unwrap();
+        assert_eq!((first.index, first.total, first.data), (1, 6, "ää"));
+
+        // Parts arrive in any order, repeats and other codes in between don't matter
+        let mut reassembler = Reassembler::new();
+        assert_eq!(reassembler.add("https://example.com"), Reassembly::Single);
+        assert_eq!(reassembler.add(&parts[3]), Reassembly::Partial { received: 1, total: 6 });
+        assert_eq!(reassembler.add(&parts[3]), Reassembly::Partial { received: 1, total: 6 });
+        for part in [&parts[5], &parts[0], &parts[2], &parts[1]] {
+            assert!(matches!(reassembler.add(part), Reassembly::Partial { .. }));
+        }
+        assert_eq!(reassembler.add(&parts[4]), Reassembly::Complete(text));
+
+        assert!(Part::parse("QRPART:3/2:abc:x").is_none());
+    }
+}
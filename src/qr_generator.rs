@@ -1,165 +1,538 @@
-use qrcode::{QrCode, render::svg};
-use image::{ImageBuffer, Luma};
+use qrcode::{EcLevel, QrCode, Version};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
-use std::path::Path;
-use tracing::info;
-use eframe::egui::ColorImage;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing::{debug, info};
+use image::RgbaImage;
+
+use crate::matrix::BitMatrix;
+use crate::qr_chunker;
+use crate::qr_style::{Color, ModuleShape, QrStyle};
+use crate::renderer::{PdfRenderer, PngRenderer, QrRenderer, RendererRegistry, SvgRenderer};
+
+/// Kind of QR symbol to generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QrSymbology {
+    #[default]
+    Qr,
+    /// Micro QR: one finder pattern and 11 to 17 modules a side, for IDs and serial numbers
+    /// of up to 35 digits or 21 characters; longer text falls back to a standard code
+    MicroQr,
+}
+
+impl QrSymbology {
+    pub const ALL: [QrSymbology; 2] = [QrSymbology::Qr, QrSymbology::MicroQr];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            QrSymbology::Qr => "qr",
+            QrSymbology::MicroQr => "micro-qr",
+        }
+    }
+
+    /// Label for menus
+    pub fn label(self) -> &'static str {
+        match self {
+            QrSymbology::Qr => "QR Code",
+            QrSymbology::MicroQr => "Micro QR when it fits",
+        }
+    }
+}
+
+impl FromStr for QrSymbology {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|symbology| symbology.name() == s)
+            .ok_or_else(|| format!("unknown symbology '{}', expected qr or micro-qr", s))
+    }
+}
+
+impl fmt::Display for QrSymbology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
 
 pub struct QRGenerator {
+    style: QrStyle,
+    error_correction: EcLevel,
+    symbology: QrSymbology,
+    /// Where [`Self::save_qr_image`] writes
+    output_dir: PathBuf,
+    /// Pixels per module of PNG files, clipboard images and GUI textures
+    pixels_per_module: u32,
+    /// Largest side of those images in pixels, overriding `pixels_per_module`
+    target_size: Option<u32>,
+    renderers: RendererRegistry,
+}
+
+impl Default for QRGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl QRGenerator {
     pub fn new() -> Self {
+        Self::with_style(QrStyle::default())
+    }
+
+    /// A generator rendering PNG, SVG and GUI images with the given colours
+    pub fn with_style(style: QrStyle) -> Self {
         Self {
+            style,
+            error_correction: EcLevel::M,
+            symbology: QrSymbology::default(),
+            output_dir: PathBuf::from("output"),
+            pixels_per_module: PngRenderer::default().scale,
+            target_size: None,
+            renderers: RendererRegistry::new(),
         }
     }
 
-    pub fn generate_qr_image(&self, text: &str) -> Result<Option<ColorImage>> {
-        if text.is_empty() {
-            return Ok(None);
+    /// Draw `foreground` modules on `background` instead of the style's colours, keeping its
+    /// shape, quiet zone, frame and caption; fails for colours scanners can't read
+    pub fn with_colors(mut self, foreground: Color, background: Color) -> Result<Self> {
+        let custom = QrStyle::custom(foreground, background)?;
+        self.style = QrStyle { name: custom.name, foreground, background, ..self.style };
+        Ok(self)
+    }
+
+    /// Draw dark modules as `shape` in PNG, SVG, PDF and GUI images
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_shape(mut self, shape: ModuleShape) -> Self {
+        self.style.shape = shape;
+        self
+    }
+
+    /// Leave `modules` light modules around codes instead of 4
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_quiet_zone(mut self, modules: usize) -> Self {
+        self.style.quiet_zone = modules;
+        self
+    }
+
+    /// Draw a dark frame `modules` wide around the quiet zone of PNG and SVG exports
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_frame(mut self, modules: usize) -> Self {
+        self.style.frame = modules;
+        self
+    }
+
+    /// Put `caption`, e.g. "Scan me", in a band under the code of PNG and SVG exports
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_caption(mut self, caption: &str) -> Self {
+        self.style = self.style.with_caption(caption);
+        self
+    }
+
+    /// Encode text with `level` instead of M, e.g. H so a logo over the centre still scans,
+    /// or L for the smallest code a short text fits in
+    pub fn with_error_correction(mut self, level: EcLevel) -> Self {
+        self.error_correction = level;
+        self
+    }
+
+    /// Encode short text as `symbology` where it fits at this generator's level
+    pub fn with_symbology(mut self, symbology: QrSymbology) -> Self {
+        self.symbology = symbology;
+        self
+    }
+
+    /// Save codes without an explicit path in `dir` instead of ./output
+    pub fn with_output_dir(mut self, dir: &Path) -> Self {
+        self.output_dir = dir.to_path_buf();
+        self
+    }
+
+    /// Draw each module `pixels` pixels wide in PNG files, clipboard images and GUI textures
+    /// instead of 8
+    pub fn with_pixels_per_module(mut self, pixels: u32) -> Self {
+        self.pixels_per_module = pixels.max(1);
+        self.renderers.register(Box::new(self.png_renderer()));
+        self
+    }
+
+    /// Make those images as close to `size` pixels square as whole pixels per module allow,
+    /// whatever the code's version; None goes back to [`Self::with_pixels_per_module`]
+    pub fn with_target_size(mut self, size: Option<u32>) -> Self {
+        self.target_size = size;
+        self.renderers.register(Box::new(self.png_renderer()));
+        self
+    }
+
+    pub fn style(&self) -> &QrStyle {
+        &self.style
+    }
+
+    pub fn error_correction(&self) -> EcLevel {
+        self.error_correction
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn symbology(&self) -> QrSymbology {
+        self.symbology
+    }
+
+    /// The PNG renderer at this generator's module size or target size
+    fn png_renderer(&self) -> PngRenderer {
+        PngRenderer { scale: self.pixels_per_module, target_size: self.target_size }
+    }
+
+    /// Pixels per module of `code` in this generator's PNG and RGBA images
+    pub fn scale_for(&self, code: &QrCode) -> u32 {
+        self.png_renderer().scale_for(code.width() + 2 * self.style.quiet_zone)
+    }
+
+    /// `text` as a code at this generator's error correction level; the smallest Micro QR
+    /// version it fits in if the generator makes those, otherwise a standard code
+    pub fn encode(&self, text: &str) -> Result<QrCode> {
+        if self.symbology == QrSymbology::MicroQr {
+            // M1 only detects errors and H is beyond every version; both just don't fit
+            let micro = (1..=4).find_map(|version| QrCode::with_version(text, Version::Micro(version), self.error_correction).ok());
+            match micro {
+                Some(code) => return Ok(code),
+                None => debug!("Text too long for Micro QR at level {:?}; using a standard code", self.error_correction),
+            }
         }
+        Ok(QrCode::with_error_correction_level(text, self.error_correction)?)
+    }
 
-        // Generate QR code
-        let code = QrCode::new(text)?;
-        
-        // Convert to image buffer
-        let image_buffer = self.qr_code_to_image(&code)?;
-        
-        // Convert to RGBA
-        let rgba_image = self.convert_to_rgba(&image_buffer)?;
-        
-        // Convert to egui ColorImage
-        let size = [rgba_image.width() as usize, rgba_image.height() as usize];
-        let pixels: Vec<egui::Color32> = rgba_image
-            .pixels()
-            .map(|pixel| {
-                egui::Color32::from_rgba_premultiplied(
-                    pixel[0], pixel[1], pixel[2], pixel[3]
-                )
-            })
-            .collect();
-        
-        Ok(Some(ColorImage { size, pixels }))
-    }
-
-    fn qr_code_to_image(&self, code: &QrCode) -> Result<ImageBuffer<Luma<u8>, Vec<u8>>> {
-        let image = code.render()
-            .dark_color(Luma([0]))
-            .light_color(Luma([255]))
-            .build();
-        
-        Ok(image)
-    }
-
-    fn convert_to_rgba(&self, luma_image: &ImageBuffer<Luma<u8>, Vec<u8>>) -> Result<ImageBuffer<image::Rgba<u8>, Vec<u8>>> {
-        let mut rgba_image = ImageBuffer::new(luma_image.width(), luma_image.height());
-        
-        for (x, y, pixel) in luma_image.enumerate_pixels() {
-            let gray_value = pixel[0];
-            let rgba_pixel = image::Rgba([gray_value, gray_value, gray_value, 255]);
-            rgba_image.put_pixel(x, y, rgba_pixel);
+    /// The texts to encode for `text`: itself if it fits in one code at this generator's
+    /// level, otherwise a numbered sequence of parts that [`qr_chunker::Reassembler`] joins
+    pub fn parts(&self, text: &str) -> Vec<String> {
+        match QrCode::with_error_correction_level(text, self.error_correction) {
+            Err(qrcode::types::QrError::DataTooLong) => qr_chunker::split(text, qr_chunker::PART_BYTES),
+            _ => vec![text.to_string()],
         }
-        
-        Ok(rgba_image)
     }
 
-    pub fn save_qr_image(&self, text: &str) -> Result<()> {
+    /// Add an output format, or replace a built-in one with the same name or extension
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn register_renderer(&mut self, renderer: Box<dyn QrRenderer>) {
+        self.renderers.register(renderer);
+    }
+
+    pub fn renderers(&self) -> &RendererRegistry {
+        &self.renderers
+    }
+
+    /// Encode `text` with the renderer called `format`, e.g. "pdf" or "sixel"
+    pub fn render(&self, text: &str, format: &str) -> Result<Vec<u8>> {
         if text.is_empty() {
             return Err(anyhow::anyhow!("No text to generate QR code"));
         }
+        self.render_code(&self.encode(text)?, format)
+    }
 
-        // Generate QR code
-        let code = QrCode::new(text)?;
-        
-        // Create output directory if it doesn't exist
-        let output_dir = Path::new("output");
-        if !output_dir.exists() {
-            fs::create_dir_all(output_dir)?;
+    /// Render a code that was already encoded, e.g. with a fixed version, error correction
+    /// level or a custom encoder, with the renderer called `format`
+    pub fn render_code(&self, code: &QrCode, format: &str) -> Result<Vec<u8>> {
+        let renderer = self
+            .renderers
+            .get(format)
+            .ok_or_else(|| anyhow::anyhow!("Unknown output format '{}', expected one of: {}", format, self.renderers.names().join(", ")))?;
+        renderer.render(code, &self.style)
+    }
+
+    /// The code as an RGBA image at the size of PNG files, e.g. for copying to the clipboard
+    pub fn generate_rgba(&self, text: &str) -> Result<RgbaImage> {
+        Ok(self.rasterize_code(&self.encode(text)?))
+    }
+
+    /// The code as a PNG file in memory, e.g. for an HTTP response or to save elsewhere
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn generate_png_bytes(&self, text: &str) -> Result<Vec<u8>> {
+        self.render(text, "png")
+    }
+
+    /// The raw module grid for `text`, for rendering to displays or engines without a renderer here
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn generate_matrix(&self, text: &str) -> Result<BitMatrix> {
+        if text.is_empty() {
+            return Err(anyhow::anyhow!("No text to generate QR code"));
         }
-        
-        // Generate filename based on content hash
+        Ok(BitMatrix::from_code(&self.encode(text)?))
+    }
+
+    /// An already encoded code as an RGBA image at the size of PNG files
+    pub fn rasterize_code(&self, code: &QrCode) -> RgbaImage {
+        self.png_renderer().rasterize(code, &self.style)
+    }
+
+    /// The code as an RGBA image, or None for empty text; GUIs upload it as a texture
+    pub fn generate_qr_image(&self, text: &str) -> Result<Option<RgbaImage>> {
+        if text.is_empty() {
+            return Ok(None);
+        }
+        self.generate_rgba(text).map(Some)
+    }
+
+    /// Save the QR code as qr_code_<hash>.png in the output folder; returns the path written
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn save_qr_image(&self, text: &str) -> Result<PathBuf> {
+        if text.is_empty() {
+            return Err(anyhow::anyhow!("No text to generate QR code"));
+        }
+
+        let filepath = self.output_dir.join(Self::default_file_name(text));
+        crate::storage::check_write("a QR image", &filepath)?;
+
+        self.save_qr_image_to(text, &filepath)
+    }
+
+    /// qr_code_<hash>.png, the name suggested for saving the code of `text`
+    pub fn default_file_name(text: &str) -> String {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
         let mut hasher = DefaultHasher::new();
         text.hash(&mut hasher);
-        let hash = hasher.finish();
-        let filename = format!("qr_code_{:x}.png", hash);
-        let filepath = output_dir.join(filename);
-        
-        // Convert to image and save
-        let image_buffer = self.qr_code_to_image(&code)?;
-        image_buffer.save(&filepath)?;
-        
-        info!("QR code saved to: {:?}", filepath);
-        Ok(())
+        format!("qr_code_{:x}.png", hasher.finish())
     }
 
-    pub fn generate_svg(&self, text: &str) -> Result<String> {
+    /// Save the QR code to `path` with the renderer registered for its extension, PNG if there
+    /// is none, replacing any file already there; returns the path written
+    pub fn save_qr_image_to(&self, text: &str, path: &Path) -> Result<PathBuf> {
         if text.is_empty() {
             return Err(anyhow::anyhow!("No text to generate QR code"));
         }
+        self.save_code_to(&self.encode(text)?, path)?;
+        Ok(path.to_path_buf())
+    }
+
+    /// Save an already encoded code to `path`, chosen by extension like [`Self::save_qr_image_to`]
+    pub fn save_code_to(&self, code: &QrCode, path: &Path) -> Result<()> {
+        // Create output directory if it doesn't exist
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent)?;
+            }
+        }
 
-        let code = QrCode::new(text)?;
-        let svg_string = code.render()
-            .min_dimensions(300, 300)
-            .dark_color(svg::Color("#000000"))
-            .light_color(svg::Color("#ffffff"))
-            .build();
-        
-        Ok(svg_string)
+        let bytes = match self.renderers.for_path(path) {
+            Some(renderer) => renderer.render(code, &self.style)?,
+            None => self.png_renderer().render(code, &self.style)?,
+        };
+        fs::write(path, bytes)?;
+
+        info!("QR code saved to: {:?}", path);
+        Ok(())
     }
 
-    pub fn print_qr_terminal(&self, text: &str) -> Result<()> {
+    /// Save one PDF with a page per text, in order
+    pub fn save_pdf_document(&self, texts: &[&str], path: &Path) -> Result<()> {
+        let codes = texts
+            .iter()
+            .map(|text| self.encode(text))
+            .collect::<Result<Vec<_>>>()?;
+        self.save_pdf_codes(&codes, path)
+    }
+
+    /// Save one PDF with a page per already encoded code, in order
+    pub fn save_pdf_codes(&self, codes: &[QrCode], path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(path, PdfRenderer::default().render_document(codes, &self.style)?)?;
+
+        info!("{} QR code(s) saved to: {:?}", codes.len(), path);
+        Ok(())
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn generate_svg(&self, text: &str) -> Result<String> {
+        Ok(String::from_utf8(self.render(text, "svg")?)?)
+    }
+
+    /// The code as SVG with another size, title or footer than the registered SVG renderer's;
+    /// colours and quiet zone come from this generator's style
+    pub fn generate_svg_with(&self, text: &str, svg: &SvgRenderer) -> Result<String> {
         if text.is_empty() {
             return Err(anyhow::anyhow!("No text to generate QR code"));
         }
+        Ok(String::from_utf8(svg.render(&self.encode(text)?, &self.style)?)?)
+    }
 
-        let code = QrCode::new(text)?;
-        let string = code.render()
-            .dark_color(' ')
-            .light_color('█')
-            .build();
-        
-        // 使用 Unicode 块字符压缩显示
-        let lines: Vec<&str> = string.lines().collect();
-        let mut compressed_lines = Vec::new();
-        
-        // 每两行合并为一行，使用上半个块和下半个块
-        for i in (0..lines.len()).step_by(2) {
-            if i + 1 < lines.len() {
-                let top_line = lines[i];
-                let bottom_line = lines[i + 1];
-                
-                let mut compressed_line = String::new();
-                for (top_char, bottom_char) in top_line.chars().zip(bottom_line.chars()) {
-                    let block_char = match (top_char, bottom_char) {
-                        ('█', '█') => '█',    // 全黑
-                        (' ', ' ') => ' ',    // 全白
-                        ('█', ' ') => '▀',    // 上黑下白
-                        (' ', '█') => '▄',    // 上白下黑
-                        _ => ' ',             // 默认白色
-                    };
-                    compressed_line.push(block_char);
-                }
-                compressed_lines.push(compressed_line);
-            } else {
-                // 处理最后一行（如果总行数是奇数）
-                let top_line = lines[i];
-                let mut compressed_line = String::new();
-                for top_char in top_line.chars() {
-                    let block_char = if top_char == '█' { '▀' } else { ' ' };
-                    compressed_line.push(block_char);
-                }
-                compressed_lines.push(compressed_line);
+    /// Print the code to the terminal; text too long for one code is printed as a sequence
+    /// of parts to scan one after the other
+    pub fn print_qr_terminal(&self, text: &str) -> Result<()> {
+        let parts = self.parts(text);
+        for (index, part) in parts.iter().enumerate() {
+            if parts.len() > 1 {
+                println!("Part {}/{}:", index + 1, parts.len());
             }
+            print!("{}", String::from_utf8(self.render(part, "terminal")?)?);
         }
-        
-        // 打印压缩后的 QR 码
-        for line in compressed_lines {
-            println!("{}", line);
-        }
-        
         Ok(())
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prebuilt_code() {
+        let generator = QRGenerator::new();
+        // A fixed version and level that `QrCode::new` wouldn't pick for this text
+        let code = QrCode::with_version("hi", Version::Normal(5), EcLevel::H).unwrap();
+
+        let image = generator.rasterize_code(&code);
+        let scale = PngRenderer::default().scale;
+        assert_eq!(image.width(), (37 + 8) * scale);
+        assert_eq!(generator.render_code(&code, "png").unwrap(), generator.renderers().get("png").unwrap().render(&code, generator.style()).unwrap());
+        assert!(generator.render_code(&code, "bmp").is_err());
+
+        struct Bmp;
+        impl QrRenderer for Bmp {
+            fn name(&self) -> &str {
+                "bmp"
+            }
+
+            fn render(&self, _code: &QrCode, _style: &QrStyle) -> Result<Vec<u8>> {
+                Ok(b"BM".to_vec())
+            }
+        }
+        let mut generator = generator;
+        generator.register_renderer(Box::new(Bmp));
+        assert_eq!(generator.render_code(&code, "bmp").unwrap(), b"BM");
+    }
+
+    #[test]
+    fn test_png_bytes() {
+        let generator = QRGenerator::new();
+        let png = image::load_from_memory(&generator.generate_png_bytes("https://example.com").unwrap()).unwrap();
+        let rgba = generator.generate_rgba("https://example.com").unwrap();
+        assert_eq!(png.to_rgba8(), rgba);
+        assert!(generator.generate_png_bytes("").is_err());
+    }
+
+    #[test]
+    fn test_module_size() {
+        // "hi" is 21 modules, 29 with the quiet zone
+        let generator = QRGenerator::new().with_pixels_per_module(3);
+        assert_eq!(generator.generate_rgba("hi").unwrap().width(), 29 * 3);
+        let png = image::load_from_memory(&generator.generate_png_bytes("hi").unwrap()).unwrap();
+        assert_eq!(png.width(), 29 * 3);
+
+        // The most whole pixels per module within the target, whatever the version
+        let generator = generator.with_target_size(Some(300));
+        assert_eq!(generator.scale_for(&generator.encode("hi").unwrap()), 10);
+        assert_eq!(generator.generate_rgba("hi").unwrap().width(), 290);
+        let long = generator.generate_rgba("https://example.com/a/much/longer/link").unwrap();
+        assert!(long.width() <= 300 && long.width() > 250);
+        let dir = std::env::temp_dir().join(format!("clipboard-qr-size-{}", std::process::id()));
+        let path = generator.save_qr_image_to("hi", &dir.join("hi.png")).unwrap();
+        assert_eq!(image::open(&path).unwrap().width(), 290);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(generator.with_target_size(None).generate_rgba("hi").unwrap().width(), 29 * 3);
+        assert_eq!(QRGenerator::new().with_target_size(Some(10)).generate_rgba("hi").unwrap().width(), 29);
+    }
+
+    #[test]
+    fn test_error_correction() {
+        let default = QRGenerator::new();
+        let high = QRGenerator::new().with_error_correction(EcLevel::H);
+        assert_eq!(default.generate_matrix("https://example.com").unwrap().error_correction(), EcLevel::M);
+        assert_eq!(high.generate_matrix("https://example.com").unwrap().error_correction(), EcLevel::H);
+        // More redundancy needs more modules for the same text
+        let side = |generator: &QRGenerator| generator.generate_qr_image("https://example.com").unwrap().unwrap().width();
+        assert!(side(&high) > side(&default));
+        assert_ne!(default.generate_svg("https://example.com").unwrap(), high.generate_svg("https://example.com").unwrap());
+    }
+
+    #[test]
+    fn test_save_to_path() {
+        let generator = QRGenerator::new();
+        let dir = std::env::temp_dir().join(format!("clipboard-qr-save-{}", std::process::id()));
+        let path = dir.join("wifi.svg");
+        assert_eq!(generator.save_qr_image_to("WIFI:S:home;;", &path).unwrap(), path);
+        assert!(fs::read_to_string(&path).unwrap().contains("<svg"));
+        // Saving again replaces the file
+        generator.save_qr_image_to("WIFI:S:office;;", &path).unwrap();
+        assert!(QRGenerator::default_file_name("WIFI:S:home;;").starts_with("qr_code_"));
+        // Without a path, the code goes to the output folder under its default name
+        let saved = generator.with_output_dir(&dir).save_qr_image("WIFI:S:home;;").unwrap();
+        assert_eq!(saved, dir.join(QRGenerator::default_file_name("WIFI:S:home;;")));
+        assert!(saved.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_shapes_and_colors() {
+        let navy = Color::rgb(0x00, 0x3F, 0x73);
+        let generator = QRGenerator::new()
+            .with_colors(navy, Color::WHITE)
+            .unwrap()
+            .with_shape(ModuleShape::Dots)
+            .with_quiet_zone(2);
+        assert!(QRGenerator::new().with_colors(Color::WHITE, navy).is_err());
+
+        let image = generator.generate_rgba("hi").unwrap();
+        let scale = PngRenderer::default().scale;
+        assert_eq!(image.width(), (21 + 4) * scale);
+        // Finder modules stay full squares; the dots of the timing pattern leave the corners
+        // of their modules light
+        assert_eq!(*image.get_pixel(2 * scale, 2 * scale), navy.to_rgba());
+        let timing = (2 + 6) * scale;
+        assert_eq!(*image.get_pixel(timing + scale / 2, (2 + 8) * scale + scale / 2), navy.to_rgba());
+        assert_eq!(*image.get_pixel(timing, (2 + 8) * scale), Color::WHITE.to_rgba());
+
+        let svg = generator.generate_svg("hi").unwrap();
+        assert!(svg.contains("viewBox=\"0 0 25 25\"") && svg.contains("#003F73") && svg.contains('C'));
+        assert!(!QRGenerator::new().generate_svg("hi").unwrap().contains('C'));
+        let captioned = SvgRenderer { min_size: 500, title: Some("Guest <Wi-Fi>".to_string()), footer: Some("Scan me".to_string()) };
+        let svg = generator.generate_svg_with("hi", &captioned).unwrap();
+        // 20 pixels per module, with three-module bands above and below
+        assert!(svg.contains("width=\"500\" height=\"620\" viewBox=\"0 0 25 31\""));
+        assert!(svg.contains(">Guest &lt;Wi-Fi&gt;</text>") && svg.contains("translate(0 3)"));
+        let framed = QRGenerator::new().with_frame(1).with_caption("Scan me");
+        assert_eq!((framed.style().frame, framed.style().caption.as_deref()), (1, Some("Scan me")));
+        let rounded = QRGenerator::new().with_shape(ModuleShape::Rounded);
+        assert!(String::from_utf8(rounded.render("hi", "pdf").unwrap()).unwrap().contains(" c\n"));
+    }
+
+    #[test]
+    fn test_micro_qr() {
+        let generator = QRGenerator::new().with_symbology(QrSymbology::MicroQr).with_error_correction(EcLevel::L);
+        assert_eq!(generator.symbology(), QrSymbology::MicroQr);
+        let code = generator.encode("12345").unwrap();
+        assert_eq!(code.version(), Version::Micro(1));
+        assert_eq!(generator.encode("SN-2024-000123").unwrap().version(), Version::Micro(3));
+        // Too long for M4, or a level no Micro QR version has
+        assert!(!generator.encode("https://example.com/products/1234").unwrap().version().is_micro());
+        let high = QRGenerator::new().with_symbology(QrSymbology::MicroQr).with_error_correction(EcLevel::H);
+        assert!(!high.encode("12345").unwrap().version().is_micro());
+
+        // Only the top left corner has a finder pattern
+        let image = generator.with_shape(ModuleShape::Dots).with_quiet_zone(2).generate_rgba("12345").unwrap();
+        let scale = PngRenderer::default().scale;
+        assert_eq!(image.width(), (11 + 4) * scale);
+        // The dark timing module at the far end of the top row is a dot, not a finder square
+        assert_eq!(*image.get_pixel((2 + 10) * scale, 2 * scale), Color::WHITE.to_rgba());
+        assert_ne!(*image.get_pixel((2 + 10) * scale + scale / 2, 2 * scale + scale / 2), Color::WHITE.to_rgba());
+        assert_eq!("micro-qr".parse::<QrSymbology>(), Ok(QrSymbology::MicroQr));
+        assert!("rmqr".parse::<QrSymbology>().is_err());
+    }
+
+    #[test]
+    fn test_parts() {
+        let generator = QRGenerator::new();
+        assert_eq!(generator.parts("short"), ["short"]);
+        // Too long for any single code at level M
+        let long = "x".repeat(5000);
+        let parts = generator.parts(&long);
+        assert_eq!(parts.len(), 5);
+        assert!(parts.iter().all(|part| generator.encode(part).is_ok()));
+    }
+}
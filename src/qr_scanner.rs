@@ -1,60 +1,535 @@
-use bardecoder;
-use image::{ImageBuffer, Rgba, DynamicImage};
+use image::{ImageBuffer, ImageError, Rgba, DynamicImage, GrayImage, RgbaImage};
+use image::codecs::gif::GifDecoder;
+use image::imageops::FilterType;
+use image::AnimationDecoder;
 use anyhow::Result;
-use tracing::{warn, debug};
+use rxing::BarcodeFormat;
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::{info, warn, debug};
+
+use crate::preprocess::{NamedPreset, RetryStep};
+use crate::qr_chunker::{Part, Reassembler, Reassembly};
+use crate::qr_style::Color;
+use crate::scan_trace::ScanTrace;
+use crate::transforms::TextTransforms;
+
+/// Longest side images from files are scaled down to before scanning; photos from phones are
+/// several times this, which only makes the detector slower
+const MAX_SCAN_DIMENSION: u32 = 2048;
+
+/// Frames read from an animated GIF; transfer loops repeat well before this
+const MAX_GIF_FRAMES: usize = 1000;
+
+/// How a [`QRScanner`] prepares images and post-processes what it decodes
+///
+/// Scanners are built on the thread that uses them, so this is what gets passed around.
+#[derive(Debug, Clone)]
+pub struct ScanSettings {
+    /// Colour transparent pixels are composited over before scanning; white by default,
+    /// black suits light codes on a transparent background
+    pub matte: Color,
+    /// Applied to decoded text before it is returned
+    pub transforms: TextTransforms,
+    /// Tried in order when the image as copied doesn't decode
+    pub presets: Vec<NamedPreset>,
+    /// Try the built-in [`RetryStep`]s after the presets
+    pub retries: bool,
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        Self {
+            matte: Color::WHITE,
+            transforms: TextTransforms::default(),
+            presets: Vec::new(),
+            retries: true,
+        }
+    }
+}
+
+impl ScanSettings {
+    pub fn scanner(&self) -> QRScanner {
+        QRScanner::new().with_settings(self.clone())
+    }
+}
+
+/// Kind of 2D code; QR codes are decoded with bardecoder, the rest with rxing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symbology {
+    QrCode,
+    MicroQr,
+    DataMatrix,
+    Aztec,
+    Pdf417,
+}
+
+impl Symbology {
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub const ALL: [Symbology; 5] = [Symbology::QrCode, Symbology::MicroQr, Symbology::DataMatrix, Symbology::Aztec, Symbology::Pdf417];
+
+    /// Looked for, in this order, when no QR code decodes
+    const FALLBACKS: [Symbology; 4] = [Symbology::MicroQr, Symbology::DataMatrix, Symbology::Aztec, Symbology::Pdf417];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Symbology::QrCode => "qr-code",
+            Symbology::MicroQr => "micro-qr-code",
+            Symbology::DataMatrix => "data-matrix",
+            Symbology::Aztec => "aztec",
+            Symbology::Pdf417 => "pdf417",
+        }
+    }
+
+    /// Label for output
+    pub fn label(self) -> &'static str {
+        match self {
+            Symbology::QrCode => "QR code",
+            Symbology::MicroQr => "Micro QR code",
+            Symbology::DataMatrix => "Data Matrix code",
+            Symbology::Aztec => "Aztec code",
+            Symbology::Pdf417 => "PDF417 code",
+        }
+    }
+
+    fn format(self) -> BarcodeFormat {
+        match self {
+            Symbology::QrCode => BarcodeFormat::QR_CODE,
+            Symbology::MicroQr => BarcodeFormat::MICRO_QR_CODE,
+            Symbology::DataMatrix => BarcodeFormat::DATA_MATRIX,
+            Symbology::Aztec => BarcodeFormat::AZTEC,
+            Symbology::Pdf417 => BarcodeFormat::PDF_417,
+        }
+    }
+}
+
+impl fmt::Display for Symbology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Pixel rectangle around a code, in image coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BoundingBox {
+    /// The smallest rectangle around `corners`, clipped to a `width`x`height` image; the
+    /// whole image without any
+    pub fn around(corners: &[(f64, f64)], width: u32, height: u32) -> Self {
+        if corners.is_empty() {
+            return Self { x: 0, y: 0, width, height };
+        }
+        let clamp = |value: f64, max: u32| value.round().clamp(0.0, max as f64) as u32;
+        let (xs, ys): (Vec<f64>, Vec<f64>) = corners.iter().copied().unzip();
+        let left = clamp(xs.iter().copied().fold(f64::INFINITY, f64::min), width);
+        let right = clamp(xs.iter().copied().fold(f64::NEG_INFINITY, f64::max), width);
+        let top = clamp(ys.iter().copied().fold(f64::INFINITY, f64::min), height);
+        let bottom = clamp(ys.iter().copied().fold(f64::NEG_INFINITY, f64::max), height);
+        Self { x: left, y: top, width: right - left, height: bottom - top }
+    }
+
+    fn overlaps(&self, other: &BoundingBox) -> bool {
+        self.x < other.x + other.width && other.x < self.x + self.width && self.y < other.y + other.height && other.y < self.y + self.height
+    }
+}
+
+/// One of the codes found by [`QRScanner::scan_multiple_qr_codes`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedCode {
+    /// Decoded text, with the transforms applied
+    pub content: String,
+    pub bounds: BoundingBox,
+    pub symbology: Symbology,
+}
 
 pub struct QRScanner {
     decoder: bardecoder::Decoder<DynamicImage, image::GrayImage, String>,
+    settings: ScanSettings,
+    /// Parts of multi-part codes scanned so far
+    reassembler: Mutex<Reassembler>,
+}
+
+impl Default for QRScanner {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl QRScanner {
     pub fn new() -> Self {
         Self {
             decoder: bardecoder::default_decoder(),
+            settings: ScanSettings::default(),
+            reassembler: Mutex::new(Reassembler::new()),
         }
     }
 
+    pub fn with_settings(mut self, settings: ScanSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
     /// Scan QR code from an RGBA image
     pub fn scan_qr_from_rgba(&self, image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Result<Option<String>> {
+        Ok(self.scan_code_from_rgba(image)?.map(|(content, _)| content))
+    }
+
+    /// Scan an RGBA image for a QR code, or failing that a Micro QR, Data Matrix, Aztec or
+    /// PDF417 code
+    pub fn scan_code_from_rgba(&self, image: &RgbaImage) -> Result<Option<(String, Symbology)>> {
         debug!("Scanning QR code from RGBA image ({}x{})", image.width(), image.height());
-        
-        // Convert RGBA to DynamicImage for bardecoder
-        let dynamic_image = DynamicImage::ImageRgba8(image.clone());
-        
-        // Try to decode QR code
-        let results = self.decoder.decode(&dynamic_image);
-        
-        if !results.is_empty() {
-            // Take the first successful result
-            if let Some(result) = results.first() {
-                match result {
-                    Ok(content) => {
-                        debug!("QR code detected: {}", content);
-                        Ok(Some(content.clone()))
-                    },
-                    Err(e) => {
-                        warn!("QR code detected but failed to decode: {}", e);
-                        Ok(None)
-                    },
-                }
-            } else {
+        match self.decode_prepared(image) {
+            Some((content, symbology)) => {
+                debug!("{} detected ({} chars)", symbology.label(), content.chars().count());
+                let content = self.reassemble(content);
+                Ok(Some((self.settings.transforms.apply(&content), symbology)))
+            }
+            None => {
+                debug!("No QR code found in image");
                 Ok(None)
             }
-        } else {
-            debug!("No QR code found in image");
-            Ok(None)
         }
     }
+
+    /// Scan an image file (PNG, JPEG, BMP, WebP, TIFF or anything else the image crate reads);
+    /// only the first page of a TIFF or frame of a GIF is scanned, see [`file_scan`](crate::file_scan)
+    /// and [`Self::scan_qr_from_gif`] for the rest
+    pub fn scan_qr_from_file(&self, path: &Path) -> Result<Option<String>> {
+        info!("Scanning QR code from file {:?}", path);
+        self.scan_qr_from_rgba(&load_image(path)?)
+    }
+
+    /// Scan every frame of an animated GIF, e.g. from tools that transfer data as a loop of
+    /// rotating codes; returns each distinct code once, in the order first seen
+    ///
+    /// Multi-part codes go through the same reassembly as scans from the clipboard: once the
+    /// last missing part has been seen the whole text replaces its parts in the results.
+    pub fn scan_qr_from_gif(&self, path: &Path) -> Result<Vec<String>> {
+        info!("Scanning animated GIF {:?}", path);
+        let frames = load_gif_frames(path)?;
+        let mut results = Vec::new();
+        for (index, frame) in frames.iter().enumerate() {
+            let Some((content, _)) = self.decode_prepared(frame) else {
+                continue;
+            };
+            debug!("Frame {}: QR code detected ({} chars)", index + 1, content.chars().count());
+            let reassembled = self.reassemble(content.clone());
+            merge_frame_result(&mut results, &content, reassembled);
+        }
+        debug!("Found {} distinct QR code(s) in {} frame(s)", results.len(), frames.len());
+        Ok(results.iter().map(|content| self.settings.transforms.apply(content)).collect())
+    }
+
+    /// The first code in `image` over the matte, trying the preprocessing presets and then
+    /// the built-in retry steps if it doesn't decode as is; nothing is reassembled or
+    /// transformed yet
+    fn decode_prepared(&self, image: &RgbaImage) -> Option<(String, Symbology)> {
+        // Thresholding ignores alpha, so transparent areas would otherwise read as their
+        // (often black) hidden colour
+        let image = composite_over(image, self.settings.matte);
+
+        let mut content = self.decode_code(&image);
+        for preset in &self.settings.presets {
+            if content.is_some() {
+                break;
+            }
+            content = self.decode_code(&preset.settings.apply(&image));
+            if content.is_some() {
+                debug!("Decoded with preprocessing preset '{}'", preset.name);
+            }
+        }
+        if !self.settings.retries {
+            return content;
+        }
+        for step in RetryStep::SEQUENCE {
+            if content.is_some() {
+                break;
+            }
+            let Some(adjusted) = step.apply(&image) else { continue };
+            content = self.decode_code(&adjusted);
+            if content.is_some() {
+                debug!("Decoded after retry step: {}", step);
+            }
+        }
+        content
+    }
+
+    /// Every QR code in `image` with where it is, in reading order: top to bottom, then left
+    /// to right; preprocessing presets are only tried when nothing decodes as is. The retry
+    /// steps aren't, as rotating or resizing would move the positions
+    pub fn scan_multiple_qr_codes(&self, image: &RgbaImage) -> Result<Vec<DecodedCode>> {
+        debug!("Scanning for all QR codes in RGBA image ({}x{})", image.width(), image.height());
+        let image = composite_over(image, self.settings.matte);
+
+        let mut codes = self.decode_all(&image);
+        for preset in &self.settings.presets {
+            if !codes.is_empty() {
+                break;
+            }
+            codes = self.decode_all(&preset.settings.apply(&image));
+            if !codes.is_empty() {
+                debug!("Decoded {} code(s) with preprocessing preset '{}'", codes.len(), preset.name);
+            }
+        }
+
+        codes.sort_by_key(|code| (code.bounds.y, code.bounds.x));
+        for code in &mut codes {
+            let content = self.reassemble(std::mem::take(&mut code.content));
+            code.content = self.settings.transforms.apply(&content);
+        }
+        debug!("Found {} QR code(s)", codes.len());
+        Ok(codes)
+    }
+
+    /// Decode every candidate region of `image`; the detector can find the same code twice
+    fn decode_all(&self, image: &RgbaImage) -> Vec<DecodedCode> {
+        let mut codes: Vec<DecodedCode> = Vec::new();
+        for candidate in ScanTrace::run(image).candidates {
+            let bounds = BoundingBox::around(&candidate.corners(), image.width(), image.height());
+            let Ok(content) = candidate.outcome else { continue };
+            if codes.iter().any(|code| code.content == content && code.bounds.overlaps(&bounds)) {
+                continue;
+            }
+            codes.push(DecodedCode { content, bounds, symbology: Symbology::QrCode });
+        }
+        // rxing finds one code of each kind
+        let luma = image::imageops::grayscale(image);
+        for symbology in Symbology::FALLBACKS {
+            if let Some((content, corners)) = decode_symbology(&luma, symbology) {
+                let bounds = BoundingBox::around(&corners, image.width(), image.height());
+                codes.push(DecodedCode { content, bounds, symbology });
+            }
+        }
+        codes
+    }
+
+    /// The whole text once the last part of a multi-part sequence is scanned; parts before
+    /// that, and ordinary codes, come back as they are
+    fn reassemble(&self, content: String) -> String {
+        let Ok(mut reassembler) = self.reassembler.lock() else {
+            return content;
+        };
+        match reassembler.add(&content) {
+            Reassembly::Single => content,
+            Reassembly::Partial { received, total } => {
+                info!("Scanned {} of {} parts of a multi-part QR code", received, total);
+                content
+            }
+            Reassembly::Complete(text) => {
+                info!("Multi-part QR code complete ({} chars)", text.chars().count());
+                text
+            }
+        }
+    }
+
+    /// Decode the first QR code in `image` as is, without the matte, presets or transforms;
+    /// Micro QR, Data Matrix, Aztec and PDF417 codes are tried when there is none
+    pub fn decode(&self, image: &RgbaImage) -> Option<String> {
+        self.decode_code(image).map(|(content, _)| content)
+    }
+
+    /// [`Self::decode`] with the kind of code that decoded
+    fn decode_code(&self, image: &RgbaImage) -> Option<(String, Symbology)> {
+        let results = self.decoder.decode(&DynamicImage::ImageRgba8(image.clone()));
+        match results.into_iter().next() {
+            Some(Ok(content)) => return Some((content, Symbology::QrCode)),
+            Some(Err(e)) => warn!("QR code detected but failed to decode: {}", e),
+            None => {}
+        }
+        let luma = image::imageops::grayscale(image);
+        Symbology::FALLBACKS
+            .into_iter()
+            .find_map(|symbology| decode_symbology(&luma, symbology).map(|(content, _)| (content, symbology)))
+    }
+}
+
+/// The text and corner points of a `symbology` code in `luma`, through rxing
+fn decode_symbology(luma: &GrayImage, symbology: Symbology) -> Option<(String, Vec<(f64, f64)>)> {
+    let result = rxing::helpers::detect_in_luma(luma.as_raw().clone(), luma.width(), luma.height(), Some(symbology.format())).ok()?;
+    let corners = result.getPoints().iter().map(|point| (point.x as f64, point.y as f64)).collect();
+    Some((result.getText().to_string(), corners))
+}
+
+/// Read an image file for scanning, scaled down to [`MAX_SCAN_DIMENSION`] if it is larger
+pub fn load_image(path: &Path) -> Result<RgbaImage> {
+    let image = image::io::Reader::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?
+        .with_guessed_format()
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?
+        .decode()
+        .map_err(|e| match e {
+            ImageError::Unsupported(_) => {
+                anyhow::anyhow!("{} isn't an image format that can be scanned (PNG, JPEG, BMP, WebP or TIFF): {}", path.display(), e)
+            }
+            e => anyhow::anyhow!("Failed to decode {}: {}", path.display(), e),
+        })?;
+    Ok(downscale(image, MAX_SCAN_DIMENSION).to_rgba8())
+}
+
+/// Decode an image held in memory for scanning, e.g. an upload, scaled down like [`load_image`]
+pub fn load_image_from_memory(bytes: &[u8]) -> Result<RgbaImage> {
+    let image = image::load_from_memory(bytes).map_err(|e| anyhow::anyhow!("Failed to decode image: {}", e))?;
+    Ok(downscale(image, MAX_SCAN_DIMENSION).to_rgba8())
+}
+
+/// Add a code decoded from one frame of an animation to `results` unless it is already there;
+/// `reassembled` is what reassembly made of it, replacing the parts it was built from
+fn merge_frame_result(results: &mut Vec<String>, content: &str, reassembled: String) {
+    if reassembled != content {
+        if let Some(part) = Part::parse(content) {
+            results.retain(|result| Part::parse(result).is_none_or(|other| other.id != part.id));
+        }
+    }
+    if !results.contains(&reassembled) {
+        results.push(reassembled);
+    }
+}
+
+/// The frames of an animated GIF as full images, scaled down like [`load_image`]; only the
+/// first [`MAX_GIF_FRAMES`] are read
+pub fn load_gif_frames(path: &Path) -> Result<Vec<RgbaImage>> {
+    let file = File::open(path).map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?;
+    let decoder = GifDecoder::new(BufReader::new(file)).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let mut frames = Vec::new();
+    for frame in decoder.into_frames() {
+        if frames.len() == MAX_GIF_FRAMES {
+            warn!("{} has more than {} frames; scanning only those", path.display(), MAX_GIF_FRAMES);
+            break;
+        }
+        let frame = frame.map_err(|e| anyhow::anyhow!("Failed to decode frame {} of {}: {}", frames.len() + 1, path.display(), e))?;
+        frames.push(downscale(DynamicImage::ImageRgba8(frame.into_buffer()), MAX_SCAN_DIMENSION).to_rgba8());
+    }
+    Ok(frames)
+}
+
+/// `image` with its longest side at most `max`, keeping the aspect ratio
+fn downscale(image: DynamicImage, max: u32) -> DynamicImage {
+    if image.width() <= max && image.height() <= max {
+        return image;
+    }
+    debug!("Scaling {}x{} image down to at most {}px for scanning", image.width(), image.height(), max);
+    // Triangle keeps module edges sharp enough, Lanczos is slow on photo-sized images
+    image.resize(max, max, FilterType::Triangle)
+}
+
+/// Flatten `image` onto an opaque `matte`; borrows when the image has no transparency
+pub fn composite_over(image: &RgbaImage, matte: Color) -> Cow<'_, RgbaImage> {
+    if image.pixels().all(|pixel| pixel[3] == 255) {
+        return Cow::Borrowed(image);
+    }
+    debug!("Compositing transparent image over {}", matte.to_hex());
+
+    let blend = |channel: u8, alpha: u8, matte: u8| {
+        let (channel, alpha, matte) = (channel as u32, alpha as u32, matte as u32);
+        ((channel * alpha + matte * (255 - alpha) + 127) / 255) as u8
+    };
+    let mut flattened = image.clone();
+    for pixel in flattened.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        *pixel = Rgba([blend(r, a, matte.r), blend(g, a, matte.g), blend(b, a, matte.b), 255]);
+    }
+    Cow::Owned(flattened)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use image::{ImageBuffer, Rgba};
+    use crate::qr_chunker;
+    use image::codecs::gif::GifEncoder;
+    use image::{Frame, Rgba};
 
     #[test]
     fn test_scanner_creation() {
-        let scanner = QRScanner::new();
+        let _scanner = QRScanner::new();
         // Test that scanner can be created
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_load_image() {
+        let image = downscale(DynamicImage::new_rgba8(400, 100), 200);
+        assert_eq!((image.width(), image.height()), (200, 50));
+        let image = downscale(DynamicImage::new_rgba8(40, 10), 200);
+        assert_eq!((image.width(), image.height()), (40, 10));
+
+        let path = std::env::temp_dir().join(format!("clipboard-qr-not-an-image-{}.png", std::process::id()));
+        std::fs::write(&path, b"not an image").unwrap();
+        let error = load_image(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).ok();
+        assert!(error.contains("clipboard-qr-not-an-image"), "{}", error);
+        assert!(load_image(Path::new("/nonexistent/clipboard-qr.png")).unwrap_err().to_string().starts_with("Failed to open"));
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let corners = [(10.4, 20.0), (50.0, 18.6), (52.0, 61.0), (-3.0, 59.0)];
+        let bounds = BoundingBox::around(&corners, 40, 100);
+        assert_eq!(bounds, BoundingBox { x: 0, y: 19, width: 40, height: 42 });
+        assert!(bounds.overlaps(&BoundingBox { x: 39, y: 60, width: 5, height: 5 }));
+        assert!(!bounds.overlaps(&BoundingBox { x: 40, y: 0, width: 5, height: 5 }));
+    }
+
+    #[test]
+    fn test_symbologies() {
+        let names: std::collections::HashSet<&str> = Symbology::ALL.iter().map(|symbology| symbology.name()).collect();
+        assert_eq!(names.len(), Symbology::ALL.len());
+        assert!(!Symbology::FALLBACKS.contains(&Symbology::QrCode));
+        assert_eq!(Symbology::Pdf417.format(), BarcodeFormat::PDF_417);
+
+        // rxing may report a code without corner points
+        assert_eq!(BoundingBox::around(&[], 30, 20), BoundingBox { x: 0, y: 0, width: 30, height: 20 });
+        // A blank image holds no code of any kind
+        let blank = RgbaImage::from_pixel(64, 64, Rgba([255, 255, 255, 255]));
+        assert!(QRScanner::new().scan_multiple_qr_codes(&blank).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_composite_over() {
+        let opaque = RgbaImage::from_pixel(2, 2, Rgba([10, 20, 30, 255]));
+        assert!(matches!(composite_over(&opaque, Color::WHITE), Cow::Borrowed(_)));
+
+        // Transparent black, as many screenshot tools store the empty background
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([0, 0, 0, 0]));
+        image.put_pixel(1, 0, Rgba([0, 0, 0, 128]));
+        let flattened = composite_over(&image, Color::WHITE);
+        assert_eq!(flattened.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+        assert_eq!(flattened.get_pixel(1, 0), &Rgba([127, 127, 127, 255]));
+        assert_eq!(composite_over(&image, Color::BLACK).get_pixel(0, 0), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_gif_frames_and_merging() {
+        let path = std::env::temp_dir().join(format!("clipboard-qr-test-{}.gif", std::process::id()));
+        {
+            let mut encoder = GifEncoder::new(File::create(&path).unwrap());
+            let frames = [0u8, 255, 0].map(|shade| Frame::new(RgbaImage::from_pixel(16, 16, Rgba([shade, shade, shade, 255]))));
+            encoder.encode_frames(frames).unwrap();
+        }
+        let frames = load_gif_frames(&path);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(frames.unwrap().len(), 3);
+
+        // A loop of rotating parts with another code in between; repeats are kept once
+        let text = "x".repeat(30);
+        let parts = qr_chunker::split(&text, 10);
+        let mut reassembler = Reassembler::new();
+        let mut results = Vec::new();
+        for content in [&parts[1], &"https://example.com".to_string(), &parts[1], &parts[2], &parts[0], &parts[1]] {
+            let reassembled = match reassembler.add(content) {
+                Reassembly::Complete(text) => text,
+                _ => content.clone(),
+            };
+            merge_frame_result(&mut results, content, reassembled);
+        }
+        assert_eq!(results, ["https://example.com".to_string(), text, parts[1].clone()]);
+    }
+}
@@ -0,0 +1,318 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::matrix::QUIET_ZONE;
+
+/// Minimum WCAG contrast ratio between foreground and background for a style
+/// to be considered reliably scannable by phone cameras
+pub const MIN_CONTRAST_RATIO: f64 = 4.5;
+
+/// An sRGB colour, written as `#RRGGBB` in config files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    pub const WHITE: Self = Self::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let digits = hex.trim().trim_start_matches('#');
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow::anyhow!("Invalid colour '{}', expected #RRGGBB", hex));
+        }
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16);
+        Ok(Self::rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    pub fn to_hex(self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+
+    pub fn to_rgba(self) -> image::Rgba<u8> {
+        image::Rgba([self.r, self.g, self.b, 255])
+    }
+
+    /// WCAG 2.x relative luminance
+    pub fn relative_luminance(self) -> f64 {
+        let linear = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::from_hex(&value)
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> Self {
+        color.to_hex()
+    }
+}
+
+/// WCAG contrast ratio between two colours, from 1.0 to 21.0
+pub fn contrast_ratio(a: Color, b: Color) -> f64 {
+    let (la, lb) = (a.relative_luminance(), b.relative_luminance());
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// Built-in styles, all checked against [`MIN_CONTRAST_RATIO`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StylePreset {
+    /// Black on white
+    #[default]
+    Classic,
+    /// Pure black on pure white, the safe choice for laser and inkjet printing
+    HighContrastPrint,
+    /// Okabe-Ito blue on white, distinguishable under all common colour vision deficiencies
+    OkabeItoBlue,
+    /// Dark navy on light amber; blue/yellow stays distinct for red-green colour blindness
+    NavyAmber,
+    /// Dark brown on cream
+    Espresso,
+}
+
+impl StylePreset {
+    pub const ALL: [StylePreset; 5] = [
+        StylePreset::Classic,
+        StylePreset::HighContrastPrint,
+        StylePreset::OkabeItoBlue,
+        StylePreset::NavyAmber,
+        StylePreset::Espresso,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StylePreset::Classic => "classic",
+            StylePreset::HighContrastPrint => "high-contrast-print",
+            StylePreset::OkabeItoBlue => "okabe-ito-blue",
+            StylePreset::NavyAmber => "navy-amber",
+            StylePreset::Espresso => "espresso",
+        }
+    }
+
+    pub fn style(self) -> QrStyle {
+        let (foreground, background) = match self {
+            StylePreset::Classic | StylePreset::HighContrastPrint => (Color::BLACK, Color::WHITE),
+            StylePreset::OkabeItoBlue => (Color::rgb(0x00, 0x72, 0xB2), Color::WHITE),
+            StylePreset::NavyAmber => (Color::rgb(0x00, 0x3F, 0x73), Color::rgb(0xFF, 0xF3, 0xD6)),
+            StylePreset::Espresso => (Color::rgb(0x5A, 0x2A, 0x00), Color::rgb(0xFF, 0xF8, 0xE7)),
+        };
+        QrStyle {
+            name: self.name().to_string(),
+            foreground,
+            background,
+            shape: ModuleShape::default(),
+            quiet_zone: QUIET_ZONE,
+            frame: 0,
+            caption: None,
+        }
+    }
+}
+
+impl FromStr for StylePreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|p| p.name()).collect();
+                format!("unknown style '{}', expected one of: {}", s, names.join(", "))
+            })
+    }
+}
+
+impl fmt::Display for StylePreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How dark modules are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModuleShape {
+    #[default]
+    Square,
+    /// Squares with their outer corners rounded, so runs of modules read as smooth strokes
+    Rounded,
+    /// A dot per module; finder patterns stay square so scanners still find the code
+    Dots,
+}
+
+impl ModuleShape {
+    pub const ALL: [ModuleShape; 3] = [ModuleShape::Square, ModuleShape::Rounded, ModuleShape::Dots];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ModuleShape::Square => "square",
+            ModuleShape::Rounded => "rounded",
+            ModuleShape::Dots => "dots",
+        }
+    }
+}
+
+impl FromStr for ModuleShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|shape| shape.name() == s)
+            .ok_or_else(|| format!("unknown module shape '{}', expected square, rounded or dots", s))
+    }
+}
+
+impl fmt::Display for ModuleShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Colours and shapes used to render QR codes
+#[derive(Debug, Clone, PartialEq)]
+pub struct QrStyle {
+    pub name: String,
+    pub foreground: Color,
+    pub background: Color,
+    pub shape: ModuleShape,
+    /// Light modules around the code; the specification asks for 4, most phone scanners
+    /// manage with 2
+    pub quiet_zone: usize,
+    /// Modules of a dark frame around the quiet zone in PNG and SVG exports; 0 for none
+    pub frame: usize,
+    /// Text in a band under the code in PNG and SVG exports, e.g. "Scan me"
+    pub caption: Option<String>,
+}
+
+impl Default for QrStyle {
+    fn default() -> Self {
+        StylePreset::Classic.style()
+    }
+}
+
+impl QrStyle {
+    /// A custom style; fails if the colours would produce an unscannable code
+    pub fn custom(foreground: Color, background: Color) -> Result<Self> {
+        let style = Self {
+            name: format!("custom ({} on {})", foreground.to_hex(), background.to_hex()),
+            foreground,
+            background,
+            shape: ModuleShape::default(),
+            quiet_zone: QUIET_ZONE,
+            frame: 0,
+            caption: None,
+        };
+        style.validate()?;
+        Ok(style)
+    }
+
+    pub fn with_shape(mut self, shape: ModuleShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// `modules` light modules around the code instead of 4
+    pub fn with_quiet_zone(mut self, modules: usize) -> Self {
+        self.quiet_zone = modules;
+        self
+    }
+
+    /// A dark frame `modules` wide around the quiet zone of exported images
+    pub fn with_frame(mut self, modules: usize) -> Self {
+        self.frame = modules;
+        self
+    }
+
+    /// `caption` in a band under the code of exported images; empty text for none
+    pub fn with_caption(mut self, caption: &str) -> Self {
+        self.caption = Some(caption.trim().to_string()).filter(|caption| !caption.is_empty());
+        self
+    }
+
+    pub fn contrast_ratio(&self) -> f64 {
+        contrast_ratio(self.foreground, self.background)
+    }
+
+    /// Reject colour combinations that scanners can't read reliably
+    pub fn validate(&self) -> Result<()> {
+        if self.foreground.relative_luminance() >= self.background.relative_luminance() {
+            return Err(anyhow::anyhow!(
+                "Foreground {} must be darker than background {}; many scanners can't read inverted codes",
+                self.foreground.to_hex(),
+                self.background.to_hex()
+            ));
+        }
+
+        let ratio = self.contrast_ratio();
+        if ratio < MIN_CONTRAST_RATIO {
+            return Err(anyhow::anyhow!(
+                "Contrast ratio {:.2}:1 between {} and {} is below the minimum of {}:1",
+                ratio,
+                self.foreground.to_hex(),
+                self.background.to_hex(),
+                MIN_CONTRAST_RATIO
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_scannable() {
+        for preset in StylePreset::ALL {
+            assert!(preset.style().validate().is_ok(), "{} fails validation", preset);
+            assert_eq!(preset.name().parse::<StylePreset>(), Ok(preset));
+        }
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert!((contrast_ratio(Color::BLACK, Color::WHITE) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(Color::WHITE, Color::WHITE) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_rejects_unscannable_styles() {
+        // Inverted
+        assert!(QrStyle::custom(Color::WHITE, Color::BLACK).is_err());
+        // Yellow on white
+        assert!(QrStyle::custom(Color::rgb(0xFF, 0xD7, 0x00), Color::WHITE).is_err());
+        assert!(QrStyle::custom(Color::from_hex("#003F73").unwrap(), Color::WHITE).is_ok());
+    }
+
+    #[test]
+    fn test_color_hex() {
+        assert_eq!(Color::from_hex("#0072b2").unwrap(), Color::rgb(0x00, 0x72, 0xB2));
+        assert_eq!(Color::rgb(0x00, 0x72, 0xB2).to_hex(), "#0072B2");
+        assert!(Color::from_hex("blue").is_err());
+    }
+}
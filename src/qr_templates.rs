@@ -0,0 +1,258 @@
+use anyhow::Result;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::redaction::Redactor;
+
+/// Characters with a meaning in `WIFI:` fields, escaped with a backslash
+const SPECIAL_CHARS: [char; 5] = ['\\', ';', ',', '"', ':'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiSecurity {
+    /// WPA, WPA2 and WPA3 personal all go by `WPA`
+    Wpa,
+    Wep,
+    /// An open network
+    None,
+}
+
+impl WifiSecurity {
+    pub const ALL: [WifiSecurity; 3] = [WifiSecurity::Wpa, WifiSecurity::Wep, WifiSecurity::None];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WifiSecurity::Wpa => "wpa",
+            WifiSecurity::Wep => "wep",
+            WifiSecurity::None => "none",
+        }
+    }
+
+    /// The `T:` value; open networks leave it out
+    fn code(self) -> Option<&'static str> {
+        match self {
+            WifiSecurity::Wpa => Some("WPA"),
+            WifiSecurity::Wep => Some("WEP"),
+            WifiSecurity::None => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WifiSecurity::Wpa => "WPA/WPA2/WPA3",
+            WifiSecurity::Wep => "WEP",
+            WifiSecurity::None => "Open",
+        }
+    }
+}
+
+impl FromStr for WifiSecurity {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|security| security.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|security| security.name()).collect();
+                format!("unknown Wi-Fi security '{}', expected one of: {}", s, names.join(", "))
+            })
+    }
+}
+
+impl fmt::Display for WifiSecurity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Network credentials in the `WIFI:S:<ssid>;T:WPA;P:<password>;;` format phones join networks from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    pub security: WifiSecurity,
+    /// Ignored for open networks
+    pub password: String,
+    /// The network doesn't broadcast its SSID
+    pub hidden: bool,
+}
+
+impl WifiNetwork {
+    pub fn new(ssid: impl Into<String>, security: WifiSecurity, password: impl Into<String>) -> Self {
+        Self {
+            ssid: ssid.into(),
+            security,
+            password: password.into(),
+            hidden: false,
+        }
+    }
+
+    /// Why these credentials can't make a working code
+    pub fn validate(&self) -> Result<()> {
+        if self.ssid.is_empty() {
+            return Err(anyhow::anyhow!("The network name (SSID) is empty"));
+        }
+        match self.security {
+            WifiSecurity::Wpa if !(8..=63).contains(&self.password.chars().count()) => {
+                Err(anyhow::anyhow!("WPA passwords are 8 to 63 characters long"))
+            }
+            WifiSecurity::Wep if self.password.is_empty() => Err(anyhow::anyhow!("WEP networks need a password")),
+            _ => Ok(()),
+        }
+    }
+
+    /// The QR code text, with special characters escaped
+    pub fn payload(&self) -> String {
+        let mut payload = format!("WIFI:S:{};", escape(&self.ssid));
+        if let Some(code) = self.security.code() {
+            payload.push_str(&format!("T:{};P:{};", code, escape(&self.password)));
+        }
+        if self.hidden {
+            payload.push_str("H:true;");
+        }
+        payload.push(';');
+        payload
+    }
+
+    /// The credentials in a `WIFI:` payload, or `None` for other text
+    pub fn parse(text: &str) -> Option<Self> {
+        let trimmed = text.trim();
+        let body = trimmed.get(..5).filter(|start| start.eq_ignore_ascii_case("WIFI:")).map(|_| &trimmed[5..])?;
+        let mut network = WifiNetwork::new("", WifiSecurity::None, "");
+        let mut ssid = None;
+        for field in split_fields(body) {
+            let Some((key, value)) = field.split_once(':') else {
+                continue;
+            };
+            let value = unescape(value);
+            match key.to_ascii_uppercase().as_str() {
+                "S" => ssid = Some(value),
+                "T" => {
+                    network.security = match value.to_ascii_uppercase().as_str() {
+                        "WEP" => WifiSecurity::Wep,
+                        "" | "NOPASS" | "NONE" => WifiSecurity::None,
+                        // WPA2, WPA3 and SAE are joined the same way
+                        _ => WifiSecurity::Wpa,
+                    }
+                }
+                "P" => network.password = value,
+                "H" => network.hidden = value.eq_ignore_ascii_case("true"),
+                _ => {}
+            }
+        }
+        network.ssid = ssid?;
+        Some(network)
+    }
+
+    /// A label and value per field, for printing a scanned code; the password goes through
+    /// `redactor`
+    pub fn fields(&self, redactor: &Redactor) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("SSID", self.ssid.clone()), ("Security", self.security.label().to_string())];
+        if self.security != WifiSecurity::None {
+            fields.push(("Password", redactor.text(&self.password).into_owned()));
+        }
+        if self.hidden {
+            fields.push(("Hidden", "yes".to_string()));
+        }
+        fields
+    }
+
+    /// One line for the tray and console that leaves the password out, e.g. `home (WPA/WPA2/WPA3)`
+    pub fn summary(&self) -> String {
+        format!("{} ({})", self.ssid, self.security.label())
+    }
+}
+
+/// Print the fields of a decoded `WIFI:` payload, one per line; other text prints nothing
+pub fn print_wifi(text: &str, redactor: &Redactor) {
+    if let Some(network) = WifiNetwork::parse(text) {
+        for (label, value) in network.fields(redactor) {
+            println!("   {:<9} {}", format!("{}:", label), value);
+        }
+    }
+}
+
+/// Escape `value` for a `WIFI:` field; values that are all hex digits are quoted too, or some
+/// phones take them for a hex-encoded key
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if SPECIAL_CHARS.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_hexdigit()) {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+fn unescape(value: &str) -> String {
+    // Quotes around the whole value only mark it as text, see `escape`
+    let value = match value.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+        Some(quoted) if !quoted.ends_with('\\') || quoted.ends_with("\\\\") => quoted,
+        _ => value,
+    };
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// `body` split at unescaped semicolons, with escapes left in place
+fn split_fields(body: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ';' => {
+                fields.push(&body[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&body[start..]);
+    fields.into_iter().filter(|field| !field.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wifi_payload() {
+        let mut network = WifiNetwork::new("Café; \"Guest\"", WifiSecurity::Wpa, "pa:ss\\word,1");
+        assert_eq!(network.payload(), r#"WIFI:S:Café\; \"Guest\";T:WPA;P:pa\:ss\\word\,1;;"#);
+        assert_eq!(WifiNetwork::parse(&network.payload()), Some(network.clone()));
+        network.hidden = true;
+        assert_eq!(WifiNetwork::parse(&network.payload()), Some(network));
+
+        // All-hex values are quoted so they aren't read as keys
+        let hex = WifiNetwork::new("home", WifiSecurity::Wpa, "deadbeef");
+        assert_eq!(hex.payload(), r#"WIFI:S:home;T:WPA;P:"deadbeef";;"#);
+        assert_eq!(WifiNetwork::parse(&hex.payload()).unwrap().password, "deadbeef");
+
+        let open = WifiNetwork::new("library", WifiSecurity::None, "ignored");
+        assert_eq!(open.payload(), "WIFI:S:library;;");
+        assert!(open.validate().is_ok());
+        assert!(WifiNetwork::new("home", WifiSecurity::Wpa, "short").validate().is_err());
+
+        let parsed = WifiNetwork::parse("wifi:T:WPA2;P:secret123;S:office;H:true;;").unwrap();
+        assert_eq!((parsed.ssid.as_str(), parsed.security, parsed.hidden), ("office", WifiSecurity::Wpa, true));
+        assert_eq!(parsed.summary(), "office (WPA/WPA2/WPA3)");
+        let fields = parsed.fields(&Redactor::new(false));
+        assert_eq!(fields[2], ("Password", "secret123".to_string()));
+        assert!(WifiNetwork::parse("https://example.com").is_none());
+        assert_eq!("WEP".parse::<WifiSecurity>(), Ok(WifiSecurity::Wep));
+    }
+}
@@ -0,0 +1,62 @@
+use std::borrow::Cow;
+
+/// Replaces clipboard/QR content with a type + length summary when privacy redaction is on
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Redactor {
+    enabled: bool,
+}
+
+impl Redactor {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The text itself, or e.g. "Text, 142 chars" when redacting
+    pub fn text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.enabled {
+            Cow::Owned(summarize_text(text))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Like [`Redactor::text`], but truncated to `max_chars` when shown in full
+    pub fn text_truncated(&self, text: &str, max_chars: usize) -> String {
+        if self.enabled {
+            summarize_text(text)
+        } else if text.chars().count() > max_chars {
+            format!("{}...", text.chars().take(max_chars).collect::<String>())
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+pub fn summarize_text(text: &str) -> String {
+    let count = text.chars().count();
+    format!("Text, {} char{}", count, if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redaction_enabled() {
+        let redactor = Redactor::new(true);
+        assert_eq!(redactor.text("secret password"), "Text, 15 chars");
+        assert_eq!(redactor.text_truncated("secret password", 3), "Text, 15 chars");
+        assert_eq!(redactor.text("é"), "Text, 1 char");
+    }
+
+    #[test]
+    fn test_redaction_disabled() {
+        let redactor = Redactor::new(false);
+        assert_eq!(redactor.text("hello"), "hello");
+        assert_eq!(redactor.text_truncated("héllo world", 5), "héllo...");
+    }
+}
@@ -0,0 +1,425 @@
+use anyhow::Result;
+use base64::Engine;
+use chrono::{DateTime, Local};
+use image::{imageops, DynamicImage, ImageOutputFormat, RgbaImage};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{Cursor, ErrorKind};
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+use tracing::{debug, info, warn};
+
+use crate::own_windows;
+use crate::storage;
+
+/// Captured regions remembered for re-scanning
+const CAPACITY: usize = 5;
+/// Longest side of a remembered region's thumbnail, in pixels
+const THUMBNAIL_SIZE: u32 = 64;
+
+/// A rectangle on the screen in physical pixels, written like an X geometry:
+/// "640x480+100+200" is 640 by 480 pixels, 100 from the left and 200 from the top
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("invalid region '{}', expected WIDTHxHEIGHT+X+Y such as 640x480+100+200", s);
+        // slurp writes a negative offset as "+-100"
+        let pattern = Regex::new(r"^(\d+)x(\d+)([+-]-?\d+)([+-]-?\d+)$").expect("valid pattern");
+        let captures = pattern.captures(s.trim()).ok_or_else(invalid)?;
+        let offset = |index: usize| captures[index].trim_start_matches('+').parse::<i32>().map_err(|_| invalid());
+        let region = Region {
+            width: captures[1].parse().map_err(|_| invalid())?,
+            height: captures[2].parse().map_err(|_| invalid())?,
+            x: offset(3)?,
+            y: offset(4)?,
+        };
+        if region.width == 0 || region.height == 0 {
+            return Err(format!("region '{}' is empty", s));
+        }
+        Ok(region)
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}{:+}{:+}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// Let the user drag out a region with the mouse, using slurp on Wayland and slop on X11;
+/// `None` if they cancelled
+pub fn select() -> Result<Option<Region>> {
+    let (program, args): (&str, &[&str]) = if cfg!(any(windows, target_os = "macos")) {
+        return Err(anyhow::anyhow!(
+            "Selecting a region with the mouse isn't supported on this platform yet; pass the region, e.g. 640x480+100+200"
+        ));
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        ("slurp", &["-f", "%wx%h+%x+%y"])
+    } else {
+        ("slop", &["-f", "%g"])
+    };
+    let output = Command::new(program).args(args).output().map_err(|e| match e.kind() {
+        ErrorKind::NotFound => anyhow::anyhow!("Selecting a region needs {} installed; or pass the region, e.g. 640x480+100+200", program),
+        _ => anyhow::anyhow!("Failed to run {}: {}", program, e),
+    })?;
+    // Both exit with an error when the selection is cancelled with Esc or a right click
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let geometry = String::from_utf8_lossy(&output.stdout);
+    geometry.trim().parse().map(Some).map_err(|e: String| anyhow::anyhow!(e))
+}
+
+/// Take a screenshot of `region`
+pub fn capture(region: Region) -> Result<RgbaImage> {
+    let (mut image, captured) = capture_platform(Some(region))?;
+    hide_own_windows(&mut image, captured);
+    info!("Captured screen region {}", region);
+    Ok(image)
+}
+
+/// Take a screenshot of the whole screen: every monitor on Windows, X11 and Wayland, the
+/// main display on macOS
+pub fn capture_screen() -> Result<RgbaImage> {
+    let (mut image, captured) = capture_platform(None)?;
+    hide_own_windows(&mut image, captured);
+    info!("Captured the screen ({}x{})", image.width(), image.height());
+    Ok(image)
+}
+
+/// Blank out the app's own windows in a capture of `captured`, so a code shown by the popup
+/// or kiosk isn't scanned back and copied again
+fn hide_own_windows(image: &mut RgbaImage, captured: Region) {
+    let windows = own_windows::visible();
+    let hidden = mask(image, captured, &windows);
+    if hidden > 0 {
+        debug!("Left {} of the app's windows out of the capture", hidden);
+    }
+}
+
+/// Paint the parts of `windows` inside the capture of `captured` white, returning how many
+/// overlapped it
+pub fn mask(image: &mut RgbaImage, captured: Region, windows: &[Region]) -> usize {
+    let (right, bottom) = (
+        (captured.x as i64 + image.width() as i64).min(captured.x as i64 + captured.width as i64),
+        (captured.y as i64 + image.height() as i64).min(captured.y as i64 + captured.height as i64),
+    );
+    let mut hidden = 0;
+    for window in windows {
+        let left = (window.x as i64).max(captured.x as i64);
+        let top = (window.y as i64).max(captured.y as i64);
+        let window_right = (window.x as i64 + window.width as i64).min(right);
+        let window_bottom = (window.y as i64 + window.height as i64).min(bottom);
+        if left >= window_right || top >= window_bottom {
+            continue;
+        }
+        for y in top..window_bottom {
+            for x in left..window_right {
+                image.put_pixel((x - captured.x as i64) as u32, (y - captured.y as i64) as u32, image::Rgba([255, 255, 255, 255]));
+            }
+        }
+        hidden += 1;
+    }
+    hidden
+}
+
+#[cfg(target_os = "macos")]
+fn capture_platform(region: Option<Region>) -> Result<(RgbaImage, Region)> {
+    let path = std::env::temp_dir().join(format!("clipboard-qr-region-{}.png", std::process::id()));
+    let mut command = Command::new("screencapture");
+    command.arg("-x");
+    if let Some(region) = region {
+        command.arg(format!("-R{},{},{},{}", region.x, region.y, region.width, region.height));
+    }
+    let status = command
+        .arg(&path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to run screencapture: {}", e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("screencapture failed; is screen recording allowed in the privacy settings?"));
+    }
+    let image = image::open(&path).map(|image| image.to_rgba8());
+    let _ = fs::remove_file(&path);
+    let image = image?;
+    let captured = region.unwrap_or_else(|| whole(&image));
+    Ok((image, captured))
+}
+
+/// The place of a capture of the whole screen, for tools that don't say where it starts
+#[cfg(unix)]
+fn whole(image: &RgbaImage) -> Region {
+    Region { x: 0, y: 0, width: image.width(), height: image.height() }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn capture_platform(region: Option<Region>) -> Result<(RgbaImage, Region)> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        // Wayland compositors only hand screenshots to dedicated tools
+        let mut command = Command::new("grim");
+        if let Some(region) = region {
+            command.arg("-g").arg(format!("{},{} {}x{}", region.x, region.y, region.width, region.height));
+        }
+        let output = command
+            .arg("-")
+            .output()
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => anyhow::anyhow!("Capturing the screen on Wayland needs grim installed"),
+                _ => anyhow::anyhow!("Failed to run grim: {}", e),
+            })?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("grim failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        let image = image::load_from_memory(&output.stdout)?.to_rgba8();
+        let captured = region.unwrap_or_else(|| whole(&image));
+        return Ok((image, captured));
+    }
+
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, ImageFormat};
+
+    let (conn, screen_num) = x11rb::connect(None)?;
+    let screen = &conn.setup().roots[screen_num];
+    let root = screen.root;
+    // The root window spans every monitor
+    let region = region.unwrap_or(Region {
+        x: 0,
+        y: 0,
+        width: screen.width_in_pixels.into(),
+        height: screen.height_in_pixels.into(),
+    });
+    let out_of_range = || anyhow::anyhow!("Region {} is outside the X11 coordinate range", region);
+    let (x, y) = (i16::try_from(region.x).map_err(|_| out_of_range())?, i16::try_from(region.y).map_err(|_| out_of_range())?);
+    let (width, height) = (
+        u16::try_from(region.width).map_err(|_| out_of_range())?,
+        u16::try_from(region.height).map_err(|_| out_of_range())?,
+    );
+    let reply = conn.get_image(ImageFormat::Z_PIXMAP, root, x, y, width, height, !0)?.reply()?;
+
+    // 24- and 32-bit visuals both store pixels as BGRX
+    let pixels = region.width as usize * region.height as usize;
+    if reply.data.len() != pixels * 4 {
+        return Err(anyhow::anyhow!("Unsupported X11 screen depth {}", reply.depth));
+    }
+    let mut image = RgbaImage::new(region.width, region.height);
+    for (pixel, bgrx) in image.pixels_mut().zip(reply.data.chunks_exact(4)) {
+        *pixel = image::Rgba([bgrx[2], bgrx[1], bgrx[0], 255]);
+    }
+    Ok((image, region))
+}
+
+#[cfg(windows)]
+fn capture_platform(region: Option<Region>) -> Result<(RgbaImage, Region)> {
+    use std::ptr::null_mut;
+    use winapi::um::wingdi::{
+        BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDIBits, SelectObject, BITMAPINFO,
+        BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, SRCCOPY,
+    };
+    use winapi::um::winuser::{GetDC, GetSystemMetrics, ReleaseDC, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN};
+
+    // The virtual screen spans every monitor
+    let region = region.unwrap_or_else(|| unsafe {
+        Region {
+            x: GetSystemMetrics(SM_XVIRTUALSCREEN),
+            y: GetSystemMetrics(SM_YVIRTUALSCREEN),
+            width: GetSystemMetrics(SM_CXVIRTUALSCREEN).max(1) as u32,
+            height: GetSystemMetrics(SM_CYVIRTUALSCREEN).max(1) as u32,
+        }
+    });
+
+    let (width, height) = (region.width as i32, region.height as i32);
+    let mut bgra = vec![0u8; region.width as usize * region.height as usize * 4];
+    let rows = unsafe {
+        let screen = GetDC(null_mut());
+        if screen.is_null() {
+            return Err(anyhow::anyhow!("Failed to get the screen device context"));
+        }
+        let memory = CreateCompatibleDC(screen);
+        let bitmap = CreateCompatibleBitmap(screen, width, height);
+        let previous = SelectObject(memory, bitmap as _);
+        let copied = BitBlt(memory, 0, 0, width, height, screen, region.x, region.y, SRCCOPY);
+
+        let mut info: BITMAPINFO = std::mem::zeroed();
+        info.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
+        info.bmiHeader.biWidth = width;
+        // Negative for rows from the top down
+        info.bmiHeader.biHeight = -height;
+        info.bmiHeader.biPlanes = 1;
+        info.bmiHeader.biBitCount = 32;
+        info.bmiHeader.biCompression = BI_RGB;
+        let rows = if copied != 0 {
+            GetDIBits(memory, bitmap, 0, height as u32, bgra.as_mut_ptr() as _, &mut info, DIB_RGB_COLORS)
+        } else {
+            0
+        };
+
+        SelectObject(memory, previous);
+        DeleteObject(bitmap as _);
+        DeleteDC(memory);
+        ReleaseDC(null_mut(), screen);
+        rows
+    };
+    if rows == 0 {
+        return Err(anyhow::anyhow!("Failed to capture screen region {}", region));
+    }
+    for pixel in bgra.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+        pixel[3] = 255;
+    }
+    let image = RgbaImage::from_raw(region.width, region.height, bgra).ok_or_else(|| anyhow::anyhow!("Failed to create image buffer"))?;
+    Ok((image, region))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn capture_platform(_region: Option<Region>) -> Result<(RgbaImage, Region)> {
+    Err(anyhow::anyhow!("Capturing the screen isn't supported on this platform"))
+}
+
+/// A region captured earlier, with a small picture of what it showed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedRegion {
+    pub region: Region,
+    pub captured_at: DateTime<Local>,
+    /// PNG, base64-encoded
+    thumbnail: String,
+}
+
+impl CapturedRegion {
+    pub fn thumbnail(&self) -> Option<RgbaImage> {
+        let png = base64::engine::general_purpose::STANDARD.decode(&self.thumbnail).ok()?;
+        image::load_from_memory(&png).ok().map(|image| image.to_rgba8())
+    }
+}
+
+/// The last few captured regions, newest first, so a code that keeps changing in the same
+/// place on the screen can be scanned again without selecting it each time
+#[derive(Debug, Default)]
+pub struct RegionHistory {
+    path: Option<PathBuf>,
+    regions: Vec<CapturedRegion>,
+}
+
+impl RegionHistory {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("clipboard-qr").join("regions.json"))
+    }
+
+    /// The history at the default path; kept in memory only in in-memory mode or if `persist`
+    /// is false, as in guest mode, since thumbnails show what was on the screen
+    pub fn open_default(persist: bool) -> Self {
+        let path = if persist && !storage::in_memory_mode() { Self::default_path() } else { None };
+        Self::open(path)
+    }
+
+    pub fn open(path: Option<PathBuf>) -> Self {
+        let regions = match &path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                    warn!("Ignoring unreadable region history {:?}: {}", path, e);
+                    Vec::new()
+                }),
+                Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+                Err(e) => {
+                    warn!("Failed to read region history {:?}: {}", path, e);
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        Self { path, regions }
+    }
+
+    /// Newest first
+    pub fn regions(&self) -> &[CapturedRegion] {
+        &self.regions
+    }
+
+    pub fn last(&self) -> Option<Region> {
+        self.regions.first().map(|captured| captured.region)
+    }
+
+    /// Move `region` to the front with a thumbnail of `image`, what it showed just now
+    pub fn remember(&mut self, region: Region, image: &RgbaImage) -> Result<()> {
+        let longest = image.width().max(image.height());
+        let scale = |side: u32| (side as u64 * THUMBNAIL_SIZE as u64 / longest as u64).max(1) as u32;
+        let thumbnail = if longest > THUMBNAIL_SIZE {
+            imageops::thumbnail(image, scale(image.width()), scale(image.height()))
+        } else {
+            image.clone()
+        };
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(thumbnail).write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+
+        self.regions.retain(|captured| captured.region != region);
+        self.regions.insert(
+            0,
+            CapturedRegion {
+                region,
+                captured_at: Local::now(),
+                thumbnail: base64::engine::general_purpose::STANDARD.encode(png),
+            },
+        );
+        self.regions.truncate(CAPACITY);
+
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.regions)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_history() {
+        let region: Region = "640x480+100+200".parse().unwrap();
+        assert_eq!(region, Region { x: 100, y: 200, width: 640, height: 480 });
+        assert_eq!(region.to_string(), "640x480+100+200");
+        let left_monitor: Region = "300x300+-1920+0".parse().unwrap();
+        assert_eq!(left_monitor.to_string(), "300x300-1920+0");
+        assert_eq!("300x300-1920+0".parse(), Ok(left_monitor));
+        assert!("0x300+0+0".parse::<Region>().is_err());
+        assert!("640x480".parse::<Region>().is_err());
+
+        // Windows of the app are painted white where they overlap the capture
+        let mut capture = RgbaImage::from_pixel(100, 50, image::Rgba([0, 0, 0, 255]));
+        let captured = Region { x: -100, y: 20, width: 100, height: 50 };
+        let windows = [Region { x: -20, y: 60, width: 50, height: 50 }, Region { x: 0, y: 0, width: 10, height: 10 }];
+        assert_eq!(mask(&mut capture, captured, &windows), 1);
+        assert_eq!(capture.get_pixel(80, 40), &image::Rgba([255, 255, 255, 255]));
+        assert_eq!(capture.get_pixel(99, 49), &image::Rgba([255, 255, 255, 255]));
+        assert_eq!(capture.get_pixel(79, 49), &image::Rgba([0, 0, 0, 255]));
+        assert_eq!(capture.get_pixel(80, 39), &image::Rgba([0, 0, 0, 255]));
+
+        let path = std::env::temp_dir().join(format!("clipboard-qr-regions-{}.json", std::process::id()));
+        let mut history = RegionHistory::open(Some(path.clone()));
+        let image = RgbaImage::from_pixel(200, 100, image::Rgba([0, 0, 0, 255]));
+        for x in 0..7 {
+            history.remember(Region { x, ..region }, &image).unwrap();
+        }
+        // Capturing a remembered region again moves it to the front
+        history.remember(Region { x: 4, ..region }, &image).unwrap();
+
+        let history = RegionHistory::open(Some(path.clone()));
+        let xs: Vec<i32> = history.regions().iter().map(|captured| captured.region.x).collect();
+        assert_eq!(xs, [4, 6, 5, 3, 2]);
+        assert_eq!(history.last(), Some(Region { x: 4, ..region }));
+        assert_eq!(history.regions()[0].thumbnail().unwrap().dimensions(), (64, 32));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tracing::info;
+use url::Url;
+
+/// Path, relative to an instance's base URL, that accepts pushed clipboard content
+pub const CLIPBOARD_ENDPOINT: &str = "clipboard";
+
+/// Where pushed content came from on the sending machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PushSource {
+    Clipboard,
+    QrScan,
+}
+
+/// Body of a push request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PushPayload {
+    pub text: String,
+    pub source: PushSource,
+    /// Name of the sending machine, set by agents so the host can tell guests apart
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+}
+
+/// Sends clipboard text and decoded QR content to another instance's HTTP API
+pub struct PushClient {
+    endpoint: Url,
+    token: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl PushClient {
+    pub fn new(base: &Url, token: Option<String>) -> Result<Self> {
+        if !matches!(base.scheme(), "http" | "https") {
+            return Err(anyhow::anyhow!("Unsupported URL scheme '{}', expected http or https", base.scheme()));
+        }
+
+        // Treat the base as a directory so "http://host:port/qr" pushes to "/qr/clipboard"
+        let mut base = base.clone();
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(10))
+            .user_agent(concat!("clipboard-qr/", env!("CARGO_PKG_VERSION")))
+            .build();
+
+        Ok(Self {
+            endpoint: base.join(CLIPBOARD_ENDPOINT)?,
+            token: token.filter(|token| !token.is_empty()),
+            agent,
+        })
+    }
+
+    pub fn endpoint(&self) -> &Url {
+        &self.endpoint
+    }
+
+    pub fn push(&self, payload: &PushPayload) -> Result<()> {
+        let mut request = self
+            .agent
+            .post(self.endpoint.as_str())
+            .set("Content-Type", "application/json");
+        if let Some(token) = &self.token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+
+        match request.send_string(&serde_json::to_string(payload)?) {
+            Ok(_) => {
+                info!("Pushed {} chars to {}", payload.text.chars().count(), self.endpoint);
+                Ok(())
+            }
+            Err(ureq::Error::Status(401, _)) | Err(ureq::Error::Status(403, _)) => {
+                Err(anyhow::anyhow!("{} rejected the token", self.endpoint))
+            }
+            Err(ureq::Error::Status(code, response)) => Err(anyhow::anyhow!(
+                "{} returned HTTP {}: {}",
+                self.endpoint,
+                code,
+                response.into_string().unwrap_or_default().trim()
+            )),
+            Err(e) => Err(anyhow::anyhow!("Failed to reach {}: {}", self.endpoint, e)),
+        }
+    }
+}
+
+/// Payloads waiting to be sent while the receiving instance is unreachable
+///
+/// Holds at most `capacity` payloads; when full the oldest is dropped, since
+/// recent clipboard content is the useful part.
+pub struct Outbox {
+    queue: VecDeque<PushPayload>,
+    capacity: usize,
+    dropped: usize,
+}
+
+impl Outbox {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            capacity: capacity.max(1),
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, payload: PushPayload) {
+        if self.queue.len() == self.capacity {
+            self.queue.pop_front();
+            self.dropped += 1;
+        }
+        self.queue.push_back(payload);
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Payloads dropped because the outbox was full
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Send queued payloads in order, stopping at the first failure so nothing is reordered;
+    /// returns how many were sent
+    pub fn flush(&mut self, mut send: impl FnMut(&PushPayload) -> Result<()>) -> Result<usize> {
+        let mut sent = 0;
+        while let Some(payload) = self.queue.front() {
+            send(payload)?;
+            self.queue.pop_front();
+            sent += 1;
+        }
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_endpoint() {
+        let base = Url::parse("http://desktop.local:8787").unwrap();
+        let client = PushClient::new(&base, None).unwrap();
+        assert_eq!(client.endpoint().as_str(), "http://desktop.local:8787/clipboard");
+
+        let base = Url::parse("http://desktop.local:8787/qr").unwrap();
+        let client = PushClient::new(&base, None).unwrap();
+        assert_eq!(client.endpoint().as_str(), "http://desktop.local:8787/qr/clipboard");
+
+        assert!(PushClient::new(&Url::parse("ftp://desktop.local").unwrap(), None).is_err());
+    }
+
+    #[test]
+    fn test_payload_json() {
+        let payload = PushPayload {
+            text: "hello".to_string(),
+            source: PushSource::QrScan,
+            origin: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&payload).unwrap(),
+            r#"{"text":"hello","source":"qr_scan"}"#
+        );
+    }
+
+    #[test]
+    fn test_outbox() {
+        let payload = |text: &str| PushPayload {
+            text: text.to_string(),
+            source: PushSource::Clipboard,
+            origin: Some("vm-01".to_string()),
+        };
+        let mut outbox = Outbox::new(2);
+        for text in ["one", "two", "three"] {
+            outbox.push(payload(text));
+        }
+        assert_eq!((outbox.len(), outbox.dropped()), (2, 1));
+
+        // The host goes away after the first send; the rest stays queued in order
+        let mut sent = Vec::new();
+        let result = outbox.flush(|payload| {
+            if sent.is_empty() {
+                sent.push(payload.text.clone());
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!("connection refused"))
+            }
+        });
+        assert!(result.is_err());
+        assert_eq!(sent, ["two"]);
+        assert_eq!(outbox.flush(|_| Ok(())).unwrap(), 1);
+        assert!(outbox.is_empty());
+    }
+}
@@ -0,0 +1,842 @@
+use anyhow::{anyhow, Result};
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::{imageops, ImageBuffer, ImageOutputFormat, RgbaImage};
+use qrcode::QrCode;
+use std::fmt::Write as _;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::matrix::BitMatrix;
+use crate::qr_style::{Color, ModuleShape, QrStyle};
+
+/// Turns an encoded QR code into the bytes of one output format
+pub trait QrRenderer: Send + Sync {
+    /// Short name used to pick the renderer, e.g. "png"
+    fn name(&self) -> &str;
+
+    /// File extensions (lowercase, without the dot) this renderer writes
+    fn extensions(&self) -> &[&str] {
+        &[]
+    }
+
+    fn render(&self, code: &QrCode, style: &QrStyle) -> Result<Vec<u8>>;
+}
+
+/// The renderers a [`crate::QRGenerator`] can use; later registrations win
+pub struct RendererRegistry {
+    renderers: Vec<Box<dyn QrRenderer>>,
+}
+
+impl Default for RendererRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RendererRegistry {
+    /// A registry with the built-in PNG, SVG, PDF, terminal and sixel renderers
+    pub fn new() -> Self {
+        Self {
+            renderers: vec![
+                Box::new(PngRenderer::default()),
+                Box::new(SvgRenderer::default()),
+                Box::new(PdfRenderer::default()),
+                Box::new(TerminalRenderer),
+                Box::new(SixelRenderer::default()),
+            ],
+        }
+    }
+
+    /// Add a renderer, replacing any earlier one with the same name or extensions
+    pub fn register(&mut self, renderer: Box<dyn QrRenderer>) {
+        self.renderers.push(renderer);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn QrRenderer> {
+        self.renderers
+            .iter()
+            .rev()
+            .find(|renderer| renderer.name().eq_ignore_ascii_case(name))
+            .map(|renderer| renderer.as_ref())
+    }
+
+    /// The renderer for a file, chosen by its extension
+    pub fn for_path(&self, path: &Path) -> Option<&dyn QrRenderer> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        self.renderers
+            .iter()
+            .rev()
+            .find(|renderer| renderer.extensions().contains(&extension.as_str()))
+            .map(|renderer| renderer.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for renderer in &self.renderers {
+            if !names.contains(&renderer.name()) {
+                names.push(renderer.name());
+            }
+        }
+        names
+    }
+}
+
+/// Rounding of [`ModuleShape::Rounded`] corners, in modules; half a module makes the ends of
+/// runs semicircles
+const ROUNDED_RADIUS: f32 = 0.5;
+
+/// Gap between neighbouring [`ModuleShape::Dots`], in modules on each side
+const DOT_INSET: f32 = 0.05;
+
+/// Distance of cubic Bézier control points along the tangents for a quarter circle of radius 1
+const KAPPA: f32 = 0.5523;
+
+/// Each corner from the top-left, clockwise: which way it points from the module's centre, and
+/// the directions a clockwise outline arrives at and leaves it in
+const CORNERS: [[(f32, f32); 3]; 4] = [
+    [(-1.0, -1.0), (0.0, -1.0), (1.0, 0.0)],
+    [(1.0, -1.0), (1.0, 0.0), (0.0, 1.0)],
+    [(1.0, 1.0), (0.0, 1.0), (-1.0, 0.0)],
+    [(-1.0, 1.0), (-1.0, 0.0), (0.0, -1.0)],
+];
+
+/// A step of an outline, in modules
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PathOp {
+    Move((f32, f32)),
+    Line((f32, f32)),
+    /// Cubic Bézier: two control points and the end point
+    Curve([(f32, f32); 3]),
+}
+
+/// The shape one dark module is drawn as, in module units
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Outline {
+    /// Gap between the shape and the edges of its module
+    inset: f32,
+    /// Corner radii from the top-left, clockwise, as in [`CORNERS`]
+    radii: [f32; 4],
+}
+
+impl Outline {
+    const SQUARE: Outline = Outline { inset: 0.0, radii: [0.0; 4] };
+
+    fn is_square(&self) -> bool {
+        *self == Self::SQUARE
+    }
+
+    /// Whether the point `(x, y)` within the module, each from 0 to 1, is inside the shape
+    fn covers(&self, x: f32, y: f32) -> bool {
+        let (low, high) = (self.inset, 1.0 - self.inset);
+        if x < low || x > high || y < low || y > high {
+            return false;
+        }
+        let half = 0.5 - self.inset;
+        CORNERS.iter().zip(self.radii).all(|(&[(sx, sy), _, _], radius)| {
+            // Centre of the corner's circle; only points beyond it on both axes can be outside
+            let (cx, cy) = (0.5 + sx * (half - radius), 0.5 + sy * (half - radius));
+            (x - cx) * sx <= 0.0 || (y - cy) * sy <= 0.0 || (x - cx).powi(2) + (y - cy).powi(2) <= radius * radius
+        })
+    }
+
+    /// The outline of the module at `(x, y)`, clockwise from the top-left corner
+    fn path(&self, x: f32, y: f32) -> Vec<PathOp> {
+        let half = 0.5 - self.inset;
+        let mut ops = Vec::with_capacity(8);
+        for (index, (&[(sx, sy), (ix, iy), (ox, oy)], radius)) in CORNERS.iter().zip(self.radii).enumerate() {
+            let (px, py) = (x + 0.5 + sx * half, y + 0.5 + sy * half);
+            let arrive = (px - radius * ix, py - radius * iy);
+            ops.push(if index == 0 { PathOp::Move(arrive) } else { PathOp::Line(arrive) });
+            if radius > 0.0 {
+                let (leave, k) = ((px + radius * ox, py + radius * oy), KAPPA * radius);
+                ops.push(PathOp::Curve([(arrive.0 + k * ix, arrive.1 + k * iy), (leave.0 - k * ox, leave.1 - k * oy), leave]));
+            }
+        }
+        ops
+    }
+}
+
+/// The module grid of a code with its quiet zone
+struct Modules {
+    /// Modules per side including the quiet zone
+    size: usize,
+    quiet_zone: usize,
+    matrix: BitMatrix,
+    /// Micro QR codes have a single finder pattern
+    micro: bool,
+}
+
+impl Modules {
+    fn new(code: &QrCode, quiet_zone: usize) -> Self {
+        let matrix = BitMatrix::from_code(code);
+        Self { size: matrix.width() + 2 * quiet_zone, quiet_zone, matrix, micro: code.version().is_micro() }
+    }
+
+    /// Whether the module at `(x, y)`, counted from the outer corner of the quiet zone, is dark
+    fn is_dark(&self, x: isize, y: isize) -> bool {
+        let code = |value: isize| usize::try_from(value).ok()?.checked_sub(self.quiet_zone);
+        match (code(x), code(y)) {
+            (Some(x), Some(y)) => self.matrix.get(x, y),
+            _ => false,
+        }
+    }
+
+    /// Whether `(x, y)` is in one of the three 7x7 finder patterns, or the top left one of
+    /// a Micro QR code
+    fn is_finder(&self, x: usize, y: usize) -> bool {
+        let width = self.matrix.width();
+        let near = |value: usize| value.checked_sub(self.quiet_zone).map(|value| (value < 7, value >= width.saturating_sub(7) && value < width));
+        match (near(x), near(y)) {
+            (Some((left, _)), Some((top, _))) if self.micro => top && left,
+            (Some((left, right)), Some((top, bottom))) => (top && (left || right)) || (bottom && left),
+            _ => false,
+        }
+    }
+
+    /// The outline of each module row by row, `None` for light ones
+    fn outlines(&self, shape: ModuleShape) -> Vec<Option<Outline>> {
+        (0..self.size * self.size)
+            .map(|index| {
+                let (x, y) = (index % self.size, index / self.size);
+                self.is_dark(x as isize, y as isize).then(|| self.outline(x, y, shape))
+            })
+            .collect()
+    }
+
+    fn outline(&self, x: usize, y: usize, shape: ModuleShape) -> Outline {
+        match shape {
+            ModuleShape::Square => Outline::SQUARE,
+            ModuleShape::Dots if self.is_finder(x, y) => Outline::SQUARE,
+            ModuleShape::Dots => Outline { inset: DOT_INSET, radii: [0.5 - DOT_INSET; 4] },
+            ModuleShape::Rounded => {
+                // Only corners with light modules on both sides they touch are rounded
+                let light = |dx: isize, dy: isize| !self.is_dark(x as isize + dx, y as isize + dy);
+                let radii = CORNERS.map(|[(sx, sy), _, _]| {
+                    let (dx, dy) = (sx as isize, sy as isize);
+                    if light(dx, 0) && light(0, dy) {
+                        ROUNDED_RADIUS
+                    } else {
+                        0.0
+                    }
+                });
+                Outline { inset: 0.0, radii }
+            }
+        }
+    }
+}
+
+/// Raster image of the code with `scale` pixels per module
+pub fn rasterize(code: &QrCode, style: &QrStyle, scale: u32) -> RgbaImage {
+    let modules = Modules::new(code, style.quiet_zone);
+    let outlines = modules.outlines(style.shape);
+    let size = modules.size;
+    let dark = style.foreground.to_rgba();
+    let light = style.background.to_rgba();
+    let pixels = size as u32 * scale;
+    // Sampled at the pixel centre
+    let within = |pixel: u32| ((pixel % scale) as f32 + 0.5) / scale as f32;
+    ImageBuffer::from_fn(pixels, pixels, |x, y| {
+        let index = (y / scale) as usize * size + (x / scale) as usize;
+        if outlines[index].is_some_and(|outline| outline.covers(within(x), within(y))) {
+            dark
+        } else {
+            light
+        }
+    })
+}
+
+/// The largest on-screen size up to `available` points at which each of `modules` modules
+/// covers a whole number of physical pixels, so a scaled code stays sharp on any display scale
+///
+/// Falls back to `available` when the space is too small for one pixel per module.
+pub fn crisp_display_size(available: f32, pixels_per_point: f32, modules: u32) -> f32 {
+    if modules == 0 || pixels_per_point <= 0.0 {
+        return available.max(0.0);
+    }
+    let pixels_per_module = (available * pixels_per_point / modules as f32).floor();
+    if pixels_per_module < 1.0 {
+        return available.max(0.0);
+    }
+    pixels_per_module * modules as f32 / pixels_per_point
+}
+
+/// Height of a title, footer or caption band, in modules
+const CAPTION_MODULES: usize = 3;
+
+/// Height of caption text, in modules
+const CAPTION_FONT_MODULES: f32 = 1.6;
+
+pub struct PngRenderer {
+    /// Pixels per module
+    pub scale: u32,
+    /// Largest side of the image in pixels; overrides `scale` with the most whole pixels per
+    /// module that fit, so modules stay sharp
+    pub target_size: Option<u32>,
+}
+
+impl Default for PngRenderer {
+    fn default() -> Self {
+        Self { scale: 8, target_size: None }
+    }
+}
+
+impl PngRenderer {
+    /// Pixels per module for a code `modules` wide, quiet zone included; at least one
+    pub fn scale_for(&self, modules: usize) -> u32 {
+        let scale = match self.target_size {
+            Some(size) => size / modules.max(1) as u32,
+            None => self.scale,
+        };
+        scale.max(1)
+    }
+
+    /// The code as an image at [`Self::scale_for`] its size
+    pub fn rasterize(&self, code: &QrCode, style: &QrStyle) -> RgbaImage {
+        rasterize(code, style, self.scale_for(code.width() + 2 * style.quiet_zone))
+    }
+}
+
+impl QrRenderer for PngRenderer {
+    fn name(&self) -> &str {
+        "png"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["png"]
+    }
+
+    fn render(&self, code: &QrCode, style: &QrStyle) -> Result<Vec<u8>> {
+        let mut bytes = Cursor::new(Vec::new());
+        // The frame counts towards the target size; the caption band goes below it
+        let scale = self.scale_for(code.width() + 2 * (style.quiet_zone + style.frame));
+        decorate(rasterize(code, style, scale), style, scale).write_to(&mut bytes, ImageOutputFormat::Png)?;
+        Ok(bytes.into_inner())
+    }
+}
+
+pub struct SvgRenderer {
+    /// Minimum width and height of the code in pixels
+    pub min_size: u32,
+    /// Text in a band above the code
+    pub title: Option<String>,
+    /// Text in a band below the code
+    pub footer: Option<String>,
+}
+
+impl Default for SvgRenderer {
+    fn default() -> Self {
+        Self {
+            min_size: 300,
+            title: None,
+            footer: None,
+        }
+    }
+}
+
+impl SvgRenderer {
+    /// A caption band `y` modules from the top, centred on the code
+    fn caption(svg: &mut String, text: &str, y: usize, size: usize, style: &QrStyle) -> Result<()> {
+        let escaped = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        // Roughly half an em per character; longer captions are squeezed into the code's width
+        let room = size.saturating_sub(2) as f32;
+        let fit = if text.chars().count() as f32 * 0.9 > room {
+            format!(" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\"", svg_number(room))
+        } else {
+            String::new()
+        };
+        write!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\"{}>{}</text>",
+            svg_number(size as f32 / 2.0),
+            svg_number(y as f32 + CAPTION_MODULES as f32 / 2.0),
+            svg_number(CAPTION_FONT_MODULES),
+            style.foreground.to_hex(),
+            fit,
+            escaped
+        )?;
+        Ok(())
+    }
+}
+
+impl QrRenderer for SvgRenderer {
+    fn name(&self) -> &str {
+        "svg"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["svg"]
+    }
+
+    /// One path for all dark modules over a background rectangle, in module units
+    fn render(&self, code: &QrCode, style: &QrStyle) -> Result<Vec<u8>> {
+        let modules = Modules::new(code, style.quiet_zone);
+        let code_size = modules.size;
+        // The frame goes around the quiet zone; the style's caption stands in for a footer
+        let (frame, size) = (style.frame, code_size + 2 * style.frame);
+        let footer = self.footer.as_ref().or(style.caption.as_ref());
+        let scale = self.min_size.div_ceil(size as u32).max(1);
+        let band = |caption: Option<&String>| if caption.is_some() { CAPTION_MODULES } else { 0 };
+        let (top, bottom) = (band(self.title.as_ref()), band(footer));
+        let height = size + top + bottom;
+        let (width_pixels, height_pixels) = (scale * size as u32, scale * height as u32);
+        // Curves look jagged with crisp edges
+        let rendering = if style.shape == ModuleShape::Square { " shape-rendering=\"crispEdges\"" } else { "" };
+
+        let mut svg = String::from("<?xml version=\"1.0\" standalone=\"yes\"?>");
+        write!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" width=\"{width_pixels}\" height=\"{height_pixels}\" viewBox=\"0 0 {size} {height}\"{rendering}>"
+        )?;
+        write!(svg, "<path d=\"M0 0h{size}v{height}H0z\" fill=\"{}\"/>", style.background.to_hex())?;
+        if let Some(title) = &self.title {
+            Self::caption(&mut svg, title, 0, size, style)?;
+        }
+        if let Some(footer) = footer {
+            Self::caption(&mut svg, footer, top + size, size, style)?;
+        }
+        if frame > 0 {
+            let inner = top + frame;
+            write!(
+                svg,
+                "<path fill=\"{}\" fill-rule=\"evenodd\" d=\"M0 {top}h{size}v{size}H0zM{frame} {inner}h{code_size}v{code_size}h-{code_size}z\"/>",
+                style.foreground.to_hex()
+            )?;
+        }
+        let (dx, dy) = (frame, top + frame);
+        if dx > 0 || dy > 0 {
+            write!(svg, "<g transform=\"translate({dx} {dy})\">")?;
+        }
+        write!(svg, "<path fill=\"{}\" d=\"", style.foreground.to_hex())?;
+        for (index, outline) in modules.outlines(style.shape).iter().enumerate() {
+            let Some(outline) = outline else { continue };
+            let (x, y) = (index % code_size, index / code_size);
+            if outline.is_square() {
+                write!(svg, "M{x} {y}h1v1h-1z")?;
+                continue;
+            }
+            for op in outline.path(x as f32, y as f32) {
+                match op {
+                    PathOp::Move((x, y)) => write!(svg, "M{} {}", svg_number(x), svg_number(y))?,
+                    PathOp::Line((x, y)) => write!(svg, "L{} {}", svg_number(x), svg_number(y))?,
+                    PathOp::Curve(points) => {
+                        svg.push('C');
+                        let points: Vec<String> = points.iter().map(|(x, y)| format!("{} {}", svg_number(*x), svg_number(*y))).collect();
+                        svg.push_str(&points.join(" "));
+                    }
+                }
+            }
+            svg.push('z');
+        }
+        svg.push_str("\"/>");
+        if dx > 0 || dy > 0 {
+            svg.push_str("</g>");
+        }
+        svg.push_str("</svg>");
+        Ok(svg.into_bytes())
+    }
+}
+
+/// `image` of a code with the style's frame around it and its caption in a band below,
+/// `scale` pixels per module
+pub fn decorate(image: RgbaImage, style: &QrStyle, scale: u32) -> RgbaImage {
+    if style.frame == 0 && style.caption.is_none() {
+        return image;
+    }
+    let frame = style.frame as u32 * scale;
+    let side = image.width() + 2 * frame;
+    let band = if style.caption.is_some() { CAPTION_MODULES as u32 * scale } else { 0 };
+    let dark = style.foreground.to_rgba();
+    let mut decorated = ImageBuffer::from_fn(side, side + band, |x, y| {
+        let in_frame = y < side && (x < frame || y < frame || x >= side - frame || y >= side - frame);
+        if in_frame {
+            dark
+        } else {
+            style.background.to_rgba()
+        }
+    });
+    imageops::replace(&mut decorated, &image, frame as i64, frame as i64);
+    if let Some(caption) = &style.caption {
+        draw_caption(&mut decorated, caption, side, band, scale, style);
+    }
+    decorated
+}
+
+/// One line of text centred in the band `top..top + height`, squeezed to fit the width
+fn draw_caption(image: &mut RgbaImage, text: &str, top: u32, height: u32, scale: u32, style: &QrStyle) {
+    let font = FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT).expect("embedded font is valid");
+    let text = text.lines().next().unwrap_or_default();
+    let room = image.width().saturating_sub(2 * scale) as f32;
+    let line_width = |size: PxScale| {
+        let scaled = font.as_scaled(size);
+        text.chars().map(|c| scaled.h_advance(font.glyph_id(c))).sum::<f32>()
+    };
+    let mut size = PxScale::from(CAPTION_FONT_MODULES * scale as f32);
+    let width = line_width(size);
+    if width > room {
+        size = PxScale::from(size.y * room / width);
+    }
+
+    let scaled = font.as_scaled(size);
+    let mut x = (image.width() as f32 - line_width(size)) / 2.0;
+    let baseline = top as f32 + (height as f32 + scaled.ascent() + scaled.descent()) / 2.0;
+    let (dark, light) = (style.foreground.to_rgba(), style.background.to_rgba());
+    for c in text.chars() {
+        let glyph_id = font.glyph_id(c);
+        let glyph = glyph_id.with_scale_and_position(size, ab_glyph::point(x, baseline));
+        x += scaled.h_advance(glyph_id);
+        let Some(outlined) = font.outline_glyph(glyph) else { continue };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let (px, py) = (bounds.min.x as i32 + gx as i32, bounds.min.y as i32 + gy as i32);
+            if px < 0 || py < 0 || px as u32 >= image.width() || py as u32 >= image.height() {
+                return;
+            }
+            let ink = coverage.clamp(0.0, 1.0);
+            let blend = |channel: usize| (light[channel] as f32 + (dark[channel] as f32 - light[channel] as f32) * ink).round() as u8;
+            image.put_pixel(px as u32, py as u32, image::Rgba([blend(0), blend(1), blend(2), blend(3)]));
+        });
+    }
+}
+
+/// Coordinates rounded to a thousandth of a module, without trailing zeros
+fn svg_number(value: f32) -> f32 {
+    (value * 1000.0).round() / 1000.0
+}
+
+/// Single-page vector PDF, one filled shape per dark module
+pub struct PdfRenderer {
+    /// Module size in points (1/72 inch)
+    pub module_pt: f32,
+}
+
+impl Default for PdfRenderer {
+    fn default() -> Self {
+        Self { module_pt: 6.0 }
+    }
+}
+
+impl QrRenderer for PdfRenderer {
+    fn name(&self) -> &str {
+        "pdf"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["pdf"]
+    }
+
+    fn render(&self, code: &QrCode, style: &QrStyle) -> Result<Vec<u8>> {
+        self.render_document(std::slice::from_ref(code), style)
+    }
+}
+
+impl PdfRenderer {
+    /// One PDF with a page per code, in order
+    pub fn render_document(&self, codes: &[QrCode], style: &QrStyle) -> Result<Vec<u8>> {
+        if codes.is_empty() {
+            return Err(anyhow!("No QR codes to put in the PDF"));
+        }
+
+        // Objects 1 and 2 are the catalog and page tree; each page is followed by its content stream
+        let page_ids: Vec<usize> = (0..codes.len()).map(|index| 3 + 2 * index).collect();
+        let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), codes.len()),
+        ];
+        for (code, page_id) in codes.iter().zip(&page_ids) {
+            let (page, content) = self.page_content(code, style)?;
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {page:.2} {page:.2}] /Contents {} 0 R >>",
+                page_id + 1
+            ));
+            objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+        }
+
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (number, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            writeln!(pdf, "{} 0 obj\n{}\nendobj", number + 1, object)?;
+        }
+        let xref = pdf.len();
+        writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1)?;
+        for offset in offsets {
+            writeln!(pdf, "{:010} 00000 n ", offset)?;
+        }
+        write!(pdf, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref)?;
+        Ok(pdf.into_bytes())
+    }
+
+    /// Page size in points and the drawing commands for one code
+    fn page_content(&self, code: &QrCode, style: &QrStyle) -> Result<(f32, String)> {
+        let modules = Modules::new(code, style.quiet_zone);
+        let size = modules.size;
+        let page = size as f32 * self.module_pt;
+        // PDF's origin is the bottom-left corner
+        let point = |(x, y): (f32, f32)| format!("{:.2} {:.2}", x * self.module_pt, (size as f32 - y) * self.module_pt);
+
+        let mut content = String::new();
+        writeln!(content, "{} rg 0 0 {page:.2} {page:.2} re f", pdf_color(style.background))?;
+        writeln!(content, "{} rg", pdf_color(style.foreground))?;
+        for (index, outline) in modules.outlines(style.shape).iter().enumerate() {
+            let Some(outline) = outline else { continue };
+            let (x, y) = (index % size, index / size);
+            if outline.is_square() {
+                let bottom = (size - 1 - y) as f32 * self.module_pt;
+                writeln!(
+                    content,
+                    "{:.2} {:.2} {m:.2} {m:.2} re",
+                    x as f32 * self.module_pt,
+                    bottom,
+                    m = self.module_pt
+                )?;
+                continue;
+            }
+            for op in outline.path(x as f32, y as f32) {
+                match op {
+                    PathOp::Move(to) => writeln!(content, "{} m", point(to))?,
+                    PathOp::Line(to) => writeln!(content, "{} l", point(to))?,
+                    PathOp::Curve([first, second, to]) => writeln!(content, "{} {} {} c", point(first), point(second), point(to))?,
+                }
+            }
+            content.push_str("h\n");
+        }
+        content.push_str("f\n");
+        Ok((page, content))
+    }
+}
+
+fn pdf_color(color: Color) -> String {
+    format!(
+        "{:.3} {:.3} {:.3}",
+        color.r as f32 / 255.0,
+        color.g as f32 / 255.0,
+        color.b as f32 / 255.0
+    )
+}
+
+/// Unicode half blocks, two module rows per line, for dark terminal backgrounds
+pub struct TerminalRenderer;
+
+impl QrRenderer for TerminalRenderer {
+    fn name(&self) -> &str {
+        "terminal"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+
+    fn render(&self, code: &QrCode, _style: &QrStyle) -> Result<Vec<u8>> {
+        let string = code.render().dark_color(' ').light_color('█').build();
+
+        // 使用 Unicode 块字符压缩显示
+        let lines: Vec<&str> = string.lines().collect();
+        let mut output = String::new();
+
+        // 每两行合并为一行，使用上半个块和下半个块
+        for pair in lines.chunks(2) {
+            match pair {
+                [top_line, bottom_line] => {
+                    for (top_char, bottom_char) in top_line.chars().zip(bottom_line.chars()) {
+                        output.push(match (top_char, bottom_char) {
+                            ('█', '█') => '█', // 全黑
+                            (' ', ' ') => ' ', // 全白
+                            ('█', ' ') => '▀', // 上黑下白
+                            (' ', '█') => '▄', // 上白下黑
+                            _ => ' ',          // 默认白色
+                        });
+                    }
+                }
+                // 处理最后一行（如果总行数是奇数）
+                [top_line] => {
+                    for top_char in top_line.chars() {
+                        output.push(if top_char == '█' { '▀' } else { ' ' });
+                    }
+                }
+                _ => unreachable!(),
+            }
+            output.push('\n');
+        }
+
+        Ok(output.into_bytes())
+    }
+}
+
+/// DEC sixel graphics for terminals that support them (xterm -ti vt340, mlterm, foot, WezTerm)
+pub struct SixelRenderer {
+    /// Pixels per module
+    pub scale: u32,
+}
+
+impl Default for SixelRenderer {
+    fn default() -> Self {
+        Self { scale: 4 }
+    }
+}
+
+impl QrRenderer for SixelRenderer {
+    fn name(&self) -> &str {
+        "sixel"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["six", "sixel"]
+    }
+
+    fn render(&self, code: &QrCode, style: &QrStyle) -> Result<Vec<u8>> {
+        if self.scale == 0 {
+            return Err(anyhow!("Sixel scale must be at least 1"));
+        }
+        let image = rasterize(code, style, self.scale);
+        let dark = style.foreground.to_rgba();
+        let (width, height) = image.dimensions();
+
+        let mut sixel = String::from("\x1bPq");
+        write!(sixel, "\"1;1;{};{}", width, height)?;
+        write!(sixel, "#0;2;{}#1;2;{}", sixel_color(style.background), sixel_color(style.foreground))?;
+
+        for band in (0..height).step_by(6) {
+            for (register, is_dark) in [(0, false), (1, true)] {
+                write!(sixel, "#{}", register)?;
+                let columns = (0..width).map(|x| {
+                    let bits = (0..6)
+                        .filter(|row| band + row < height)
+                        .filter(|row| (*image.get_pixel(x, band + row) == dark) == is_dark)
+                        .fold(0u8, |bits, row| bits | 1 << row);
+                    (63 + bits) as char
+                });
+                push_run_length(&mut sixel, columns);
+                // Graphics carriage return: draw the other colour over the same band
+                sixel.push('$');
+            }
+            sixel.push('-');
+        }
+        sixel.push_str("\x1b\\");
+        Ok(sixel.into_bytes())
+    }
+}
+
+/// Sixel colour components are percentages
+fn sixel_color(color: Color) -> String {
+    let percent = |component: u8| component as u32 * 100 / 255;
+    format!("{};{};{}", percent(color.r), percent(color.g), percent(color.b))
+}
+
+fn push_run_length(sixel: &mut String, columns: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |sixel: &mut String, (c, count): (char, usize)| {
+        if count > 3 {
+            let _ = write!(sixel, "!{}{}", count, c);
+        } else {
+            sixel.extend(std::iter::repeat_n(c, count));
+        }
+    };
+    for c in columns {
+        run = match run {
+            Some((last, count)) if last == c => Some((last, count + 1)),
+            Some(previous) => {
+                flush(sixel, previous);
+                Some((c, 1))
+            }
+            None => Some((c, 1)),
+        };
+    }
+    if let Some(last) = run {
+        flush(sixel, last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::QUIET_ZONE;
+    use image::GenericImageView;
+
+    struct Upper;
+
+    impl QrRenderer for Upper {
+        fn name(&self) -> &str {
+            "svg"
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["svg"]
+        }
+
+        fn render(&self, _code: &QrCode, _style: &QrStyle) -> Result<Vec<u8>> {
+            Ok(b"custom".to_vec())
+        }
+    }
+
+    #[test]
+    fn test_registry_lookup() {
+        let mut registry = RendererRegistry::new();
+        assert_eq!(registry.for_path(Path::new("out/code.PDF")).unwrap().name(), "pdf");
+        assert_eq!(registry.for_path(Path::new("code.six")).unwrap().name(), "sixel");
+        assert!(registry.for_path(Path::new("code")).is_none());
+
+        registry.register(Box::new(Upper));
+        let code = QrCode::new("hi").unwrap();
+        let svg = registry.for_path(Path::new("code.svg")).unwrap();
+        assert_eq!(svg.render(&code, &QrStyle::default()).unwrap(), b"custom");
+        assert_eq!(registry.names(), ["png", "svg", "pdf", "terminal", "sixel"]);
+    }
+
+    fn render_to_string(renderer: &dyn QrRenderer, code: &QrCode, style: &QrStyle) -> String {
+        String::from_utf8(renderer.render(code, style).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_builtin_formats() {
+        let registry = RendererRegistry::new();
+        let code = QrCode::new("https://example.com").unwrap();
+        let style = QrStyle::default();
+
+        let png = registry.get("png").unwrap().render(&code, &style).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+
+        let pdf = render_to_string(registry.get("pdf").unwrap(), &code, &style);
+        assert!(pdf.starts_with("%PDF-1.4") && pdf.ends_with("%%EOF\n"));
+
+        let codes = [QrCode::new("one").unwrap(), QrCode::new("two").unwrap()];
+        let document = String::from_utf8(PdfRenderer::default().render_document(&codes, &style).unwrap()).unwrap();
+        assert!(document.contains("/Kids [3 0 R 5 0 R] /Count 2"));
+
+        let sixel = render_to_string(registry.get("sixel").unwrap(), &code, &style);
+        assert!(sixel.starts_with("\x1bPq") && sixel.ends_with("\x1b\\"));
+
+        let terminal = render_to_string(registry.get("terminal").unwrap(), &code, &style);
+        assert_eq!(terminal.lines().count(), (code.width() + 2 * QUIET_ZONE).div_ceil(2));
+    }
+
+    #[test]
+    fn test_frame_and_caption() {
+        let code = QrCode::new("hi").unwrap();
+        let style = QrStyle::default().with_quiet_zone(2).with_frame(1).with_caption("Scan me");
+        let png = PngRenderer { scale: 4, target_size: None }.render(&code, &style).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        // 21 modules, two of quiet zone and one of frame on each side, and the caption band
+        assert_eq!(image.dimensions(), (27 * 4, (27 + CAPTION_MODULES as u32) * 4));
+        assert_eq!(*image.get_pixel(1, 1), Color::BLACK.to_rgba());
+        assert_eq!(*image.get_pixel(5, 5), Color::WHITE.to_rgba());
+        assert_eq!(*image.get_pixel(3 * 4, 3 * 4), Color::BLACK.to_rgba());
+        let band = image.view(0, 27 * 4, 27 * 4, CAPTION_MODULES as u32 * 4);
+        assert!(band.pixels().any(|(_, _, pixel)| pixel != Color::WHITE.to_rgba()));
+        assert_eq!(PngRenderer::default().render(&code, &QrStyle::default().with_caption("")).unwrap(), PngRenderer::default().render(&code, &QrStyle::default()).unwrap());
+
+        let svg = render_to_string(&SvgRenderer::default(), &code, &style);
+        assert!(svg.contains("viewBox=\"0 0 27 30\"") && svg.contains(">Scan me</text>"));
+        assert!(svg.contains("fill-rule=\"evenodd\" d=\"M0 0h27v27H0zM1 1h25v25h-25z\"") && svg.contains("translate(1 1)"));
+    }
+
+    #[test]
+    fn test_crisp_display_size() {
+        // 33 modules on a 150% display: 500pt is 750px, 22px per module
+        let size = crisp_display_size(500.0, 1.5, 33);
+        assert_eq!(size * 1.5, 22.0 * 33.0);
+        assert!(size <= 500.0);
+        assert_eq!(crisp_display_size(400.0, 1.0, 40), 400.0);
+        assert_eq!(crisp_display_size(20.0, 1.0, 40), 20.0);
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use base64::Engine;
+use chrono::{DateTime, Local};
+use image::{ImageOutputFormat, Rgba};
+use qrcode::{EcLevel, QrCode, Version};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use tracing::info;
+
+use crate::locale;
+use crate::qr_generator::QRGenerator;
+
+/// A self-contained description of a generated QR code, for attaching to docs or tickets
+pub struct QrReport {
+    pub text: String,
+    pub version: Version,
+    pub error_correction: EcLevel,
+    pub modules: usize,
+    pub style: String,
+    pub generated_at: DateTime<Local>,
+    pub png: Vec<u8>,
+}
+
+impl QrReport {
+    pub fn new(generator: &QRGenerator, text: &str) -> Result<Self> {
+        if text.is_empty() {
+            return Err(anyhow::anyhow!("No text to generate QR code"));
+        }
+
+        let style = generator.style();
+        let code = QrCode::new(text)?;
+        let image = code.render::<Rgba<u8>>()
+            .min_dimensions(300, 300)
+            .dark_color(style.foreground.to_rgba())
+            .light_color(style.background.to_rgba())
+            .build();
+
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+
+        Ok(Self {
+            text: text.to_string(),
+            version: code.version(),
+            error_correction: code.error_correction_level(),
+            modules: code.width(),
+            style: format!(
+                "{} ({} on {})",
+                style.name,
+                style.foreground.to_hex(),
+                style.background.to_hex()
+            ),
+            generated_at: Local::now(),
+            png,
+        })
+    }
+
+    /// Render the report as Markdown with the PNG embedded as a data URI
+    pub fn to_markdown(&self) -> String {
+        let png = base64::engine::general_purpose::STANDARD.encode(&self.png);
+        let fence = if self.text.contains("```") { "~~~~" } else { "```" };
+        let locale = locale::current();
+        let generated = format!("{} {}", locale.date_time(&self.generated_at), self.generated_at.format("%:z"));
+
+        format!(
+            "# QR Code Report\n\n\
+             ![QR code](data:image/png;base64,{png})\n\n\
+             ## Content\n\n\
+             {fence}text\n{text}\n{fence}\n\n\
+             ## Details\n\n\
+             | Property | Value |\n\
+             |---|---|\n\
+             | Version | {version} |\n\
+             | Error correction | {ecc} |\n\
+             | Size | {modules}x{modules} modules |\n\
+             | Characters | {chars} |\n\
+             | Style | {style} |\n\
+             | Generated | {time} |\n\
+             | Generator | clipboard-qr {app_version} |\n",
+            png = png,
+            fence = fence,
+            text = self.text,
+            version = describe_version(self.version),
+            ecc = describe_ec_level(self.error_correction),
+            modules = self.modules,
+            chars = locale.number(self.text.chars().count() as u64),
+            style = self.style,
+            time = generated,
+            app_version = env!("CARGO_PKG_VERSION"),
+        )
+    }
+
+    pub fn save_markdown(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(path, self.to_markdown())?;
+        info!("QR report saved to: {:?}", path);
+        Ok(())
+    }
+}
+
+pub fn describe_version(version: Version) -> String {
+    match version {
+        Version::Normal(v) => v.to_string(),
+        Version::Micro(v) => format!("M{}", v),
+    }
+}
+
+pub fn describe_ec_level(level: EcLevel) -> &'static str {
+    match level {
+        EcLevel::L => "L (~7%)",
+        EcLevel::M => "M (~15%)",
+        EcLevel::Q => "Q (~25%)",
+        EcLevel::H => "H (~30%)",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_markdown() {
+        let report = QrReport::new(&QRGenerator::new(), "https://example.com").unwrap();
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("data:image/png;base64,iVBOR"));
+        assert!(markdown.contains("https://example.com"));
+        assert!(markdown.contains("| Error correction | M (~15%) |"));
+    }
+
+    #[test]
+    fn test_report_empty_text() {
+        assert!(QrReport::new(&QRGenerator::new(), "").is_err());
+    }
+}
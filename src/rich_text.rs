@@ -0,0 +1,276 @@
+use base64::Engine;
+use image::RgbaImage;
+use regex::{Captures, Regex};
+use std::sync::OnceLock;
+
+use crate::qr_scanner;
+
+/// HTML or RTF from the clipboard, reduced to what can be encoded or scanned
+///
+/// Browsers and word processors often put only rich text on the clipboard, with images
+/// inlined as data URIs or RTF pictures rather than as a separate image format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RichContent {
+    /// The visible text, with paragraphs and line breaks as newlines
+    pub text: String,
+    /// Embedded images as encoded files (PNG, JPEG, ...), in document order
+    pub images: Vec<Vec<u8>>,
+}
+
+impl RichContent {
+    /// Text and data-URI images of an HTML document or fragment
+    pub fn from_html(html: &str) -> Self {
+        Self { text: html_to_text(html), images: html_images(html) }
+    }
+
+    /// Text and PNG or JPEG pictures of an RTF document
+    pub fn from_rtf(rtf: &str) -> Self {
+        RtfParser::default().parse(rtf)
+    }
+
+    /// The first embedded image that decodes
+    pub fn image(&self) -> Option<RgbaImage> {
+        self.images.iter().find_map(|bytes| qr_scanner::load_image_from_memory(bytes).ok())
+    }
+}
+
+fn html_to_text(html: &str) -> String {
+    static HIDDEN: OnceLock<Regex> = OnceLock::new();
+    static BREAK: OnceLock<Regex> = OnceLock::new();
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let hidden = HIDDEN.get_or_init(|| Regex::new(r"(?is)<!--.*?-->|<script\b.*?</script\s*>|<style\b.*?</style\s*>|<head\b.*?</head\s*>").unwrap());
+    let line_break = BREAK.get_or_init(|| Regex::new(r"(?i)<br\s*/?>|</(p|div|li|tr|h[1-6]|blockquote|pre|table)\s*>").unwrap());
+    let tag = TAG.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
+
+    // Windows CF_HTML starts with a "Version:0.9\r\nStartHTML:..." header
+    let html = match html.strip_prefix("Version:") {
+        Some(_) => html.find('<').map_or("", |start| &html[start..]),
+        None => html,
+    };
+    let html = hidden.replace_all(html, "");
+    // Line breaks in the source are plain whitespace; only markup breaks lines
+    let html = html.split_whitespace().collect::<Vec<_>>().join(" ");
+    let html = line_break.replace_all(&html, "\n");
+    let text = decode_entities(&tag.replace_all(&html, ""));
+    tidy_lines(&text)
+}
+
+fn html_images(html: &str) -> Vec<Vec<u8>> {
+    static DATA_URI: OnceLock<Regex> = OnceLock::new();
+    let data_uri = DATA_URI.get_or_init(|| Regex::new(r#"(?i)<img\b[^>]*?\bsrc\s*=\s*["']?data:image/[a-z0-9.+-]+;base64,([a-z0-9+/=\s]+)"#).unwrap());
+    data_uri
+        .captures_iter(html)
+        .filter_map(|captures| {
+            let encoded: String = captures[1].split_whitespace().collect();
+            base64::engine::general_purpose::STANDARD.decode(encoded).ok()
+        })
+        .collect()
+}
+
+fn decode_entities(text: &str) -> String {
+    static ENTITY: OnceLock<Regex> = OnceLock::new();
+    let entity = ENTITY.get_or_init(|| Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap());
+    entity
+        .replace_all(text, |captures: &Captures| {
+            let name = &captures[1];
+            let decoded = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => name.strip_prefix('#').and_then(|decimal| decimal.parse().ok()).and_then(char::from_u32),
+                },
+            };
+            decoded.map_or_else(|| captures[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+/// Trim every line and drop runs of blank lines the markup left behind
+fn tidy_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if !line.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// RTF groups whose content isn't part of the visible text
+const RTF_HIDDEN_DESTINATIONS: [&str; 14] = [
+    "fonttbl", "colortbl", "stylesheet", "info", "listtable", "listoverridetable", "generator", "header", "footer",
+    "fldinst", "themedata", "colorschememapping", "latentstyles", "datastore",
+];
+
+#[derive(Debug, Clone, Copy)]
+struct RtfGroup {
+    hidden: bool,
+    /// Characters that stand in for each `\u` character, for readers without Unicode
+    fallback_chars: usize,
+}
+
+/// A `\pict` group being read
+#[derive(Debug)]
+struct RtfPicture {
+    /// Group depth the picture started at
+    depth: usize,
+    /// Whether it is a PNG or JPEG; other formats such as WMF are skipped
+    supported: bool,
+    hex: String,
+}
+
+#[derive(Debug, Default)]
+struct RtfParser {
+    groups: Vec<RtfGroup>,
+    picture: Option<RtfPicture>,
+    /// Fallback characters still to drop after a `\u` character
+    skip: usize,
+    content: RichContent,
+}
+
+impl RtfParser {
+    fn parse(mut self, rtf: &str) -> RichContent {
+        let mut group = RtfGroup { hidden: false, fallback_chars: 1 };
+        let mut chars = rtf.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => self.groups.push(group),
+                '}' => {
+                    group = self.groups.pop().unwrap_or(group);
+                    self.finish_picture();
+                }
+                '\\' => match chars.next() {
+                    Some(c @ ('\\' | '{' | '}')) => self.push(c, group),
+                    Some('\'') => {
+                        let hex: String = chars.by_ref().take(2).collect();
+                        // Code page text; Latin-1 covers the common Windows-1252 characters
+                        if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                            self.push(char::from(byte), group);
+                        }
+                    }
+                    Some('*') => group.hidden = true,
+                    Some('~') => self.push(' ', group),
+                    Some('_') => self.push('-', group),
+                    Some('\r' | '\n') => self.push('\n', group),
+                    Some(c) if c.is_ascii_alphabetic() => {
+                        let mut word = c.to_string();
+                        while let Some(c) = chars.next_if(char::is_ascii_alphabetic) {
+                            word.push(c);
+                        }
+                        let mut parameter = String::new();
+                        if let Some(minus) = chars.next_if_eq(&'-') {
+                            parameter.push(minus);
+                        }
+                        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                            parameter.push(digit);
+                        }
+                        chars.next_if_eq(&' ');
+                        self.control_word(&word, parameter.parse().ok(), &mut group);
+                    }
+                    _ => {}
+                },
+                '\r' | '\n' => {}
+                c => match &mut self.picture {
+                    Some(picture) if !group.hidden => {
+                        if c.is_ascii_hexdigit() {
+                            picture.hex.push(c);
+                        }
+                    }
+                    _ => self.push(c, group),
+                },
+            }
+        }
+        self.content.text = tidy_lines(&self.content.text);
+        self.content
+    }
+
+    fn control_word(&mut self, word: &str, parameter: Option<i32>, group: &mut RtfGroup) {
+        match word {
+            "par" | "line" | "row" => self.push('\n', *group),
+            "tab" | "cell" => self.push('\t', *group),
+            "u" => {
+                // Negative values stand for code points above 32767
+                let code = parameter.map(|code| if code < 0 { code + 65536 } else { code });
+                if let Some(c) = code.and_then(|code| char::from_u32(code as u32)) {
+                    self.push(c, *group);
+                }
+                self.skip = group.fallback_chars;
+            }
+            "uc" => group.fallback_chars = parameter.unwrap_or(1).max(0) as usize,
+            "pict" if !group.hidden => {
+                self.picture = Some(RtfPicture { depth: self.groups.len(), supported: false, hex: String::new() });
+            }
+            "pngblip" | "jpegblip" => {
+                if let Some(picture) = &mut self.picture {
+                    picture.supported = true;
+                }
+            }
+            word if RTF_HIDDEN_DESTINATIONS.contains(&word) => group.hidden = true,
+            _ => {}
+        }
+    }
+
+    fn push(&mut self, c: char, group: RtfGroup) {
+        if self.skip > 0 {
+            self.skip -= 1;
+        } else if !group.hidden && self.picture.is_none() {
+            self.content.text.push(c);
+        }
+    }
+
+    /// Keep the picture whose group just closed
+    fn finish_picture(&mut self) {
+        if self.picture.as_ref().is_none_or(|picture| self.groups.len() >= picture.depth) {
+            return;
+        }
+        let Some(picture) = self.picture.take() else { return };
+        if !picture.supported {
+            return;
+        }
+        let bytes: Option<Vec<u8>> = picture
+            .hex
+            .as_bytes()
+            .chunks_exact(2)
+            .map(|pair| std::str::from_utf8(pair).ok().and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+            .collect();
+        if let Some(bytes) = bytes.filter(|bytes| !bytes.is_empty()) {
+            self.content.images.push(bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rich_text() {
+        let html = "Version:0.9\r\nStartHTML:0000000105\r\n<html><head><title>Menu</title><style>p { color: red }</style></head>\n<body><!--StartFragment--><p>Fish &amp; chips\n  <b>today</b></p><p>Wi-Fi:&nbsp;Caf&#233;<br>x &lt; y</p><!--EndFragment--></body></html>";
+        let content = RichContent::from_html(html);
+        assert_eq!(content.text, "Fish & chips today\nWi-Fi: Café\nx < y");
+        assert!(content.images.is_empty());
+
+        let mut png = std::io::Cursor::new(Vec::new());
+        RgbaImage::from_pixel(4, 4, image::Rgba([0, 0, 0, 255])).write_to(&mut png, image::ImageOutputFormat::Png).unwrap();
+        let png = png.into_inner();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&png);
+        let content = RichContent::from_html(&format!(r#"<img alt="code" src="data:image/png;base64,{}">"#, encoded));
+        assert_eq!(content.text, "");
+        assert_eq!(content.images, std::slice::from_ref(&png));
+        assert_eq!(content.image().map(|image| image.dimensions()), Some((4, 4)));
+
+        let hex: String = png.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let rtf = format!(
+            r"{{\rtf1\ansi\deff0{{\fonttbl{{\f0 Arial;}}}}{{\*\generator Word}}\uc1 Caf\'e9 \u8364? 5\par Line two\tab end{{\pict\pngblip\picw4\pich4 {}}}}}",
+            hex
+        );
+        let content = RichContent::from_rtf(&rtf);
+        assert_eq!(content.text, "Café € 5\nLine two\tend");
+        assert_eq!(content.images, [png]);
+    }
+}
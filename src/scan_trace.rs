@@ -0,0 +1,223 @@
+use anyhow::Result;
+use bardecoder::decode::{Decode, QRDecoder};
+use bardecoder::detect::{Detect, LineScan, Location};
+use bardecoder::extract::{Extract, QRExtractor};
+use bardecoder::prepare::{BlockedMean, Prepare};
+use image::{DynamicImage, GrayImage, Rgba, RgbaImage};
+use std::fmt;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Same block sizes as `bardecoder::default_decoder`, so the trace matches a normal scan
+const BLOCK_SIZE: u32 = 5;
+const BLOCK_MEAN_SIZE: u32 = 7;
+
+/// Finder patterns extend this many modules from their centre
+const FINDER_RADIUS: f64 = 3.5;
+
+const DECODED: Rgba<u8> = Rgba([0, 200, 0, 255]);
+const FAILED: Rgba<u8> = Rgba([230, 0, 0, 255]);
+const FINDER: Rgba<u8> = Rgba([255, 170, 0, 255]);
+/// Tint for pixels the binarizer turned dark, i.e. what the finder search sees
+const SEARCHED: Rgba<u8> = Rgba([40, 90, 255, 255]);
+
+/// A region the detector took for a QR code, and what became of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// Centres of the top-left, top-right and bottom-left finder patterns
+    pub finders: [(f64, f64); 3],
+    pub module_size: f64,
+    pub version: u32,
+    /// Decoded text, or why extraction or decoding failed
+    pub outcome: Result<String, String>,
+}
+
+impl Candidate {
+    /// Outer corners of the code: top-left, top-right, bottom-right, bottom-left
+    pub fn corners(&self) -> [(f64, f64); 4] {
+        let [top_left, top_right, bottom_left] = self.finders;
+        // Unit vectors along the top and left edges, scaled to the finder radius
+        let along = |from: (f64, f64), to: (f64, f64)| {
+            let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+            let length = (dx * dx + dy * dy).sqrt().max(f64::EPSILON);
+            let scale = FINDER_RADIUS * self.module_size / length;
+            (dx * scale, dy * scale)
+        };
+        let right = along(top_left, top_right);
+        let down = along(top_left, bottom_left);
+        let bottom_right = (top_right.0 + bottom_left.0 - top_left.0, top_right.1 + bottom_left.1 - top_left.1);
+        [
+            (top_left.0 - right.0 - down.0, top_left.1 - right.1 - down.1),
+            (top_right.0 + right.0 - down.0, top_right.1 + right.1 - down.1),
+            (bottom_right.0 + right.0 + down.0, bottom_right.1 + right.1 + down.1),
+            (bottom_left.0 - right.0 + down.0, bottom_left.1 - right.1 + down.1),
+        ]
+    }
+}
+
+/// Every stage of one decode attempt, for images that won't decode
+pub struct ScanTrace {
+    /// The binarized image the detector searched
+    pub prepared: GrayImage,
+    pub candidates: Vec<Candidate>,
+}
+
+impl ScanTrace {
+    /// Run the decoder stage by stage on `image`, keeping each candidate region
+    pub fn run(image: &RgbaImage) -> Self {
+        let source = DynamicImage::ImageRgba8(image.clone());
+        let prepared = BlockedMean::new(BLOCK_SIZE, BLOCK_MEAN_SIZE).prepare(&source);
+        let extractor = QRExtractor::new();
+        let decoder = QRDecoder::new();
+
+        let candidates: Vec<Candidate> = LineScan::new()
+            .detect(&prepared)
+            .into_iter()
+            .map(|location| {
+                let Location::QR(location) = location;
+                let finders = [location.top_left, location.top_right, location.bottom_left].map(|point| (point.x, point.y));
+                let (module_size, version) = (location.module_size, location.version);
+                let outcome = decoder
+                    .decode(extractor.extract(&prepared, location))
+                    .map_err(|e| e.to_string());
+                debug!("Candidate version {} at {:?}: {:?}", version, finders[0], outcome.as_ref().map(|_| ()));
+                Candidate { finders, module_size, version, outcome }
+            })
+            .collect();
+
+        info!("Scan trace found {} candidate region(s)", candidates.len());
+        Self { prepared, candidates }
+    }
+
+    pub fn decoded(&self) -> Option<&str> {
+        self.candidates.iter().find_map(|candidate| candidate.outcome.as_deref().ok())
+    }
+
+    /// The image with the searched area tinted, candidate regions outlined green when
+    /// they decoded and red when they didn't, and finder pattern centres marked
+    pub fn render(&self, image: &RgbaImage) -> RgbaImage {
+        let mut annotated = RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+            let Rgba([r, g, b, a]) = *image.get_pixel(x, y);
+            // Fade the original so the annotations stand out
+            let fade = |channel: u8| ((channel as u32 * a as u32 / 255 + 255 * 2) / 3) as u8;
+            let faded = Rgba([fade(r), fade(g), fade(b), 255]);
+            match self.prepared.get_pixel_checked(x, y) {
+                Some(luma) if luma[0] < 128 => blend(faded, SEARCHED, 0.45),
+                _ => faded,
+            }
+        });
+
+        let thickness = (image.width().max(image.height()) / 300).max(1) as i64;
+        for candidate in &self.candidates {
+            let colour = if candidate.outcome.is_ok() { DECODED } else { FAILED };
+            let corners = candidate.corners();
+            for index in 0..corners.len() {
+                let next = corners[(index + 1) % corners.len()];
+                draw_line(&mut annotated, corners[index], next, colour, thickness);
+            }
+            let mark = (candidate.module_size * 1.5).max(2.0);
+            for (x, y) in candidate.finders {
+                draw_line(&mut annotated, (x - mark, y), (x + mark, y), FINDER, thickness);
+                draw_line(&mut annotated, (x, y - mark), (x, y + mark), FINDER, thickness);
+            }
+        }
+        annotated
+    }
+
+    /// Render the annotated image to `path`
+    pub fn save(&self, image: &RgbaImage, path: &Path) -> Result<()> {
+        self.render(image).save(path)?;
+        info!("Scan heatmap saved to {:?}", path);
+        Ok(())
+    }
+}
+
+impl fmt::Display for ScanTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.candidates.is_empty() {
+            return write!(f, "No candidate regions: the detector found no three finder patterns forming a code");
+        }
+        writeln!(f, "{} candidate region(s):", self.candidates.len())?;
+        for (index, candidate) in self.candidates.iter().enumerate() {
+            let (x, y) = candidate.finders[0];
+            write!(
+                f,
+                "  {}. version {} at ({:.0}, {:.0}), {:.1} px per module: ",
+                index + 1,
+                candidate.version,
+                x,
+                y,
+                candidate.module_size
+            )?;
+            match &candidate.outcome {
+                Ok(_) => write!(f, "decoded")?,
+                Err(e) => write!(f, "failed ({})", e)?,
+            }
+            if index + 1 < self.candidates.len() {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn blend(base: Rgba<u8>, tint: Rgba<u8>, amount: f32) -> Rgba<u8> {
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount).round() as u8;
+    Rgba([mix(base[0], tint[0]), mix(base[1], tint[1]), mix(base[2], tint[2]), 255])
+}
+
+/// Draw a line `thickness` pixels wide, clipped to the image
+fn draw_line(image: &mut RgbaImage, from: (f64, f64), to: (f64, f64), colour: Rgba<u8>, thickness: i64) {
+    let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().max(1.0) as usize;
+    let reach = thickness / 2;
+    for step in 0..=steps {
+        let t = step as f64 / steps as f64;
+        let x = (from.0 + (to.0 - from.0) * t).round() as i64;
+        let y = (from.1 + (to.1 - from.1) * t).round() as i64;
+        for py in y - reach..=y + reach {
+            for px in x - reach..=x + reach {
+                if px >= 0 && py >= 0 && (px as u32) < image.width() && (py as u32) < image.height() {
+                    image.put_pixel(px as u32, py as u32, colour);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_corners() {
+        let candidate = Candidate {
+            finders: [(13.5, 13.5), (43.5, 13.5), (13.5, 43.5)],
+            module_size: 2.0,
+            version: 2,
+            outcome: Err("format information unreadable".to_string()),
+        };
+        // Finder centres sit 3.5 modules in from the outer edge
+        assert_eq!(candidate.corners(), [(6.5, 6.5), (50.5, 6.5), (50.5, 50.5), (6.5, 50.5)]);
+    }
+
+    #[test]
+    fn test_render_and_summary() {
+        let image = RgbaImage::from_pixel(60, 60, Rgba([255, 255, 255, 255]));
+        let trace = ScanTrace {
+            prepared: GrayImage::from_pixel(60, 60, image::Luma([0])),
+            candidates: vec![Candidate {
+                finders: [(13.5, 13.5), (43.5, 13.5), (13.5, 43.5)],
+                module_size: 2.0,
+                version: 2,
+                outcome: Err("bad format".to_string()),
+            }],
+        };
+        let annotated = trace.render(&image);
+        assert_eq!(annotated.get_pixel(30, 7), &FAILED);
+        assert_eq!(annotated.get_pixel(14, 14), &FINDER);
+        // Searched pixels are tinted, not left as faded white
+        assert_ne!(annotated.get_pixel(30, 30), &Rgba([255, 255, 255, 255]));
+        assert_eq!(trace.decoded(), None);
+        assert!(trace.to_string().contains("failed (bad format)"));
+    }
+}
@@ -0,0 +1,312 @@
+use anyhow::Result;
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::clipboard_handler::{ClipboardData, ClipboardHandler};
+use crate::content_preview;
+use crate::file_scan::{self, LastScanDir, PageResult, IMAGE_EXTENSIONS};
+use crate::monitor::ScanOutcome;
+use crate::qr_scanner::{self, DecodedCode, QRScanner, ScanSettings};
+use crate::redaction::Redactor;
+use crate::totp::{self, Totp};
+
+/// Largest side of the scanned image in the window, in points
+const PREVIEW_SIDE: f32 = 420.0;
+
+const BOX_COLOR: egui::Color32 = egui::Color32::from_rgb(0, 200, 83);
+
+/// Open a window for scanning image files: pick one with the file dialog, which starts in
+/// the folder last picked from, or type its path and press Enter. The image is shown with a
+/// numbered box around each code found
+pub fn run(scan: ScanSettings, redactor: Redactor) -> Result<()> {
+    info!("Opening scan window");
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("Clipboard QR - Scan Files")
+            .with_inner_size([560.0, 640.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Clipboard QR Scanner",
+        options,
+        Box::new(move |_cc| {
+            Ok(Box::new(ScanWindow {
+                scanner: scan.scanner(),
+                redactor,
+                last_dir: LastScanDir::open_default(),
+                path: String::new(),
+                source: None,
+                results: Ok(Vec::new()),
+                overlay: None,
+                status: None,
+                clipboard: ClipboardHandler::builder().spawn_listener(false).build(),
+            }))
+        }),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to open the scan window: {}", e))
+}
+
+struct ScanWindow {
+    scanner: QRScanner,
+    redactor: Redactor,
+    last_dir: LastScanDir,
+    /// The path field, also filled in by Browse…
+    path: String,
+    /// What the results are from: a file, or the clipboard
+    source: Option<String>,
+    /// A result per page, or why the file couldn't be read
+    results: Result<Vec<PageResult>, String>,
+    /// The first page with every code on it
+    overlay: Option<Overlay>,
+    status: Option<String>,
+    /// Kept while the window is open, as X11 and Wayland clipboard content only lasts as
+    /// long as the handler that set it
+    clipboard: ClipboardHandler,
+}
+
+/// A scanned image and where its codes are
+struct Overlay {
+    texture: egui::TextureHandle,
+    codes: Vec<DecodedCode>,
+}
+
+impl ScanWindow {
+    fn browse(&mut self, ctx: &egui::Context) {
+        let mut dialog = rfd::FileDialog::new().set_title("Scan image").add_filter("Image", &IMAGE_EXTENSIONS);
+        if let Some(dir) = self.last_dir.get() {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(path) = dialog.pick_file() else { return };
+        self.path = path.display().to_string();
+        self.scan(ctx);
+    }
+
+    fn scan(&mut self, ctx: &egui::Context) {
+        let path = PathBuf::from(self.path.trim());
+        if path.as_os_str().is_empty() {
+            return;
+        }
+        self.status = None;
+        self.results = scan(&self.scanner, &path).map_err(|e| e.to_string());
+        self.overlay = None;
+        match &self.results {
+            Ok(_) => {
+                if let Err(e) = self.last_dir.remember(&path) {
+                    warn!("Failed to remember the scan folder: {}", e);
+                }
+                match qr_scanner::load_image(&path) {
+                    Ok(image) => self.overlay = Some(self.overlay(ctx, &image)),
+                    Err(e) => warn!("Failed to load {:?} for the overlay: {}", path, e),
+                }
+            }
+            Err(e) => error!("Failed to scan {:?}: {}", path, e),
+        }
+        self.source = Some(path.display().to_string());
+    }
+
+    fn scan_clipboard(&mut self, ctx: &egui::Context) {
+        self.status = None;
+        self.overlay = None;
+        let image = match self.clipboard.get_data() {
+            Ok(ClipboardData::Image(image)) => image,
+            // A copied image file is scanned like one picked with Browse…
+            Ok(ClipboardData::Files(files)) if file_scan::single_image(&files).is_some() => {
+                self.path = files[0].display().to_string();
+                self.scan(ctx);
+                return;
+            }
+            Ok(_) => {
+                self.status = Some("❌ No image on the clipboard".to_string());
+                return;
+            }
+            Err(e) => {
+                self.status = Some(format!("❌ {}", e));
+                return;
+            }
+        };
+        let outcome = match self.scanner.scan_qr_from_rgba(&image) {
+            Ok(Some(content)) => ScanOutcome::Decoded(content),
+            Ok(None) => ScanOutcome::NotFound,
+            Err(e) => ScanOutcome::Failed(e.to_string()),
+        };
+        self.results = Ok(vec![PageResult { page: 1, outcome }]);
+        self.overlay = Some(self.overlay(ctx, &image));
+        self.source = Some("Clipboard image".to_string());
+    }
+
+    /// `image` as a texture with every code found on it
+    fn overlay(&self, ctx: &egui::Context, image: &image::RgbaImage) -> Overlay {
+        let codes = match self.scanner.scan_multiple_qr_codes(image) {
+            Ok(codes) => codes,
+            Err(e) => {
+                warn!("Failed to locate the codes: {}", e);
+                Vec::new()
+            }
+        };
+        let size = [image.width() as usize, image.height() as usize];
+        let texture = ctx.load_texture("scanned-image", egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()), egui::TextureOptions::LINEAR);
+        Overlay { texture, codes }
+    }
+
+    fn copy(&mut self, content: &str) {
+        self.status = Some(match self.clipboard.set_text(content) {
+            Ok(()) => "📋 Copied to the clipboard".to_string(),
+            Err(e) => format!("❌ {}", e),
+        });
+    }
+
+    /// The image with a numbered box around each code; hovering a box shows its text
+    fn overlay_image(&self, ui: &mut egui::Ui, overlay: &Overlay) {
+        let [width, height] = overlay.texture.size();
+        let scale = (PREVIEW_SIDE / width.max(height) as f32).min(ui.available_width() / width as f32).min(1.0);
+        let size = egui::vec2(width as f32 * scale, height as f32 * scale);
+        let rect = ui.add(egui::Image::new(&overlay.texture).fit_to_exact_size(size)).rect;
+
+        let painter = ui.painter_at(rect);
+        for (index, code) in overlay.codes.iter().enumerate() {
+            let bounds = &code.bounds;
+            let min = rect.min + egui::vec2(bounds.x as f32, bounds.y as f32) * scale;
+            let code_rect = egui::Rect::from_min_size(min, egui::vec2(bounds.width as f32, bounds.height as f32) * scale);
+            painter.rect_stroke(code_rect, 0.0, egui::Stroke::new(2.0, BOX_COLOR), egui::StrokeKind::Outside);
+            let number = painter.layout_no_wrap((index + 1).to_string(), egui::FontId::proportional(14.0), egui::Color32::WHITE);
+            let position = code_rect.left_top() + egui::vec2(3.0, 2.0);
+            painter.rect_filled(egui::Rect::from_min_size(position, number.size()).expand(2.0), 2.0, BOX_COLOR);
+            painter.galley(position, number, egui::Color32::WHITE);
+            ui.interact(code_rect, ui.id().with(("scanned-code", index)), egui::Sense::hover())
+                .on_hover_text(format!("{} {}: {}", code.symbology.label(), index + 1, totp::console_text(&code.content, &self.redactor)));
+        }
+    }
+
+    fn results(&mut self, ui: &mut egui::Ui) {
+        let Some(source) = &self.source else {
+            ui.label("Pick an image, or type its path and press Enter.");
+            return;
+        };
+        let pages = match &self.results {
+            Ok(pages) => pages,
+            Err(e) => {
+                ui.label(format!("❌ {}: {}", source, e));
+                return;
+            }
+        };
+        let mut copy = None;
+        let mut live_code = false;
+        let redactor = self.redactor;
+        // A one-time password setup shows its current code instead of the URI with the secret
+        let mut decoded = |ui: &mut egui::Ui, content: &str| match Totp::parse(content) {
+            Ok(totp) => {
+                let (code, remaining) = totp.now();
+                if ui.button("Copy Code").clicked() {
+                    copy = Some(code.clone());
+                }
+                let shown = if redactor.is_enabled() { totp.name } else { format!("{}  {}", totp.name, code) };
+                ui.label(format!("🔑 {}  ⏱ {}s", shown, remaining));
+                live_code = true;
+            }
+            Err(_) => {
+                if ui.button("Copy").clicked() {
+                    copy = Some(content.to_string());
+                }
+                // JSON, XML and URLs with a query fold out as a tree, unless the content is hidden
+                match content_preview::build_tree(content).filter(|_| !redactor.is_enabled()) {
+                    Some(_) => {
+                        if ui.button("Copy Formatted").clicked() {
+                            copy = Some(content_preview::pretty_print(content));
+                        }
+                        ui.vertical(|ui| ui.push_id(content, |ui| content_preview::show_preview(ui, content)));
+                    }
+                    None => {
+                        ui.label(redactor.text(content));
+                    }
+                }
+            }
+        };
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            // Codes on the image are numbered as on the overlay; other pages are listed after
+            let located = self.overlay.as_ref().filter(|overlay| !overlay.codes.is_empty());
+            if let Some(overlay) = located {
+                for (index, code) in overlay.codes.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}.", index + 1));
+                        decoded(ui, &code.content);
+                    });
+                }
+            }
+            for result in pages.iter().filter(|result| located.is_none() || result.page > 1) {
+                ui.horizontal(|ui| {
+                    if pages.len() > 1 {
+                        ui.label(format!("Page {}:", result.page));
+                    }
+                    match &result.outcome {
+                        ScanOutcome::Decoded(content) => decoded(ui, content),
+                        ScanOutcome::NotFound | ScanOutcome::Skipped => {
+                            ui.label("No QR code found");
+                        }
+                        ScanOutcome::Failed(e) => {
+                            ui.label(format!("❌ {}", e));
+                        }
+                    }
+                });
+            }
+            if let Some(overlay) = &self.overlay {
+                ui.add_space(8.0);
+                self.overlay_image(ui, overlay);
+            }
+        });
+        // Keep the countdown going
+        if live_code {
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
+        }
+        if let Some(content) = copy {
+            self.copy(&content);
+        }
+    }
+}
+
+/// Every page of a file, or every distinct code of an animated GIF as pages
+fn scan(scanner: &QRScanner, path: &Path) -> Result<Vec<PageResult>> {
+    if !file_scan::is_gif(path) {
+        return file_scan::scan_file(scanner, path);
+    }
+    let codes = scanner.scan_qr_from_gif(path)?;
+    if codes.is_empty() {
+        return Ok(vec![PageResult { page: 1, outcome: ScanOutcome::NotFound }]);
+    }
+    let pages = codes.into_iter().enumerate().map(|(index, content)| PageResult { page: index + 1, outcome: ScanOutcome::Decoded(content) });
+    Ok(pages.collect())
+}
+
+impl eframe::App for ScanWindow {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Image");
+                let field = ui.add(egui::TextEdit::singleline(&mut self.path).desired_width(340.0).hint_text("Path to an image file"));
+                if field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    self.scan(ctx);
+                }
+                if ui.button("Browse…").clicked() {
+                    self.browse(ctx);
+                }
+                if ui.add_enabled(!self.path.trim().is_empty(), egui::Button::new("Scan")).clicked() {
+                    self.scan(ctx);
+                }
+            });
+            if ui.button("Scan Clipboard Image").clicked() {
+                self.scan_clipboard(ctx);
+            }
+            ui.separator();
+            self.results(ui);
+            if let Some(status) = &self.status {
+                ui.separator();
+                ui.label(status);
+            }
+        });
+    }
+}
@@ -0,0 +1,314 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::normalize::NormalizationProfile;
+use crate::qr_generator::QRGenerator;
+use crate::storage;
+
+/// How often the scheduler thread wakes up to check for due tasks and shutdown
+const TICK: Duration = Duration::from_secs(1);
+
+/// A config-defined task that regenerates a QR code from a dynamic source
+///
+/// ```toml
+/// [[tasks]]
+/// name = "guest-wifi"
+/// source = { command = "wifi-qr-string guest" }
+/// output = "/srv/www/guest-wifi.png"
+/// at = "07:00"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskConfig {
+    pub name: String,
+    pub source: TaskSource,
+    /// Output file; `.svg` is written as SVG, anything else as PNG
+    pub output: PathBuf,
+    /// Run at a fixed interval, e.g. "30m", "6h", "1d"
+    #[serde(default)]
+    pub every: Option<String>,
+    /// Run once a day at a local time, e.g. "07:00"
+    #[serde(default)]
+    pub at: Option<String>,
+}
+
+/// Where a task's QR content comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskSource {
+    /// Contents of a file
+    File(PathBuf),
+    /// Standard output of a shell command
+    Command(String),
+    /// Body of an HTTP GET request
+    Url(String),
+}
+
+impl TaskSource {
+    /// Fetch the current content, with surrounding whitespace removed
+    pub fn read(&self) -> Result<String> {
+        let content = match self {
+            TaskSource::File(path) => fs::read_to_string(path)?,
+            TaskSource::Command(command) => {
+                #[cfg(windows)]
+                let output = Command::new("cmd").args(["/C", command]).output()?;
+                #[cfg(not(windows))]
+                let output = Command::new("sh").args(["-c", command]).output()?;
+
+                if !output.status.success() {
+                    return Err(anyhow::anyhow!(
+                        "Command exited with {}: {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ));
+                }
+                String::from_utf8(output.stdout)?
+            }
+            TaskSource::Url(url) => ureq::get(url)
+                .timeout(Duration::from_secs(30))
+                .call()?
+                .into_string()?,
+        };
+        Ok(content.trim().to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    Every(Duration),
+    DailyAt(NaiveTime),
+}
+
+impl Schedule {
+    /// The first run time strictly after `after`
+    pub fn next_after(&self, after: DateTime<Local>) -> DateTime<Local> {
+        match *self {
+            Schedule::Every(interval) => after + ChronoDuration::from_std(interval).unwrap_or(ChronoDuration::MAX),
+            Schedule::DailyAt(time) => {
+                let mut date = after.date_naive();
+                loop {
+                    // Skip times that don't exist locally, e.g. during a DST change
+                    if let Some(next) = date.and_time(time).and_local_timezone(Local).earliest() {
+                        if next > after {
+                            return next;
+                        }
+                    }
+                    date = date.succ_opt().unwrap_or(date);
+                }
+            }
+        }
+    }
+}
+
+impl TaskConfig {
+    pub fn schedule(&self) -> Result<Schedule> {
+        match (&self.every, &self.at) {
+            (Some(every), None) => Ok(Schedule::Every(parse_interval(every)?)),
+            (None, Some(at)) => NaiveTime::parse_from_str(at, "%H:%M")
+                .map(Schedule::DailyAt)
+                .map_err(|_| anyhow::anyhow!("Invalid time '{}', expected HH:MM", at)),
+            _ => Err(anyhow::anyhow!("Set exactly one of 'every' or 'at'")),
+        }
+    }
+
+    /// Fetch the source and write the QR code; returns false if the content was unchanged
+    fn run(&self, generator: &QRGenerator, normalization: NormalizationProfile, last: &mut Option<String>) -> Result<bool> {
+        let content = self.source.read()?;
+        if content.is_empty() {
+            return Err(anyhow::anyhow!("Source produced no content"));
+        }
+        let unchanged = last.as_deref().is_some_and(|last| normalization.same(last, &content));
+        if unchanged && self.output.exists() {
+            return Ok(false);
+        }
+
+        storage::check_write("a scheduled task's output", &self.output)?;
+        generator.save_qr_image_to(&content, &self.output)?;
+        *last = Some(content);
+        Ok(true)
+    }
+}
+
+/// Longest interval accepted, in seconds; about a century, far from where run times overflow
+const MAX_INTERVAL_SECS: u64 = 100 * 365 * 24 * 60 * 60;
+
+/// Parse an interval such as "90s", "15m", "6h" or "1d"
+pub fn parse_interval(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid interval '{}', expected e.g. 30m or 6h", value))?;
+
+    let unit_secs = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        _ => return Err(anyhow::anyhow!("Invalid interval '{}', expected a unit of s, m, h or d", value)),
+    };
+    let seconds = number
+        .checked_mul(unit_secs)
+        .filter(|seconds| *seconds <= MAX_INTERVAL_SECS)
+        .ok_or_else(|| anyhow::anyhow!("Interval '{}' is too long", value))?;
+    if seconds == 0 {
+        return Err(anyhow::anyhow!("Interval must be greater than zero"));
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+struct ScheduledTask {
+    config: TaskConfig,
+    schedule: Schedule,
+    next_run: DateTime<Local>,
+    last_content: Option<String>,
+}
+
+/// Runs scheduled QR generation tasks on a background thread
+pub struct Scheduler {
+    tasks: Vec<ScheduledTask>,
+    generator: QRGenerator,
+    normalization: NormalizationProfile,
+}
+
+impl Scheduler {
+    /// Tasks with an invalid schedule are logged and skipped
+    pub fn new(tasks: &[TaskConfig], generator: QRGenerator) -> Self {
+        let now = Local::now();
+        let tasks = tasks
+            .iter()
+            .filter_map(|config| match config.schedule() {
+                Ok(schedule) => Some(ScheduledTask {
+                    config: config.clone(),
+                    schedule,
+                    // Interval tasks run immediately so outputs exist after startup
+                    next_run: match schedule {
+                        Schedule::Every(_) => now,
+                        Schedule::DailyAt(_) => schedule.next_after(now),
+                    },
+                    last_content: None,
+                }),
+                Err(e) => {
+                    warn!("Skipping task '{}': {}", config.name, e);
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            tasks,
+            generator,
+            normalization: NormalizationProfile::default(),
+        }
+    }
+
+    /// How fetched content is compared with the last run to skip unchanged outputs
+    pub fn with_normalization(mut self, profile: NormalizationProfile) -> Self {
+        self.normalization = profile;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Start running tasks on a new thread
+    pub fn start(mut self) -> SchedulerHandle {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+
+        let thread = thread::spawn(move || {
+            info!("Scheduler started with {} task(s)", self.tasks.len());
+
+            while thread_running.load(Ordering::Relaxed) {
+                let now = Local::now();
+                for task in self.tasks.iter_mut().filter(|task| task.next_run <= now) {
+                    match task.config.run(&self.generator, self.normalization, &mut task.last_content) {
+                        Ok(true) => info!("Task '{}' wrote {:?}", task.config.name, task.config.output),
+                        Ok(false) => info!("Task '{}' content unchanged", task.config.name),
+                        Err(e) => error!("Task '{}' failed: {}", task.config.name, e),
+                    }
+                    task.next_run = task.schedule.next_after(now);
+                }
+
+                thread::sleep(TICK);
+            }
+
+            info!("Scheduler stopped");
+        });
+
+        SchedulerHandle {
+            running,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Controls a running [`Scheduler`]; the scheduler stops when this is dropped
+pub struct SchedulerHandle {
+    running: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Drop for SchedulerHandle {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("Scheduler thread panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_interval("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_interval("1d").unwrap(), Duration::from_secs(86400));
+        assert!(parse_interval("0h").is_err());
+        assert!(parse_interval("soon").is_err());
+        assert!(parse_interval("10").is_err());
+        assert!(parse_interval("18446744073709551615d").is_err());
+        assert!(parse_interval("36501d").is_err());
+    }
+
+    #[test]
+    fn test_daily_schedule() {
+        let schedule = Schedule::DailyAt(NaiveTime::from_hms_opt(7, 0, 0).unwrap());
+        let before = Local.with_ymd_and_hms(2024, 3, 1, 6, 0, 0).unwrap();
+        let after = Local.with_ymd_and_hms(2024, 3, 1, 8, 0, 0).unwrap();
+
+        assert_eq!(schedule.next_after(before), Local.with_ymd_and_hms(2024, 3, 1, 7, 0, 0).unwrap());
+        assert_eq!(schedule.next_after(after), Local.with_ymd_and_hms(2024, 3, 2, 7, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_task_config() {
+        let config: TaskConfig = toml::from_str(
+            r#"
+            name = "guest-wifi"
+            source = { file = "/etc/guest-wifi" }
+            output = "wifi.png"
+            every = "6h"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.source, TaskSource::File(PathBuf::from("/etc/guest-wifi")));
+        assert_eq!(config.schedule().unwrap(), Schedule::Every(Duration::from_secs(6 * 3600)));
+    }
+}
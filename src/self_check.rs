@@ -0,0 +1,175 @@
+use std::fmt;
+use tracing::{debug, info};
+
+/// The graphical session the app is running in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayServer {
+    Windows,
+    MacOs,
+    X11,
+    Wayland,
+}
+
+/// Whether a system tray icon can be shown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraySupport {
+    Available,
+    Missing(String),
+    /// Detection wasn't possible; assume the tray works
+    Unknown,
+}
+
+/// What the startup self-check found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub display: Option<DisplayServer>,
+    pub ssh_session: bool,
+    pub tray: TraySupport,
+}
+
+impl Capabilities {
+    /// Probe the current environment
+    pub fn detect() -> Self {
+        let display = detect_display(|name| std::env::var_os(name).is_some_and(|value| !value.is_empty()));
+        let ssh_session = std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_TTY").is_some();
+        let tray = match display {
+            None => TraySupport::Missing("no display server".to_string()),
+            Some(DisplayServer::Windows) | Some(DisplayServer::MacOs) => TraySupport::Available,
+            Some(server) => linux_tray_support(server),
+        };
+
+        let capabilities = Self { display, ssh_session, tray };
+        info!("Startup self-check: {}", capabilities);
+        capabilities
+    }
+
+    /// Why tray mode can't work here, if it can't
+    pub fn tray_problem(&self) -> Option<String> {
+        match (&self.display, &self.tray) {
+            (None, _) if self.ssh_session => Some("no display found; this looks like a headless SSH session".to_string()),
+            (None, _) => Some("no display found (DISPLAY and WAYLAND_DISPLAY are unset)".to_string()),
+            (Some(_), TraySupport::Missing(reason)) => Some(format!("no system tray available: {}", reason)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let display = match self.display {
+            Some(server) => format!("{:?}", server),
+            None => "none".to_string(),
+        };
+        let tray = match &self.tray {
+            TraySupport::Available => "available".to_string(),
+            TraySupport::Missing(reason) => format!("missing ({})", reason),
+            TraySupport::Unknown => "unknown".to_string(),
+        };
+        write!(f, "display={}, ssh={}, tray={}", display, self.ssh_session, tray)
+    }
+}
+
+/// Work out the display server from environment variables; `is_set` reports whether one is set
+pub fn detect_display(is_set: impl Fn(&str) -> bool) -> Option<DisplayServer> {
+    if cfg!(windows) {
+        return Some(DisplayServer::Windows);
+    }
+    if cfg!(target_os = "macos") {
+        return Some(DisplayServer::MacOs);
+    }
+    if is_set("WAYLAND_DISPLAY") {
+        Some(DisplayServer::Wayland)
+    } else if is_set("DISPLAY") {
+        Some(DisplayServer::X11)
+    } else {
+        None
+    }
+}
+
+/// Tray icons on Linux need a StatusNotifierItem host, or an XEmbed tray on X11
+fn linux_tray_support(server: DisplayServer) -> TraySupport {
+    let watcher = status_notifier_watcher_present();
+    match watcher {
+        Some(true) => return TraySupport::Available,
+        Some(false) => debug!("No StatusNotifierWatcher on the session bus"),
+        None => debug!("Could not query the session bus for a StatusNotifierWatcher"),
+    }
+
+    #[cfg(unix)]
+    if server == DisplayServer::X11 {
+        return match xembed_tray_present() {
+            Some(true) => TraySupport::Available,
+            Some(false) => TraySupport::Missing("no StatusNotifierItem host or XEmbed system tray".to_string()),
+            None => TraySupport::Unknown,
+        };
+    }
+
+    match watcher {
+        Some(false) => TraySupport::Missing(format!(
+            "the {:?} session has no StatusNotifierItem host (e.g. GNOME without the AppIndicator extension)",
+            server
+        )),
+        _ => TraySupport::Unknown,
+    }
+}
+
+/// Ask the session bus whether a StatusNotifierItem watcher is running
+fn status_notifier_watcher_present() -> Option<bool> {
+    let output = std::process::Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus.NameHasOwner",
+            "string:org.kde.StatusNotifierWatcher",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).contains("boolean true"))
+}
+
+/// Whether an X11 XEmbed system tray owns the `_NET_SYSTEM_TRAY_S<screen>` selection
+#[cfg(unix)]
+fn xembed_tray_present() -> Option<bool> {
+    use x11rb::protocol::xproto::ConnectionExt;
+
+    let (conn, screen_num) = x11rb::connect(None).ok()?;
+    let name = format!("_NET_SYSTEM_TRAY_S{}", screen_num);
+    let atom = conn.intern_atom(false, name.as_bytes()).ok()?.reply().ok()?.atom;
+    let owner = conn.get_selection_owner(atom).ok()?.reply().ok()?.owner;
+    Some(owner != x11rb::NONE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn test_detect_display() {
+        assert_eq!(detect_display(|_| false), None);
+        assert_eq!(detect_display(|name| name == "DISPLAY"), Some(DisplayServer::X11));
+        assert_eq!(detect_display(|_| true), Some(DisplayServer::Wayland));
+    }
+
+    #[test]
+    fn test_tray_problem() {
+        let headless = Capabilities {
+            display: None,
+            ssh_session: true,
+            tray: TraySupport::Missing("no display server".to_string()),
+        };
+        assert!(headless.tray_problem().unwrap().contains("SSH"));
+
+        let unknown = Capabilities {
+            display: Some(DisplayServer::Wayland),
+            ssh_session: false,
+            tray: TraySupport::Unknown,
+        };
+        assert_eq!(unknown.tray_problem(), None);
+    }
+}
@@ -0,0 +1,411 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use serde_json::json;
+use std::io::{Cursor, Read};
+use std::net::SocketAddr;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Method, Request, Response};
+use tracing::{debug, info, warn};
+
+use crate::generator_pool::{GenerationJob, GeneratorPool};
+use crate::monitor::{ClipboardMonitor, MonitorEvent, ScanOutcome};
+use crate::privacy::SensitiveFilter;
+use crate::qr_scanner::{load_image_from_memory, QRScanner, ScanSettings};
+use crate::clipboard_handler::ClipboardHandler;
+use crate::remote::{PushPayload, PushSource};
+
+/// Largest request body accepted, enough for a photo of a code
+const MAX_BODY_BYTES: u64 = 20 * 1024 * 1024;
+
+/// What `GET /clipboard/latest` returns
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatestClipboard {
+    pub text: String,
+    pub source: PushSource,
+    pub copied_at: DateTime<Local>,
+}
+
+/// The newest copied text or decoded code, shared between the monitor and the server
+pub type SharedLatest = Arc<Mutex<Option<LatestClipboard>>>;
+
+/// Puts text pushed with `POST /clipboard` on the local clipboard
+pub type ClipboardSink = Box<dyn Fn(&str) -> Result<()> + Send + Sync>;
+
+/// Body of `POST /generate` when sent as JSON; plain text bodies are encoded as they are
+#[derive(Debug, Deserialize)]
+struct GenerateRequest {
+    text: String,
+    format: Option<String>,
+}
+
+pub struct ServerOptions {
+    pub bind: SocketAddr,
+    /// Bearer token every request must carry; without one anything that can connect may use the API
+    pub token: Option<String>,
+    /// Threads handling requests, each with its own scanner
+    pub workers: usize,
+    pub scan: ScanSettings,
+    /// Text `GET /clipboard/latest` never serves, see `[privacy.sensitive]`
+    pub sensitive: SensitiveFilter,
+}
+
+struct ServerState {
+    pool: GeneratorPool,
+    token: Option<String>,
+    latest: SharedLatest,
+    clipboard: ClipboardSink,
+}
+
+/// A running API server; stops when dropped
+pub struct ServerHandle {
+    server: Arc<tiny_http::Server>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// The address actually bound, e.g. with port 0 for any free port
+    pub fn address(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        // Each call wakes one worker waiting for a request
+        for _ in &self.workers {
+            self.server.unblock();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Serve the HTTP API on a few threads of its own, generating with `pool`, answering
+/// `GET /clipboard/latest` from `latest` and handing pushed text to `clipboard`
+pub fn start(options: ServerOptions, pool: GeneratorPool, latest: SharedLatest, clipboard: ClipboardSink) -> Result<ServerHandle> {
+    let server = tiny_http::Server::http(options.bind).map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", options.bind, e))?;
+    let server = Arc::new(server);
+    if options.token.is_none() && !options.bind.ip().is_loopback() {
+        warn!("API server listening on {} without a token; anyone on the network can use it", options.bind);
+    }
+
+    let state = Arc::new(ServerState {
+        pool,
+        token: options.token.filter(|token| !token.is_empty()),
+        latest,
+        clipboard,
+    });
+    let workers = (0..options.workers.max(1))
+        .map(|index| {
+            let server = Arc::clone(&server);
+            let state = Arc::clone(&state);
+            let scan = options.scan.clone();
+            thread::spawn(move || {
+                // Scanners are built on the thread that uses them
+                let scanner = scan.scanner();
+                while let Ok(mut request) = server.recv() {
+                    let response = handle(&mut request, &state, &scanner);
+                    debug!("{} {} -> {}", request.method(), request.url(), response.status_code().0);
+                    if let Err(e) = request.respond(response) {
+                        debug!("Failed to send response: {}", e);
+                    }
+                }
+                debug!("API worker {} stopped", index);
+            })
+        })
+        .collect();
+
+    info!("API server listening on {}", options.bind);
+    Ok(ServerHandle { server, workers })
+}
+
+/// Serve the API and keep [`LatestClipboard`] up to date from the clipboard until Ctrl+C
+pub fn run(options: ServerOptions, pool: GeneratorPool) -> Result<()> {
+    let latest: SharedLatest = Arc::new(Mutex::new(None));
+    let bind = options.bind;
+    let scan = options.scan.clone();
+    let sensitive = options.sensitive.clone();
+    // Kept for the whole run: on X11 and Wayland the clipboard is only served while its owner lives
+    let handler = Mutex::new(ClipboardHandler::builder().spawn_listener(false).build());
+    let clipboard: ClipboardSink = Box::new(move |text| handler.lock().map_err(|_| anyhow::anyhow!("Clipboard handler poisoned"))?.set_text(text));
+    let server = start(options, pool, latest.clone(), clipboard)?;
+
+    let monitor = ClipboardMonitor::new()
+        .with_scanner(move || scan.scanner())
+        .with_sensitive_filter(sensitive)
+        .start(move |event, _context| {
+            let Ok(mut latest) = latest.lock() else {
+                return;
+            };
+            match event {
+                MonitorEvent::Text(text) => {
+                    *latest = Some(LatestClipboard { text: text.clone(), source: PushSource::Clipboard, copied_at: Local::now() });
+                }
+                MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } => {
+                    *latest = Some(LatestClipboard { text: content.clone(), source: PushSource::QrScan, copied_at: Local::now() });
+                }
+                // Don't keep serving what the user has since replaced with something private
+                MonitorEvent::Cleared | MonitorEvent::Excluded | MonitorEvent::Sensitive(_) => *latest = None,
+                _ => {}
+            }
+        });
+
+    let (stop_tx, stop_rx) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = stop_tx.send(());
+    })?;
+    println!("Serving the API on http://{} (Ctrl+C to stop)", server.address().unwrap_or(bind));
+    let _ = stop_rx.recv();
+
+    info!("Stopping API server");
+    monitor.stop();
+    drop(server);
+    Ok(())
+}
+
+fn handle(request: &mut Request, state: &ServerState, scanner: &QRScanner) -> Response<Cursor<Vec<u8>>> {
+    if let Some(token) = &state.token {
+        let expected = format!("Bearer {}", token);
+        let authorized = request
+            .headers()
+            .iter()
+            .any(|header| header.field.equiv("Authorization") && constant_time_eq(header.value.as_str().as_bytes(), expected.as_bytes()));
+        if !authorized {
+            return error(401, "Missing or wrong bearer token");
+        }
+    }
+
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let method = request.method().clone();
+    match (method, path) {
+        (Method::Post, "/generate") => generate(request, query, state),
+        (Method::Post, "/scan") => scan(request, scanner),
+        (Method::Get, "/clipboard/latest") => latest(state),
+        (Method::Post, "/clipboard") => push(request, state),
+        (_, "/generate" | "/scan" | "/clipboard/latest" | "/clipboard") => error(405, "Method not allowed"),
+        _ => error(404, "Not found"),
+    }
+}
+
+/// `POST /generate`: the code for a JSON `{"text", "format"}` body, or for a plain text body
+/// with the format in `?format=`; PNG unless asked otherwise
+fn generate(request: &mut Request, query: &str, state: &ServerState) -> Response<Cursor<Vec<u8>>> {
+    let body = match read_body(request) {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    let is_json = request
+        .headers()
+        .iter()
+        .any(|header| header.field.equiv("Content-Type") && header.value.as_str().starts_with("application/json"));
+    let (text, format) = if is_json {
+        match serde_json::from_slice::<GenerateRequest>(&body) {
+            Ok(GenerateRequest { text, format }) => (text, format),
+            Err(e) => return error(400, &format!("Invalid JSON: {}", e)),
+        }
+    } else {
+        match String::from_utf8(body) {
+            Ok(text) => (text, None),
+            Err(_) => return error(400, "Body isn't UTF-8 text"),
+        }
+    };
+    let format = format
+        .or_else(|| url::form_urlencoded::parse(query.as_bytes()).find(|(key, _)| key == "format").map(|(_, value)| value.into_owned()))
+        .unwrap_or_else(|| "png".to_string());
+
+    match state.pool.generate(GenerationJob::new(text, format.as_str())) {
+        Ok(bytes) => reply(200, content_type(&format), bytes),
+        Err(e) => error(400, &e.to_string()),
+    }
+}
+
+/// `POST /scan`: every code in the image sent as the body, with where it is
+fn scan(request: &mut Request, scanner: &QRScanner) -> Response<Cursor<Vec<u8>>> {
+    let body = match read_body(request) {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    let codes = match load_image_from_memory(&body).and_then(|image| scanner.scan_multiple_qr_codes(&image)) {
+        Ok(codes) => codes,
+        Err(e) => return error(400, &e.to_string()),
+    };
+    let codes: Vec<_> = codes
+        .iter()
+        .map(|code| {
+            let bounds = code.bounds;
+            json!({
+                "content": code.content,
+                "symbology": code.symbology.name(),
+                "bounds": { "x": bounds.x, "y": bounds.y, "width": bounds.width, "height": bounds.height },
+            })
+        })
+        .collect();
+    reply(200, "application/json", json!({ "codes": codes }).to_string().into_bytes())
+}
+
+/// `GET /clipboard/latest`: the newest copied text or decoded code
+fn latest(state: &ServerState) -> Response<Cursor<Vec<u8>>> {
+    let latest = state.latest.lock().ok().and_then(|latest| latest.clone());
+    match latest {
+        Some(latest) => {
+            let body = json!({
+                "text": latest.text,
+                "source": latest.source,
+                "copied_at": latest.copied_at.to_rfc3339(),
+            });
+            reply(200, "application/json", body.to_string().into_bytes())
+        }
+        None => error(404, "Nothing copied since the server started"),
+    }
+}
+
+/// `POST /clipboard`: put a [`PushPayload`] from another instance's `push` or `agent` on the
+/// local clipboard; only with a token, since anyone who can connect could fill the clipboard
+fn push(request: &mut Request, state: &ServerState) -> Response<Cursor<Vec<u8>>> {
+    if state.token.is_none() {
+        return error(403, "Pushing to the clipboard needs a server started with --token");
+    }
+    let body = match read_body(request) {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+    let payload = match serde_json::from_slice::<PushPayload>(&body) {
+        Ok(payload) => payload,
+        Err(e) => return error(400, &format!("Invalid JSON: {}", e)),
+    };
+    if let Err(e) = (state.clipboard)(&payload.text) {
+        warn!("Failed to set pushed content on the clipboard: {}", e);
+        return error(500, &e.to_string());
+    }
+    info!(
+        "Received {} chars from {}",
+        payload.text.chars().count(),
+        payload.origin.as_deref().unwrap_or("another instance")
+    );
+    if let Ok(mut latest) = state.latest.lock() {
+        *latest = Some(LatestClipboard { text: payload.text, source: payload.source, copied_at: Local::now() });
+    }
+    reply(200, "application/json", json!({ "ok": true }).to_string().into_bytes())
+}
+
+/// Compares every byte whatever the first difference, so response times don't give the
+/// token away
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |difference, (x, y)| difference | (x ^ y)) == 0
+}
+
+fn read_body(request: &mut Request) -> Result<Vec<u8>, Response<Cursor<Vec<u8>>>> {
+    if request.body_length().is_some_and(|length| length as u64 > MAX_BODY_BYTES) {
+        return Err(error(413, "Request body too large"));
+    }
+    let mut body = Vec::new();
+    match request.as_reader().take(MAX_BODY_BYTES + 1).read_to_end(&mut body) {
+        Ok(_) if body.len() as u64 > MAX_BODY_BYTES => Err(error(413, "Request body too large")),
+        Ok(_) => Ok(body),
+        Err(e) => Err(error(400, &format!("Failed to read request body: {}", e))),
+    }
+}
+
+fn content_type(format: &str) -> &'static str {
+    match format {
+        "png" => "image/png",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn reply(status: u16, content_type: &str, body: Vec<u8>) -> Response<Cursor<Vec<u8>>> {
+    let response = Response::from_data(body).with_status_code(status);
+    match Header::from_bytes("Content-Type", content_type) {
+        Ok(header) => response.with_header(header),
+        Err(()) => response,
+    }
+}
+
+fn error(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    reply(status, "application/json", json!({ "error": message }).to_string().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::qr_generator::QRGenerator;
+    use crate::qr_style::QrStyle;
+    use crate::remote::PushClient;
+
+    #[test]
+    fn test_api_endpoints() {
+        let latest: SharedLatest = Arc::new(Mutex::new(None));
+        let options = ServerOptions {
+            bind: "127.0.0.1:0".parse().unwrap(),
+            token: Some("secret".to_string()),
+            workers: 2,
+            scan: ScanSettings::default(),
+            sensitive: SensitiveFilter::default(),
+        };
+        let pushed = Arc::new(Mutex::new(Vec::new()));
+        let recorder = pushed.clone();
+        let clipboard: ClipboardSink = Box::new(move |text| {
+            recorder.lock().unwrap().push(text.to_string());
+            Ok(())
+        });
+        let server = start(options, GeneratorPool::new(1, QrStyle::default()), latest.clone(), clipboard).unwrap();
+        let base = format!("http://{}", server.address().unwrap());
+        let request = |method: &str, path: &str| ureq::request(method, &format!("{}{}", base, path)).set("Authorization", "Bearer secret");
+
+        let svg = send(request("POST", "/generate?format=svg"), b"https://example.com");
+        assert_eq!((svg.0, svg.1.as_str()), (200, "image/svg+xml"));
+        let expected = QRGenerator::new().generate_svg("https://example.com").unwrap();
+        assert_eq!(svg.2, expected.as_bytes());
+        let generate = |body: serde_json::Value| send(request("POST", "/generate").set("Content-Type", "application/json"), body.to_string().as_bytes());
+        let png = generate(json!({ "text": "hello" }));
+        assert_eq!((png.0, png.1.as_str()), (200, "image/png"));
+        assert_eq!(generate(json!({ "text": "hello", "format": "bmp" })).0, 400);
+
+        let (status, _, body) = send(request("POST", "/scan"), &png.2);
+        assert_eq!(status, 200);
+        assert!(serde_json::from_slice::<serde_json::Value>(&body).unwrap()["codes"].is_array());
+        assert_eq!(send(request("POST", "/scan"), b"not an image").0, 400);
+
+        assert_eq!(send(request("GET", "/clipboard/latest"), b"").0, 404);
+        *latest.lock().unwrap() = Some(LatestClipboard { text: "copied".to_string(), source: PushSource::Clipboard, copied_at: Local::now() });
+        let body: serde_json::Value = serde_json::from_slice(&send(request("GET", "/clipboard/latest"), b"").2).unwrap();
+        assert_eq!((body["text"].as_str(), body["source"].as_str()), (Some("copied"), Some("clipboard")));
+
+        // What `push` and `agent` send lands on the clipboard and becomes the latest content
+        let base_url = url::Url::parse(&base).unwrap();
+        let payload = PushPayload { text: "from the laptop".to_string(), source: PushSource::QrScan, origin: Some("laptop".to_string()) };
+        PushClient::new(&base_url, Some("secret".to_string())).unwrap().push(&payload).unwrap();
+        assert_eq!(*pushed.lock().unwrap(), ["from the laptop"]);
+        let body: serde_json::Value = serde_json::from_slice(&send(request("GET", "/clipboard/latest"), b"").2).unwrap();
+        assert_eq!((body["text"].as_str(), body["source"].as_str()), (Some("from the laptop"), Some("qr_scan")));
+        assert!(PushClient::new(&base_url, Some("wrong".to_string())).unwrap().push(&payload).is_err());
+        assert_eq!(pushed.lock().unwrap().len(), 1);
+
+        assert_eq!(send(ureq::get(&format!("{}/clipboard/latest", base)), b"").0, 401);
+        assert_eq!(send(request("POST", "/nowhere"), b"").0, 404);
+        assert_eq!(send(request("GET", "/scan"), b"").0, 405);
+    }
+
+    /// Status, content type and body, for error statuses too
+    fn send(request: ureq::Request, body: &[u8]) -> (u16, String, Vec<u8>) {
+        let response = match request.send_bytes(body) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => panic!("{}", e),
+        };
+        let (status, content_type) = (response.status(), response.content_type().to_string());
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body).unwrap();
+        (status, content_type, body)
+    }
+}
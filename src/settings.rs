@@ -0,0 +1,333 @@
+use anyhow::Result;
+use chrono::Weekday;
+use eframe::egui;
+use std::path::PathBuf;
+use tracing::{error, info};
+
+use crate::autostart;
+use crate::config::{AppConfig, ErrorCorrection};
+use crate::notification::{CopyDecoded, NotificationPolicy};
+use crate::qr_generator::QrSymbology;
+use crate::qr_style::{Color, ModuleShape, QrStyle, StylePreset};
+use crate::snooze::{CalendarState, SnoozeWindow, SnoozeWindowConfig};
+
+const WEEKDAYS: [Weekday; 7] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+
+/// Edit the common settings of `config.toml` in a window
+///
+/// Only the [`EDITABLE_SETTINGS`](crate::config::EDITABLE_SETTINGS) are written back; the
+/// rest of the file stays as it is. Running instances pick the changes up when restarted.
+pub fn run(config: AppConfig) -> Result<()> {
+    info!("Opening settings");
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_title("Clipboard QR Settings").with_inner_size([460.0, 520.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Clipboard QR Settings",
+        options,
+        Box::new(move |_cc| Ok(Box::new(SettingsApp::new(config)))),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to open settings window: {}", e))
+}
+
+/// Open the settings window in its own process, for windows that can't host a second one
+/// in their event loop, like the tray
+pub fn open_window() -> Result<()> {
+    let exe = std::env::current_exe()?;
+    std::process::Command::new(exe)
+        .arg("settings")
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to open settings: {}", e))?;
+    Ok(())
+}
+
+struct SettingsApp {
+    config: AppConfig,
+    /// Custom colours are on; the pickers edit these until then
+    custom_colors: bool,
+    foreground: [u8; 3],
+    background: [u8; 3],
+    output_dir: String,
+    /// The `[snooze]` calendar file, empty for none
+    calendar: String,
+    /// Result of the last save
+    status: Option<String>,
+}
+
+impl SettingsApp {
+    fn new(config: AppConfig) -> Self {
+        let style = config.style.resolve();
+        Self {
+            custom_colors: config.style.foreground.is_some() || config.style.background.is_some(),
+            foreground: [style.foreground.r, style.foreground.g, style.foreground.b],
+            background: [style.background.r, style.background.g, style.background.b],
+            output_dir: config.output.dir.display().to_string(),
+            calendar: config.snooze.calendar.as_ref().map(|path| path.display().to_string()).unwrap_or_default(),
+            config,
+            status: None,
+        }
+    }
+
+    /// The custom colours, or why they can't be scanned
+    fn custom_style(&self) -> Result<QrStyle> {
+        let [r, g, b] = self.foreground;
+        let foreground = Color::rgb(r, g, b);
+        let [r, g, b] = self.background;
+        QrStyle::custom(foreground, Color::rgb(r, g, b))
+    }
+
+    fn save(&mut self) {
+        if self.custom_colors {
+            if let Err(e) = self.custom_style() {
+                self.status = Some(format!("❌ {}", e));
+                return;
+            }
+            let ([fr, fg, fb], [br, bg, bb]) = (self.foreground, self.background);
+            self.config.style.foreground = Some(Color::rgb(fr, fg, fb));
+            self.config.style.background = Some(Color::rgb(br, bg, bb));
+        } else {
+            self.config.style.foreground = None;
+            self.config.style.background = None;
+        }
+        if let Some(e) = self.config.snooze.windows.iter().find_map(|window| SnoozeWindow::new(window).err()) {
+            self.status = Some(format!("❌ Snooze: {}", e));
+            return;
+        }
+        self.config.output.dir = PathBuf::from(self.output_dir.trim());
+        self.config.snooze.calendar = Some(self.calendar.trim()).filter(|path| !path.is_empty()).map(PathBuf::from);
+
+        // Takes effect at the next login, so it is applied right away
+        let saved = self.config.save().and_then(|path| autostart::set(self.config.startup.autostart).map(|()| path));
+        self.status = Some(match saved {
+            Ok(path) => format!("✅ Saved to {}; restart clipboard-qr to apply", path.display()),
+            Err(e) => {
+                error!("Failed to save settings: {}", e);
+                format!("❌ {}", e)
+            }
+        });
+    }
+
+    fn monitoring(&mut self, ui: &mut egui::Ui) {
+        let monitor = &mut self.config.monitor;
+        ui.checkbox(&mut monitor.text, "Turn copied text into QR codes");
+        ui.checkbox(&mut monitor.image, "Scan copied images for QR codes");
+        ui.horizontal(|ui| {
+            ui.label("Poll interval");
+            ui.add(egui::DragValue::new(&mut monitor.poll_interval_ms).range(10..=5000).suffix(" ms"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Clipboard entries kept");
+            ui.add(egui::DragValue::new(&mut monitor.clipboard_history).range(0..=200));
+        });
+        egui::ComboBox::from_label("Decoded QR content")
+            .selected_text(monitor.copy_decoded.label())
+            .show_ui(ui, |ui| {
+                for mode in CopyDecoded::ALL {
+                    ui.selectable_value(&mut monitor.copy_decoded, mode, mode.label());
+                }
+            });
+    }
+
+    fn qr_codes(&mut self, ui: &mut egui::Ui) {
+        let style = &mut self.config.style;
+        egui::ComboBox::from_label("Colours")
+            .selected_text(style.preset.clone())
+            .show_ui(ui, |ui| {
+                for preset in StylePreset::ALL {
+                    ui.selectable_value(&mut style.preset, preset.name().to_string(), preset.name());
+                }
+            });
+        egui::ComboBox::from_label("Error correction")
+            .selected_text(format!("{:?}", style.error_correction))
+            .show_ui(ui, |ui| {
+                for level in ErrorCorrection::ALL {
+                    ui.selectable_value(&mut style.error_correction, level, format!("{:?}", level));
+                }
+            });
+        egui::ComboBox::from_label("Symbol")
+            .selected_text(style.symbology.label())
+            .show_ui(ui, |ui| {
+                for symbology in QrSymbology::ALL {
+                    ui.selectable_value(&mut style.symbology, symbology, symbology.label());
+                }
+            });
+        egui::ComboBox::from_label("Module shape")
+            .selected_text(style.shape.name())
+            .show_ui(ui, |ui| {
+                for shape in ModuleShape::ALL {
+                    ui.selectable_value(&mut style.shape, shape, shape.name());
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label("Quiet zone");
+            ui.add(egui::DragValue::new(&mut style.quiet_zone).range(0..=16).suffix(" modules"));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Frame");
+            ui.add(egui::DragValue::new(&mut style.frame).range(0..=8).suffix(" modules"));
+            ui.label("Caption");
+            let mut caption = style.caption.clone().unwrap_or_default();
+            if ui.add(egui::TextEdit::singleline(&mut caption).desired_width(160.0).hint_text("Scan me")).changed() {
+                style.caption = Some(caption).filter(|caption| !caption.is_empty());
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut fixed_size = style.target_size.is_some();
+            ui.checkbox(&mut fixed_size, "Image size");
+            match (fixed_size, &mut style.target_size) {
+                (true, Some(size)) => {
+                    ui.add(egui::DragValue::new(size).range(21..=4096).suffix(" px"));
+                }
+                (true, None) => style.target_size = Some(512),
+                (false, _) => {
+                    style.target_size = None;
+                    ui.label("Module size");
+                    ui.add(egui::DragValue::new(&mut style.pixels_per_module).range(1..=64).suffix(" px"));
+                }
+            }
+        });
+
+        ui.checkbox(&mut self.custom_colors, "Custom colours instead of the preset's");
+        if self.custom_colors {
+            ui.horizontal(|ui| {
+                ui.label("Foreground");
+                ui.color_edit_button_srgb(&mut self.foreground);
+                ui.label("Background");
+                ui.color_edit_button_srgb(&mut self.background);
+            });
+            if let Err(e) = self.custom_style() {
+                ui.colored_label(ui.visuals().warn_fg_color, e.to_string());
+            }
+        }
+    }
+
+    fn output(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Save QR images in");
+            ui.text_edit_singleline(&mut self.output_dir);
+            if ui.button("Browse…").clicked() {
+                if let Some(dir) = rfd::FileDialog::new().set_directory(&self.output_dir).pick_folder() {
+                    self.output_dir = dir.display().to_string();
+                }
+            }
+        });
+    }
+
+    fn snooze(&mut self, ui: &mut egui::Ui) {
+        let snooze = &mut self.config.snooze;
+        ui.label("Pause monitoring between these times, on the days selected or every day");
+        let mut removed = None;
+        for (index, window) in snooze.windows.iter_mut().enumerate() {
+            ui.push_id(index, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut window.from).desired_width(48.0).hint_text("18:00"));
+                    ui.label("to");
+                    ui.add(egui::TextEdit::singleline(&mut window.to).desired_width(48.0).hint_text("09:00"));
+                    for day in WEEKDAYS {
+                        let selected = window.days.iter().any(|name| name.parse::<Weekday>().ok() == Some(day));
+                        let name = day.to_string();
+                        if ui.selectable_label(selected, &name[..2]).clicked() {
+                            if selected {
+                                window.days.retain(|name| name.parse::<Weekday>().ok() != Some(day));
+                            } else {
+                                window.days.push(name.to_lowercase());
+                            }
+                        }
+                    }
+                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                });
+                if let Err(e) = SnoozeWindow::new(window) {
+                    ui.colored_label(ui.visuals().warn_fg_color, e.to_string());
+                }
+            });
+        }
+        if let Some(index) = removed {
+            snooze.windows.remove(index);
+        }
+        if ui.button("Add time range").clicked() {
+            snooze.windows.push(SnoozeWindowConfig {
+                from: "18:00".to_string(),
+                to: "09:00".to_string(),
+                days: Vec::new(),
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Calendar");
+            ui.add(egui::TextEdit::singleline(&mut self.calendar).hint_text("iCalendar file"));
+            if ui.button("Browse…").clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("iCalendar", &["ics"]).pick_file() {
+                    self.calendar = path.display().to_string();
+                }
+            }
+        });
+        egui::ComboBox::from_label("Pause monitoring")
+            .selected_text(match snooze.pause_when {
+                CalendarState::Busy => "During events",
+                CalendarState::Free => "Outside events",
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut snooze.pause_when, CalendarState::Busy, "During events");
+                ui.selectable_value(&mut snooze.pause_when, CalendarState::Free, "Outside events");
+            });
+    }
+
+    fn notifications(&mut self, ui: &mut egui::Ui) {
+        let level = &mut self.config.notifications.level;
+        egui::ComboBox::from_label("Notify about")
+            .selected_text(level.name())
+            .show_ui(ui, |ui| {
+                for policy in NotificationPolicy::ALL {
+                    ui.selectable_value(level, policy, policy.name());
+                }
+            });
+        ui.checkbox(&mut self.config.notifications.desktop, "Show desktop notifications");
+        ui.checkbox(&mut self.config.privacy.redact_content, "Hide clipboard content in logs, tooltips and notifications");
+    }
+}
+
+impl eframe::App for SettingsApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::bottom("settings-actions").show(ctx, |ui| {
+            ui.add_space(4.0);
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    self.save();
+                }
+                if ui.button("Close").clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+            ui.add_space(4.0);
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.heading("Monitoring");
+                self.monitoring(ui);
+                ui.separator();
+                ui.heading("QR codes");
+                self.qr_codes(ui);
+                ui.separator();
+                ui.heading("Output");
+                self.output(ui);
+                ui.separator();
+                ui.heading("Notifications");
+                self.notifications(ui);
+                ui.separator();
+                ui.heading("Snooze");
+                self.snooze(ui);
+                ui.separator();
+                ui.heading("Startup");
+                ui.checkbox(&mut self.config.startup.autostart, "Start Clipboard QR at login");
+                ui.separator();
+                ui.label("Other settings are kept as they are in the config file.");
+            });
+        });
+    }
+}
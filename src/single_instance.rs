@@ -0,0 +1,143 @@
+#[cfg(unix)]
+pub use unix::{acquire, on_activate};
+
+#[cfg(unix)]
+mod unix {
+    use anyhow::Result;
+    use std::path::Path;
+    use tracing::{info, warn};
+
+    use crate::control::{self, ControlRequest, ControlServer};
+
+    /// Held by the only tray instance; on Unix its bound control socket is the lock
+    pub struct InstanceGuard {
+        control: Option<ControlServer>,
+    }
+
+    impl InstanceGuard {
+        /// The control socket to serve, see [`control::serve_monitor`]; the lock goes with it
+        pub fn take_control(&mut self) -> Option<ControlServer> {
+            self.control.take()
+        }
+    }
+
+    /// Become the only tray instance of the current user by binding the control socket at
+    /// `socket`, or ask the instance already running to show its status window
+    ///
+    /// Returns `None` when another instance is running; this launch should then exit. Fails
+    /// when the socket can't be bound and nothing answers on it.
+    pub fn acquire(socket: &Path) -> Result<Option<InstanceGuard>> {
+        let bind_error = match ControlServer::bind(socket.to_path_buf()) {
+            Ok(control) => return Ok(Some(InstanceGuard { control: Some(control) })),
+            Err(e) => e,
+        };
+        match control::send(socket, &ControlRequest::Activate) {
+            Ok(_) => {
+                info!("Another instance is running; asked it to show its status window");
+                Ok(None)
+            }
+            // Answers, but can't show a window, e.g. `watch` or an older version
+            Err(e) if control::send(socket, &ControlRequest::Ping).is_ok() => {
+                warn!("Another instance is running but can't show its status window: {:#}", e);
+                Ok(None)
+            }
+            Err(_) => Err(anyhow::anyhow!("Failed to take the control socket {}: {:#}", socket.display(), bind_error)),
+        }
+    }
+
+    /// Call `activate` whenever a later launch asks this instance to show itself; on Unix
+    /// that request arrives on the control socket instead, as [`ControlRequest::Activate`]
+    pub fn on_activate(_activate: impl Fn() + Send + 'static) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+pub use windows::{acquire, on_activate};
+
+#[cfg(windows)]
+mod windows {
+    use anyhow::Result;
+    use std::io;
+    use std::path::Path;
+    use std::ptr::null_mut;
+    use std::thread;
+    use tracing::info;
+    use winapi::shared::minwindef::FALSE;
+    use winapi::shared::winerror::ERROR_ALREADY_EXISTS;
+    use winapi::um::errhandlingapi::GetLastError;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::synchapi::{CreateEventW, CreateMutexW, SetEvent, WaitForSingleObject};
+    use winapi::um::winbase::{INFINITE, WAIT_OBJECT_0};
+    use winapi::um::winnt::HANDLE;
+
+    use crate::control::ControlServer;
+
+    /// Exists while an instance runs in this login session
+    const MUTEX_NAME: &str = r"Local\ClipboardQR-instance";
+    /// Signalled by later launches to ask the instance to show itself
+    const ACTIVATE_NAME: &str = r"Local\ClipboardQR-activate";
+
+    fn wide(name: &str) -> Vec<u16> {
+        name.encode_utf16().chain(Some(0)).collect()
+    }
+
+    /// The activation event, created by whichever launch comes first
+    fn activate_event() -> Result<HANDLE> {
+        let event = unsafe { CreateEventW(null_mut(), FALSE, FALSE, wide(ACTIVATE_NAME).as_ptr()) };
+        if event.is_null() {
+            return Err(anyhow::anyhow!("Failed to create the activation event: {}", io::Error::last_os_error()));
+        }
+        Ok(event)
+    }
+
+    /// Held by the only tray instance; closing the mutex lets the next launch take over
+    pub struct InstanceGuard {
+        mutex: HANDLE,
+    }
+
+    impl InstanceGuard {
+        /// There is no control socket on Windows yet; the mutex is the lock
+        pub fn take_control(&mut self) -> Option<ControlServer> {
+            None
+        }
+    }
+
+    impl Drop for InstanceGuard {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.mutex) };
+        }
+    }
+
+    /// Become the only tray instance in this login session, or signal the one already
+    /// running to show its status window; `None` means this launch should exit
+    pub fn acquire(_socket: &Path) -> Result<Option<InstanceGuard>> {
+        let mutex = unsafe { CreateMutexW(null_mut(), FALSE, wide(MUTEX_NAME).as_ptr()) };
+        if mutex.is_null() {
+            return Err(anyhow::anyhow!("Failed to create the instance lock: {}", io::Error::last_os_error()));
+        }
+        if unsafe { GetLastError() } != ERROR_ALREADY_EXISTS {
+            return Ok(Some(InstanceGuard { mutex }));
+        }
+        unsafe { CloseHandle(mutex) };
+        let event = activate_event()?;
+        unsafe {
+            SetEvent(event);
+            CloseHandle(event);
+        }
+        info!("Another instance is running; asked it to show its status window");
+        Ok(None)
+    }
+
+    /// Call `activate` on a background thread whenever a later launch signals this instance
+    pub fn on_activate(activate: impl Fn() + Send + 'static) -> Result<()> {
+        // Handles can't cross threads as pointers; the event lives until the process exits
+        let event = activate_event()? as usize;
+        thread::spawn(move || {
+            while unsafe { WaitForSingleObject(event as HANDLE, INFINITE) } == WAIT_OBJECT_0 {
+                activate();
+            }
+        });
+        Ok(())
+    }
+}
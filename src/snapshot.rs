@@ -0,0 +1,229 @@
+use anyhow::Result;
+use regex::Regex;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::config::AppConfig;
+use crate::control::InstanceStatus;
+use crate::locale;
+use crate::self_check::{Capabilities, DisplayServer};
+use crate::storage;
+
+/// Config keys whose text values may be secrets or clipboard content
+const REDACTED_KEYS: [&str; 9] = ["token", "password", "secret", "url", "host", "command", "input", "text", "defaults"];
+
+const REDACTED: &str = "[redacted]";
+
+/// The files of a bug report archive, built up before anything is written
+///
+/// Nothing copied to the clipboard goes in unless asked for: config values that may be
+/// secrets are replaced, and so are URLs in the log, which is where opened links end up.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    files: Vec<(String, String)>,
+    include_content: bool,
+}
+
+impl Snapshot {
+    pub fn new(include_content: bool) -> Self {
+        let mut snapshot = Self { files: Vec::new(), include_content };
+        snapshot.add("README.txt", snapshot.readme());
+        snapshot
+    }
+
+    fn readme(&self) -> String {
+        let mut readme = String::from(
+            "clipboard-qr debug snapshot\n\n\
+             system.txt      version, platform, self-check and which backends are configured\n\
+             config.toml     the config file, with tokens, URLs, commands and texts redacted\n\
+             instance.json   statistics of the running instance, if one answered\n\
+             log.txt         its recent log lines, URLs replaced\n\
+             last-exit.json  why the last daemon run stopped, if it failed\n",
+        );
+        if self.include_content {
+            readme.push_str("clipboard.txt   the clipboard text, included on request\n");
+        } else {
+            readme.push_str("\nNo clipboard content is included.\n");
+        }
+        readme
+    }
+
+    pub fn add(&mut self, name: &str, contents: impl Into<String>) {
+        self.files.push((name.to_string(), contents.into()));
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Version, platform, the startup self-check and the configured backends, never their settings
+    pub fn add_system(&mut self, config: &AppConfig) {
+        let capabilities = Capabilities::detect();
+        let listener = match capabilities.display {
+            Some(DisplayServer::Windows) => "clipboard format listener",
+            Some(DisplayServer::MacOs) => "pasteboard change count",
+            Some(DisplayServer::X11) => "X11 selection events, with polling as fallback",
+            _ => "polling",
+        };
+        let notifiers: Vec<String> = config
+            .notifications
+            .backends(capabilities.display.is_some())
+            .iter()
+            .filter_map(|backend| serde_json::to_value(&backend.kind).ok())
+            .filter_map(|kind| kind["type"].as_str().map(str::to_string))
+            .collect();
+        let payload_types: Vec<&str> = config.payload_types.iter().map(|payload| payload.name.as_str()).collect();
+        let system = format!(
+            "clipboard-qr {}\n\
+             os: {} {}\n\
+             self-check: {}\n\
+             clipboard listener: {}\n\
+             locale: {}\n\
+             guest mode: {}\n\
+             in-memory mode: {}\n\
+             content redaction: {}\n\
+             history backend: {:?}\n\
+             notifiers: {}\n\
+             custom payload types: {}\n",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            capabilities,
+            listener,
+            locale::current().name(),
+            config.guest.enabled,
+            storage::in_memory_mode(),
+            config.privacy.redact_content,
+            config.history.backend,
+            notifiers.join(", "),
+            payload_types.join(", "),
+        );
+        self.add("system.txt", system);
+    }
+
+    /// The config file at `path` with [`REDACTED_KEYS`] replaced
+    pub fn add_config(&mut self, path: Option<&Path>) {
+        let config = match path.map(fs::read_to_string) {
+            Some(Ok(text)) => redact_config(&text).unwrap_or_else(|e| format!("# The config file doesn't parse, so it's left out: {}\n", e)),
+            Some(Err(e)) => format!("# No config file read ({}), defaults apply\n", e),
+            None => "# No config directory, defaults apply\n".to_string(),
+        };
+        self.add("config.toml", config);
+    }
+
+    /// What the running instance answered, or why none did; without an instance, this
+    /// process's own log is better than nothing
+    pub fn add_instance(&mut self, status: Result<InstanceStatus>, own_log: Vec<String>) {
+        let log = match status {
+            Ok(status) => {
+                let json = serde_json::json!({ "version": status.version, "stats": status.stats, "summary": status.stats.to_string() });
+                self.add("instance.json", serde_json::to_string_pretty(&json).unwrap_or_default());
+                status.log
+            }
+            Err(e) => {
+                self.add("instance.json", serde_json::json!({ "error": format!("No running instance answered: {:#}", e) }).to_string());
+                own_log
+            }
+        };
+        let log: Vec<String> = log.iter().map(|line| self.scrub(line)).collect();
+        self.add("log.txt", log.join("\n"));
+    }
+
+    /// The last exit report, if there is one
+    pub fn add_exit_report(&mut self, path: Option<&Path>) {
+        if let Some(Ok(report)) = path.map(fs::read_to_string) {
+            let report = self.scrub(&report);
+            self.add("last-exit.json", report);
+        }
+    }
+
+    /// `text` without URLs unless content was asked for
+    fn scrub(&self, text: &str) -> String {
+        if self.include_content {
+            return text.to_string();
+        }
+        static URL: OnceLock<Regex> = OnceLock::new();
+        let url = URL.get_or_init(|| Regex::new(r"[a-zA-Z][a-zA-Z0-9+.-]*://[^\s\x22']+").unwrap());
+        url.replace_all(text, "[url]").into_owned()
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut zip = ZipWriter::new(File::create(path).map_err(|e| anyhow::anyhow!("Failed to create {}: {}", path.display(), e))?);
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, contents) in &self.files {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(contents.as_bytes())?;
+        }
+        zip.finish()?;
+        info!("Wrote debug snapshot with {} file(s) to {:?}", self.files.len(), path);
+        Ok(())
+    }
+}
+
+/// `text` parsed as TOML with every string under [`REDACTED_KEYS`] replaced, at any depth
+pub fn redact_config(text: &str) -> Result<String> {
+    let mut config: toml::Value = toml::from_str(text)?;
+    redact_value(&mut config);
+    Ok(toml::to_string_pretty(&config)?)
+}
+
+fn redact_value(value: &mut toml::Value) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                if REDACTED_KEYS.contains(&key.as_str()) && !matches!(value, toml::Value::Boolean(_) | toml::Value::Integer(_) | toml::Value::Float(_)) {
+                    *value = toml::Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(value);
+                }
+            }
+        }
+        toml::Value::Array(values) => values.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_snapshot() {
+        let config = redact_config(
+            r#"
+            [monitor]
+            text = true
+
+            [remote]
+            token = "hunter2"
+
+            [[snippets]]
+            name = "wifi"
+            text = "WIFI:S:home;P:secret;;"
+            "#,
+        )
+        .unwrap();
+        assert!(config.contains("text = true"));
+        assert!(config.contains("name = \"wifi\""));
+        assert!(!config.contains("hunter2"));
+        assert!(!config.contains("WIFI:"));
+
+        let mut snapshot = Snapshot::new(false);
+        snapshot.add_instance(Err(anyhow::anyhow!("not running")), vec!["INFO Opened https://example.com/reset?code=1".to_string()]);
+        let path = std::env::temp_dir().join(format!("clipboard-qr-snapshot-{}.zip", std::process::id()));
+        snapshot.write(&path).unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut log = String::new();
+        archive.by_name("log.txt").unwrap().read_to_string(&mut log).unwrap();
+        assert_eq!(log, "INFO Opened [url]");
+        assert!(archive.by_name("README.txt").is_ok());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::Result;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Placeholders filled in automatically when a snippet is used
+pub const BUILTIN_PLACEHOLDERS: [&str; 2] = ["date", "time"];
+
+/// Frequently used QR content, e.g. a Wi-Fi login, with `{name}` placeholders
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    /// Text to encode; `{{` and `}}` stand for literal braces
+    pub text: String,
+    /// Values suggested when asking for a placeholder
+    #[serde(default)]
+    pub defaults: BTreeMap<String, String>,
+    /// Delete files exported from this snippet after this long, e.g. "15m", for
+    /// snippets holding passwords
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expire: Option<String>,
+}
+
+impl Snippet {
+    pub fn find<'a>(snippets: &'a [Snippet], name: &str) -> Option<&'a Snippet> {
+        snippets.iter().find(|snippet| snippet.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Placeholders the user has to provide, in order of first use
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for token in tokenize(&self.text) {
+            if let Token::Placeholder(name) = token {
+                if !BUILTIN_PLACEHOLDERS.contains(&name) && !names.iter().any(|known| known == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
+
+    /// Fill every placeholder, asking `ask(name, default)` for the ones that aren't built in;
+    /// returns `None` if `ask` does
+    pub fn fill(&self, mut ask: impl FnMut(&str, Option<&str>) -> Option<String>) -> Result<Option<String>> {
+        let mut values = BTreeMap::new();
+        let now = Local::now();
+        values.insert("date".to_string(), now.format("%Y-%m-%d").to_string());
+        values.insert("time".to_string(), now.format("%H:%M").to_string());
+        for name in self.placeholders() {
+            let Some(value) = ask(&name, self.defaults.get(&name).map(String::as_str)) else {
+                return Ok(None);
+            };
+            values.insert(name, value);
+        }
+        self.render(&values).map(Some)
+    }
+
+    /// Substitute `values` into the text
+    pub fn render(&self, values: &BTreeMap<String, String>) -> Result<String> {
+        let mut text = String::with_capacity(self.text.len());
+        for token in tokenize(&self.text) {
+            match token {
+                Token::Literal(literal) => text.push_str(literal),
+                Token::Placeholder(name) => {
+                    let value = values
+                        .get(name)
+                        .ok_or_else(|| anyhow::anyhow!("No value for placeholder '{{{}}}' in snippet '{}'", name, self.name))?;
+                    text.push_str(value);
+                }
+            }
+        }
+        Ok(text)
+    }
+}
+
+enum Token<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Split snippet text into literals and `{name}` placeholders; unmatched braces are literal
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find(['{', '}']) {
+        let (literal, tail) = rest.split_at(open);
+        tokens.push(Token::Literal(literal));
+        if let Some(tail) = tail.strip_prefix("{{") {
+            tokens.push(Token::Literal("{"));
+            rest = tail;
+        } else if let Some(tail) = tail.strip_prefix("}}") {
+            tokens.push(Token::Literal("}"));
+            rest = tail;
+        } else if let Some(end) = tail.find('}').filter(|&end| is_placeholder_name(&tail[1..end])) {
+            tokens.push(Token::Placeholder(&tail[1..end]));
+            rest = &tail[end + 1..];
+        } else {
+            tokens.push(Token::Literal(&tail[..1]));
+            rest = &tail[1..];
+        }
+    }
+    tokens.push(Token::Literal(rest));
+    tokens
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wifi() -> Snippet {
+        Snippet {
+            name: "wifi".to_string(),
+            text: "WIFI:T:WPA;S:{ssid};P:{password};; {{not a placeholder}} {ssid}".to_string(),
+            defaults: BTreeMap::from([("ssid".to_string(), "Office".to_string())]),
+            expire: None,
+        }
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(wifi().placeholders(), ["ssid", "password"]);
+
+        let dated = Snippet {
+            name: "dated".to_string(),
+            text: "Visitor {name} on {date} {".to_string(),
+            defaults: BTreeMap::new(),
+            expire: None,
+        };
+        assert_eq!(dated.placeholders(), ["name"]);
+    }
+
+    #[test]
+    fn test_fill() {
+        let filled = wifi()
+            .fill(|name, default| Some(default.unwrap_or(name).to_uppercase()))
+            .unwrap();
+        assert_eq!(filled.as_deref(), Some("WIFI:T:WPA;S:OFFICE;P:PASSWORD;; {not a placeholder} OFFICE"));
+
+        // Cancelling a prompt cancels the snippet
+        assert_eq!(wifi().fill(|_, _| None).unwrap(), None);
+    }
+}
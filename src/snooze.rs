@@ -0,0 +1,596 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+/// When clipboard monitoring pauses by itself, e.g. outside work hours
+///
+/// ```toml
+/// [snooze]
+/// windows = [
+///     { from = "18:00", to = "09:00" },
+///     { from = "00:00", to = "00:00", days = ["sat", "sun"] },
+/// ]
+/// calendar = "/home/me/.calendars/work.ics"
+/// pause_when = "free"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnoozeConfig {
+    /// Local time ranges with monitoring off
+    pub windows: Vec<SnoozeWindowConfig>,
+    /// iCalendar file whose events mark the user busy; re-read when it changes
+    pub calendar: Option<PathBuf>,
+    /// Which calendar state pauses monitoring
+    pub pause_when: CalendarState,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnoozeWindowConfig {
+    /// Start time, "HH:MM"
+    pub from: String,
+    /// End time, "HH:MM"; earlier than `from` wraps past midnight, equal to it covers the whole day
+    pub to: String,
+    /// Days the window starts on, e.g. ["sat", "sun"]; every day if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CalendarState {
+    /// Pause during calendar events, e.g. to keep meetings out of the history
+    #[default]
+    Busy,
+    /// Pause outside calendar events, e.g. to only run during scheduled shifts
+    Free,
+}
+
+/// A parsed time range
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnoozeWindow {
+    from: NaiveTime,
+    to: NaiveTime,
+    days: Vec<Weekday>,
+}
+
+impl SnoozeWindow {
+    pub fn new(config: &SnoozeWindowConfig) -> Result<Self> {
+        let time = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| anyhow::anyhow!("Invalid time '{}', expected HH:MM", value))
+        };
+        let days = config
+            .days
+            .iter()
+            .map(|day| day.parse::<Weekday>().map_err(|_| anyhow::anyhow!("Invalid day '{}', expected e.g. mon or sat", day)))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            from: time(&config.from)?,
+            to: time(&config.to)?,
+            days,
+        })
+    }
+
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Whether `at` falls inside the window; a window wrapping midnight belongs to the day it starts
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let (day, time) = (at.weekday(), at.time());
+        if self.from < self.to {
+            self.starts_on(day) && self.from <= time && time < self.to
+        } else if self.from == self.to {
+            self.starts_on(day)
+        } else {
+            (self.starts_on(day) && time >= self.from) || (self.starts_on(day.pred()) && time < self.to)
+        }
+    }
+}
+
+/// Busy periods read from an iCalendar file
+///
+/// Recurring events repeat daily, weekly, monthly or yearly with the `INTERVAL`, `COUNT`,
+/// `UNTIL` and `BYDAY` of their `RRULE`, leaving out their `EXDATE`s and the occurrences
+/// a `RECURRENCE-ID` moved; other rules count for their first occurrence only. Times with
+/// a `TZID` are read as local time, and events marked transparent or cancelled are ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BusyCalendar {
+    events: Vec<BusyEvent>,
+}
+
+impl BusyCalendar {
+    pub fn parse(ics: &str) -> Self {
+        // Long lines are folded by starting the continuation with a space or tab
+        let mut lines: Vec<String> = Vec::new();
+        for line in ics.lines() {
+            match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+                (Some(rest), Some(last)) => last.push_str(rest),
+                _ => lines.push(line.to_string()),
+            }
+        }
+
+        // Events by UID, and occurrences of recurring events moved or cancelled by another
+        let mut events: Vec<(Option<String>, BusyEvent)> = Vec::new();
+        let mut moved: Vec<(String, NaiveDateTime)> = Vec::new();
+        let mut zones = BTreeSet::new();
+        let (mut start, mut end, mut ignored) = (None, None, false);
+        let (mut rule, mut excluded, mut uid, mut recurrence_id) = (None, Vec::new(), None, None);
+        for line in &lines {
+            let Some((name, value)) = line.split_once(':') else { continue };
+            // Parameters such as `;TZID=...` or `;VALUE=DATE` follow the name
+            let mut parameters = name.split(';');
+            let name = parameters.next().unwrap_or(name);
+            if let Some(zone) = parameters.find_map(|parameter| parameter.strip_prefix("TZID=")) {
+                zones.insert(zone.trim_matches('"').to_string());
+            }
+            match name.to_ascii_uppercase().as_str() {
+                "BEGIN" if value.eq_ignore_ascii_case("VEVENT") => {
+                    (start, end, ignored) = (None, None, false);
+                    (rule, excluded, uid, recurrence_id) = (None, Vec::new(), None, None);
+                }
+                "DTSTART" => start = parse_ics_time(value),
+                "DTEND" => end = parse_ics_time(value),
+                "TRANSP" => ignored |= value.eq_ignore_ascii_case("TRANSPARENT"),
+                "STATUS" => ignored |= value.eq_ignore_ascii_case("CANCELLED"),
+                "RRULE" => rule = Some(value.to_string()),
+                "EXDATE" => excluded.extend(value.split(',').filter_map(parse_ics_time).map(|(at, _)| at.naive_local())),
+                "UID" => uid = Some(value.to_string()),
+                "RECURRENCE-ID" => recurrence_id = parse_ics_time(value).map(|(at, _)| at.naive_local()),
+                "END" if value.eq_ignore_ascii_case("VEVENT") => {
+                    if let (Some(uid), Some(at)) = (&uid, recurrence_id.take()) {
+                        moved.push((uid.clone(), at));
+                    }
+                    match (start.take(), end.take()) {
+                        (Some((start, all_day)), end) if !ignored => {
+                            // All-day events without an end last one day
+                            let end = end.map(|(end, _)| end).unwrap_or(if all_day { start + ChronoDuration::days(1) } else { start });
+                            let recurrence = rule.take().and_then(|rule| match Recurrence::parse(&rule, start.naive_local()) {
+                                Ok(recurrence) => Some(recurrence),
+                                Err(e) => {
+                                    warn!("Only the first occurrence of a calendar event repeating as {} counts: {:#}", rule, e);
+                                    None
+                                }
+                            });
+                            if end > start {
+                                let excluded = std::mem::take(&mut excluded);
+                                events.push((uid.take(), BusyEvent { start, end, recurrence, excluded }));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (uid, at) in moved {
+            for (_, event) in events.iter_mut().filter(|(id, event)| id.as_ref() == Some(&uid) && event.recurrence.is_some()) {
+                event.excluded.push(at);
+            }
+        }
+        if !zones.is_empty() {
+            warn!("Calendar times in {} are read as local time", zones.into_iter().collect::<Vec<_>>().join(", "));
+        }
+        Self {
+            events: events.into_iter().map(|(_, event)| event).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn is_busy(&self, at: DateTime<Local>) -> bool {
+        self.events.iter().any(|event| event.is_busy(at))
+    }
+}
+
+/// An event of a [`BusyCalendar`]; `start` and `end` are those of its first occurrence
+#[derive(Debug, Clone, PartialEq)]
+struct BusyEvent {
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    recurrence: Option<Recurrence>,
+    /// Starts of occurrences left out, in local time
+    excluded: Vec<NaiveDateTime>,
+}
+
+impl BusyEvent {
+    fn is_busy(&self, at: DateTime<Local>) -> bool {
+        let Some(recurrence) = &self.recurrence else {
+            return self.start <= at && at < self.end;
+        };
+        // Only the periods of occurrences that could still be running at `at`
+        let (first, duration) = (self.start.naive_local(), self.end - self.start);
+        let from = recurrence.period_of(first.date(), (at - duration).naive_local().date()).max(0);
+        let to = recurrence.period_of(first.date(), at.naive_local().date());
+        (from..=to)
+            .flat_map(|period| recurrence.occurrences_in(first, period))
+            .filter(|start| !self.excluded.contains(start))
+            .filter_map(|start| Local.from_local_datetime(&start).earliest())
+            .any(|start| start <= at && at < start + duration)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// How an event repeats, from the parts of its `RRULE` a [`BusyCalendar`] understands
+#[derive(Debug, Clone, PartialEq)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    /// Latest start of an occurrence, in local time; a `COUNT` is turned into one too
+    until: Option<NaiveDateTime>,
+    /// Weekdays of weekly rules; for monthly ones with the week of the month, counted from
+    /// its end if negative, or every such day of the month without one
+    days: Vec<(Option<i32>, Weekday)>,
+}
+
+impl Recurrence {
+    /// The rule of an event first starting at `start`, or why it isn't understood
+    fn parse(rule: &str, start: NaiveDateTime) -> Result<Self> {
+        let (mut frequency, mut interval, mut count, mut until, mut days) = (None, 1, None, None, Vec::new());
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part.split_once('=').ok_or_else(|| anyhow::anyhow!("'{}' has no value", part))?;
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(anyhow::anyhow!("FREQ={} isn't supported", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value.parse().ok().filter(|interval| *interval > 0).ok_or_else(|| anyhow::anyhow!("Invalid INTERVAL '{}'", value))?
+                }
+                "COUNT" => count = Some(value.parse::<usize>().map_err(|_| anyhow::anyhow!("Invalid COUNT '{}'", value))?),
+                "UNTIL" => {
+                    let (at, date) = parse_ics_time(value).ok_or_else(|| anyhow::anyhow!("Invalid UNTIL '{}'", value))?;
+                    // A date includes the whole day
+                    let at = at.naive_local();
+                    until = Some(if date { at + ChronoDuration::days(1) - ChronoDuration::seconds(1) } else { at });
+                }
+                "BYDAY" => days = value.split(',').map(parse_ics_day).collect::<Result<_>>()?,
+                // Only moves the weeks counted for an interval, from Monday to another day
+                "WKST" => {}
+                _ => return Err(anyhow::anyhow!("{} isn't supported", name)),
+            }
+        }
+        let frequency = frequency.ok_or_else(|| anyhow::anyhow!("No FREQ"))?;
+        let weeks_of_month = days.iter().any(|(week, _)| week.is_some());
+        if (weeks_of_month && frequency != Frequency::Monthly) || (!days.is_empty() && matches!(frequency, Frequency::Daily | Frequency::Yearly)) {
+            return Err(anyhow::anyhow!("BYDAY isn't supported with FREQ={:?}", frequency));
+        }
+        let mut recurrence = Self { frequency, interval, until, days };
+        if let Some(count) = count {
+            // Found once here instead of counting from the first occurrence on every check
+            recurrence.until = (0..MAX_PERIODS)
+                .flat_map(|period| recurrence.occurrences_in(start, period))
+                .nth(count.saturating_sub(1));
+        }
+        Ok(recurrence)
+    }
+
+    /// The period `date` falls in, counted in units of the frequency from the one of `first`
+    fn period_of(&self, first: NaiveDate, date: NaiveDate) -> i64 {
+        let month = |date: NaiveDate| date.year() as i64 * 12 + date.month0() as i64;
+        match self.frequency {
+            Frequency::Daily => (date - first).num_days(),
+            Frequency::Weekly => (date.week(Weekday::Mon).first_day() - first.week(Weekday::Mon).first_day()).num_weeks(),
+            Frequency::Monthly => month(date) - month(first),
+            Frequency::Yearly => (date.year() - first.year()) as i64,
+        }
+    }
+
+    /// Starts of the occurrences in a period, see [`Recurrence::period_of`], in order
+    fn occurrences_in(&self, first: NaiveDateTime, period: i64) -> Vec<NaiveDateTime> {
+        if period < 0 || period % self.interval as i64 != 0 {
+            return Vec::new();
+        }
+        let date = first.date();
+        let mut dates: Vec<NaiveDate> = match self.frequency {
+            Frequency::Daily => vec![date + ChronoDuration::days(period)],
+            Frequency::Weekly if self.days.is_empty() => vec![date + ChronoDuration::weeks(period)],
+            Frequency::Weekly => {
+                let monday = date.week(Weekday::Mon).first_day() + ChronoDuration::weeks(period);
+                self.days.iter().map(|(_, day)| monday + ChronoDuration::days(day.num_days_from_monday() as i64)).collect()
+            }
+            Frequency::Monthly => {
+                let month = date.year() as i64 * 12 + date.month0() as i64 + period;
+                let (year, month) = ((month / 12) as i32, (month % 12) as u32 + 1);
+                if self.days.is_empty() {
+                    // Months without the day are skipped
+                    NaiveDate::from_ymd_opt(year, month, date.day()).into_iter().collect()
+                } else {
+                    days_of_month(year, month, &self.days)
+                }
+            }
+            Frequency::Yearly => NaiveDate::from_ymd_opt(date.year() + period as i32, date.month(), date.day()).into_iter().collect(),
+        };
+        dates.sort();
+        dates
+            .into_iter()
+            .map(|date| date.and_time(first.time()))
+            .filter(|start| *start >= first && self.until.is_none_or(|until| *start <= until))
+            .collect()
+    }
+}
+
+/// Periods looked through for the last occurrence of a rule with a `COUNT`
+const MAX_PERIODS: i64 = 100_000;
+
+/// `BYDAY` values such as "MO", "2TU" or "-1FR"
+fn parse_ics_day(value: &str) -> Result<(Option<i32>, Weekday)> {
+    let value = value.trim();
+    let split = value.len().saturating_sub(2);
+    let (week, day) = value.split_at(split);
+    let day = match day.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(anyhow::anyhow!("Invalid BYDAY '{}'", value)),
+    };
+    let week = match week {
+        "" => None,
+        week => Some(week.parse::<i32>().ok().filter(|week| *week != 0).ok_or_else(|| anyhow::anyhow!("Invalid BYDAY '{}'", value))?),
+    };
+    Ok((week, day))
+}
+
+/// The dates of a month on `days`: (Some(1), Mon) is its first Monday, (Some(-1), Fri) its
+/// last Friday and (None, Tue) every Tuesday
+fn days_of_month(year: i32, month: u32, days: &[(Option<i32>, Weekday)]) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    for &(week, day) in days {
+        let all: Vec<NaiveDate> = (1..=31)
+            .filter_map(|of_month| NaiveDate::from_ymd_opt(year, month, of_month))
+            .filter(|date| date.weekday() == day)
+            .collect();
+        match week {
+            None => dates.extend(all),
+            Some(week) if week > 0 => dates.extend(all.get(week as usize - 1)),
+            Some(week) => dates.extend(all.len().checked_sub(week.unsigned_abs() as usize).and_then(|index| all.get(index))),
+        }
+    }
+    dates
+}
+
+/// An iCalendar date or date-time, and whether it was a bare date
+fn parse_ics_time(value: &str) -> Option<(DateTime<Local>, bool)> {
+    let value = value.trim();
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((Local.from_local_datetime(&date.and_time(NaiveTime::MIN)).earliest()?, true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&time).with_timezone(&Local), false));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Some((Local.from_local_datetime(&time).earliest()?, false))
+}
+
+/// The calendar file and the version of it last read
+#[derive(Debug)]
+struct CalendarFile {
+    path: PathBuf,
+    /// `None` until the first read; `Some(None)` if the file was missing
+    modified: Option<Option<SystemTime>>,
+    calendar: BusyCalendar,
+}
+
+impl CalendarFile {
+    /// Re-read the file if it changed since the last call; a file that can't be read
+    /// keeps the last good calendar
+    fn refresh(&mut self) {
+        let modified = fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok();
+        if self.modified == Some(modified) {
+            return;
+        }
+        self.modified = Some(modified);
+        match fs::read_to_string(&self.path) {
+            Ok(ics) => {
+                self.calendar = BusyCalendar::parse(&ics);
+                info!("Loaded {} calendar event(s) from {:?}", self.calendar.len(), self.path);
+            }
+            Err(e) => warn!("Failed to read calendar {:?}: {}", self.path, e),
+        }
+    }
+}
+
+/// Decides whether the monitor is snoozed at a given time
+#[derive(Debug)]
+pub struct SnoozeSchedule {
+    windows: Vec<SnoozeWindow>,
+    calendar: Option<CalendarFile>,
+    pause_when: CalendarState,
+}
+
+impl SnoozeSchedule {
+    /// `None` if nothing is configured; fails on an invalid window
+    pub fn new(config: &SnoozeConfig) -> Result<Option<Self>> {
+        if config.windows.is_empty() && config.calendar.is_none() {
+            return Ok(None);
+        }
+        let windows = config
+            .windows
+            .iter()
+            .map(|window| SnoozeWindow::new(window).map_err(|e| anyhow::anyhow!("Invalid [snooze] window {}-{}: {}", window.from, window.to, e)))
+            .collect::<Result<_>>()?;
+        Ok(Some(Self {
+            windows,
+            calendar: config.calendar.clone().map(|path| CalendarFile {
+                path,
+                modified: None,
+                calendar: BusyCalendar::default(),
+            }),
+            pause_when: config.pause_when,
+        }))
+    }
+
+    /// Whether monitoring should be off at `at`; re-reads the calendar file if it changed
+    pub fn is_snoozed(&mut self, at: DateTime<Local>) -> bool {
+        if self.windows.iter().any(|window| window.contains(at.naive_local())) {
+            return true;
+        }
+        match &mut self.calendar {
+            Some(file) => {
+                file.refresh();
+                let busy = file.calendar.is_busy(at);
+                match self.pause_when {
+                    CalendarState::Busy => busy,
+                    CalendarState::Free => !busy,
+                }
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(from: &str, to: &str, days: &[&str]) -> SnoozeWindow {
+        SnoozeWindow::new(&SnoozeWindowConfig {
+            from: from.to_string(),
+            to: to.to_string(),
+            days: days.iter().map(|day| day.to_string()).collect(),
+        })
+        .unwrap()
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2024-03-01 was a Friday
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_windows() {
+        let evenings = window("18:00", "09:00", &[]);
+        assert!(evenings.contains(at(1, 18, 0)));
+        assert!(evenings.contains(at(1, 8, 59)));
+        assert!(!evenings.contains(at(1, 9, 0)));
+        assert!(!evenings.contains(at(1, 12, 0)));
+
+        // A Friday night window runs into Saturday morning but not Saturday night
+        let friday_night = window("22:00", "06:00", &["fri"]);
+        assert!(friday_night.contains(at(2, 5, 0)));
+        assert!(!friday_night.contains(at(2, 23, 0)));
+
+        let weekend = window("00:00", "00:00", &["sat", "sun"]);
+        assert!(weekend.contains(at(2, 12, 0)));
+        assert!(!weekend.contains(at(4, 12, 0)));
+
+        assert!(SnoozeWindow::new(&SnoozeWindowConfig {
+            from: "9am".to_string(),
+            to: "17:00".to_string(),
+            days: Vec::new(),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_calendar() {
+        let calendar = BusyCalendar::parse(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\n\
+             SUMMARY:Stand-up\r\n\
+             DTSTART;TZID=Europe/Berlin:20240301T093000\r\n\
+             DTEND;TZID=Europe/Berlin:20240301T100000\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             SUMMARY:Focus time\r\n\
+             TRANSP:TRANSPARENT\r\n\
+             DTSTART:20240301T140000\r\n\
+             DTEND:20240301T160000\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             DTSTART;VALUE=DATE:20240304\r\n\
+             SUMMARY:Holi\r\n \
+              day\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        );
+        let local = |day, hour, minute| Local.from_local_datetime(&at(day, hour, minute)).unwrap();
+        assert_eq!(calendar.len(), 2);
+        assert!(!calendar.is_empty() && BusyCalendar::parse("").is_empty());
+        assert!(calendar.is_busy(local(1, 9, 45)));
+        assert!(!calendar.is_busy(local(1, 10, 0)));
+        assert!(!calendar.is_busy(local(1, 15, 0)));
+        assert!(calendar.is_busy(local(4, 23, 0)));
+    }
+
+    #[test]
+    fn test_recurring_calendar() {
+        let calendar = BusyCalendar::parse(
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:sync\r\n\
+             DTSTART:20240301T110000\r\n\
+             DTEND:20240301T111500\r\n\
+             RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR\r\n\
+             EXDATE:20240306T110000\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:sync\r\n\
+             RECURRENCE-ID:20240311T110000\r\n\
+             DTSTART:20240311T130000\r\n\
+             DTEND:20240311T131500\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             DTSTART:20240329T150000\r\n\
+             DTEND:20240329T160000\r\n\
+             RRULE:FREQ=MONTHLY;BYDAY=-1FR;COUNT=2\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VEVENT\r\n\
+             DTSTART:20240302T200000\r\n\
+             DTEND:20240302T210000\r\n\
+             RRULE:FREQ=WEEKLY;BYMONTH=3\r\n\
+             END:VEVENT\r\n\
+             END:VCALENDAR\r\n",
+        );
+        let local = |year, month, day, hour, minute| {
+            let at = NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(hour, minute, 0).unwrap();
+            Local.from_local_datetime(&at).unwrap()
+        };
+        assert!(calendar.is_busy(local(2024, 3, 4, 11, 5)));
+        assert!(!calendar.is_busy(local(2024, 3, 5, 11, 5)));
+        // Left out, then moved to the afternoon
+        assert!(!calendar.is_busy(local(2024, 3, 6, 11, 5)));
+        assert!(!calendar.is_busy(local(2024, 3, 11, 11, 5)));
+        assert!(calendar.is_busy(local(2024, 3, 11, 13, 5)));
+        assert!(calendar.is_busy(local(2025, 1, 6, 11, 5)));
+
+        // The last Friday of the month, twice
+        assert!(calendar.is_busy(local(2024, 3, 29, 15, 30)));
+        assert!(calendar.is_busy(local(2024, 4, 26, 15, 30)));
+        assert!(!calendar.is_busy(local(2024, 4, 19, 15, 30)));
+        assert!(!calendar.is_busy(local(2024, 5, 31, 15, 30)));
+
+        // A rule that isn't understood keeps the first occurrence
+        assert!(calendar.is_busy(local(2024, 3, 2, 20, 30)));
+        assert!(!calendar.is_busy(local(2024, 3, 9, 20, 30)));
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::locale;
+use crate::monitor::{MonitorEvent, ScanOutcome};
+
+/// Counters for one run of the app, shared between the monitor thread and the main thread
+#[derive(Debug)]
+pub struct SessionStats {
+    started: Instant,
+    events: AtomicU64,
+    generated: AtomicU64,
+    decoded: AtomicU64,
+    errors: AtomicU64,
+    listener_restarts: AtomicU64,
+    activity: Mutex<Activity>,
+    /// See [`crate::config::AppConfig::apply_guest_mode`]
+    guest: AtomicBool,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionStats {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            events: AtomicU64::new(0),
+            generated: AtomicU64::new(0),
+            decoded: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            listener_restarts: AtomicU64::new(0),
+            activity: Mutex::new(Activity::default()),
+            guest: AtomicBool::new(false),
+        }
+    }
+
+    /// Count a clipboard event, including decodes and errors it carries
+    pub fn record(&self, event: &MonitorEvent) {
+        if let MonitorEvent::ListenerRestarted(_) = event {
+            // Not a clipboard change, so only counted as an incident
+            self.listener_restarts.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.events.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut activity) = self.activity.lock() {
+            activity.last_change = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|elapsed| elapsed.as_secs());
+            if let MonitorEvent::Image { scan: ScanOutcome::Decoded(content), .. } = event {
+                activity.last_decoded = Some(content.clone());
+            }
+        }
+        match event {
+            MonitorEvent::Image { scan: ScanOutcome::Decoded(_), .. } => {
+                self.decoded.fetch_add(1, Ordering::Relaxed);
+            }
+            MonitorEvent::Image { scan: ScanOutcome::Failed(_), .. } | MonitorEvent::Error(_) => {
+                self.errors.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+    }
+
+    /// Count a QR code rendered for the user
+    pub fn record_generated(&self) {
+        self.generated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Note that monitoring was paused or resumed from the tray
+    pub fn set_paused(&self, paused: bool) {
+        if let Ok(mut activity) = self.activity.lock() {
+            activity.paused = paused;
+        }
+    }
+
+    /// Note that the `[snooze]` schedule paused or resumed monitoring
+    pub fn set_snoozed(&self, snoozed: bool) {
+        if let Ok(mut activity) = self.activity.lock() {
+            activity.snoozed = snoozed;
+        }
+    }
+
+    /// Note that this run is in guest mode, for the status window
+    pub fn set_guest(&self, guest: bool) {
+        self.guest.store(guest, Ordering::Relaxed);
+    }
+
+    pub fn guest(&self) -> bool {
+        self.guest.load(Ordering::Relaxed)
+    }
+
+    pub fn activity(&self) -> Activity {
+        self.activity.lock().map(|activity| activity.clone()).unwrap_or_default()
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            uptime: self.started.elapsed(),
+            events: self.events.load(Ordering::Relaxed),
+            generated: self.generated.load(Ordering::Relaxed),
+            decoded: self.decoded.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            listener_restarts: self.listener_restarts.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A snapshot of [`SessionStats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub uptime: Duration,
+    pub events: u64,
+    pub generated: u64,
+    pub decoded: u64,
+    pub errors: u64,
+    /// Times the watchdog restarted a stuck clipboard listener
+    pub listener_restarts: u64,
+}
+
+impl fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let locale = locale::current();
+        write!(
+            f,
+            "uptime {}, {} clipboard event(s), {} QR code(s) generated, {} decoded, {} error(s)",
+            format_uptime(self.uptime),
+            locale.number(self.events),
+            locale.number(self.generated),
+            locale.number(self.decoded),
+            locale.number(self.errors)
+        )?;
+        if self.listener_restarts > 0 {
+            write!(f, ", {} listener restart(s)", locale.number(self.listener_restarts))?;
+        }
+        Ok(())
+    }
+}
+
+/// What the monitor is doing and last saw, for the status window
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Activity {
+    /// Paused from the tray
+    pub paused: bool,
+    /// Paused by the `[snooze]` schedule
+    pub snoozed: bool,
+    /// When the clipboard last changed, in seconds since the Unix epoch
+    pub last_change: Option<u64>,
+    /// The content of the last QR code decoded from a copied image
+    pub last_decoded: Option<String>,
+}
+
+/// e.g. "2d 3h 04m", "5m 09s"
+pub fn format_uptime(uptime: Duration) -> String {
+    let seconds = uptime.as_secs();
+    let (days, hours, minutes, seconds) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60, seconds % 60);
+    if days > 0 {
+        format!("{}d {}h {:02}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else {
+        format!("{}m {:02}s", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_stats() {
+        let stats = SessionStats::new();
+        stats.record(&MonitorEvent::Text("hello".to_string()));
+        stats.record_generated();
+        stats.record(&MonitorEvent::Error("denied".to_string()));
+        stats.record(&MonitorEvent::Image {
+            image: image::ImageBuffer::new(1, 1),
+            scan: ScanOutcome::Decoded("hi".to_string()),
+        });
+        stats.record(&MonitorEvent::ListenerRestarted("stuck".to_string()));
+
+        let summary = stats.summary();
+        assert_eq!((summary.events, summary.generated, summary.decoded, summary.errors), (3, 1, 1, 1));
+        assert_eq!(summary.listener_restarts, 1);
+
+        stats.set_paused(true);
+        let activity = stats.activity();
+        assert!(activity.paused && !activity.snoozed && activity.last_change.is_some());
+        assert_eq!(activity.last_decoded.as_deref(), Some("hi"));
+
+        assert!(!stats.guest());
+        stats.set_guest(true);
+        assert!(stats.guest());
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(Duration::from_secs(309)), "5m 09s");
+        assert_eq!(format_uptime(Duration::from_secs(3 * 3600 + 240)), "3h 04m");
+        assert_eq!(format_uptime(Duration::from_secs(2 * 86400 + 3 * 3600 + 240)), "2d 3h 04m");
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use eframe::egui;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Stdio};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+use crate::content_preview;
+use crate::control::{self, ControlRequest, InstanceStatus};
+use crate::locale;
+use crate::redaction::Redactor;
+use crate::stats::format_uptime;
+use crate::totp::{self, Totp};
+
+/// How often the window asks the running instance for its status again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Characters of the last decoded content shown
+const PREVIEW_CHARS: usize = 200;
+
+/// Show what the running instance is doing: whether it is monitoring, when the clipboard
+/// last changed, its counters and the last decoded code. Refreshed from the control socket
+/// at `socket`; where that isn't available, `initial` is shown as it was when opened
+pub fn run(socket: PathBuf, initial: Option<InstanceStatus>, redactor: Redactor) -> Result<()> {
+    info!("Opening status window");
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_title("Clipboard QR Status").with_inner_size([420.0, 340.0]),
+        ..Default::default()
+    };
+    let opened = SystemTime::now();
+    eframe::run_native(
+        "Clipboard QR Status",
+        options,
+        Box::new(move |_cc| {
+            Ok(Box::new(StatusApp {
+                socket,
+                status: initial.map(|status| (status, opened)),
+                live: false,
+                error: None,
+                last_refresh: None,
+                redactor,
+            }))
+        }),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to open the status window: {}", e))
+}
+
+/// Open the status window in its own process, handing it `status` to show until it reaches
+/// the instance, since the tray's event loop can't host egui
+pub fn open_window(status: &InstanceStatus) -> Result<Child> {
+    let exe = std::env::current_exe()?;
+    let mut child = std::process::Command::new(exe)
+        .args(["status", "--initial-from-stdin"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to open the status window: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        serde_json::to_writer(&mut stdin, status)?;
+        stdin.flush()?;
+    }
+    Ok(child)
+}
+
+/// The status handed over by [`open_window`]
+pub fn read_initial_status() -> Result<InstanceStatus> {
+    Ok(serde_json::from_reader(std::io::stdin().lock())?)
+}
+
+struct StatusApp {
+    socket: PathBuf,
+    /// The last status and when it was taken
+    status: Option<(InstanceStatus, SystemTime)>,
+    /// The last refresh reached the instance
+    live: bool,
+    /// Why the last refresh didn't reach the instance
+    error: Option<String>,
+    last_refresh: Option<Instant>,
+    redactor: Redactor,
+}
+
+impl StatusApp {
+    fn refresh(&mut self) {
+        if self.last_refresh.is_some_and(|last| last.elapsed() < REFRESH_INTERVAL) {
+            return;
+        }
+        self.last_refresh = Some(Instant::now());
+        let status = control::send(&self.socket, &ControlRequest::Status).and_then(|data| {
+            let data = data.ok_or_else(|| anyhow::anyhow!("The instance is too old to report its status"))?;
+            Ok(serde_json::from_value::<InstanceStatus>(data)?)
+        });
+        match status {
+            Ok(status) => {
+                self.status = Some((status, SystemTime::now()));
+                self.live = true;
+                self.error = None;
+            }
+            Err(e) => {
+                debug!("Status refresh failed: {:#}", e);
+                self.live = false;
+                self.error = Some(format!("{:#}", e));
+            }
+        }
+    }
+
+    fn details(&self, ui: &mut egui::Ui, status: &InstanceStatus, taken: SystemTime) {
+        let locale = locale::current();
+        let activity = &status.activity;
+        let monitoring = if activity.paused {
+            "⏸ Paused"
+        } else if activity.snoozed {
+            "💤 Snoozed by schedule"
+        } else {
+            "▶ Monitoring"
+        };
+        let last_change = match activity.last_change.and_then(|seconds| Local.timestamp_opt(seconds as i64, 0).single()) {
+            Some(at) => {
+                let now = taken.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+                let ago = Duration::from_secs(now.saturating_sub(at.timestamp() as u64));
+                format!("{} ({} ago)", locale.date_time(&at), format_uptime(ago))
+            }
+            None => "Not since starting".to_string(),
+        };
+        let stats = &status.stats;
+        egui::Grid::new("status-grid").num_columns(2).spacing([16.0, 6.0]).show(ui, |ui| {
+            let mut row = |label: &str, value: String| {
+                ui.label(label);
+                ui.label(value);
+                ui.end_row();
+            };
+            row("Clipboard", monitoring.to_string());
+            let modes: Vec<&str> = [(status.guest, "Guest"), (status.in_memory, "In memory; nothing is written to disk")]
+                .into_iter()
+                .filter_map(|(on, mode)| on.then_some(mode))
+                .collect();
+            if !modes.is_empty() {
+                row("Mode", modes.join(", "));
+            }
+            row("Last change", last_change);
+            row("Running for", format_uptime(stats.uptime));
+            row("Clipboard events", locale.number(stats.events));
+            row("QR codes generated", locale.number(stats.generated));
+            row("QR codes decoded", locale.number(stats.decoded));
+            row("Errors", locale.number(stats.errors));
+            if stats.listener_restarts > 0 {
+                row("Listener restarts", locale.number(stats.listener_restarts));
+            }
+            row("Version", status.version.clone());
+        });
+
+        ui.separator();
+        ui.label("Last decoded QR code");
+        match &activity.last_decoded {
+            // Structured content folds out as a tree; a one-time password setup never does,
+            // its URI holds the secret
+            Some(content)
+                if !self.redactor.is_enabled() && Totp::parse(content).is_err() && content_preview::build_tree(content).is_some() =>
+            {
+                content_preview::show_preview(ui, content);
+            }
+            Some(content) => {
+                // A one-time password setup shows its account, not the URI with the secret
+                let preview = totp::console_text(content, &self.redactor);
+                let preview: String = preview.chars().take(PREVIEW_CHARS).collect();
+                ui.add(egui::Label::new(egui::RichText::new(preview).monospace()).wrap());
+            }
+            None => {
+                ui.weak("None yet");
+            }
+        }
+    }
+}
+
+impl eframe::App for StatusApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+        self.refresh();
+
+        egui::TopBottomPanel::bottom("status-footer").show(ctx, |ui| {
+            ui.add_space(4.0);
+            match (&self.status, self.live) {
+                (Some(_), true) => ui.weak("Live; refreshed every 2 seconds"),
+                (Some((_, taken)), false) => {
+                    let taken = chrono::DateTime::<Local>::from(*taken);
+                    ui.weak(format!("As of {}; the running instance can't be asked for updates", locale::current().time(&taken)))
+                }
+                (None, _) => ui.weak(self.error.as_deref().unwrap_or("Waiting for the running instance…")),
+            };
+            ui.add_space(4.0);
+        });
+        egui::CentralPanel::default().show(ctx, |ui| match &self.status {
+            Some((status, taken)) => self.details(ui, status, *taken),
+            None => {
+                ui.label("No running Clipboard QR instance answered.");
+            }
+        });
+        ctx.request_repaint_after(REFRESH_INTERVAL);
+    }
+}
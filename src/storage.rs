@@ -0,0 +1,63 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info, warn};
+
+/// Set once at startup; see [`enable_in_memory_mode`]
+static IN_MEMORY: AtomicBool = AtomicBool::new(false);
+
+/// Refuse every disk write the app would make on its own from now on: history,
+/// presets, exit reports, scheduled outputs and default output files
+///
+/// Files the user names explicitly (an `--output` path) are still written.
+pub fn enable_in_memory_mode() {
+    IN_MEMORY.store(true, Ordering::Relaxed);
+    info!("In-memory mode enabled; nothing will be written to disk");
+}
+
+pub fn in_memory_mode() -> bool {
+    IN_MEMORY.load(Ordering::Relaxed)
+}
+
+/// Called before every write the user didn't explicitly ask for; fails in in-memory mode
+pub fn check_write(what: &str, path: &Path) -> Result<()> {
+    check(in_memory_mode(), what, path)
+}
+
+fn check(in_memory: bool, what: &str, path: &Path) -> Result<()> {
+    if in_memory {
+        warn!("In-memory mode: refused to write {} to {:?}", what, path);
+        return Err(anyhow::anyhow!(
+            "In-memory mode: not writing {} to {}; choose an output path explicitly to save",
+            what,
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// A RAM-backed directory for files a library insists on writing, such as tray icons
+/// on Linux; `None` where no such directory is known
+pub fn memory_backed_dir() -> Option<PathBuf> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from("/dev/shm")))
+        .filter(|dir| dir.is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_write() {
+        // The global flag is left alone so other tests can still write
+        let path = Path::new("output/qr.png");
+        assert!(check(false, "a QR image", path).is_ok());
+        let error = check(true, "a QR image", path).unwrap_err();
+        assert!(error.to_string().contains("not writing a QR image"));
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Result;
+use eframe::egui;
+use std::fs;
+use tracing::{error, info};
+
+use crate::qr_generator::QRGenerator;
+use crate::qr_style::{Color, QrStyle};
+use crate::renderer::SvgRenderer;
+
+/// SVG export options: size, colours, quiet zone and captions, with a Save button
+pub struct SvgExportDialog {
+    /// Shown as a window by [`Self::show`]
+    pub open: bool,
+    min_size: u32,
+    foreground: [u8; 3],
+    background: [u8; 3],
+    quiet_zone: usize,
+    /// Modules of dark frame around the quiet zone
+    frame: usize,
+    title: String,
+    footer: String,
+    /// Result of the last save
+    status: Option<String>,
+}
+
+impl SvgExportDialog {
+    /// Start from the colours, quiet zone and frame of `style` and the size and captions of
+    /// `svg`, with the style's caption as the footer if `svg` has none
+    pub fn new(style: &QrStyle, svg: &SvgRenderer) -> Self {
+        let (fg, bg) = (style.foreground, style.background);
+        Self {
+            open: false,
+            min_size: svg.min_size,
+            foreground: [fg.r, fg.g, fg.b],
+            background: [bg.r, bg.g, bg.b],
+            quiet_zone: style.quiet_zone,
+            frame: style.frame,
+            title: svg.title.clone().unwrap_or_default(),
+            footer: svg.footer.clone().or_else(|| style.caption.clone()).unwrap_or_default(),
+            status: None,
+        }
+    }
+
+    /// A generator like `base` with the chosen colours, quiet zone and frame, and the renderer for
+    /// the chosen size and captions; fails for colours scanners can't read
+    fn options(&self, base: &QRGenerator) -> Result<(QRGenerator, SvgRenderer)> {
+        let [r, g, b] = self.foreground;
+        let foreground = Color::rgb(r, g, b);
+        let [r, g, b] = self.background;
+        let style = QrStyle::custom(foreground, Color::rgb(r, g, b))?
+            .with_shape(base.style().shape)
+            .with_quiet_zone(self.quiet_zone)
+            .with_frame(self.frame);
+        let generator = QRGenerator::with_style(style).with_error_correction(base.error_correction());
+        let caption = |text: &str| Some(text.trim().to_string()).filter(|text| !text.is_empty());
+        let svg = SvgRenderer {
+            min_size: self.min_size,
+            title: caption(&self.title),
+            footer: caption(&self.footer),
+        };
+        Ok((generator, svg))
+    }
+
+    /// Ask where to save and write the SVG of `text` there
+    fn save(&mut self, base: &QRGenerator, text: &str) {
+        let svg = match self.options(base).and_then(|(generator, svg)| generator.generate_svg_with(text, &svg)) {
+            Ok(svg) => svg,
+            Err(e) => {
+                self.status = Some(format!("❌ {}", e));
+                return;
+            }
+        };
+        let name = QRGenerator::default_file_name(text).replace(".png", ".svg");
+        let Some(path) = rfd::FileDialog::new().set_title("Export SVG").set_file_name(name).add_filter("SVG image", &["svg"]).save_file() else {
+            return;
+        };
+        self.status = Some(match fs::write(&path, svg) {
+            Ok(()) => {
+                info!("SVG exported to {:?}", path);
+                format!("✅ Saved to {}", path.display())
+            }
+            Err(e) => {
+                error!("Failed to export SVG to {:?}: {}", path, e);
+                format!("❌ {}", e)
+            }
+        });
+    }
+
+    fn form(&mut self, ui: &mut egui::Ui, base: &QRGenerator, text: &str) {
+        egui::Grid::new("svg-export").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+            ui.label("Minimum size");
+            ui.add(egui::DragValue::new(&mut self.min_size).range(50..=10_000).suffix(" px"));
+            ui.end_row();
+
+            ui.label("Colours");
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgb(&mut self.foreground);
+                ui.label("on");
+                ui.color_edit_button_srgb(&mut self.background);
+            });
+            ui.end_row();
+
+            ui.label("Quiet zone");
+            ui.add(egui::DragValue::new(&mut self.quiet_zone).range(0..=16).suffix(" modules"));
+            ui.end_row();
+
+            ui.label("Frame");
+            ui.add(egui::DragValue::new(&mut self.frame).range(0..=8).suffix(" modules"));
+            ui.end_row();
+
+            ui.label("Title");
+            ui.text_edit_singleline(&mut self.title);
+            ui.end_row();
+
+            ui.label("Footer");
+            ui.text_edit_singleline(&mut self.footer);
+            ui.end_row();
+        });
+
+        let valid = self.options(base);
+        if let Err(e) = &valid {
+            ui.colored_label(ui.visuals().warn_fg_color, e.to_string());
+        }
+        ui.add_space(4.0);
+        if ui.add_enabled(valid.is_ok(), egui::Button::new("Save…")).clicked() {
+            self.save(base, text);
+        }
+        if let Some(status) = &self.status {
+            ui.label(status);
+        }
+    }
+
+    /// Show the dialog as a window over the current viewport while [`Self::open`] is set
+    pub fn show(&mut self, ctx: &egui::Context, base: &QRGenerator, text: &str) {
+        let mut open = self.open;
+        egui::Window::new("Export SVG")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| self.form(ui, base, text));
+        self.open = open;
+    }
+}
+
+/// Export `text` as SVG from a window of its own, starting from `svg`'s size and captions
+pub fn run(generator: QRGenerator, text: String, svg: SvgRenderer) -> Result<()> {
+    info!("Opening SVG export");
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_title("Export SVG").with_inner_size([380.0, 280.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Export SVG",
+        options,
+        Box::new(move |_cc| {
+            let dialog = SvgExportDialog::new(generator.style(), &svg);
+            Ok(Box::new(SvgExportApp { dialog, generator, text }))
+        }),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to open SVG export: {}", e))
+}
+
+struct SvgExportApp {
+    dialog: SvgExportDialog,
+    generator: QRGenerator,
+    text: String,
+}
+
+impl eframe::App for SvgExportApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| self.dialog.form(ui, &self.generator, &self.text));
+    }
+}
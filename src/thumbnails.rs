@@ -0,0 +1,124 @@
+use image::{Rgba, RgbaImage};
+use qrcode::{QrCode, Version};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use crate::matrix::BitMatrix;
+use crate::payload::PayloadType;
+
+/// Side of a menu icon at 100% scaling
+pub const BASE_SIZE: u32 = 16;
+/// Thumbnails kept; older ones are regenerated if their entry comes back into view
+const CAPACITY: usize = 64;
+/// Codes above this version are a grey blur at menu icon sizes, so they get a plain swatch
+const MAX_DETAILED_VERSION: i16 = 6;
+
+const LIGHT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const DARK: Rgba<u8> = Rgba([32, 32, 32, 255]);
+
+/// Small QR code pictures for history menu items, cached by content hash
+///
+/// Thumbnails are only drawn when asked for, and each refresh may draw at most a fixed
+/// number of new ones, so opening a long history doesn't stall the tray.
+pub struct ThumbnailCache {
+    size: u32,
+    thumbnails: HashMap<u64, RgbaImage>,
+    /// Hashes, oldest first
+    order: VecDeque<u64>,
+}
+
+impl ThumbnailCache {
+    /// Thumbnails for a display scale factor such as 1.5, at least 16 and at most 64 pixels
+    pub fn for_scale(scale: f64) -> Self {
+        Self::new((BASE_SIZE as f64 * scale).round().clamp(BASE_SIZE as f64, 64.0) as u32)
+    }
+
+    pub fn new(size: u32) -> Self {
+        Self {
+            size,
+            thumbnails: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// The thumbnail for `content`; `None` if it isn't cached and `budget`, the number of new
+    /// thumbnails this refresh may still draw, is used up
+    pub fn get(&mut self, content: &str, budget: &mut usize) -> Option<&RgbaImage> {
+        let key = hash(content);
+        if !self.thumbnails.contains_key(&key) {
+            if *budget == 0 {
+                return None;
+            }
+            *budget -= 1;
+            if self.order.len() >= CAPACITY {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.thumbnails.remove(&oldest);
+                }
+            }
+            self.thumbnails.insert(key, render(content, self.size));
+            self.order.push_back(key);
+        }
+        self.thumbnails.get(&key)
+    }
+}
+
+fn hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The code for `content` scaled to `size` pixels, dark modules in the colour of its payload
+/// type; a swatch of that colour if the code is too dense or can't be encoded
+pub fn render(content: &str, size: u32) -> RgbaImage {
+    let colour = PayloadType::detect(content)
+        .badge_colour()
+        .map(|colour| Rgba([colour.r, colour.g, colour.b, 255]))
+        .unwrap_or(DARK);
+    let matrix = QrCode::new(content)
+        .ok()
+        .filter(|code| matches!(code.version(), Version::Normal(version) if version <= MAX_DETAILED_VERSION))
+        .map(|code| BitMatrix::from_code(&code));
+    let Some(matrix) = matrix else {
+        return RgbaImage::from_pixel(size, size, colour);
+    };
+
+    // Nearest-neighbour: every pixel takes the module under its centre, with a one module
+    // quiet zone so the thumbnail still reads as a QR code without wasting space
+    let modules = matrix.width() + 2;
+    RgbaImage::from_fn(size, size, |x, y| {
+        let module = |pixel: u32| ((pixel as f64 + 0.5) * modules as f64 / size as f64) as usize;
+        match (module(x).checked_sub(1), module(y).checked_sub(1)) {
+            (Some(x), Some(y)) if matrix.get(x, y) => colour,
+            _ => LIGHT,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_budget() {
+        let mut cache = ThumbnailCache::for_scale(1.5);
+        assert_eq!(cache.size(), 24);
+
+        let mut budget = 1;
+        assert!(cache.get("https://example.com", &mut budget).is_some());
+        assert!(cache.get("hello", &mut budget).is_none());
+        // Cached thumbnails don't need any budget
+        let thumbnail = cache.get("https://example.com", &mut budget).unwrap();
+        assert_eq!(thumbnail.dimensions(), (24, 24));
+
+        // Too dense for a readable thumbnail: a plain swatch
+        let dense = render(&"x".repeat(1000), 16);
+        assert!(dense.pixels().all(|pixel| *pixel == DARK));
+        assert!(render("hello", 16).pixels().any(|pixel| *pixel == LIGHT));
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::Result;
+use sha1::{Digest, Sha1};
+use std::borrow::Cow;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use crate::password_manager::OtpAuth;
+use crate::redaction::Redactor;
+
+/// Block size of SHA-1, which HMAC pads its key to
+const SHA1_BLOCK: usize = 64;
+
+/// A time-based one-time password generator from an `otpauth://totp/...` URI, see RFC 6238
+///
+/// Only shows the account and the current code; the secret stays out of `Debug` and logs.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Totp {
+    /// "GitHub (sam@example.com)"
+    pub name: String,
+    secret: Vec<u8>,
+    digits: u32,
+    /// Seconds each code is valid for
+    period: u64,
+}
+
+impl Totp {
+    /// Parse a TOTP setup URI; HOTP and algorithms other than SHA-1 are rejected
+    pub fn parse(text: &str) -> Result<Self> {
+        let otp = OtpAuth::parse(text)?;
+        if otp.method != "totp" {
+            return Err(anyhow::anyhow!("Only time-based (totp) one-time passwords show live codes"));
+        }
+        let url = Url::parse(&otp.uri)?;
+        let query = |name: &str| url.query_pairs().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.into_owned());
+        if let Some(algorithm) = query("algorithm").filter(|algorithm| !algorithm.eq_ignore_ascii_case("SHA1")) {
+            return Err(anyhow::anyhow!("{} one-time passwords aren't supported, only SHA1", algorithm));
+        }
+        let digits = match query("digits") {
+            Some(digits) => digits.parse().ok().filter(|digits| (6..=8).contains(digits)),
+            None => Some(6),
+        }
+        .ok_or_else(|| anyhow::anyhow!("One-time passwords have 6 to 8 digits"))?;
+        let period = match query("period") {
+            Some(period) => period.parse().ok().filter(|period| *period > 0),
+            None => Some(30),
+        }
+        .ok_or_else(|| anyhow::anyhow!("The otpauth period must be a positive number of seconds"))?;
+        let secret = base32_decode(otp.secret()).ok_or_else(|| anyhow::anyhow!("The otpauth secret isn't valid base32"))?;
+        Ok(Self { name: otp.describe(), secret, digits, period })
+    }
+
+    /// The code for `unix_time` seconds since the epoch
+    pub fn code_at(&self, unix_time: u64) -> String {
+        let counter = (unix_time / self.period).to_be_bytes();
+        let mac = hmac_sha1(&self.secret, &counter);
+        // Dynamic truncation: 31 bits from the offset the last nibble gives
+        let offset = (mac[19] & 0x0f) as usize;
+        let value = u32::from_be_bytes([mac[offset] & 0x7f, mac[offset + 1], mac[offset + 2], mac[offset + 3]]);
+        format!("{:0width$}", value % 10u32.pow(self.digits), width = self.digits as usize)
+    }
+
+    /// Seconds `code_at(unix_time)` stays valid for
+    pub fn remaining_at(&self, unix_time: u64) -> u64 {
+        self.period - unix_time % self.period
+    }
+
+    /// The current code and the seconds until it changes
+    pub fn now(&self) -> (String, u64) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        (self.code_at(now), self.remaining_at(now))
+    }
+
+    /// "GitHub (sam@example.com): 287082, 17s left", for the console
+    pub fn summary(&self) -> String {
+        let (code, remaining) = self.now();
+        format!("{}: {}, {}s left", self.name, code, remaining)
+    }
+}
+
+impl fmt::Debug for Totp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Totp").field("name", &self.name).field("digits", &self.digits).field("period", &self.period).finish_non_exhaustive()
+    }
+}
+
+/// Decoded content for the console: a TOTP setup as its current code instead of the URI
+/// with its secret, anything else as the redactor shows it
+pub fn console_text<'a>(content: &'a str, redactor: &Redactor) -> Cow<'a, str> {
+    if redactor.is_enabled() {
+        return redactor.text(content);
+    }
+    match Totp::parse(content) {
+        Ok(totp) => Cow::Owned(format!("🔑 One-time password for {}", totp.summary())),
+        Err(_) => Cow::Borrowed(content),
+    }
+}
+
+/// RFC 4648 base32, case-insensitive, ignoring padding and spaces as authenticator apps do
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u64, 0);
+    for c in text.chars().filter(|c| *c != '=' && !c.is_whitespace()) {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    (!bytes.is_empty()).then_some(bytes)
+}
+
+fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    let mut block = [0u8; SHA1_BLOCK];
+    if key.len() > SHA1_BLOCK {
+        block[..20].copy_from_slice(&Sha1::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|key| key ^ byte);
+    let inner = Sha1::new().chain_update(pad(0x36)).chain_update(message).finalize();
+    Sha1::new().chain_update(pad(0x5c)).chain_update(inner).finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totp() {
+        // The SHA-1 test vectors of RFC 6238, secret "12345678901234567890"
+        let secret = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+        let totp = Totp::parse(&format!("otpauth://totp/Example:sam?secret={}&issuer=Example&digits=8", secret)).unwrap();
+        assert_eq!(totp.name, "Example (sam)");
+        assert_eq!(totp.code_at(59), "94287082");
+        assert_eq!(totp.code_at(1111111109), "07081804");
+        assert_eq!(totp.code_at(20000000000), "65353130");
+        assert_eq!(totp.remaining_at(59), 1);
+        assert!(!format!("{:?}", totp).contains(secret));
+
+        // Six digits by default, lower case and padded secrets are fine
+        let totp = Totp::parse("otpauth://totp/sam?secret=gezdgnbvgy3tqojqgezdgnbvgy3tqojq====").unwrap();
+        assert_eq!(totp.code_at(59), "287082");
+
+        assert!(Totp::parse("otpauth://hotp/sam?secret=JBSWY3DP&counter=1").is_err());
+        assert!(Totp::parse("otpauth://totp/sam?secret=JBSWY3DP&algorithm=SHA256").is_err());
+        assert!(Totp::parse("otpauth://totp/sam?secret=not-base32").is_err());
+
+        let redactor = Redactor::new(false);
+        let uri = format!("otpauth://totp/sam?secret={}", secret);
+        let shown = console_text(&uri, &redactor);
+        assert!(shown.starts_with("🔑 One-time password for sam: ") && !shown.contains(secret));
+        assert_eq!(console_text("hello", &redactor), "hello");
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::Url;
+
+/// Query parameters that only track where a link was clicked
+const TRACKING_PARAMETERS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "mkt_tok",
+    "_hsenc", "_hsmi",
+];
+
+/// What a transform does to the text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum TransformKind {
+    /// Remove `utm_*` and other tracking parameters from http(s) URLs
+    StripTracking,
+    Uppercase,
+    Lowercase,
+    Trim,
+    /// Regex replacement; `with` may refer to groups as `$1` or `${name}`
+    Replace { pattern: String, with: String },
+}
+
+/// One post-processing step for decoded QR text, as written in the config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransformConfig {
+    #[serde(flatten)]
+    pub kind: TransformKind,
+    /// Only apply to text matching this regex
+    #[serde(default)]
+    pub when: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    StripTracking,
+    Uppercase,
+    Lowercase,
+    Trim,
+    Replace(Regex, String),
+}
+
+/// Compiled transforms, applied in order to the text decoded from a QR code
+/// before it is shown, copied or stored
+#[derive(Debug, Clone, Default)]
+pub struct TextTransforms {
+    steps: Vec<(Step, Option<Regex>)>,
+}
+
+impl TextTransforms {
+    pub fn new(configs: &[TransformConfig]) -> Result<Self> {
+        let compile = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| anyhow::anyhow!("Invalid transform pattern '{}': {}", pattern, e))
+        };
+        let steps = configs
+            .iter()
+            .map(|config| {
+                let step = match &config.kind {
+                    TransformKind::StripTracking => Step::StripTracking,
+                    TransformKind::Uppercase => Step::Uppercase,
+                    TransformKind::Lowercase => Step::Lowercase,
+                    TransformKind::Trim => Step::Trim,
+                    TransformKind::Replace { pattern, with } => Step::Replace(compile(pattern)?, with.clone()),
+                };
+                let when = config.when.as_deref().map(compile).transpose()?;
+                Ok((step, when))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { steps })
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (step, when) in &self.steps {
+            if when.as_ref().is_some_and(|when| !when.is_match(&text)) {
+                continue;
+            }
+            text = match step {
+                Step::StripTracking => strip_tracking(&text).unwrap_or(text),
+                Step::Uppercase => text.to_uppercase(),
+                Step::Lowercase => text.to_lowercase(),
+                Step::Trim => text.trim().to_string(),
+                Step::Replace(pattern, with) => pattern.replace_all(&text, with.as_str()).into_owned(),
+            };
+        }
+        text
+    }
+}
+
+/// The URL without tracking parameters, or `None` if `text` isn't an http(s) URL that has any
+fn strip_tracking(text: &str) -> Option<String> {
+    let mut url = Url::parse(text.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    let is_tracking = |name: &str| name.starts_with("utm_") || TRACKING_PARAMETERS.contains(&name);
+    let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let kept: Vec<&(String, String)> = pairs.iter().filter(|(name, _)| !is_tracking(name)).collect();
+    if kept.len() == pairs.len() {
+        return None;
+    }
+
+    debug!("Removed {} tracking parameter(s)", pairs.len() - kept.len());
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    Some(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transforms(toml: &str) -> TextTransforms {
+        #[derive(Deserialize)]
+        struct Config {
+            transforms: Vec<TransformConfig>,
+        }
+        TextTransforms::new(&toml::from_str::<Config>(toml).unwrap().transforms).unwrap()
+    }
+
+    #[test]
+    fn test_strip_tracking() {
+        let transforms = transforms("[[transforms]]\ntype = \"strip-tracking\"");
+        assert_eq!(
+            transforms.apply("https://example.com/a?id=7&utm_source=qr&fbclid=x#top"),
+            "https://example.com/a?id=7#top"
+        );
+        assert_eq!(transforms.apply("https://example.com/?utm_medium=print"), "https://example.com/");
+        assert_eq!(transforms.apply("https://example.com/?q=1"), "https://example.com/?q=1");
+        assert_eq!(transforms.apply("WIFI:S:utm_home;;"), "WIFI:S:utm_home;;");
+    }
+
+    #[test]
+    fn test_conditional_and_replace() {
+        let transforms = transforms(
+            r#"
+            [[transforms]]
+            type = "uppercase"
+            when = "^sn-"
+
+            [[transforms]]
+            type = "replace"
+            pattern = "^http://(.+)$"
+            with = "https://$1"
+            "#,
+        );
+        assert_eq!(transforms.apply("sn-00af"), "SN-00AF");
+        assert_eq!(transforms.apply("not a serial"), "not a serial");
+        assert_eq!(transforms.apply("http://example.com"), "https://example.com");
+
+        let invalid = [TransformConfig {
+            kind: TransformKind::Replace { pattern: "(".to_string(), with: String::new() },
+            when: None,
+        }];
+        assert!(TextTransforms::new(&invalid).is_err());
+    }
+}
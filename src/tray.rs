@@ -3,25 +3,130 @@ use std::env;
 use std::sync::{Arc, Mutex};
 use tracing::{info, warn};
 use tray_icon::{
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{CheckMenuItem, Icon as MenuIcon, IconMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     Icon, TrayIcon, TrayIconBuilder,
 };
 
+use crate::clipboard_handler::{ClipboardData, ClipboardFormats};
+use crate::clipboard_history::ClipboardHistoryEntry;
+use crate::content_classifier::{ContentClass, SmartAction};
+use crate::event_bus::{AppEvent, EventBus, PauseSource};
+use crate::file_scan;
 use crate::global_state::GlobalClipboardState;
+use crate::history::HistoryEntry;
+use crate::notification::NotificationPolicy;
+use crate::notifier::{Notification, Notifier};
+use crate::payload::PayloadType;
+use crate::locale;
+use crate::qr_style::Color;
+use crate::redaction::Redactor;
+use crate::region::{CapturedRegion, Region};
+use crate::snippets::Snippet;
+use crate::storage;
+use crate::thumbnails::ThumbnailCache;
+
+/// The app icon, a QR code motif; the tray draws its state badges over it
+const APP_ICON_PNG: &[u8] = include_bytes!("../assets/tray-icon.png");
+
+/// Badge colour for a decoded code whose type has none of its own
+const DETECTED_COLOUR: Color = Color::rgb(0, 150, 136);
+const ERROR_COLOUR: Color = Color::rgb(207, 34, 46);
+const PAUSED_COLOUR: Color = Color::rgb(110, 118, 129);
+
+/// New thumbnails drawn per History submenu refresh; the rest follow on later refreshes
+const THUMBNAILS_PER_REFRESH: usize = 4;
+
+/// What a screen capture menu item asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionRequest {
+    /// Capture the whole screen
+    Screen,
+    /// Let the user select a new region
+    Select,
+    /// Capture this region again
+    Capture(Region),
+}
 
 pub struct SystemTray {
     tray_icon: TrayIcon,
     clipboard_state: Arc<Mutex<GlobalClipboardState>>,
+    redactor: Redactor,
+    /// Tooltip prefix; marks guest and in-memory mode
+    title: String,
+    /// What the icon currently shows
+    icon_state: IconState,
+    /// The clipboard content the tooltip describes, as last published on the [`EventBus`]
+    last_data: Option<ClipboardData>,
+    /// Type of the last text copied or decoded from an image
+    last_payload: Option<PayloadType>,
+    /// The last text copied or decoded from an image
+    last_content: Option<String>,
+    /// The last content was decoded from a QR code rather than copied as text
+    detected: bool,
+    /// The sensitive-content rule the last content matched, see [`MonitorEvent::Sensitive`](crate::monitor::MonitorEvent::Sensitive)
+    sensitive_rule: Option<String>,
+    /// The last error, until something new is copied
+    last_error: Option<String>,
+    /// Monitoring is paused from the tray, see [`MonitorHandle::set_paused`](crate::monitor::MonitorHandle::set_paused)
+    paused: bool,
+    /// Monitoring is paused by the `[snooze]` schedule
+    snoozed: bool,
+    monitor_text_item: CheckMenuItem,
+    monitor_image_item: CheckMenuItem,
+    pause_item: CheckMenuItem,
+    /// Checked while the app is set to start at login, see [`autostart`](crate::autostart)
+    autostart_item: CheckMenuItem,
+    notification_items: Vec<(NotificationPolicy, CheckMenuItem)>,
+    desktop_notifications_item: CheckMenuItem,
+    /// Enabled while the last content is a one-time password setup and a password manager is configured
+    import_otp_item: MenuItem,
+    otp_import: bool,
+    /// Filled in with the smart actions for the last content, see [`ContentClass::actions`]
+    actions_menu: Submenu,
+    action_items: Vec<(MenuItem, SmartAction)>,
+    /// Menu item id of each snippet, by index into the config's snippets
+    snippet_ids: Vec<String>,
+    history_menu: Submenu,
+    /// Items in the History submenu with the content each one copies
+    history_items: Vec<(IconMenuItem, String)>,
+    /// Created on the first refresh, when the display scale is known
+    thumbnails: Option<ThumbnailCache>,
+    clipboard_history_menu: Submenu,
+    /// Items in the Clipboard History submenu with the id of the entry each one regenerates
+    clipboard_history_items: Vec<(MenuItem, u64)>,
+    /// Enabled once a region has been captured
+    rescan_region_item: MenuItem,
+    regions_menu: Submenu,
+    /// Items in the Recent Regions submenu with the region each one captures, newest first
+    region_items: Vec<(IconMenuItem, Region)>,
     pub quit_id: String,
     pub status_id: String,
+    /// Also what a left click on the icon does, where the platform reports clicks
+    pub show_qr_id: String,
     pub about_id: String,
+    pub import_otp_id: String,
+    pub scan_screen_id: String,
+    pub settings_id: String,
+    pub wifi_template_id: String,
+    pub scan_region_id: String,
+    pub rescan_region_id: String,
+    pub monitor_text_id: String,
+    pub monitor_image_id: String,
+    pub pause_id: String,
+    pub autostart_id: String,
 }
 
 impl SystemTray {
     pub fn new(
-        clipboard_state: Arc<Mutex<GlobalClipboardState>>
+        clipboard_state: Arc<Mutex<GlobalClipboardState>>,
+        redactor: Redactor,
+        formats: ClipboardFormats,
+        guest: bool,
+        snippets: &[Snippet],
+        otp_import: bool,
+        autostart: bool,
     ) -> Result<Self> {
-        let tray = Self::create_tray(clipboard_state)?;
+        let tray = Self::create_tray(clipboard_state, redactor, formats, guest, snippets, otp_import, autostart)?;
         Ok(tray)
     }
 
@@ -53,32 +158,15 @@ impl SystemTray {
         }
     }
 
-    fn load_icon() -> Result<Icon> {
-        let mut icon_data = Vec::new();
-
-        // Blue background (74, 144, 226, 255)
-        for _ in 0..256 {
-            icon_data.extend_from_slice(&[74, 144, 226, 255]);
-        }
-
-        // Make center 8x8 area white
-        for y in 4..12 {
-            for x in 4..12 {
-                let index = (y * 16 + x) * 4;
-                if index + 3 < icon_data.len() {
-                    icon_data[index] = 255; // R
-                    icon_data[index + 1] = 255; // G
-                    icon_data[index + 2] = 255; // B
-                    icon_data[index + 3] = 255; // A
-                }
-            }
-        }
-
-        let icon = Icon::from_rgba(icon_data, 16, 16)?;
-        Ok(icon)
-    }
-
-    fn create_tray(clipboard_state: Arc<Mutex<GlobalClipboardState>>) -> Result<Self> {
+    fn create_tray(
+        clipboard_state: Arc<Mutex<GlobalClipboardState>>,
+        redactor: Redactor,
+        formats: ClipboardFormats,
+        guest: bool,
+        snippets: &[Snippet],
+        otp_import: bool,
+        autostart: bool,
+    ) -> Result<Self> {
         #[cfg(unix)]
         if Self::detect_wayland_environment() {
             info!("Running in Wayland environment");
@@ -88,92 +176,612 @@ impl SystemTray {
             std::thread::sleep(std::time::Duration::from_millis(500));
         }
 
-        // Create a simple 16x16 icon with blue background and white center
-        let icon = Self::load_icon()?;
+        // The app icon until something happens
+        let icon = IconState::default().icon()?;
 
         // Create menu with proper IDs
         let quit_item = MenuItem::new("Exit", true, None);
         let status_item = MenuItem::new("Show Status", true, None);
+        let show_qr_item = MenuItem::new("Show QR Code", true, None);
         let about_item = MenuItem::new("About ClipboardQR", true, None);
+        let monitor_text_item = CheckMenuItem::new("Monitor Text", true, formats.text, None);
+        let monitor_image_item = CheckMenuItem::new("Monitor Images", true, formats.image, None);
+        let pause_item = CheckMenuItem::new("Pause Monitoring", true, false, None);
+        let (policy, desktop_notifications) = clipboard_state
+            .lock()
+            .map(|state| (state.notification_policy, state.desktop_notifications))
+            .unwrap_or((NotificationPolicy::default(), true));
+        let notification_items: Vec<(NotificationPolicy, CheckMenuItem)> = NotificationPolicy::ALL
+            .into_iter()
+            .map(|item_policy| (item_policy, CheckMenuItem::new(item_policy.label(), true, item_policy == policy, None)))
+            .collect();
+        let notifications_menu = Submenu::new("Notifications", true);
+        for (_, item) in &notification_items {
+            notifications_menu.append(item)?;
+        }
+        let desktop_notifications_item = CheckMenuItem::new("Desktop Notifications", true, desktop_notifications, None);
+        notifications_menu.append(&PredefinedMenuItem::separator())?;
+        notifications_menu.append(&desktop_notifications_item)?;
+
+        let snippets_menu = Submenu::new("Snippets", !snippets.is_empty());
+        let mut snippet_ids = Vec::with_capacity(snippets.len());
+        for snippet in snippets {
+            let item = MenuItem::new(&snippet.name, true, None);
+            snippet_ids.push(item.id().0.clone());
+            snippets_menu.append(&item)?;
+        }
+
+        let import_otp_item = MenuItem::new("Import OTP to Password Manager...", false, None);
+        // Filled in by `show_actions` when something is copied
+        let actions_menu = Submenu::new("Actions", false);
+
+        // Filled in by `show_history` once the history store has been read
+        let history_menu = Submenu::new("History", false);
+        // Filled in by `show_clipboard_history` as content is copied
+        let clipboard_history_menu = Submenu::new("Clipboard History", false);
+
+        let scan_screen_item = MenuItem::new("Scan QR from Screen", true, None);
+        let scan_region_item = MenuItem::new("Scan Screen Region...", true, None);
+        // Both filled in by `show_regions`
+        let rescan_region_item = MenuItem::new("Re-scan Last Region", false, None);
+        let regions_menu = Submenu::new("Recent Regions", false);
+
+        let wifi_template_item = MenuItem::new("Wi-Fi QR Code...", true, None);
+
+        let in_memory = storage::in_memory_mode();
+        // Neither mode writes the config file
+        let settings_item = MenuItem::new("Settings...", !guest && !in_memory, None);
+        let autostart_item = CheckMenuItem::new("Start at Login", !guest && !in_memory, autostart, None);
+        let title = match (guest, in_memory) {
+            (false, false) => "Clipboard QR".to_string(),
+            (true, false) => "Clipboard QR (Guest)".to_string(),
+            (false, true) => "Clipboard QR (In-memory)".to_string(),
+            (true, true) => "Clipboard QR (Guest, In-memory)".to_string(),
+        };
 
         let tray_menu = Menu::new();
+        if guest {
+            tray_menu.append(&MenuItem::new("Guest Mode: nothing is saved or sent", false, None))?;
+        }
+        if in_memory {
+            tray_menu.append(&MenuItem::new("In-memory Mode: nothing is written to disk", false, None))?;
+        }
         tray_menu.append(&about_item)?;
         tray_menu.append(&PredefinedMenuItem::separator())?;
+        tray_menu.append(&show_qr_item)?;
         tray_menu.append(&status_item)?;
+        tray_menu.append(&monitor_text_item)?;
+        tray_menu.append(&monitor_image_item)?;
+        tray_menu.append(&pause_item)?;
+        tray_menu.append(&actions_menu)?;
+        tray_menu.append(&snippets_menu)?;
+        tray_menu.append(&wifi_template_item)?;
+        tray_menu.append(&history_menu)?;
+        tray_menu.append(&clipboard_history_menu)?;
+        tray_menu.append(&scan_screen_item)?;
+        tray_menu.append(&scan_region_item)?;
+        tray_menu.append(&rescan_region_item)?;
+        tray_menu.append(&regions_menu)?;
+        if otp_import {
+            tray_menu.append(&import_otp_item)?;
+        }
+        tray_menu.append(&notifications_menu)?;
+        tray_menu.append(&settings_item)?;
+        tray_menu.append(&autostart_item)?;
         tray_menu.append(&PredefinedMenuItem::separator())?;
         tray_menu.append(&quit_item)?;
 
         // Create tray icon
-        let tray_icon = TrayIconBuilder::new()
+        let mut builder = TrayIconBuilder::new()
             .with_menu(Box::new(tray_menu))
-            .with_tooltip("Clipboard QR")
+            .with_tooltip(&title)
             .with_icon(icon)
-            .build()?;
+            // Left click shows the QR popup instead; Linux trays always open the menu
+            .with_menu_on_left_click(false);
+        // On Linux the icon is handed to the tray as a file
+        if in_memory {
+            match storage::memory_backed_dir() {
+                Some(dir) => builder = builder.with_temp_dir_path(dir.join("clipboard-qr-tray")),
+                None if cfg!(target_os = "linux") => {
+                    return Err(anyhow::anyhow!("In-memory mode: no RAM-backed directory for the tray icon; use `watch` instead"));
+                }
+                None => {}
+            }
+        }
+        let tray_icon = builder.build()?;
 
         info!("Tray icon created successfully");
         Ok(Self {
             tray_icon,
             clipboard_state,
+            redactor,
+            title,
+            icon_state: IconState::default(),
+            last_data: None,
+            last_payload: None,
+            last_content: None,
+            detected: false,
+            sensitive_rule: None,
+            last_error: None,
+            paused: false,
+            snoozed: false,
             quit_id: quit_item.id().0.clone(),
             status_id: status_item.id().0.clone(),
+            show_qr_id: show_qr_item.id().0.clone(),
             about_id: about_item.id().0.clone(),
+            import_otp_id: import_otp_item.id().0.clone(),
+            scan_screen_id: scan_screen_item.id().0.clone(),
+            settings_id: settings_item.id().0.clone(),
+            wifi_template_id: wifi_template_item.id().0.clone(),
+            scan_region_id: scan_region_item.id().0.clone(),
+            rescan_region_id: rescan_region_item.id().0.clone(),
+            monitor_text_id: monitor_text_item.id().0.clone(),
+            monitor_image_id: monitor_image_item.id().0.clone(),
+            pause_id: pause_item.id().0.clone(),
+            autostart_id: autostart_item.id().0.clone(),
+            monitor_text_item,
+            monitor_image_item,
+            pause_item,
+            autostart_item,
+            notification_items,
+            desktop_notifications_item,
+            import_otp_item,
+            actions_menu,
+            action_items: Vec::new(),
+            otp_import,
+            snippet_ids,
+            history_menu,
+            history_items: Vec::new(),
+            thumbnails: None,
+            clipboard_history_menu,
+            clipboard_history_items: Vec::new(),
+            rescan_region_item,
+            regions_menu,
+            region_items: Vec::new(),
         })
     }
 
-    pub fn update_icon(&mut self) -> Result<()> {
-        // Update tray icon based on clipboard state
+    /// The snippet a Snippets submenu item stands for
+    pub fn snippet_index(&self, id: &str) -> Option<usize> {
+        self.snippet_ids.iter().position(|snippet_id| snippet_id == id)
+    }
+
+    /// The last clipboard content if it is a one-time password setup that can be imported
+    pub fn otp_to_import(&self) -> Option<String> {
+        (self.otp_import && self.last_payload == Some(PayloadType::Otp)).then(|| self.last_content.clone()).flatten()
+    }
+
+    /// Rebuild the Actions submenu for `content`
+    fn show_actions(&mut self, content: Option<&str>) -> Result<()> {
+        for (item, _) in self.action_items.drain(..) {
+            self.actions_menu.remove(&item)?;
+        }
+        let actions = content.map(|content| ContentClass::classify(content).actions()).unwrap_or_default();
+        for action in actions {
+            let item = MenuItem::new(action.label(), true, None);
+            self.actions_menu.append(&item)?;
+            self.action_items.push((item, action));
+        }
+        self.actions_menu.set_enabled(!self.action_items.is_empty());
+        Ok(())
+    }
+
+    /// The smart action an Actions submenu item stands for
+    pub fn action(&self, id: &str) -> Option<&SmartAction> {
+        self.action_items.iter().find(|(item, _)| item.id().0 == id).map(|(_, action)| action)
+    }
+
+    /// Rebuild the History submenu from `entries`, newest first, with QR thumbnails as item
+    /// icons where the platform shows them; `scale` is the display scale factor
+    pub fn show_history(&mut self, entries: &[HistoryEntry], scale: f64) -> Result<()> {
+        for (item, _) in self.history_items.drain(..) {
+            self.history_menu.remove(&item)?;
+        }
+
+        let wanted = ThumbnailCache::for_scale(scale).size();
+        let thumbnails = match &mut self.thumbnails {
+            Some(cache) if cache.size() == wanted => cache,
+            cache => cache.insert(ThumbnailCache::new(wanted)),
+        };
+        let mut budget = THUMBNAILS_PER_REFRESH;
+        for entry in entries {
+            // A thumbnail would show what redaction hides
+            let icon = if self.redactor.is_enabled() {
+                None
+            } else {
+                thumbnails
+                    .get(&entry.content, &mut budget)
+                    .and_then(|image| MenuIcon::from_rgba(image.as_raw().clone(), image.width(), image.height()).ok())
+            };
+            // A lone `&` would mark a mnemonic
+            let label = self.redactor.text_truncated(&entry.content, 40).replace('&', "&&");
+            let item = IconMenuItem::new(label, true, icon, None);
+            self.history_menu.append(&item)?;
+            self.history_items.push((item, entry.content.clone()));
+        }
+        self.history_menu.set_enabled(!self.history_items.is_empty());
+        Ok(())
+    }
+
+    /// The content a History submenu item copies
+    pub fn history_content(&self, id: &str) -> Option<&str> {
+        self.history_items
+            .iter()
+            .find(|(item, _)| item.id().0 == id)
+            .map(|(_, content)| content.as_str())
+    }
+
+    /// Rebuild the Clipboard History submenu from the shared state's clipboard history
+    pub fn show_clipboard_history(&mut self) -> Result<()> {
+        let entries: Vec<ClipboardHistoryEntry> = match self.clipboard_state.lock() {
+            Ok(state) => state.clipboard_history.entries().cloned().collect(),
+            Err(_) => return Ok(()),
+        };
+        for (item, _) in self.clipboard_history_items.drain(..) {
+            self.clipboard_history_menu.remove(&item)?;
+        }
+        for entry in &entries {
+            let label = format!("{}  {}", locale::current().time(&entry.copied_at), entry.label(&self.redactor, 40));
+            // Images without a QR code are listed, but there is nothing to regenerate
+            let item = MenuItem::new(label.replace('&', "&&"), entry.qr_content().is_some(), None);
+            self.clipboard_history_menu.append(&item)?;
+            self.clipboard_history_items.push((item, entry.id));
+        }
+        self.clipboard_history_menu.set_enabled(!self.clipboard_history_items.is_empty());
+        Ok(())
+    }
+
+    /// The text a Clipboard History submenu item regenerates a QR code for
+    pub fn clipboard_history_content(&self, id: &str) -> Option<String> {
+        let (_, entry_id) = self.clipboard_history_items.iter().find(|(item, _)| item.id().0 == id)?;
+        let state = self.clipboard_state.lock().ok()?;
+        state.clipboard_history.get(*entry_id)?.qr_content().map(str::to_string)
+    }
+
+    /// Rebuild the Recent Regions submenu from `regions`, newest first, each with a thumbnail
+    /// of what it showed when last captured; `scale` is the display scale factor
+    pub fn show_regions(&mut self, regions: &[CapturedRegion], scale: f64) -> Result<()> {
+        for (item, _) in self.region_items.drain(..) {
+            self.regions_menu.remove(&item)?;
+        }
+
+        let size = ThumbnailCache::for_scale(scale).size();
+        for captured in regions {
+            // The thumbnail is a screenshot, which redaction would rather hide
+            let icon = if self.redactor.is_enabled() {
+                None
+            } else {
+                captured.thumbnail().and_then(|thumbnail| {
+                    let longest = thumbnail.width().max(thumbnail.height());
+                    let scaled = |side: u32| (side * size / longest).max(1);
+                    let icon = image::imageops::thumbnail(&thumbnail, scaled(thumbnail.width()), scaled(thumbnail.height()));
+                    MenuIcon::from_rgba(icon.as_raw().clone(), icon.width(), icon.height()).ok()
+                })
+            };
+            let region = captured.region;
+            let label = format!(
+                "{}x{} at {},{} ({})",
+                region.width,
+                region.height,
+                region.x,
+                region.y,
+                locale::current().time(&captured.captured_at)
+            );
+            let item = IconMenuItem::new(label, true, icon, None);
+            self.regions_menu.append(&item)?;
+            self.region_items.push((item, region));
+        }
+        self.regions_menu.set_enabled(!self.region_items.is_empty());
+        self.rescan_region_item.set_enabled(!self.region_items.is_empty());
+        Ok(())
+    }
+
+    /// What a screen capture menu item asks for; `None` if `id` isn't one of them
+    pub fn region_request(&self, id: &str) -> Option<RegionRequest> {
+        if id == self.scan_screen_id {
+            return Some(RegionRequest::Screen);
+        }
+        if id == self.scan_region_id {
+            return Some(RegionRequest::Select);
+        }
+        if id == self.rescan_region_id {
+            return self.region_items.first().map(|(_, region)| RegionRequest::Capture(*region));
+        }
+        self.region_items
+            .iter()
+            .find(|(item, _)| item.id().0 == id)
+            .map(|(_, region)| RegionRequest::Capture(*region))
+    }
+
+    /// Apply a Notifications submenu selection; returns false if `id` isn't one of its items
+    pub fn select_notification_policy(&self, id: &str) -> bool {
+        let Some(&(policy, _)) = self.notification_items.iter().find(|(_, item)| item.id().0 == id) else {
+            return false;
+        };
+        // Behave like radio items
+        for (item_policy, item) in &self.notification_items {
+            item.set_checked(*item_policy == policy);
+        }
         if let Ok(mut state) = self.clipboard_state.lock() {
-            if state.has_changed {
-                // Update tooltip to show change
-                let tooltip = if let Some(data) = &state.last_data {
-                    match data {
-                        crate::clipboard_handler::ClipboardData::Text(text) => {
-                            format!(
-                                "Clipboard QR - Text: {}",
-                                if text.len() > 30 {
-                                    format!("{}...", &text[..30])
-                                } else {
-                                    text.clone()
-                                }
-                            )
-                        }
-                        crate::clipboard_handler::ClipboardData::Image(image) => {
-                            format!("Clipboard QR - Image: {}x{}", image.width(), image.height())
-                        }
-                        crate::clipboard_handler::ClipboardData::Empty => {
-                            "Clipboard QR - Empty".to_string()
-                        }
-                    }
-                } else {
-                    "Clipboard QR - Monitoring...".to_string()
+            state.notification_policy = policy;
+        }
+        info!("Notification level set to {}", policy);
+        true
+    }
+
+    /// Apply the Desktop Notifications item; returns false if `id` isn't it
+    pub fn toggle_desktop_notifications(&self, id: &str) -> bool {
+        if self.desktop_notifications_item.id().0 != id {
+            return false;
+        }
+        let enabled = self.desktop_notifications_item.is_checked();
+        if let Ok(mut state) = self.clipboard_state.lock() {
+            state.desktop_notifications = enabled;
+        }
+        info!("Desktop notifications {}", if enabled { "on" } else { "off" });
+        true
+    }
+
+    /// Whether the Pause Monitoring item now asks for monitoring to be paused; the tooltip
+    /// follows once the change is published
+    pub fn toggle_pause(&self) -> bool {
+        let paused = self.pause_item.is_checked();
+        info!("Clipboard monitoring {} from the tray", if paused { "paused" } else { "resumed" });
+        paused
+    }
+
+    /// Whether the Start at Login item now asks for the app to start at login
+    pub fn toggle_autostart(&self) -> bool {
+        self.autostart_item.is_checked()
+    }
+
+    /// Put the Start at Login item back when the login entry couldn't be changed
+    pub fn set_autostart(&self, enabled: bool) {
+        self.autostart_item.set_checked(enabled);
+    }
+
+    /// Formats selected by the monitoring check items; at least one always stays checked
+    pub fn monitored_formats(&self, toggled_id: &str) -> ClipboardFormats {
+        let text = self.monitor_text_item.is_checked();
+        let image = self.monitor_image_item.is_checked();
+        if !text && !image {
+            // Turning off the last format would stop monitoring entirely
+            let text = toggled_id == self.monitor_text_id;
+            self.monitor_text_item.set_checked(text);
+            self.monitor_image_item.set_checked(!text);
+            return ClipboardFormats::content(text, !text);
+        }
+        ClipboardFormats::content(text, image)
+    }
+
+    /// Follow an event from the [`EventBus`]; the tooltip changes with pauses and with
+    /// [`AppEvent::Notified`], so only what the notification level lets through shows up
+    pub fn handle_event(&mut self, event: &AppEvent) -> Result<()> {
+        match event {
+            AppEvent::ClipboardChanged { data, sensitive_rule } => {
+                let text = match data {
+                    ClipboardData::Text(text) => Some(text.clone()),
+                    _ => None,
                 };
+                self.last_payload = text.as_deref().map(PayloadType::detect);
+                self.last_content = text;
+                self.detected = false;
+                self.sensitive_rule = sensitive_rule.clone();
+                self.last_data = Some(data.clone());
+                self.last_error = None;
+                Ok(())
+            }
+            AppEvent::QrDecoded(content) => {
+                self.last_payload = Some(PayloadType::detect(content));
+                self.last_content = Some(content.clone());
+                self.detected = true;
+                Ok(())
+            }
+            AppEvent::Error(e) => {
+                self.last_error = Some(e.clone());
+                Ok(())
+            }
+            AppEvent::MonitoringPaused { paused, source: PauseSource::User } => {
+                self.paused = *paused;
+                self.pause_item.set_checked(*paused);
+                self.refresh()
+            }
+            AppEvent::MonitoringPaused { paused, source: PauseSource::Schedule } => {
+                self.snoozed = *paused;
+                self.refresh()
+            }
+            AppEvent::Notified => self.refresh(),
+        }
+    }
 
-                if let Err(e) = self.tray_icon.set_tooltip(Some(tooltip)) {
-                    warn!("Failed to update tray tooltip: {}", e);
+    /// Rebuild the tooltip, icon and Actions submenu from the last published content
+    fn refresh(&mut self) -> Result<()> {
+        let payload = self.last_payload;
+        let tooltip = if self.paused {
+            format!("{} - ⏸ Paused", self.title)
+        } else if self.snoozed {
+            format!("{} - 💤 Snoozed by schedule", self.title)
+        } else if let Some(e) = &self.last_error {
+            format!("{} - ❌ {}", self.title, self.redactor.text_truncated(e, 40))
+        } else if let Some(data) = &self.last_data {
+            match (data, payload) {
+                (ClipboardData::Text(text), _) => {
+                    let kind = payload.unwrap_or(PayloadType::Text);
+                    let summary = kind.summary(text);
+                    format!(
+                        "{} - {} {}: {}",
+                        self.title,
+                        kind.glyph(),
+                        kind.label(),
+                        self.redactor.text_truncated(summary.as_deref().unwrap_or(text), 30)
+                    )
                 }
+                (ClipboardData::Image(image), Some(kind)) => {
+                    format!(
+                        "{} - {} {} QR in image {}x{}",
+                        self.title,
+                        kind.glyph(),
+                        kind.label(),
+                        image.width(),
+                        image.height()
+                    )
+                }
+                (ClipboardData::Image(image), None) => {
+                    format!("{} - Image: {}x{}", self.title, image.width(), image.height())
+                }
+                (ClipboardData::Files(files), _) => {
+                    format!("{} - 📁 {}", self.title, self.redactor.text_truncated(&file_scan::describe_files(files), 30))
+                }
+                (ClipboardData::Empty, _) => {
+                    format!("{} - Empty", self.title)
+                }
+                (ClipboardData::Excluded, _) => match &self.sensitive_rule {
+                    Some(rule) => format!("{} - 🔒 Sensitive content skipped ({})", self.title, rule),
+                    None => format!("{} - Private content (ignored)", self.title),
+                },
+            }
+        } else {
+            format!("{} - Monitoring...", self.title)
+        };
+
+        if let Err(e) = self.tray_icon.set_tooltip(Some(tooltip)) {
+            warn!("Failed to update tray tooltip: {}", e);
+        }
+
+        self.import_otp_item.set_enabled(self.otp_import && payload == Some(PayloadType::Otp));
 
-                state.has_changed = false;
+        self.update_icon();
 
-                info!("Clipboard state updated, tray icon tooltip updated");
+        info!("Tray icon tooltip updated");
+        let content = self.last_content.clone();
+        self.show_actions(content.as_deref())
+    }
+
+    /// Switch the icon to the state the last events left the tray in
+    fn update_icon(&mut self) {
+        let state = if self.paused || self.snoozed {
+            IconState::Paused
+        } else if self.last_error.is_some() {
+            IconState::Error
+        } else {
+            match self.last_payload {
+                Some(kind) if self.detected => IconState::Detected(kind),
+                payload => IconState::Normal(payload),
             }
+        };
+        if state == self.icon_state {
+            return;
+        }
+        match state.icon() {
+            Ok(icon) => {
+                if let Err(e) = self.tray_icon.set_icon(Some(icon)) {
+                    warn!("Failed to update the tray icon: {}", e);
+                }
+                self.icon_state = state;
+            }
+            Err(e) => warn!("Failed to draw the tray icon: {}", e),
+        }
+    }
+}
+
+/// What the tray icon shows, as a badge in its bottom-right corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IconState {
+    /// Monitoring; text copied with a type that has a badge colour shows that badge
+    Normal(Option<PayloadType>),
+    /// A QR code was decoded from a copied image, badged with its type
+    Detected(PayloadType),
+    /// The last clipboard change failed
+    Error,
+    /// Monitoring is paused from the tray or by the schedule
+    Paused,
+}
+
+impl Default for IconState {
+    fn default() -> Self {
+        IconState::Normal(None)
+    }
+}
+
+impl IconState {
+    /// Colour and 5x5 pictogram of the badge, if the state has one
+    fn badge(self) -> Option<(Color, [&'static str; 5])> {
+        match self {
+            IconState::Normal(kind) => kind.and_then(|kind| Some((kind.badge_colour()?, badge_glyph(kind)))),
+            IconState::Detected(kind) => Some((kind.badge_colour().unwrap_or(DETECTED_COLOUR), badge_glyph(kind))),
+            // An exclamation mark
+            IconState::Error => Some((ERROR_COLOUR, ["..#..", "..#..", "..#..", ".....", "..#.."])),
+            // Two bars
+            IconState::Paused => Some((PAUSED_COLOUR, [".#.#.", ".#.#.", ".#.#.", ".#.#.", ".#.#."])),
         }
-        Ok(())
     }
 
-    pub fn show_notification(&self, title: &str, message: &str) -> Result<()> {
-        // Update tooltip to show notification
-        if let Err(e) = self
-            .tray_icon
-            .set_tooltip(Some(format!("{}: {}", title, message)))
-        {
-            warn!("Failed to set notification tooltip: {}", e);
+    /// The app icon for this state; faded while paused, with a 7x7 cell badge holding a
+    /// white pictogram
+    fn icon(self) -> Result<Icon> {
+        let mut icon = image::load_from_memory(APP_ICON_PNG)?.to_rgba8();
+        if self == IconState::Paused {
+            for pixel in icon.pixels_mut() {
+                let grey = ((pixel[0] as u32 * 3 + pixel[1] as u32 * 6 + pixel[2] as u32) / 10) as u8;
+                *pixel = image::Rgba([grey, grey, grey, pixel[3] / 2]);
+            }
         }
-        info!("Notification: {} - {}", title, message);
-        println!("🔔 {}: {}", title, message);
+        if let Some((colour, glyph)) = self.badge() {
+            let cell = (icon.width() / 16).max(1);
+            let origin = icon.width() - 7 * cell;
+            for y in 0..7 * cell {
+                for x in 0..7 * cell {
+                    let (column, row) = ((x / cell) as usize, (y / cell) as usize);
+                    let lit = (1..6).contains(&column) && (1..6).contains(&row) && glyph[row - 1].as_bytes()[column - 1] == b'#';
+                    let pixel = if lit { [255, 255, 255, 255] } else { [colour.r, colour.g, colour.b, 255] };
+                    icon.put_pixel(origin + x, origin + y, image::Rgba(pixel));
+                }
+            }
+        }
+        Ok(Icon::from_rgba(icon.as_raw().clone(), icon.width(), icon.height())?)
+    }
+}
+
+/// Notifications through the tray tooltip, which shows the last clipboard content
+pub struct TooltipNotifier {
+    events: EventBus,
+}
+
+impl TooltipNotifier {
+    pub fn new(events: EventBus) -> Self {
+        Self { events }
+    }
+}
+
+impl Notifier for TooltipNotifier {
+    fn name(&self) -> &str {
+        "tooltip"
+    }
+
+    fn notify(&self, _notification: &Notification) -> Result<()> {
+        // The tray rebuilds the tooltip from the events published before this one
+        self.events.publish(AppEvent::Notified);
         Ok(())
     }
 }
 
+/// 5x5 pictogram drawn in the icon badge
+fn badge_glyph(kind: PayloadType) -> [&'static str; 5] {
+    match kind {
+        // An arrow pointing out, as on external links
+        PayloadType::Url => ["..###", "...##", "..#.#", ".#...", "#...."],
+        // Signal arcs over a dot
+        PayloadType::Wifi => ["#####", ".....", ".###.", ".....", "..#.."],
+        // Head and shoulders
+        PayloadType::Contact => [".###.", ".###.", ".....", "#####", "#####"],
+        // Key bow and bit
+        PayloadType::Otp => ["###..", "#.#..", "#####", "...#.", "...##"],
+        PayloadType::Text => [".....", "#####", ".....", "###..", "....."],
+        // A tag, for the types added through the config file or plugins
+        PayloadType::Custom(_) => ["###..", "#.##.", "##.##", ".##.#", "..###"],
+    }
+}
+
 impl Drop for SystemTray {
     fn drop(&mut self) {
         info!("System tray dropped");
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use eframe::egui;
+use image::RgbaImage;
+use tracing::{error, info};
+
+use crate::preprocess::{PreprocessSettings, PresetStore};
+use crate::qr_scanner::{self, QRScanner, ScanSettings};
+use crate::redaction::Redactor;
+
+const DEFAULT_THRESHOLD: u8 = 128;
+
+/// Open a window for tuning preprocessing on an image that doesn't decode, previewing
+/// the adjusted image and re-running the decoder as the sliders move
+pub fn run(image: RgbaImage, scan: ScanSettings, redactor: Redactor) -> Result<()> {
+    info!("Starting preprocessing tuner for a {}x{} image", image.width(), image.height());
+
+    let store = match PresetStore::default_path() {
+        Some(path) => Some(PresetStore::open(&path)?),
+        None => None,
+    };
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("Clipboard QR - Tune Scanning")
+            .with_inner_size([960.0, 640.0]),
+        ..Default::default()
+    };
+
+    eframe::run_native(
+        "Clipboard QR Tuner",
+        options,
+        Box::new(move |_cc| Ok(Box::new(TuneApp::new(image, scan, store, redactor)))),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to start tuning window: {}", e))
+}
+
+struct TuneApp {
+    /// The image after compositing over the matte, as the scanner sees it
+    original: RgbaImage,
+    scanner: QRScanner,
+    redactor: Redactor,
+    store: Option<PresetStore>,
+    settings: PreprocessSettings,
+    /// Slider value kept while binarization is switched off
+    threshold: u8,
+    /// Settings the preview and result were computed with
+    applied: Option<PreprocessSettings>,
+    preview: Option<egui::TextureHandle>,
+    decoded: Option<String>,
+    preset_name: String,
+    status: String,
+}
+
+impl TuneApp {
+    fn new(image: RgbaImage, scan: ScanSettings, store: Option<PresetStore>, redactor: Redactor) -> Self {
+        let original = qr_scanner::composite_over(&image, scan.matte).into_owned();
+        Self {
+            original,
+            scanner: QRScanner::new(),
+            redactor,
+            store,
+            settings: PreprocessSettings::default(),
+            threshold: DEFAULT_THRESHOLD,
+            applied: None,
+            preview: None,
+            decoded: None,
+            preset_name: String::new(),
+            status: String::new(),
+        }
+    }
+
+    /// Re-run preprocessing and decoding when a setting changed since the last frame
+    fn refresh(&mut self, ctx: &egui::Context) {
+        if self.applied == Some(self.settings) {
+            return;
+        }
+        let adjusted = self.settings.apply(&self.original);
+        self.decoded = self.scanner.decode(&adjusted);
+
+        let size = [adjusted.width() as usize, adjusted.height() as usize];
+        let image = egui::ColorImage::from_rgba_unmultiplied(size, adjusted.as_raw());
+        self.preview = Some(ctx.load_texture("tune-preview", image, egui::TextureOptions::NEAREST));
+        self.applied = Some(self.settings);
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Preprocessing");
+        ui.add(egui::Slider::new(&mut self.settings.scale, 0.25..=4.0).text("Scale"));
+        ui.add(egui::Slider::new(&mut self.settings.contrast, 0.5..=4.0).text("Contrast"));
+
+        let mut binarize = self.settings.threshold.is_some();
+        ui.checkbox(&mut binarize, "Binarize");
+        ui.add_enabled(binarize, egui::Slider::new(&mut self.threshold, 0..=255).text("Threshold"));
+        self.settings.threshold = binarize.then_some(self.threshold);
+        ui.checkbox(&mut self.settings.invert, "Invert");
+
+        if ui.add_enabled(!self.settings.is_identity(), egui::Button::new("Reset")).clicked() {
+            self.settings = PreprocessSettings::default();
+            self.threshold = DEFAULT_THRESHOLD;
+        }
+
+        if let Some(store) = &self.store {
+            if !store.presets().is_empty() {
+                ui.separator();
+                egui::ComboBox::from_label("Start from preset")
+                    .selected_text("Choose…")
+                    .show_ui(ui, |ui| {
+                        for preset in store.presets() {
+                            if ui.selectable_label(false, &preset.name).clicked() {
+                                self.settings = preset.settings;
+                                self.threshold = preset.settings.threshold.unwrap_or(DEFAULT_THRESHOLD);
+                                self.preset_name = preset.name.clone();
+                            }
+                        }
+                    });
+            }
+        }
+
+        ui.separator();
+        match &self.decoded {
+            Some(content) => {
+                ui.label(egui::RichText::new("✅ Decoded").strong());
+                ui.label(self.redactor.text(content));
+            }
+            None => {
+                ui.label("❌ No QR code found with these settings");
+            }
+        }
+
+        ui.separator();
+        ui.label("Save as preset");
+        ui.text_edit_singleline(&mut self.preset_name);
+        let can_save = self.decoded.is_some() && self.store.is_some() && !self.preset_name.trim().is_empty();
+        let save = ui
+            .add_enabled(can_save, egui::Button::new("Save preset"))
+            .on_disabled_hover_text("Name a preset once the code decodes");
+        if save.clicked() {
+            if let Some(store) = &mut self.store {
+                self.status = match store.save(&self.preset_name, self.settings) {
+                    Ok(()) => format!("Saved '{}'; scanning will try it when an image doesn't decode", self.preset_name.trim()),
+                    Err(e) => {
+                        error!("Failed to save preset: {}", e);
+                        format!("Failed to save: {}", e)
+                    }
+                };
+            }
+        }
+        if !self.status.is_empty() {
+            ui.label(&self.status);
+        }
+    }
+}
+
+impl eframe::App for TuneApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::left("tune-controls").min_width(260.0).show(ctx, |ui| self.controls(ui));
+        self.refresh(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(preview) = &self.preview {
+                ui.add(egui::Image::new(preview).shrink_to_fit());
+            }
+        });
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::capacity::{DISTANCE_PER_MODULE, MIN_MODULE_MM};
+use crate::storage;
+
+/// Millimetres per logical point on a display at the usual 96 points per inch
+const MM_PER_POINT: f32 = 25.4 / 96.0;
+
+/// How far away the people scanning a displayed code are, which decides its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViewingDistance {
+    /// A phone held at the desk, about 40 cm from the screen
+    Desk,
+    /// Phones across a meeting room, about 4 m away
+    Room,
+    /// A projected image, whose physical size the app can't know
+    Projector,
+}
+
+impl ViewingDistance {
+    pub const ALL: [ViewingDistance; 3] = [ViewingDistance::Desk, ViewingDistance::Room, ViewingDistance::Projector];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ViewingDistance::Desk => "desk",
+            ViewingDistance::Room => "room",
+            ViewingDistance::Projector => "projector",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ViewingDistance::Desk => "Phone at desk",
+            ViewingDistance::Room => "Across the room",
+            ViewingDistance::Projector => "Projector",
+        }
+    }
+
+    /// The preset after this one, wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|preset| *preset == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Scanning distance in millimetres; `None` when the code should simply fill the screen
+    pub fn distance_mm(self) -> Option<f32> {
+        match self {
+            ViewingDistance::Desk => Some(400.0),
+            ViewingDistance::Room => Some(4000.0),
+            ViewingDistance::Projector => None,
+        }
+    }
+
+    /// Whether the code gets the whole screen rather than a window sized to fit it
+    pub fn fullscreen(self) -> bool {
+        self != ViewingDistance::Desk
+    }
+
+    /// Caption text size in points, larger for readers further away
+    pub fn caption_size(self) -> f32 {
+        match self {
+            ViewingDistance::Desk => 16.0,
+            ViewingDistance::Room => 32.0,
+            ViewingDistance::Projector => 40.0,
+        }
+    }
+
+    /// Side in points for a code `modules` wide, quiet zone included, so each module is large
+    /// enough to scan from this distance; never more than `available`
+    pub fn code_side(self, modules: u32, available: f32) -> f32 {
+        match self.distance_mm() {
+            Some(distance) => {
+                let module_mm = (distance / DISTANCE_PER_MODULE).max(MIN_MODULE_MM);
+                (modules as f32 * module_mm / MM_PER_POINT).min(available)
+            }
+            None => available,
+        }
+    }
+}
+
+impl FromStr for ViewingDistance {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|preset| preset.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = Self::ALL.iter().map(|preset| preset.name()).collect();
+            format!("unknown viewing distance '{}', expected one of: {}", s, names.join(", "))
+        })
+    }
+}
+
+impl fmt::Display for ViewingDistance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The viewing distance last picked on each display, keyed by its size such as "1920x1080",
+/// so plugging in the projector brings back the projector preset
+#[derive(Debug, Default)]
+pub struct ViewingMemory {
+    path: Option<PathBuf>,
+    choices: BTreeMap<String, ViewingDistance>,
+}
+
+impl ViewingMemory {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("clipboard-qr").join("viewing.json"))
+    }
+
+    /// The memory at the default path, or one kept in memory in in-memory mode
+    pub fn open_default() -> Self {
+        let path = if storage::in_memory_mode() { None } else { Self::default_path() };
+        Self::open(path)
+    }
+
+    pub fn open(path: Option<PathBuf>) -> Self {
+        let choices = match &path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                    warn!("Ignoring unreadable viewing distances {:?}: {}", path, e);
+                    BTreeMap::new()
+                }),
+                Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+                Err(e) => {
+                    warn!("Failed to read viewing distances {:?}: {}", path, e);
+                    BTreeMap::new()
+                }
+            },
+            None => BTreeMap::new(),
+        };
+        Self { path, choices }
+    }
+
+    pub fn get(&self, display: &str) -> Option<ViewingDistance> {
+        self.choices.get(display).copied()
+    }
+
+    pub fn remember(&mut self, display: &str, viewing: ViewingDistance) -> Result<()> {
+        if self.choices.insert(display.to_string(), viewing) == Some(viewing) {
+            return Ok(());
+        }
+        let Some(path) = &self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.choices)?)?;
+        Ok(())
+    }
+}
+
+/// The key a display's choice is remembered under
+pub fn display_key(width: f32, height: f32) -> String {
+    format!("{}x{}", width.round() as u32, height.round() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewing_presets() {
+        // Version 1 plus the quiet zone: 29 modules of 1.33 mm at the desk
+        let desk = ViewingDistance::Desk.code_side(29, 1000.0);
+        assert!((desk - 146.2).abs() < 0.5, "{}", desk);
+        // Too large to fit across the room, so it takes the available space
+        assert_eq!(ViewingDistance::Room.code_side(29, 1000.0), 1000.0);
+        assert_eq!(ViewingDistance::Projector.code_side(29, 800.0), 800.0);
+        assert_eq!(ViewingDistance::Projector.next(), ViewingDistance::Desk);
+        assert_eq!("room".parse::<ViewingDistance>(), Ok(ViewingDistance::Room));
+
+        let path = std::env::temp_dir().join(format!("clipboard-qr-viewing-{}.json", std::process::id()));
+        let mut memory = ViewingMemory::open(Some(path.clone()));
+        memory.remember(&display_key(1920.0, 1080.0), ViewingDistance::Projector).unwrap();
+        let memory = ViewingMemory::open(Some(path.clone()));
+        assert_eq!(memory.get("1920x1080"), Some(ViewingDistance::Projector));
+        assert_eq!(memory.get("1280x800"), None);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::Result;
+use chrono::Local;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::clipboard_handler::ClipboardHandler;
+use crate::control::{self, ControlServer};
+use crate::diagnostics::{Fatal, FailureKind};
+use crate::file_scan;
+use crate::kdeconnect::PhoneBridge;
+use crate::locale;
+use crate::monitor::{ClipboardMonitor, MonitorEvent, ScanOutcome};
+use crate::normalize::NormalizationProfile;
+use crate::notification::{CopyDecoded, NotificationKind, NotificationPolicy};
+use crate::notifier::{Notification, Notifiers};
+use crate::payload::PayloadType;
+use crate::privacy::SensitiveFilter;
+use crate::qr_generator::QRGenerator;
+use crate::qr_scanner::ScanSettings;
+use crate::redaction::Redactor;
+use crate::scheduler::Scheduler;
+use crate::snooze::SnoozeSchedule;
+use crate::stats::SessionStats;
+use crate::totp::Totp;
+
+const SUMMARY_CHARS: usize = 60;
+
+pub struct WatchOptions {
+    pub term: bool,
+    pub show_qr: bool,
+    pub normalization: NormalizationProfile,
+    pub scan: ScanSettings,
+    /// See [`ClipboardMonitor::give_up_after`]
+    pub give_up: Option<Duration>,
+    /// See [`ClipboardMonitor::with_snooze`]
+    pub snooze: Option<SnoozeSchedule>,
+    /// Which events go to `notifiers`, e.g. a webhook on a headless machine
+    pub level: NotificationPolicy,
+    pub notifiers: Notifiers,
+    /// Phone to send decoded content to, see [`PhoneBridge::offer`]
+    pub phone: Option<PhoneBridge>,
+    /// The bound control socket to answer on; without one the watch can't be controlled
+    pub control: Option<ControlServer>,
+    /// Accept injected clipboard events on the control socket
+    pub test_mode: bool,
+    /// For --show-qr
+    pub generator: QRGenerator,
+    /// See [`MonitorConfig::poll_interval`](crate::config::MonitorConfig::poll_interval)
+    pub poll_interval: Duration,
+    /// Text that is neither printed nor shown as a code
+    pub sensitive: SensitiveFilter,
+    /// What to do with the text of decoded QR codes
+    pub copy_decoded: CopyDecoded,
+    /// Generation tasks run for as long as the watch, as in the tray
+    pub scheduler: Scheduler,
+}
+
+/// Run the clipboard monitor in the foreground, without a tray icon, until Ctrl+C
+/// or until the clipboard stays unavailable
+pub fn run(options: WatchOptions, redactor: Redactor, stats: Arc<SessionStats>) -> Result<()> {
+    info!("Watching clipboard");
+
+    // `None` is Ctrl+C, `Some` the reason the monitor gave up
+    let (stop_tx, stop_rx) = mpsc::channel::<Option<String>>();
+    let interrupt = stop_tx.clone();
+    ctrlc::set_handler(move || {
+        let _ = interrupt.send(None);
+    })?;
+
+    let scan = options.scan;
+    let term = options.term;
+    let mut phone = options.phone;
+    let control_stats = stats.clone();
+    let monitor = ClipboardMonitor::new()
+        .with_handler(ClipboardHandler::builder().normalization(options.normalization).poll_interval(options.poll_interval))
+        .with_generator(options.generator)
+        .with_scanner(move || scan.scanner())
+        .with_sensitive_filter(options.sensitive)
+        .give_up_after(options.give_up, move |reason| {
+            let _ = stop_tx.send(Some(reason));
+        })
+        .with_snooze(options.snooze, move |snoozed| {
+            // The monitor logs the change itself
+            if term {
+                let state = if snoozed { "💤 Monitoring snoozed by schedule" } else { "Monitoring resumed" };
+                println!("{}  {}", locale::current().date_time(&Local::now()), state);
+            }
+        })
+        .start(move |event, context| {
+            stats.record(event);
+            if options.level.allows(NotificationKind::of(event)) {
+                options.notifiers.dispatch(&options.copy_decoded.apply(Notification::of(event, &redactor)));
+            }
+            if let Some(content) = options.copy_decoded.to_copy(event) {
+                match context.handler.set_text(content) {
+                    Ok(()) => info!("Copied decoded QR content to the clipboard"),
+                    Err(e) => warn!("Failed to copy decoded QR content: {}", e),
+                }
+            }
+            if let Some(phone) = phone.as_mut() {
+                phone.offer(event);
+            }
+            let (kind, summary, qr_text) = summarize(event, redactor);
+            let line = format!("{}  {:<7}  {}", locale::current().date_time(&Local::now()), kind, summary);
+
+            if !options.term {
+                info!("{}", line);
+                return;
+            }
+
+            println!("{}", line);
+            if options.show_qr {
+                if let Some(text) = qr_text {
+                    match context.generator.print_qr_terminal(text) {
+                        Ok(()) => stats.record_generated(),
+                        Err(e) => println!("❌ Failed to generate QR code: {}", e),
+                    }
+                }
+            }
+        });
+
+    let _scheduler = (!options.scheduler.is_empty()).then(|| options.scheduler.start());
+    let _control = options.control.and_then(|server| {
+        control::serve_monitor(server, options.test_mode, monitor.injector(), control_stats, None)
+            .map_err(|e| warn!("Control socket unavailable: {}", e))
+            .ok()
+    });
+
+    let failure = stop_rx.recv().ok().flatten();
+    info!("Stopping clipboard watch");
+    monitor.stop();
+    match failure {
+        Some(reason) => Err(Fatal::new(FailureKind::ClipboardUnavailable, format!("Clipboard unavailable: {}", reason)).into()),
+        None => Ok(()),
+    }
+}
+
+/// Event type, a one-line summary, and the text to render as QR (if any)
+fn summarize(event: &MonitorEvent, redactor: Redactor) -> (&'static str, String, Option<&str>) {
+    match event {
+        MonitorEvent::Text(text) => {
+            let summary = PayloadType::detect(text).summary(text);
+            ("text", one_line(summary.as_deref().unwrap_or(text), redactor), Some(text))
+        }
+        MonitorEvent::Image { image, scan } => {
+            let size = format!("{}x{}", image.width(), image.height());
+            match scan {
+                ScanOutcome::Decoded(content) => {
+                    // A one-time password setup as its current code, not the URI with the secret
+                    let shown = match Totp::parse(content) {
+                        Ok(totp) if !redactor.is_enabled() => format!("🔑 {}", totp.summary()),
+                        _ => one_line(content, redactor),
+                    };
+                    let summary = format!("{} QR: {}", size, shown);
+                    ("image", summary, Some(content))
+                }
+                ScanOutcome::NotFound => ("image", format!("{} no QR code", size), None),
+                ScanOutcome::Skipped => ("image", format!("{} not scanned", size), None),
+                ScanOutcome::Failed(e) => ("image", format!("{} scan failed: {}", size, e), None),
+            }
+        }
+        MonitorEvent::Files(files) => ("files", one_line(&file_scan::describe_files(files), redactor), None),
+        MonitorEvent::Cleared => ("empty", "clipboard cleared".to_string(), None),
+        MonitorEvent::Excluded => ("private", "content marked private by its source, ignored".to_string(), None),
+        MonitorEvent::Sensitive(rule) => ("private", format!("sensitive content skipped ({})", rule), None),
+        MonitorEvent::Error(e) => ("error", e.clone(), None),
+        MonitorEvent::ListenerRestarted(reason) => ("restart", format!("clipboard listener restarted: {}", reason), None),
+    }
+}
+
+fn one_line(text: &str, redactor: Redactor) -> String {
+    redactor
+        .text_truncated(text, SUMMARY_CHARS)
+        .replace(['\r', '\n', '\t'], " ")
+}
@@ -0,0 +1,221 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use wayland_client::backend::ObjectId;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_registry, wl_seat::WlSeat};
+use wayland_client::{event_created_child, Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols::ext::data_control::v1::client::{
+    ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+    ext_data_control_manager_v1::{self, ExtDataControlManagerV1},
+    ext_data_control_offer_v1::{self, ExtDataControlOfferV1},
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::{self, ZwlrDataControlManagerV1},
+    zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+};
+
+use crate::clipboard_handler::{ClipboardEvent, ClipboardFormats};
+
+/// Which formats an offer with these MIME types carries
+pub fn formats_of(mime_types: &[String]) -> ClipboardFormats {
+    ClipboardFormats {
+        text: mime_types
+            .iter()
+            .any(|mime| mime.starts_with("text/plain") || ["UTF8_STRING", "STRING", "TEXT"].contains(&mime.as_str())),
+        image: mime_types.iter().any(|mime| mime.starts_with("image/")),
+        rich_text: mime_types.iter().any(|mime| ["text/html", "text/rtf", "text/richtext"].contains(&mime.as_str())),
+        files: mime_types.iter().any(|mime| mime == "text/uri-list"),
+    }
+}
+
+/// A data offer of either protocol, kept until the selection it belongs to is announced
+enum Offer {
+    Ext(ExtDataControlOfferV1),
+    Wlr(ZwlrDataControlOfferV1),
+}
+
+impl Offer {
+    fn destroy(&self) {
+        match self {
+            Offer::Ext(offer) => offer.destroy(),
+            Offer::Wlr(offer) => offer.destroy(),
+        }
+    }
+}
+
+struct Listener {
+    tx: mpsc::Sender<ClipboardEvent>,
+    /// Offers announced so far with their MIME types; the content itself is read through
+    /// the normal clipboard, so they are destroyed once their selection is known
+    offers: HashMap<ObjectId, (Offer, Vec<String>)>,
+    receiver_gone: bool,
+    /// The compositor invalidated the data device, e.g. because the seat went away
+    finished: bool,
+}
+
+impl Listener {
+    fn selection(&mut self, offer: Option<ObjectId>) {
+        let types = offer.and_then(|id| self.offers.get(&id)).map(|(_, types)| types.clone());
+        self.drop_offers();
+        let event = match types {
+            Some(types) => ClipboardEvent::Updated { formats: formats_of(&types) },
+            // Cleared; there's no format to filter on
+            None => ClipboardEvent::OwnerChanged,
+        };
+        if self.tx.send(event).is_err() {
+            self.receiver_gone = true;
+        }
+    }
+
+    fn drop_offers(&mut self) {
+        for (_, (offer, _)) in self.offers.drain() {
+            offer.destroy();
+        }
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for Listener {
+    fn event(_: &mut Self, _: &wl_registry::WlRegistry, _: wl_registry::Event, _: &GlobalListContents, _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+impl Dispatch<WlSeat, ()> for Listener {
+    fn event(_: &mut Self, _: &WlSeat, _: <WlSeat as Proxy>::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+}
+
+/// The two protocols only differ in their names
+macro_rules! data_control {
+    ($variant:ident, $manager:ident, $manager_module:ident, $device:ident, $device_module:ident, $offer:ident, $offer_module:ident) => {
+        impl Dispatch<$manager, ()> for Listener {
+            fn event(_: &mut Self, _: &$manager, _: $manager_module::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {}
+        }
+
+        impl Dispatch<$device, ()> for Listener {
+            fn event(listener: &mut Self, _: &$device, event: $device_module::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {
+                match event {
+                    $device_module::Event::DataOffer { id } => {
+                        listener.offers.insert(id.id(), (Offer::$variant(id), Vec::new()));
+                    }
+                    $device_module::Event::Selection { id } => listener.selection(id.map(|offer| offer.id())),
+                    // Only the clipboard is monitored, not the primary selection
+                    $device_module::Event::PrimarySelection { .. } => listener.drop_offers(),
+                    $device_module::Event::Finished => listener.finished = true,
+                    _ => {}
+                }
+            }
+
+            event_created_child!(Listener, $device, [
+                $device_module::EVT_DATA_OFFER_OPCODE => ($offer, ()),
+            ]);
+        }
+
+        impl Dispatch<$offer, ()> for Listener {
+            fn event(listener: &mut Self, offer: &$offer, event: $offer_module::Event, _: &(), _: &Connection, _: &QueueHandle<Self>) {
+                if let $offer_module::Event::Offer { mime_type } = event {
+                    if let Some((_, types)) = listener.offers.get_mut(&offer.id()) {
+                        types.push(mime_type);
+                    }
+                }
+            }
+        }
+    };
+}
+
+data_control!(
+    Ext,
+    ExtDataControlManagerV1,
+    ext_data_control_manager_v1,
+    ExtDataControlDeviceV1,
+    ext_data_control_device_v1,
+    ExtDataControlOfferV1,
+    ext_data_control_offer_v1
+);
+data_control!(
+    Wlr,
+    ZwlrDataControlManagerV1,
+    zwlr_data_control_manager_v1,
+    ZwlrDataControlDeviceV1,
+    zwlr_data_control_device_v1,
+    ZwlrDataControlOfferV1,
+    zwlr_data_control_offer_v1
+);
+
+/// Report clipboard changes through the compositor's data-control protocol, ext-data-control-v1
+/// or wlr-data-control-unstable-v1, on a thread of its own
+///
+/// Fails if the compositor offers neither; GNOME doesn't, wlroots compositors, KDE Plasma and
+/// most others do. If the connection breaks later the thread falls back to sending poll ticks.
+pub fn start(tx: mpsc::Sender<ClipboardEvent>, poll_interval: Duration) -> Result<thread::JoinHandle<()>> {
+    let connection = Connection::connect_to_env()?;
+    let (globals, mut queue) = registry_queue_init::<Listener>(&connection)?;
+    let qh = queue.handle();
+    let seat: WlSeat = globals.bind(&qh, 1..=8, ())?;
+
+    let protocol = if let Ok(manager) = globals.bind::<ExtDataControlManagerV1, _, _>(&qh, 1..=1, ()) {
+        manager.get_data_device(&seat, &qh, ());
+        "ext-data-control-v1"
+    } else if let Ok(manager) = globals.bind::<ZwlrDataControlManagerV1, _, _>(&qh, 1..=2, ()) {
+        manager.get_data_device(&seat, &qh, ());
+        "wlr-data-control-unstable-v1"
+    } else {
+        return Err(anyhow::anyhow!("the compositor offers neither ext-data-control-v1 nor wlr-data-control-unstable-v1"));
+    };
+
+    let mut listener = Listener {
+        tx,
+        offers: HashMap::new(),
+        receiver_gone: false,
+        finished: false,
+    };
+    // Protocol errors show up here rather than on the thread; this also reports the current selection
+    queue.roundtrip(&mut listener)?;
+    info!("Wayland clipboard listener started ({})", protocol);
+
+    Ok(thread::spawn(move || {
+        let reason = loop {
+            if let Err(e) = queue.blocking_dispatch(&mut listener) {
+                break format!("Wayland connection failed: {}", e);
+            }
+            if listener.receiver_gone {
+                debug!("Wayland clipboard listener stopped");
+                return;
+            }
+            if listener.finished {
+                break "The compositor invalidated the clipboard device".to_string();
+            }
+        };
+
+        warn!("{}. Falling back to polling.", reason);
+        let _ = listener.tx.send(ClipboardEvent::ListenerDegraded(reason));
+        loop {
+            thread::sleep(poll_interval);
+            if listener.tx.send(ClipboardEvent::Poll).is_err() {
+                break;
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_formats() {
+        let types = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(formats_of(&types(&["text/plain;charset=utf-8", "UTF8_STRING"])), ClipboardFormats::TEXT_ONLY);
+        assert_eq!(formats_of(&types(&["image/png"])), ClipboardFormats::IMAGE_ONLY);
+        assert_eq!(
+            formats_of(&types(&["text/html", "image/png", "STRING"])),
+            ClipboardFormats { files: false, ..ClipboardFormats::ALL }
+        );
+        assert_eq!(
+            formats_of(&types(&["text/uri-list"])),
+            ClipboardFormats { text: false, image: false, rich_text: false, files: true }
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::Result;
+use eframe::egui;
+use tracing::{error, info};
+
+use crate::clipboard_handler::ClipboardHandler;
+use crate::qr_generator::QRGenerator;
+use crate::qr_templates::{WifiNetwork, WifiSecurity};
+
+/// Fill in Wi-Fi credentials in a window, with a live preview of the code
+pub fn run(generator: QRGenerator) -> Result<()> {
+    info!("Opening the Wi-Fi QR code form");
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_title("Wi-Fi QR Code").with_inner_size([420.0, 600.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "Wi-Fi QR Code",
+        options,
+        Box::new(move |_cc| Ok(Box::new(WifiForm::new(generator)))),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to open Wi-Fi form: {}", e))
+}
+
+/// Open the Wi-Fi form in its own process, like [`crate::settings::open_window`]
+pub fn open_window() -> Result<()> {
+    let exe = std::env::current_exe()?;
+    std::process::Command::new(exe)
+        .args(["template", "wifi"])
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to open the Wi-Fi form: {}", e))?;
+    Ok(())
+}
+
+struct WifiForm {
+    generator: QRGenerator,
+    network: WifiNetwork,
+    show_password: bool,
+    /// The payload the preview was made for
+    previewed: Option<String>,
+    texture: Option<egui::TextureHandle>,
+    /// Result of the last copy or save
+    status: Option<String>,
+    /// Kept while the window is open, as X11 and Wayland clipboard content only lasts as
+    /// long as the handler that set it
+    clipboard: ClipboardHandler,
+}
+
+impl WifiForm {
+    fn new(generator: QRGenerator) -> Self {
+        Self {
+            generator,
+            network: WifiNetwork::new("", WifiSecurity::Wpa, ""),
+            show_password: false,
+            previewed: None,
+            texture: None,
+            status: None,
+            clipboard: ClipboardHandler::builder().spawn_listener(false).build(),
+        }
+    }
+
+    fn fields(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("wifi-fields").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+            ui.label("Network name");
+            ui.text_edit_singleline(&mut self.network.ssid);
+            ui.end_row();
+
+            ui.label("Security");
+            egui::ComboBox::from_id_salt("wifi-security")
+                .selected_text(self.network.security.label())
+                .show_ui(ui, |ui| {
+                    for security in WifiSecurity::ALL {
+                        ui.selectable_value(&mut self.network.security, security, security.label());
+                    }
+                });
+            ui.end_row();
+
+            if self.network.security != WifiSecurity::None {
+                ui.label("Password");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.network.password).password(!self.show_password));
+                    ui.checkbox(&mut self.show_password, "Show");
+                });
+                ui.end_row();
+            }
+
+            ui.label("");
+            ui.checkbox(&mut self.network.hidden, "Hidden network");
+            ui.end_row();
+        });
+    }
+
+    /// Regenerate the preview when the payload changed
+    fn refresh(&mut self, ctx: &egui::Context) {
+        let payload = self.network.validate().ok().map(|()| self.network.payload());
+        if payload == self.previewed {
+            return;
+        }
+        self.texture = match &payload {
+            Some(payload) => match self.generator.generate_qr_image(payload) {
+                Ok(image) => image.map(|image| {
+                    let size = [image.width() as usize, image.height() as usize];
+                    ctx.load_texture("wifi-preview", egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw()), egui::TextureOptions::NEAREST)
+                }),
+                Err(e) => {
+                    error!("Failed to generate Wi-Fi QR code: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        self.previewed = payload;
+    }
+
+    fn copy(&mut self, payload: &str) {
+        let copied = self.generator.generate_rgba(payload).and_then(|image| self.clipboard.set_image(&image));
+        self.status = Some(match copied {
+            Ok(()) => "📋 QR code copied to the clipboard".to_string(),
+            Err(e) => format!("❌ {}", e),
+        });
+    }
+
+    fn save(&mut self, payload: &str) {
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(format!("wifi-{}.png", self.network.ssid))
+            .add_filter("Image", &["png", "svg", "pdf"])
+            .save_file()
+        else {
+            return;
+        };
+        self.status = Some(match self.generator.save_qr_image_to(payload, &path) {
+            Ok(path) => format!("✅ Saved to {}", path.display()),
+            Err(e) => format!("❌ {}", e),
+        });
+    }
+}
+
+impl eframe::App for WifiForm {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.refresh(ctx);
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
+        egui::TopBottomPanel::bottom("wifi-actions").show(ctx, |ui| {
+            ui.add_space(4.0);
+            let payload = self.previewed.clone();
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(payload.is_some(), |ui| {
+                    if ui.button("Copy").clicked() {
+                        if let Some(payload) = &payload {
+                            self.copy(payload);
+                        }
+                    }
+                    if ui.button("Save…").clicked() {
+                        if let Some(payload) = &payload {
+                            self.save(payload);
+                        }
+                    }
+                });
+                if ui.button("Close").clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+            if let Some(status) = &self.status {
+                ui.label(status);
+            }
+            ui.add_space(4.0);
+        });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.fields(ui);
+            ui.separator();
+            match (&self.texture, self.network.validate()) {
+                (Some(texture), _) => {
+                    let available = ui.available_size();
+                    let size = texture.size_vec2();
+                    let scale = (available.x / size.x).min(available.y / size.y).min(1.0);
+                    ui.centered_and_justified(|ui| ui.image((texture.id(), size * scale)));
+                }
+                (None, Err(e)) => {
+                    ui.colored_label(ui.visuals().warn_fg_color, e.to_string());
+                }
+                (None, Ok(())) => {
+                    ui.label("The code couldn't be generated");
+                }
+            }
+        });
+    }
+}